@@ -3,8 +3,8 @@ use proc_macro::TokenStream;
 use proc_macro2::Span;
 use quote::{format_ident, quote, quote_spanned};
 use syn::{
-    parse_macro_input, parse_quote, Attribute, Expr, GenericArgument, Generics, Ident, Index,
-    ItemFn, Lit, Meta, PathArguments, Signature, Type, Visibility,
+    parse_macro_input, parse_quote, spanned::Spanned, Attribute, Expr, GenericArgument, Generics,
+    Ident, Index, ItemFn, Lit, Meta, PathArguments, Signature, Type, Visibility,
 };
 
 /// Wraps a function in a system, and generates a new function which constructs that system.
@@ -105,6 +105,28 @@ use syn::{
 /// }
 /// ```
 ///
+/// Systems can send and receive events with `&mut legion::systems::EventWriter<T>` and
+/// `&mut legion::systems::EventReader<T>` parameters. Each reader remembers which events its
+/// system has already read. The `Events<T>` resource must be inserted before the system runs.
+/// As macros cannot see imports, parameters which name the event types by any other path must be
+/// marked with the `#[event]` attribute; other types named `EventReader` are left alone.
+///
+/// ```ignore
+/// # use legion_codegen::system;
+/// # use legion::systems::EventWriter;
+/// # struct Collision;
+/// # struct Damage(u32);
+/// #[system]
+/// fn apply_damage(
+///     collisions: &mut legion::systems::EventReader<Collision>,
+///     #[event] damage: &mut EventWriter<Damage>,
+/// ) {
+///     for _ in collisions.read() {
+///         damage.send(Damage(10));
+///     }
+/// }
+/// ```
+///
 /// Systems can contain their own state. Add a reference marked with the `#[state]` parameter to
 /// your function. This state will be initialized when you construct the system.
 ///
@@ -188,6 +210,8 @@ enum Error {
     [optioned] component references, state references, or resource references"
     )]
     InvalidArgument(Span),
+    #[error("event parameters must be `&mut EventReader<T>` or `&mut EventWriter<T>` references")]
+    InvalidEventArgument(Span),
     #[error("expected component type")]
     ExpectedComponentType(Span),
     #[error("expected filter expression")]
@@ -208,6 +232,7 @@ impl Error {
             Error::InvalidKey(span) => *span,
             Error::InvalidOptionArgument(span, _) => *span,
             Error::InvalidArgument(span) => *span,
+            Error::InvalidEventArgument(span) => *span,
             _ => Span::call_site(),
         }
     }
@@ -287,6 +312,8 @@ struct Sig {
     query: Vec<Type>,
     read_resources: Vec<Type>,
     write_resources: Vec<Type>,
+    read_events: Vec<Type>,
+    write_events: Vec<Type>,
     state_args: Vec<Type>,
     generics: Generics,
 }
@@ -297,6 +324,8 @@ impl Sig {
         let mut query = Vec::<Type>::new();
         let mut read_resources = Vec::new();
         let mut write_resources = Vec::new();
+        let mut read_events = Vec::new();
+        let mut write_events = Vec::new();
         let mut state_args = Vec::new();
         for param in &mut item.inputs {
             match param {
@@ -370,6 +399,14 @@ impl Sig {
                         parameters.push(Parameter::Component(query.len()));
                         query.push(parse_quote!(::legion::Entity));
                     }
+                    Type::Reference(ty) if event_type(&ty.elem, "EventReader", true).is_some() => {
+                        parameters.push(Parameter::EventReader(read_events.len()));
+                        read_events.push(event_type(&ty.elem, "EventReader", true).unwrap());
+                    }
+                    Type::Reference(ty) if event_type(&ty.elem, "EventWriter", true).is_some() => {
+                        parameters.push(Parameter::EventWriter(write_events.len()));
+                        write_events.push(event_type(&ty.elem, "EventWriter", true).unwrap());
+                    }
                     Type::Reference(ty) => {
                         let mutable = ty.mutability.is_some();
                         let resource = Self::find_remove_arg_attr(&mut arg.attrs);
@@ -391,6 +428,19 @@ impl Sig {
                                 }
                                 state_args.push(ty.elem.as_ref().clone());
                             }
+                            Some(ArgAttr::Event) => {
+                                if let Some(event) = event_type(&ty.elem, "EventReader", false) {
+                                    parameters.push(Parameter::EventReader(read_events.len()));
+                                    read_events.push(event);
+                                } else if let Some(event) =
+                                    event_type(&ty.elem, "EventWriter", false)
+                                {
+                                    parameters.push(Parameter::EventWriter(write_events.len()));
+                                    write_events.push(event);
+                                } else {
+                                    return Err(Error::InvalidEventArgument(arg.span()));
+                                }
+                            }
                             None => {
                                 parameters.push(Parameter::Component(query.len()));
                                 let elem = &ty.elem;
@@ -414,6 +464,8 @@ impl Sig {
             query,
             read_resources,
             write_resources,
+            read_events,
+            write_events,
             state_args,
         })
     }
@@ -429,6 +481,10 @@ impl Sig {
                     attributes.remove(i);
                     return Some(ArgAttr::State);
                 }
+                Some(ident) if ident == "event" => {
+                    attributes.remove(i);
+                    return Some(ArgAttr::Event);
+                }
                 _ => {}
             }
         }
@@ -439,6 +495,7 @@ impl Sig {
 enum ArgAttr {
     Resource,
     State,
+    Event,
 }
 
 fn is_type(ty: &Type, segments: &[&str]) -> bool {
@@ -452,6 +509,38 @@ fn is_type(ty: &Type, segments: &[&str]) -> bool {
     }
 }
 
+/// Returns the event type `T` if `ty` is a path to `legion::systems::name<T>`, such as
+/// `legion::systems::EventReader<T>`. If `full_path` is false, any path ending in `name<T>` is
+/// accepted.
+fn event_type(ty: &Type, name: &str, full_path: bool) -> Option<Type> {
+    let segments = match ty {
+        Type::Path(path) if path.qself.is_none() => &path.path.segments,
+        _ => return None,
+    };
+    let segment = segments.last()?;
+    if segment.ident != name {
+        return None;
+    }
+    if full_path {
+        let path: Vec<_> = segments
+            .iter()
+            .map(|segment| segment.ident.to_string())
+            .collect();
+        if path != ["legion", "systems", name] {
+            return None;
+        }
+    }
+    match &segment.arguments {
+        PathArguments::AngleBracketed(bracketed) => {
+            bracketed.args.iter().find_map(|arg| match arg {
+                GenericArgument::Type(ty) => Some(ty.clone()),
+                _ => None,
+            })
+        }
+        _ => None,
+    }
+}
+
 #[derive(Copy, Clone, PartialEq)]
 enum SystemType {
     Simple,
@@ -477,6 +566,8 @@ enum Parameter {
     Component(usize),
     Resource(usize),
     ResourceMut(usize),
+    EventReader(usize),
+    EventWriter(usize),
     State(usize),
     StateMut(usize),
 }
@@ -601,6 +692,16 @@ impl Config {
                     "par_for_each systems cannot accept mutable resource references".to_string(),
                 ));
             }
+            if self
+                .signature
+                .parameters
+                .iter()
+                .any(|param| matches!(param, Parameter::EventReader(_) | Parameter::EventWriter(_)))
+            {
+                return Err(Error::Message(
+                    "par_for_each systems cannot accept event readers or writers".to_string(),
+                ));
+            }
             if self
                 .signature
                 .parameters
@@ -646,8 +747,11 @@ impl Config {
 
         // construct function arguments
         let has_query = !signature.query.is_empty();
-        let single_resource =
-            (signature.read_resources.len() + signature.write_resources.len()) == 1;
+        let single_resource = (signature.read_resources.len()
+            + signature.write_resources.len()
+            + signature.read_events.len()
+            + signature.write_events.len())
+            == 1;
         let mut call_params = Vec::new();
         let mut fn_params = Vec::new();
         let mut world = None;
@@ -696,6 +800,23 @@ impl Config {
                     let idx = Index::from(*idx + signature.read_resources.len());
                     call_params.push(quote!(&mut *resources.#idx));
                 }
+                Parameter::EventReader(_) | Parameter::EventWriter(_) if single_resource => {
+                    call_params.push(quote!(&mut *resources))
+                }
+                Parameter::EventReader(idx) => {
+                    let idx = Index::from(
+                        *idx + signature.read_resources.len() + signature.write_resources.len(),
+                    );
+                    call_params.push(quote!(&mut resources.#idx));
+                }
+                Parameter::EventWriter(idx) => {
+                    let idx = Index::from(
+                        *idx + signature.read_resources.len()
+                            + signature.write_resources.len()
+                            + signature.read_events.len(),
+                    );
+                    call_params.push(quote!(&mut resources.#idx));
+                }
                 Parameter::State(idx) => {
                     let arg_name = format_ident!("state_{}", idx);
                     let arg_type = &signature.state_args[*idx];
@@ -753,6 +874,8 @@ impl Config {
         };
         let read_resources = &signature.read_resources;
         let write_resources = &signature.write_resources;
+        let read_events = &signature.read_events;
+        let write_events = &signature.write_events;
         let builder = quote! {
             use legion::IntoQuery;
             #generic_parameter_names
@@ -761,6 +884,8 @@ impl Config {
                 #(.write_component::<#write_components>())*
                 #(.read_resource::<#read_resources>())*
                 #(.write_resource::<#write_resources>())*
+                #(.read_events::<#read_events>())*
+                #(.write_events::<#write_events>())*
                 #query
                 .build(move |cmd, world, resources, query| {
                     #body
@@ -0,0 +1,47 @@
+//! Parent and child relationships between entities.
+//!
+//! An entity is made the child of another with [World::set_parent](../world/struct.World.html#method.set_parent).
+//! The child is given a [ChildOf](struct.ChildOf.html) component which names its parent, and the
+//! parent's [Children](struct.Children.html) component lists all of its children.
+//!
+//! Removing an entity from its world also removes all of its descendants, and detaches it from
+//! its parent.
+//!
+//! ```
+//! # use legion::*;
+//! # use legion::hierarchy::Children;
+//! # struct Local(f32);
+//! let mut world = World::default();
+//! let root = world.push((Local(1.0),));
+//! let child = world.push((Local(2.0),));
+//! let grandchild = world.push((Local(3.0),));
+//! world.set_parent(child, root).unwrap();
+//! world.set_parent(grandchild, child).unwrap();
+//!
+//! // iterate through an entity's children
+//! # use legion::world::EntityStore;
+//! let entry = world.entry_ref(root).unwrap();
+//! for child in entry.get_component::<Children>().unwrap().iter() {
+//!     assert_eq!(world.parent(child), Some(root));
+//! }
+//!
+//! // join a query on children with a query on their parents
+//! let mut query = <&Local>::query();
+//! let mut parents = <&Local>::query();
+//! query
+//!     .for_each_with_parent(&world, &mut parents, &world, |local, parent| {
+//!         assert_eq!(local.0, parent.unwrap().0 + 1.0);
+//!     })
+//!     .unwrap();
+//!
+//! // removing the root removes the entire hierarchy
+//! world.remove(root);
+//! assert!(!world.contains(grandchild));
+//! ```
+//!
+//! Relationships are preserved by [World::clone_from](../world/struct.World.html#method.clone_from)
+//! when `ChildOf` and `Children` are registered with the merger, as entity IDs are remapped when
+//! these components are cloned. They can also be serialized by registering them with a
+//! [Registry](../serialize/struct.Registry.html).
+
+pub use crate::internals::hierarchy::{ChildOf, Children, HierarchyError};
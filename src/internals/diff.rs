@@ -0,0 +1,282 @@
+use super::{
+    entity::{Entity, EntityHasher},
+    snapshot::WorldSnapshot,
+    storage::{
+        archetype::{ArchetypeIndex, EntityLayout},
+        component::ComponentTypeId,
+        next_component_version, Version,
+    },
+    world::World,
+};
+use std::{collections::HashMap, sync::Arc};
+
+/// The differences between a [world](struct.World.html) and an earlier state of it, computed
+/// with [World::diff](struct.World.html#method.diff).
+///
+/// A diff records which entities were spawned and despawned, which components were added to or
+/// removed from entities which exist in both states, and which components were changed. Only
+/// the component types and entity IDs are recorded; component values are read from the world
+/// when the diff is [serialized](#method.as_serializable), and can be applied to another world
+/// with [World::apply_patch](struct.World.html#method.apply_patch).
+///
+/// [Sparse](struct.World.html#method.register_sparse) components are compared along with the
+/// rest of each entity's components.
+#[derive(Debug)]
+pub struct WorldDiff<'a> {
+    world: &'a World,
+    version: Version,
+    spawned: Vec<Entity>,
+    despawned: Vec<Entity>,
+    added: Vec<(Entity, ComponentTypeId)>,
+    removed: Vec<(Entity, ComponentTypeId)>,
+    changed: Vec<(Entity, ComponentTypeId)>,
+}
+
+impl<'a> WorldDiff<'a> {
+    /// Returns the world from which the diff was computed.
+    pub fn world(&self) -> &'a World {
+        self.world
+    }
+
+    /// Returns the component version at which the diff was computed. Passing this version to a
+    /// later call to [World::diff](struct.World.html#method.diff) finds the components which
+    /// have been changed since this diff.
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    /// Returns the entities which exist in the world but not in the base state.
+    pub fn spawned(&self) -> &[Entity] {
+        &self.spawned
+    }
+
+    /// Returns the entities which exist in the base state but not in the world.
+    pub fn despawned(&self) -> &[Entity] {
+        &self.despawned
+    }
+
+    /// Returns the components which have been added to entities, including all of the
+    /// components of spawned entities.
+    pub fn added(&self) -> &[(Entity, ComponentTypeId)] {
+        &self.added
+    }
+
+    /// Returns the components which have been removed from entities which still exist.
+    pub fn removed(&self) -> &[(Entity, ComponentTypeId)] {
+        &self.removed
+    }
+
+    /// Returns the components which existed in the base state and have since been accessed
+    /// mutably.
+    pub fn changed(&self) -> &[(Entity, ComponentTypeId)] {
+        &self.changed
+    }
+
+    /// Returns `true` if the world has not changed.
+    pub fn is_empty(&self) -> bool {
+        self.spawned.is_empty()
+            && self.despawned.is_empty()
+            && self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+    }
+
+    /// Returns a serializable representation of the diff, containing the values of all added
+    /// and changed components. The serialized diff can be deserialized as a
+    /// [WorldPatch](../serialize/struct.WorldPatch.html).
+    #[cfg(feature = "serialize")]
+    pub fn as_serializable<W: crate::internals::serialize::ser::WorldSerializer>(
+        &'a self,
+        world_serializer: &'a W,
+    ) -> crate::internals::serialize::patch::SerializableWorldDiff<'a, W> {
+        crate::internals::serialize::patch::SerializableWorldDiff::new(self, world_serializer)
+    }
+}
+
+impl World {
+    /// Computes the differences between this world and `base`, which is an earlier state of the
+    /// same world.
+    ///
+    /// Entities are matched between the two worlds by ID. Components which exist in both worlds
+    /// are reported as changed if they have been accessed mutably in this world after the
+    /// component version `since`, usually the [version](struct.WorldDiff.html#method.version)
+    /// of the previous diff. Passing `0` reports all such components as changed, which is
+    /// required when `base` does not share its history with this world.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use legion::*;
+    /// # use legion::storage::ComponentTypeId;
+    /// # use legion::world::Duplicate;
+    /// let mut world = World::default();
+    /// let entity = world.push((1usize, false));
+    ///
+    /// let mut duplicate = Duplicate::new();
+    /// duplicate.register_copy::<usize>();
+    /// duplicate.register_copy::<bool>();
+    /// let snapshot = world.snapshot(&mut duplicate);
+    ///
+    /// *world.entry(entity).unwrap().get_component_mut::<usize>().unwrap() = 2;
+    /// let spawned = world.push((3usize,));
+    ///
+    /// let diff = world.diff_snapshot(&snapshot);
+    /// assert_eq!(diff.spawned(), &[spawned]);
+    /// assert_eq!(diff.changed(), &[(entity, ComponentTypeId::of::<usize>())]);
+    /// ```
+    pub fn diff<'a>(&'a self, base: &World, since: Version) -> WorldDiff<'a> {
+        let version = next_component_version();
+
+        let mut base_layouts = HashMap::<Entity, &Arc<EntityLayout>, EntityHasher>::default();
+        for arch in base.archetypes() {
+            for entity in arch.entities() {
+                base_layouts.insert(*entity, arch.layout());
+            }
+        }
+
+        let mut diff = WorldDiff {
+            world: self,
+            version,
+            spawned: Vec::new(),
+            despawned: Vec::new(),
+            added: Vec::new(),
+            removed: Vec::new(),
+            changed: Vec::new(),
+        };
+
+        for (i, arch) in self.archetypes().iter().enumerate() {
+            let layout = arch.layout();
+            // storages which do not track ticks report all of their components as changed
+            let ticks = layout
+                .component_types()
+                .iter()
+                .map(|type_id| {
+                    self.components()
+                        .get(*type_id)
+                        .and_then(|storage| storage.get_ticks(ArchetypeIndex(i as u32)))
+                })
+                .collect::<Vec<_>>();
+
+            for (index, entity) in arch.entities().iter().enumerate() {
+                let sparse_types = self.sparse_components().component_types(*entity);
+                let base_layout = if let Some(base_layout) = base_layouts.get(entity) {
+                    base_layout
+                } else {
+                    diff.spawned.push(*entity);
+                    for type_id in layout.component_types().iter().chain(&sparse_types) {
+                        diff.added.push((*entity, *type_id));
+                    }
+                    continue;
+                };
+                let base_has = |type_id: ComponentTypeId| {
+                    base_layout.has_component_by_id(type_id)
+                        || base.has_sparse_component(*entity, type_id)
+                };
+
+                for (type_id, ticks) in layout.component_types().iter().zip(ticks.iter()) {
+                    if !base_has(*type_id) {
+                        diff.added.push((*entity, *type_id));
+                    } else if !matches!(ticks, Some(ticks) if ticks[index].changed <= since) {
+                        diff.changed.push((*entity, *type_id));
+                    }
+                }
+                for type_id in &sparse_types {
+                    let storage = self.sparse_components().get(*type_id).unwrap();
+                    if !base_has(*type_id) {
+                        diff.added.push((*entity, *type_id));
+                    } else if !matches!(storage.get_ticks(*entity), Some(ticks) if ticks.changed <= since)
+                    {
+                        diff.changed.push((*entity, *type_id));
+                    }
+                }
+
+                let base_sparse_types = base.sparse_components().component_types(*entity);
+                for type_id in base_layout
+                    .component_types()
+                    .iter()
+                    .chain(&base_sparse_types)
+                {
+                    if !layout.has_component_by_id(*type_id) && !sparse_types.contains(type_id) {
+                        diff.removed.push((*entity, *type_id));
+                    }
+                }
+            }
+        }
+
+        for arch in base.archetypes() {
+            for entity in arch.entities() {
+                if !self.contains(*entity) {
+                    diff.despawned.push(*entity);
+                }
+            }
+        }
+
+        diff
+    }
+
+    /// Computes the differences between this world and a [snapshot](#method.snapshot) taken
+    /// earlier from it. Components are reported as changed if they have been accessed mutably
+    /// since the snapshot was taken.
+    ///
+    /// Component types which were not captured by the snapshot are reported as added to every
+    /// entity. After the world is [restored](#method.restore), all of its components are
+    /// reported as changed.
+    pub fn diff_snapshot<'a>(&'a self, snapshot: &WorldSnapshot) -> WorldDiff<'a> {
+        self.diff(snapshot.world(), snapshot.version())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::internals::world::{Duplicate, EntityStore};
+
+    #[test]
+    fn diff_worlds() {
+        let mut world = World::default();
+        let a = world.push((1usize, false));
+        let b = world.push((2usize, false));
+        let c = world.push((3usize,));
+
+        let mut duplicate = Duplicate::new();
+        duplicate.register_copy::<usize>();
+        duplicate.register_copy::<bool>();
+        duplicate.register_copy::<f32>();
+        let snapshot = world.snapshot(&mut duplicate);
+        assert!(world.diff_snapshot(&snapshot).is_empty());
+
+        *world
+            .entry(a)
+            .unwrap()
+            .get_component_mut::<usize>()
+            .unwrap() = 10;
+        {
+            let mut entry = world.entry(b).unwrap();
+            entry.add_component(1f32);
+            entry.remove_component::<bool>();
+        }
+        world.remove(c);
+        let d = world.push((4usize,));
+
+        let diff = world.diff_snapshot(&snapshot);
+        let usize_id = ComponentTypeId::of::<usize>();
+        assert_eq!(diff.spawned(), &[d]);
+        assert_eq!(diff.despawned(), &[c]);
+        assert_eq!(diff.changed(), &[(a, usize_id)]);
+        assert_eq!(diff.added().len(), 2);
+        assert!(diff.added().contains(&(b, ComponentTypeId::of::<f32>())));
+        assert!(diff.added().contains(&(d, usize_id)));
+        assert_eq!(diff.removed(), &[(b, ComponentTypeId::of::<bool>())]);
+
+        // only changes made after the diff are reported relative to its version
+        let version = diff.version();
+        assert!(world.diff(&world, version).is_empty());
+        world
+            .entry_mut(d)
+            .unwrap()
+            .get_component_mut::<usize>()
+            .unwrap();
+        assert_eq!(world.diff(&world, version).changed(), &[(d, usize_id)]);
+        assert_eq!(world.diff(&world, 0).changed().len(), 5);
+    }
+}
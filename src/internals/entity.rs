@@ -2,19 +2,38 @@ use super::{
     hash::U64Hasher,
     storage::{archetype::ArchetypeIndex, ComponentIndex},
 };
+use parking_lot::Mutex;
 use std::{
-    cell::RefCell,
-    collections::HashMap,
-    fmt::Debug,
-    hash::BuildHasherDefault,
-    sync::atomic::{AtomicU64, Ordering},
+    any::Any, cell::RefCell, collections::HashMap, fmt::Debug, hash::BuildHasherDefault, sync::Arc,
 };
 
 /// An opaque identifier for an entity.
+///
+/// Entity IDs are made up of an index and a generation. The index of a removed entity is
+/// recycled for new entities, with its generation incremented such that stale IDs which
+/// refer to the removed entity can be detected.
 #[derive(Debug, Copy, PartialEq, Eq, Hash)]
 #[repr(transparent)]
 pub struct Entity(u64);
 
+impl Entity {
+    fn new(index: u32, generation: u32) -> Self {
+        Entity((generation as u64) << 32 | index as u64)
+    }
+
+    /// Returns the index of the entity.
+    ///
+    /// Indices are unique among all living entities, but may be reused once an entity is removed.
+    pub fn index(&self) -> u32 {
+        self.0 as u32
+    }
+
+    /// Returns the generation of the entity's index.
+    pub fn generation(&self) -> u32 {
+        (self.0 >> 32) as u32
+    }
+}
+
 thread_local! {
     pub static ID_CLONE_MAPPINGS: RefCell<HashMap<Entity, Entity, EntityHasher>> = RefCell::new(HashMap::default());
 }
@@ -28,22 +47,230 @@ impl Clone for Entity {
     }
 }
 
-const BLOCK_SIZE: u64 = 16;
-const BLOCK_SIZE_USIZE: usize = BLOCK_SIZE as usize;
+const BLOCK_SIZE: usize = 16;
 
-static NEXT_ENTITY: AtomicU64 = AtomicU64::new(0);
+/// Hands out entity IDs to [worlds](../world/struct.World.html) and
+/// [command buffers](../systems/struct.CommandBuffer.html).
+///
+/// Each world holds a shared reference to an allocator, provided via
+/// [WorldOptions](../world/struct.WorldOptions.html). Worlds which share an allocator are
+/// guaranteed to never produce conflicting entity IDs, while a world with its own allocator
+/// will produce the same sequence of IDs each time the same operations are performed on it.
+pub trait EntityAllocator: Send + Sync {
+    /// Reserves `count` new entity IDs, pushing them onto the end of `ids`.
+    fn reserve(&self, count: usize, ids: &mut Vec<Entity>);
 
-/// An iterator which yields new entity IDs.
+    /// Returns IDs which were reserved but never used. These IDs may be reserved again unchanged.
+    fn unreserve(&self, ids: &[Entity]);
+
+    /// Releases the ID of an entity which has been removed from its world, allowing its index
+    /// to be reused.
+    ///
+    /// Releasing an ID which has already been released, such as when the same entity was
+    /// removed from two worlds which share an allocator, must have no effect.
+    fn release(&self, entity: Entity);
+
+    /// Takes a released ID back out of the allocator, such that its index is not reused. This
+    /// allows an entity which has been removed to be restored with its original ID.
+    ///
+    /// Returns `true` if the ID may be used again by the caller, or `false` if its index has been
+    /// handed out again since it was released. Allocators which cannot reclaim IDs always return
+    /// `false`, which is the default.
+    fn reclaim(&self, entity: Entity) -> bool {
+        let _ = entity;
+        false
+    }
+
+    /// Captures the allocator's state, such that it can later be rewound with
+    /// [restore_state](#method.restore_state). Returns `None` if the allocator cannot be
+    /// rewound, which is the default.
+    fn save_state(&self) -> Option<Box<dyn Any + Send + Sync>> {
+        None
+    }
+
+    /// Rewinds the allocator to a state previously captured with
+    /// [save_state](#method.save_state).
+    fn restore_state(&self, state: &(dyn Any + Send + Sync)) {
+        let _ = state;
+    }
+}
+
+#[derive(Debug, Clone)]
+struct AllocatorState {
+    next_index: u64,
+    free: Vec<Entity>,
+    // the generation of the most recent ID handed out for each index
+    generations: Vec<u32>,
+}
+
+/// An [EntityAllocator](trait.EntityAllocator.html) which recycles the indices of released
+/// entities with an incremented generation.
 #[derive(Debug)]
+pub struct GenerationalAllocator {
+    state: Mutex<AllocatorState>,
+}
+
+impl GenerationalAllocator {
+    /// Constructs a new allocator, which begins allocating from index `0`.
+    pub const fn new() -> Self {
+        Self {
+            state: parking_lot::const_mutex(AllocatorState {
+                next_index: 0,
+                free: Vec::new(),
+                generations: Vec::new(),
+            }),
+        }
+    }
+}
+
+impl Default for GenerationalAllocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EntityAllocator for GenerationalAllocator {
+    fn reserve(&self, count: usize, ids: &mut Vec<Entity>) {
+        let mut state = self.state.lock();
+        for _ in 0..count {
+            if let Some(entity) = state.free.pop() {
+                ids.push(entity);
+            } else {
+                assert!(
+                    state.next_index <= u32::MAX as u64,
+                    "entity index space exhausted"
+                );
+                ids.push(Entity::new(state.next_index as u32, 0));
+                state.next_index += 1;
+                state.generations.push(0);
+            }
+        }
+    }
+
+    fn unreserve(&self, ids: &[Entity]) {
+        // free IDs are popped off of the end, so push them in reverse to preserve their order
+        self.state.lock().free.extend(ids.iter().rev());
+    }
+
+    fn release(&self, entity: Entity) {
+        let mut state = self.state.lock();
+        let current = match state.generations.get_mut(entity.index() as usize) {
+            Some(current) if *current == entity.generation() => current,
+            // the ID was not handed out by this allocator, or has already been released
+            _ => return,
+        };
+        *current = current.wrapping_add(1);
+        let released = Entity::new(entity.index(), *current);
+        state.free.push(released);
+    }
+
+    fn reclaim(&self, entity: Entity) -> bool {
+        let mut state = self.state.lock();
+        let index = entity.index() as usize;
+        let current = match state.generations.get(index) {
+            Some(current) => Entity::new(entity.index(), *current),
+            None => return false,
+        };
+        // the index is only free if its latest ID is waiting to be handed out
+        match state.free.iter().rposition(|free| *free == current) {
+            Some(position) => {
+                state.free.remove(position);
+                state.generations[index] = entity.generation();
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn save_state(&self) -> Option<Box<dyn Any + Send + Sync>> {
+        Some(Box::new(self.state.lock().clone()))
+    }
+
+    fn restore_state(&self, state: &(dyn Any + Send + Sync)) {
+        let state = state
+            .downcast_ref::<AllocatorState>()
+            .expect("allocator state was not saved by a GenerationalAllocator");
+        *self.state.lock() = state.clone();
+    }
+}
+
+static SHARED_ALLOCATOR: GenerationalAllocator = GenerationalAllocator::new();
+
+/// The process-wide [EntityAllocator](trait.EntityAllocator.html) used by all worlds which have
+/// not been given their own allocator.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct SharedAllocator;
+
+impl EntityAllocator for SharedAllocator {
+    fn reserve(&self, count: usize, ids: &mut Vec<Entity>) {
+        SHARED_ALLOCATOR.reserve(count, ids)
+    }
+
+    fn unreserve(&self, ids: &[Entity]) {
+        SHARED_ALLOCATOR.unreserve(ids)
+    }
+
+    fn release(&self, entity: Entity) {
+        SHARED_ALLOCATOR.release(entity)
+    }
+
+    fn reclaim(&self, entity: Entity) -> bool {
+        SHARED_ALLOCATOR.reclaim(entity)
+    }
+}
+
+/// An iterator which yields new entity IDs.
+///
+/// Entity IDs are reserved from an [EntityAllocator](trait.EntityAllocator.html) in small blocks.
+/// Any IDs which have been reserved but not yet yielded are returned to the allocator when the
+/// iterator is dropped.
 pub struct Allocate {
-    base: u64,
-    count: u64,
+    allocator: Arc<dyn EntityAllocator>,
+    block: Vec<Entity>,
 }
 
 impl Allocate {
-    /// Constructs a new enity ID allocator iterator.
+    /// Constructs a new enity ID allocator iterator, which draws IDs from the
+    /// [SharedAllocator](struct.SharedAllocator.html).
     pub fn new() -> Self {
-        Self { base: 0, count: 0 }
+        Self::with_allocator(Arc::new(SharedAllocator))
+    }
+
+    /// Constructs a new entity ID allocator iterator, which draws IDs from the given allocator.
+    pub fn with_allocator(allocator: Arc<dyn EntityAllocator>) -> Self {
+        Self {
+            allocator,
+            block: Vec::new(),
+        }
+    }
+
+    /// Returns the allocator from which IDs are drawn.
+    pub fn allocator(&self) -> &Arc<dyn EntityAllocator> {
+        &self.allocator
+    }
+
+    /// Returns the IDs which have been reserved from the allocator but not yet yielded.
+    pub(crate) fn reserved(&self) -> &[Entity] {
+        &self.block
+    }
+
+    /// Replaces the IDs which have been reserved from the allocator but not yet yielded, without
+    /// returning the previous IDs to the allocator.
+    pub(crate) fn set_reserved(&mut self, block: Vec<Entity>) {
+        self.block = block;
+    }
+
+    /// Returns any reserved but not yet yielded ID with the same index as `entity` to the
+    /// allocator.
+    pub(crate) fn unreserve_index(&mut self, entity: Entity) {
+        if let Some(position) = self
+            .block
+            .iter()
+            .position(|reserved| reserved.index() == entity.index())
+        {
+            let reserved = self.block.remove(position);
+            self.allocator.unreserve(&[reserved]);
+        }
     }
 }
 
@@ -53,18 +280,36 @@ impl Default for Allocate {
     }
 }
 
-impl<'a> Iterator for Allocate {
+impl Debug for Allocate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Allocate")
+            .field("block", &self.block)
+            .finish()
+    }
+}
+
+impl Iterator for Allocate {
     type Item = Entity;
 
     #[inline(always)]
     fn next(&mut self) -> Option<Self::Item> {
-        if self.count == 0 {
-            self.base = NEXT_ENTITY.fetch_add(BLOCK_SIZE, Ordering::Relaxed);
-            self.count = BLOCK_SIZE;
+        if self.block.is_empty() {
+            self.allocator.reserve(BLOCK_SIZE, &mut self.block);
+
+            // entities are popped off of the end of the block
+            self.block.reverse();
         }
 
-        self.count -= 1;
-        Some(Entity(self.base + self.count))
+        self.block.pop()
+    }
+}
+
+impl Drop for Allocate {
+    fn drop(&mut self) {
+        if !self.block.is_empty() {
+            self.block.reverse();
+            self.allocator.unreserve(&self.block);
+        }
     }
 }
 
@@ -93,18 +338,19 @@ impl EntityLocation {
 pub type EntityHasher = BuildHasherDefault<U64Hasher>;
 
 /// A map of entity IDs to their storage locations.
+///
+/// Locations are stored in a dense array indexed by [entity index](struct.Entity.html#method.index),
+/// alongside the generation of the entity which currently occupies that index.
 #[derive(Clone, Default)]
 pub struct LocationMap {
     len: usize,
-    blocks: HashMap<u64, Box<[Option<EntityLocation>; BLOCK_SIZE_USIZE]>, EntityHasher>,
+    slots: Vec<Option<(u32, EntityLocation)>>,
 }
 
 impl Debug for LocationMap {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let entries = self.blocks.iter().flat_map(|(base, locs)| {
-            locs.iter()
-                .enumerate()
-                .filter_map(move |(i, loc)| loc.map(|loc| (Entity(*base + i as u64), loc)))
+        let entries = self.slots.iter().enumerate().filter_map(|(i, slot)| {
+            slot.map(|(generation, loc)| (Entity::new(i as u32, generation), loc))
         });
         f.debug_map().entries(entries).finish()
     }
@@ -127,32 +373,26 @@ impl LocationMap {
     }
 
     /// Inserts an collection of adjacent entities into the location map.
+    ///
+    /// Returns the locations of any entities which were replaced, either because they had the same
+    /// ID or because they occupied the same index with a different generation.
     pub fn insert(
         &mut self,
         ids: &[Entity],
         arch: ArchetypeIndex,
         ComponentIndex(base): ComponentIndex,
     ) -> Vec<EntityLocation> {
-        let mut current_block = u64::MAX;
-        let mut block_vec = None;
         let mut removed = Vec::new();
         for (i, entity) in ids.iter().enumerate() {
-            let block = entity.0 / BLOCK_SIZE;
-            if current_block != block {
-                block_vec = Some(
-                    self.blocks
-                        .entry(block)
-                        .or_insert_with(|| Box::new([None; BLOCK_SIZE_USIZE])),
-                );
-                current_block = block;
+            let index = entity.index() as usize;
+            if index >= self.slots.len() {
+                self.slots.resize(index + 1, None);
             }
 
-            if let Some(ref mut vec) = block_vec {
-                let idx = (entity.0 - block * BLOCK_SIZE) as usize;
-                match vec[idx].replace(EntityLocation(arch, ComponentIndex(base + i))) {
-                    Some(previous) => removed.push(previous),
-                    None => self.len += 1,
-                }
+            let location = EntityLocation(arch, ComponentIndex(base + i));
+            match self.slots[index].replace((entity.generation(), location)) {
+                Some((_, previous)) => removed.push(previous),
+                None => self.len += 1,
             }
         }
         removed
@@ -163,30 +403,138 @@ impl LocationMap {
         self.insert(&[entity], location.archetype(), location.component());
     }
 
+    /// Returns the living entity which occupies the given index, if any.
+    pub(crate) fn occupant(&self, index: u32) -> Option<Entity> {
+        self.slots
+            .get(index as usize)
+            .copied()
+            .flatten()
+            .map(|(generation, _)| Entity::new(index, generation))
+    }
+
     /// Returns the location of an entity.
     pub fn get(&self, entity: Entity) -> Option<EntityLocation> {
-        let block = entity.0 / BLOCK_SIZE;
-        let idx = (entity.0 - block * BLOCK_SIZE) as usize;
-        if let Some(&result) = self.blocks.get(&block).and_then(|v| v.get(idx)) {
-            result
-        } else {
-            None
+        match self.slots.get(entity.index() as usize) {
+            Some(Some((generation, location))) if *generation == entity.generation() => {
+                Some(*location)
+            }
+            _ => None,
         }
     }
 
     /// Removes an entity from the location map.
     pub fn remove(&mut self, entity: Entity) -> Option<EntityLocation> {
-        let block = entity.0 / BLOCK_SIZE;
-        let idx = (entity.0 - block * BLOCK_SIZE) as usize;
-        if let Some(loc) = self.blocks.get_mut(&block).and_then(|v| v.get_mut(idx)) {
-            let original = *loc;
-            if original.is_some() {
+        let slot = self.slots.get_mut(entity.index() as usize)?;
+        match slot {
+            Some((generation, location)) if *generation == entity.generation() => {
+                let location = *location;
+                *slot = None;
                 self.len -= 1;
+                Some(location)
             }
-            *loc = None;
-            original
-        } else {
-            None
+            _ => None,
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn allocate_unique() {
+        let mut allocator = Allocate::new();
+        let mut entities = (&mut allocator).take(100).collect::<Vec<_>>();
+        entities.sort_by_key(|e| e.0);
+        entities.dedup();
+        assert_eq!(entities.len(), 100);
+    }
+
+    #[test]
+    fn allocate_deterministic() {
+        let a = Arc::new(GenerationalAllocator::new());
+        let b = Arc::new(GenerationalAllocator::new());
+
+        let first = Allocate::with_allocator(a.clone())
+            .take(3)
+            .collect::<Vec<_>>();
+        let second = Allocate::with_allocator(b.clone())
+            .take(3)
+            .collect::<Vec<_>>();
+        assert_eq!(first, second);
+        assert_eq!(first[0], Entity::new(0, 0));
+
+        // unused IDs are returned to the allocator, and released IDs are reused
+        a.release(first[1]);
+        let mut allocate = Allocate::with_allocator(a);
+        assert_eq!(allocate.next(), Some(Entity::new(1, 1)));
+        assert_eq!(allocate.next(), Some(Entity::new(3, 0)));
+    }
+
+    #[test]
+    fn location_map_stale_generation() {
+        let mut map = LocationMap::default();
+        let entity = Entity::new(3, 0);
+        let stale = Entity::new(3, 1);
+        map.set(
+            entity,
+            EntityLocation::new(ArchetypeIndex(0), ComponentIndex(0)),
+        );
+
+        assert!(map.contains(entity));
+        assert!(!map.contains(stale));
+        assert!(map.remove(stale).is_none());
+        assert_eq!(map.len(), 1);
+
+        assert!(map.remove(entity).is_some());
+        assert!(map.is_empty());
+        assert!(!map.contains(entity));
+    }
+
+    #[test]
+    fn location_map_replace() {
+        let mut map = LocationMap::default();
+        let entity = Entity::new(5, 0);
+        map.set(
+            entity,
+            EntityLocation::new(ArchetypeIndex(0), ComponentIndex(0)),
+        );
+
+        let replaced = map.insert(&[entity], ArchetypeIndex(1), ComponentIndex(0));
+        assert_eq!(replaced.len(), 1);
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get(entity).unwrap().archetype(), ArchetypeIndex(1));
+    }
+
+    #[test]
+    fn location_map_live_generation() {
+        let mut map = LocationMap::default();
+        let entity = Entity::new(5, 0);
+        let reused = Entity::new(5, 1);
+        map.set(
+            entity,
+            EntityLocation::new(ArchetypeIndex(0), ComponentIndex(0)),
+        );
+
+        let replaced = map.insert(&[reused], ArchetypeIndex(1), ComponentIndex(0));
+        assert_eq!(replaced.len(), 1);
+        assert_eq!(map.len(), 1);
+        assert!(!map.contains(entity));
+        assert_eq!(map.occupant(5), Some(reused));
+    }
+
+    #[test]
+    fn release_idempotent() {
+        let allocator = Arc::new(GenerationalAllocator::new());
+        let entity = Allocate::with_allocator(allocator.clone()).next().unwrap();
+        allocator.release(entity);
+        allocator.release(entity);
+
+        let mut ids = Allocate::with_allocator(allocator)
+            .take(64)
+            .collect::<Vec<_>>();
+        ids.sort_by_key(|e| e.0);
+        ids.dedup_by_key(|e| e.index());
+        assert_eq!(ids.len(), 64);
+    }
+}
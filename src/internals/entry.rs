@@ -1,5 +1,7 @@
 use crate::internals::{
-    entity::EntityLocation,
+    entity::{Entity, EntityLocation},
+    event::Event,
+    hooks::HookKind,
     insert::ArchetypeSource,
     query::filter::{FilterResult, LayoutFilter},
     storage::{
@@ -8,7 +10,7 @@ use crate::internals::{
         ComponentStorage, Components, UnknownComponentStorage,
     },
     subworld::ComponentAccess,
-    world::World,
+    world::{EntityStore, World},
 };
 use std::sync::Arc;
 use thiserror::Error;
@@ -70,6 +72,10 @@ impl<'a> EntryRef<'a> {
         self.location
     }
 
+    fn entity(&self) -> Entity {
+        self.archetypes[self.location.archetype()].entities()[self.location.component().0]
+    }
+
     /// Returns a reference to one of the entity's components.
     pub fn into_component<T: Component>(self) -> Result<&'a T, ComponentError> {
         let component_type = ComponentTypeId::of::<T>();
@@ -80,6 +86,15 @@ impl<'a> EntryRef<'a> {
             });
         }
 
+        if let Some(storage) = self.components.sparse().get_downcast::<T>() {
+            return storage
+                .get(self.entity())
+                .ok_or_else(|| ComponentError::NotFound {
+                    component_type,
+                    component_name: std::any::type_name::<T>(),
+                });
+        }
+
         let component = self.location.component();
         let archetype = self.location.archetype();
         self.components
@@ -108,12 +123,21 @@ impl<'a> EntryRef<'a> {
             });
         }
 
+        if let Some(storage) = self.components.sparse().get_downcast::<T>() {
+            return storage
+                .get_unchecked(self.entity())
+                .ok_or_else(|| ComponentError::NotFound {
+                    component_type,
+                    component_name: std::any::type_name::<T>(),
+                });
+        }
+
         let component = self.location.component();
         let archetype = self.location.archetype();
         self.components
             .get_downcast::<T>()
             .and_then(move |storage| storage.get_mut(archetype))
-            .and_then(move |slice| slice.into_slice().get_mut(component.0))
+            .and_then(move |slice| slice.into_component(component))
             .ok_or_else(|| ComponentError::NotFound {
                 component_type,
                 component_name: std::any::type_name::<T>(),
@@ -130,6 +154,15 @@ impl<'a> EntryRef<'a> {
             });
         }
 
+        if let Some(storage) = self.components.sparse().get_downcast::<T>() {
+            return storage
+                .get(self.entity())
+                .ok_or_else(|| ComponentError::NotFound {
+                    component_type,
+                    component_name: std::any::type_name::<T>(),
+                });
+        }
+
         let component = self.location.component();
         let archetype = self.location.archetype();
         self.components
@@ -156,12 +189,21 @@ impl<'a> EntryRef<'a> {
             });
         }
 
+        if let Some(storage) = self.components.sparse().get_downcast::<T>() {
+            return storage
+                .get_unchecked(self.entity())
+                .ok_or_else(|| ComponentError::NotFound {
+                    component_type,
+                    component_name: std::any::type_name::<T>(),
+                });
+        }
+
         let component = self.location.component();
         let archetype = self.location.archetype();
         self.components
             .get_downcast::<T>()
             .and_then(move |storage| storage.get_mut(archetype))
-            .and_then(move |slice| slice.into_slice().get_mut(component.0))
+            .and_then(move |slice| slice.into_component(component))
             .ok_or_else(|| ComponentError::NotFound {
                 component_type,
                 component_name: std::any::type_name::<T>(),
@@ -202,6 +244,10 @@ impl<'a> EntryMut<'a> {
         self.location
     }
 
+    fn entity(&self) -> Entity {
+        self.archetypes[self.location.archetype()].entities()[self.location.component().0]
+    }
+
     /// Returns a reference to one of the entity's components.
     pub fn into_component<T: Component>(self) -> Result<&'a T, ComponentError> {
         let component_type = ComponentTypeId::of::<T>();
@@ -212,6 +258,15 @@ impl<'a> EntryMut<'a> {
             });
         }
 
+        if let Some(storage) = self.components.sparse().get_downcast::<T>() {
+            return storage
+                .get(self.entity())
+                .ok_or_else(|| ComponentError::NotFound {
+                    component_type,
+                    component_name: std::any::type_name::<T>(),
+                });
+        }
+
         let component = self.location.component();
         let archetype = self.location.archetype();
         self.components
@@ -240,12 +295,21 @@ impl<'a> EntryMut<'a> {
             });
         }
 
+        if let Some(storage) = self.components.sparse().get_downcast::<T>() {
+            return storage
+                .get_unchecked(self.entity())
+                .ok_or_else(|| ComponentError::NotFound {
+                    component_type,
+                    component_name: std::any::type_name::<T>(),
+                });
+        }
+
         let component = self.location.component();
         let archetype = self.location.archetype();
         self.components
             .get_downcast::<T>()
             .and_then(move |storage| storage.get_mut(archetype))
-            .and_then(move |slice| slice.into_slice().get_mut(component.0))
+            .and_then(move |slice| slice.into_component(component))
             .ok_or_else(|| ComponentError::NotFound {
                 component_type,
                 component_name: std::any::type_name::<T>(),
@@ -272,6 +336,15 @@ impl<'a> EntryMut<'a> {
             });
         }
 
+        if let Some(storage) = self.components.sparse().get_downcast::<T>() {
+            return storage
+                .get(self.entity())
+                .ok_or_else(|| ComponentError::NotFound {
+                    component_type,
+                    component_name: std::any::type_name::<T>(),
+                });
+        }
+
         let component = self.location.component();
         let archetype = self.location.archetype();
         self.components
@@ -300,12 +373,21 @@ impl<'a> EntryMut<'a> {
             });
         }
 
+        if let Some(storage) = self.components.sparse().get_downcast::<T>() {
+            return storage
+                .get_unchecked(self.entity())
+                .ok_or_else(|| ComponentError::NotFound {
+                    component_type,
+                    component_name: std::any::type_name::<T>(),
+                });
+        }
+
         let component = self.location.component();
         let archetype = self.location.archetype();
         self.components
             .get_downcast::<T>()
             .and_then(move |storage| storage.get_mut(archetype))
-            .and_then(move |slice| slice.into_slice().get_mut(component.0))
+            .and_then(move |slice| slice.into_component(component))
             .ok_or_else(|| ComponentError::NotFound {
                 component_type,
                 component_name: std::any::type_name::<T>(),
@@ -341,8 +423,21 @@ impl<'a> Entry<'a> {
         self.location
     }
 
+    fn entity(&self) -> Entity {
+        self.archetype().entities()[self.location.component().0]
+    }
+
     /// Returns a reference to one of the entity's components.
     pub fn into_component<T: Component>(self) -> Result<&'a T, ComponentError> {
+        if let Some(storage) = self.world.sparse_components().get_downcast::<T>() {
+            return storage
+                .get(self.entity())
+                .ok_or_else(|| ComponentError::NotFound {
+                    component_type: ComponentTypeId::of::<T>(),
+                    component_name: std::any::type_name::<T>(),
+                });
+        }
+
         let component = self.location.component();
         let archetype = self.location.archetype();
         self.world
@@ -370,13 +465,22 @@ impl<'a> Entry<'a> {
     pub unsafe fn into_component_unchecked<T: Component>(
         self,
     ) -> Result<&'a mut T, ComponentError> {
+        if let Some(storage) = self.world.sparse_components().get_downcast::<T>() {
+            return storage
+                .get_unchecked(self.entity())
+                .ok_or_else(|| ComponentError::NotFound {
+                    component_type: ComponentTypeId::of::<T>(),
+                    component_name: std::any::type_name::<T>(),
+                });
+        }
+
         let component = self.location.component();
         let archetype = self.location.archetype();
         self.world
             .components()
             .get_downcast::<T>()
             .and_then(move |storage| storage.get_mut(archetype))
-            .and_then(move |slice| slice.into_slice().get_mut(component.0))
+            .and_then(move |slice| slice.into_component(component))
             .ok_or_else(|| ComponentError::NotFound {
                 component_type: ComponentTypeId::of::<T>(),
                 component_name: std::any::type_name::<T>(),
@@ -385,6 +489,15 @@ impl<'a> Entry<'a> {
 
     /// Returns a reference to one of the entity's components.
     pub fn get_component<T: Component>(&self) -> Result<&T, ComponentError> {
+        if let Some(storage) = self.world.sparse_components().get_downcast::<T>() {
+            return storage
+                .get(self.entity())
+                .ok_or_else(|| ComponentError::NotFound {
+                    component_type: ComponentTypeId::of::<T>(),
+                    component_name: std::any::type_name::<T>(),
+                });
+        }
+
         let component = self.location.component();
         let archetype = self.location.archetype();
         self.world
@@ -410,13 +523,22 @@ impl<'a> Entry<'a> {
     /// This function bypasses static borrow checking. The caller must ensure that the component reference
     /// will not be mutably aliased.
     pub unsafe fn get_component_unchecked<T: Component>(&self) -> Result<&mut T, ComponentError> {
+        if let Some(storage) = self.world.sparse_components().get_downcast::<T>() {
+            return storage
+                .get_unchecked(self.entity())
+                .ok_or_else(|| ComponentError::NotFound {
+                    component_type: ComponentTypeId::of::<T>(),
+                    component_name: std::any::type_name::<T>(),
+                });
+        }
+
         let component = self.location.component();
         let archetype = self.location.archetype();
         self.world
             .components()
             .get_downcast::<T>()
             .and_then(move |storage| storage.get_mut(archetype))
-            .and_then(move |slice| slice.into_slice().get_mut(component.0))
+            .and_then(move |slice| slice.into_component(component))
             .ok_or_else(|| ComponentError::NotFound {
                 component_type: ComponentTypeId::of::<T>(),
                 component_name: std::any::type_name::<T>(),
@@ -425,9 +547,33 @@ impl<'a> Entry<'a> {
 
     /// Adds a new component to the entity.
     /// If the component already exists, its value will be replaced.
+    ///
+    /// If `T` has been registered for [sparse storage](struct.World.html#method.register_sparse),
+    /// the entity remains in its current archetype.
     pub fn add_component<T: Component>(&mut self, component: T) {
-        if let Ok(comp) = self.get_component_mut::<T>() {
-            *comp = component;
+        let entity = self.entity();
+        let type_id = ComponentTypeId::of::<T>();
+        if let Some(storage) = self.world.sparse_components().get_downcast::<T>() {
+            if storage.contains(entity) {
+                self.world
+                    .run_hooks(HookKind::Remove, type_id, self.location);
+                self.world.sparse_components_mut().insert(entity, component);
+            } else {
+                self.world.sparse_components_mut().insert(entity, component);
+                self.world.notify(
+                    self.location.archetype(),
+                    Event::ComponentAdded(entity, type_id),
+                );
+            }
+            self.world.run_hooks(HookKind::Add, type_id, self.location);
+            return;
+        }
+
+        if self.archetype().layout().has_component::<T>() {
+            self.world
+                .run_hooks(HookKind::Remove, type_id, self.location);
+            *self.get_component_mut::<T>().unwrap() = component;
+            self.world.run_hooks(HookKind::Add, type_id, self.location);
             return;
         }
 
@@ -436,7 +582,7 @@ impl<'a> Entry<'a> {
                 base: self.world.archetypes()[self.location.archetype()]
                     .layout()
                     .clone(),
-                add: &[ComponentTypeId::of::<T>()],
+                add: &[type_id],
                 add_constructors: &[|| Box::new(T::Storage::default())],
                 remove: &[],
             };
@@ -456,12 +602,36 @@ impl<'a> Entry<'a> {
             std::mem::forget(component);
             self.location = EntityLocation::new(target_arch, idx);
         };
+        self.world.run_hooks(HookKind::Add, type_id, self.location);
     }
 
     /// Removes a component from the entity.
     /// Does nothing if the entity does not have the component.
+    ///
+    /// Removing a [ChildOf](../hierarchy/struct.ChildOf.html) or
+    /// [Children](../hierarchy/struct.Children.html) component also updates the other side of the
+    /// relationship, as [World::remove_parent](struct.World.html#method.remove_parent) does.
     pub fn remove_component<T: Component>(&mut self) {
-        if !self.archetype().layout().has_component::<T>() {
+        let type_id = ComponentTypeId::of::<T>();
+        let entity = self.entity();
+        match self.world.relation(entity, type_id) {
+            Some(relation) => {
+                self.remove_component_by_id(type_id);
+                // updating the other side of the relationship may move this entity
+                self.world.unlink(entity, relation);
+                self.location = self.world.entry_ref(entity).unwrap().location();
+            }
+            None => self.remove_component_by_id(type_id),
+        }
+    }
+
+    fn remove_component_by_id(&mut self, type_id: ComponentTypeId) {
+        if self.world.sparse_components().contains_type(type_id) {
+            self.world.remove_sparse_component(self.location, type_id);
+            return;
+        }
+
+        if !self.archetype().layout().has_component_by_id(type_id) {
             return;
         }
 
@@ -472,10 +642,11 @@ impl<'a> Entry<'a> {
                     .clone(),
                 add: &[],
                 add_constructors: &[],
-                remove: &[ComponentTypeId::of::<T>()],
+                remove: &[type_id],
             };
             self.world.get_archetype_for_components(&mut source)
         };
+        let entity = self.entity();
         unsafe {
             let idx = self.world.transfer_archetype(
                 self.location.archetype(),
@@ -484,15 +655,17 @@ impl<'a> Entry<'a> {
             );
             self.location = EntityLocation::new(target_arch, idx);
         };
+        self.world.record_removal(type_id, entity);
     }
 }
 
+/// The layout of an existing archetype with some component types added or removed.
 #[derive(Clone)]
-struct DynamicArchetype<'a> {
-    base: Arc<EntityLayout>,
-    add: &'a [ComponentTypeId],
-    add_constructors: &'a [fn() -> Box<dyn UnknownComponentStorage>],
-    remove: &'a [ComponentTypeId],
+pub(crate) struct DynamicArchetype<'a> {
+    pub(crate) base: Arc<EntityLayout>,
+    pub(crate) add: &'a [ComponentTypeId],
+    pub(crate) add_constructors: &'a [fn() -> Box<dyn UnknownComponentStorage>],
+    pub(crate) remove: &'a [ComponentTypeId],
 }
 
 impl<'a> LayoutFilter for DynamicArchetype<'a> {
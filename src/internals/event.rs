@@ -1,14 +1,26 @@
 use super::entity::Entity;
+use super::hash::ComponentTypeIdHasher;
 use super::query::filter::LayoutFilter;
 use super::storage::{
     archetype::{Archetype, ArchetypeIndex},
-    component::ComponentTypeId,
+    component::{Component, ComponentTypeId},
+    packed::PackedStorage,
+    sparse::{SparseStorage, UnknownSparseStorage},
+    ComponentIndex, ComponentStorage, UnknownComponentStorage,
 };
 use std::iter::Iterator;
-use std::{fmt::Debug, sync::Arc};
+use std::{any::Any, collections::HashMap, fmt::Debug, hash::BuildHasherDefault, sync::Arc};
 
 /// Events emitted by a world to subscribers. See `World.subscribe(Sender, EntityFilter)`.
+///
+/// Component events are sent to the subscribers of the archetype which the entity belonged to
+/// when the change happened; `ComponentAdded` to those of the archetype the entity moved into, and
+/// `ComponentRemoved` to those of the archetype it moved out of.
+///
+/// New kinds of event may be added in future releases, so matches on this enum must include a
+/// wildcard arm.
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub enum Event {
     /// A new archetype has been created.
     ArchetypeCreated(ArchetypeIndex),
@@ -16,6 +28,160 @@ pub enum Event {
     EntityInserted(Entity, ArchetypeIndex),
     /// An entity has been removed from an archetype.
     EntityRemoved(Entity, ArchetypeIndex),
+    /// A component has been added to an existing entity. Entities inserted into the world with
+    /// their initial components are reported with `EntityInserted` only.
+    ComponentAdded(Entity, ComponentTypeId),
+    /// A component has been removed from an entity, either individually or because the entity
+    /// was removed from the world.
+    ///
+    /// The removed component is attached if its type was opted in with
+    /// [World::clone_removed](struct.World.html#method.clone_removed) or
+    /// [World::move_removed](struct.World.html#method.move_removed).
+    ComponentRemoved(Entity, ComponentTypeId, Option<RemovedComponent>),
+}
+
+/// A component which has been removed from an entity, attached to an
+/// [Event::ComponentRemoved](enum.Event.html#variant.ComponentRemoved).
+///
+/// The component is shared between all subscribers which receive the event, and is dropped
+/// once the last copy of the event is dropped.
+#[derive(Clone)]
+pub struct RemovedComponent(Arc<dyn Any + Send + Sync>);
+
+impl RemovedComponent {
+    fn value<T: Component>(component: T) -> Self {
+        Self(Arc::new(component))
+    }
+
+    /// Returns a reference to the removed component, or `None` if it is not of type `T`.
+    pub fn downcast_ref<T: Component>(&self) -> Option<&T> {
+        self.0.downcast_ref()
+    }
+}
+
+impl Debug for RemovedComponent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RemovedComponent").finish()
+    }
+}
+
+/// Removes a component from storage and attaches it to removal events, for component types
+/// which have been opted in.
+#[derive(Copy, Clone)]
+pub(crate) struct RemovedCapture {
+    packed:
+        fn(&mut dyn UnknownComponentStorage, ArchetypeIndex, ComponentIndex) -> RemovedComponent,
+    sparse: fn(&mut dyn UnknownSparseStorage, Entity) -> Option<RemovedComponent>,
+}
+
+impl RemovedCapture {
+    pub(crate) fn cloned<T: Component + Clone>() -> Self {
+        fn packed<T: Component + Clone>(
+            storage: &mut dyn UnknownComponentStorage,
+            archetype: ArchetypeIndex,
+            index: ComponentIndex,
+        ) -> RemovedComponent {
+            let typed = storage.downcast_ref::<T::Storage>().unwrap();
+            let component = typed.get(archetype).unwrap()[index].clone();
+            storage.swap_remove(archetype, index);
+            RemovedComponent::value(component)
+        }
+
+        fn sparse<T: Component + Clone>(
+            storage: &mut dyn UnknownSparseStorage,
+            entity: Entity,
+        ) -> Option<RemovedComponent> {
+            let component = storage
+                .downcast_mut::<SparseStorage<T>>()
+                .unwrap()
+                .remove(entity)?;
+            let cloned = component.clone();
+            drop(component);
+            Some(RemovedComponent::value(cloned))
+        }
+
+        Self {
+            packed: packed::<T>,
+            sparse: sparse::<T>,
+        }
+    }
+
+    pub(crate) fn moved<T: Component>() -> Self {
+        fn packed<T: Component>(
+            storage: &mut dyn UnknownComponentStorage,
+            archetype: ArchetypeIndex,
+            index: ComponentIndex,
+        ) -> RemovedComponent {
+            let component = storage
+                .downcast_mut::<PackedStorage<T>>()
+                .expect("component storages are of different component types")
+                .take(archetype, index);
+            RemovedComponent::value(component)
+        }
+
+        fn sparse<T: Component>(
+            storage: &mut dyn UnknownSparseStorage,
+            entity: Entity,
+        ) -> Option<RemovedComponent> {
+            storage
+                .downcast_mut::<SparseStorage<T>>()
+                .unwrap()
+                .remove(entity)
+                .map(RemovedComponent::value)
+        }
+
+        Self {
+            packed: packed::<T>,
+            sparse: sparse::<T>,
+        }
+    }
+}
+
+/// The removed component captures registered with a world, by component type.
+#[derive(Default)]
+pub(crate) struct RemovedCaptures {
+    captures: HashMap<ComponentTypeId, RemovedCapture, BuildHasherDefault<ComponentTypeIdHasher>>,
+}
+
+impl RemovedCaptures {
+    pub(crate) fn insert(&mut self, type_id: ComponentTypeId, capture: RemovedCapture) {
+        self.captures.insert(type_id, capture);
+    }
+
+    /// Removes a component from an archetype slice, returning the component if its type is
+    /// captured and `capture` is `true`.
+    pub(crate) fn remove_packed(
+        &self,
+        capture: bool,
+        type_id: ComponentTypeId,
+        storage: &mut dyn UnknownComponentStorage,
+        archetype: ArchetypeIndex,
+        index: ComponentIndex,
+    ) -> Option<RemovedComponent> {
+        match self.captures.get(&type_id) {
+            Some(captured) if capture => Some((captured.packed)(storage, archetype, index)),
+            _ => {
+                storage.swap_remove(archetype, index);
+                None
+            }
+        }
+    }
+
+    /// Removes an entity's sparse component. Returns `None` if the entity did not have the
+    /// component, otherwise the component if its type is captured and `capture` is `true`.
+    pub(crate) fn remove_sparse(
+        &self,
+        capture: bool,
+        type_id: ComponentTypeId,
+        storage: &mut dyn UnknownSparseStorage,
+        entity: Entity,
+    ) -> Option<Option<RemovedComponent>> {
+        match self.captures.get(&type_id) {
+            Some(captured) if capture => (captured.sparse)(storage, entity).map(Some),
+            _ if storage.remove_unknown(entity) => Some(None),
+            _ => None,
+        }
+    }
 }
 
 /// Describes a type which can send entity events.
@@ -70,6 +236,10 @@ pub(crate) struct Subscribers {
 }
 
 impl Subscribers {
+    pub fn is_empty(&self) -> bool {
+        self.subscribers.is_empty()
+    }
+
     pub fn push(&mut self, subscriber: Subscriber) {
         self.subscribers.push(subscriber);
     }
@@ -94,6 +264,12 @@ impl Subscribers {
     }
 }
 
+impl Debug for RemovedCaptures {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.captures.keys()).finish()
+    }
+}
+
 impl Debug for Subscribers {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Subscribers")
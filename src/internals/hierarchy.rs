@@ -0,0 +1,599 @@
+use super::{
+    entity::Entity,
+    query::{
+        filter::EntityFilter,
+        view::{read::Read, IntoView, ReadOnlyFetch, View},
+        Query,
+    },
+    storage::{component::ComponentTypeId, ComponentStorage},
+    world::{EntityAccessError, EntityStore, World},
+};
+use thiserror::Error;
+
+/// A component which marks an entity as a child of another entity.
+///
+/// Relationships are created with [World::set_parent](../world/struct.World.html#method.set_parent),
+/// which keeps the parent's [Children](struct.Children.html) component up to date.
+///
+/// The parent ID is remapped when the component is cloned via
+/// [World::clone_from](../world/struct.World.html#method.clone_from), and it is serialized as an
+/// entity reference.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChildOf(pub(crate) Entity);
+
+impl ChildOf {
+    /// Returns the entity's parent.
+    pub fn parent(&self) -> Entity {
+        self.0
+    }
+}
+
+/// A component which lists the children of an entity.
+///
+/// This component is maintained by [World::set_parent](../world/struct.World.html#method.set_parent)
+/// and [World::remove_parent](../world/struct.World.html#method.remove_parent), and is removed
+/// once the entity has no children left.
+#[derive(Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct Children(pub(crate) Vec<Entity>);
+
+impl Children {
+    /// Iterates through the entity's children, in the order in which they were attached.
+    pub fn iter(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.0.iter().copied()
+    }
+
+    /// Returns the number of children.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if the entity has no children.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns `true` if the given entity is a child of this entity.
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.0.contains(&entity)
+    }
+}
+
+impl Clone for Children {
+    fn clone(&self) -> Self {
+        // clone each ID individually, so that IDs are remapped when cloning between worlds
+        #[allow(clippy::clone_on_copy, clippy::map_clone)]
+        Self(self.0.iter().map(|entity| entity.clone()).collect())
+    }
+}
+
+/// The other side of a relationship which is broken by removing a relationship component.
+pub(crate) enum Relation {
+    Parent(Entity),
+    Children(Vec<Entity>),
+}
+
+/// Error type representing a failure to modify an entity relationship.
+#[derive(Error, Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum HierarchyError {
+    /// Attempted to relate an entity which does not exist.
+    #[error("the entity does not exist")]
+    EntityNotFound,
+    /// Attempted to make an entity a descendant of itself.
+    #[error("an entity cannot be a descendant of itself")]
+    Cycle,
+}
+
+impl World {
+    /// Makes `child` a child of `parent`, detaching it from its previous parent, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use legion::*;
+    /// # use legion::hierarchy::Children;
+    /// let mut world = World::default();
+    /// let parent = world.push((1usize,));
+    /// let child = world.push((2usize,));
+    /// world.set_parent(child, parent).unwrap();
+    ///
+    /// let entry = world.entry(parent).unwrap();
+    /// let children = entry.get_component::<Children>().unwrap();
+    /// assert_eq!(children.iter().collect::<Vec<_>>(), vec![child]);
+    ///
+    /// // removing the parent also removes its children
+    /// world.remove(parent);
+    /// assert!(!world.contains(child));
+    /// ```
+    pub fn set_parent(&mut self, child: Entity, parent: Entity) -> Result<(), HierarchyError> {
+        if !self.contains(child) || !self.contains(parent) {
+            return Err(HierarchyError::EntityNotFound);
+        }
+
+        // walk up from the new parent to ensure we are not creating a cycle
+        let mut ancestor = Some(parent);
+        while let Some(entity) = ancestor {
+            if entity == child {
+                return Err(HierarchyError::Cycle);
+            }
+            ancestor = self.parent(entity);
+        }
+
+        if self.parent(child) == Some(parent) {
+            return Ok(());
+        }
+
+        self.remove_parent(child);
+
+        let mut entry = self.entry(parent).unwrap();
+        if let Ok(children) = entry.get_component_mut::<Children>() {
+            children.0.push(child);
+        } else {
+            entry.add_component(Children(vec![child]));
+        }
+
+        self.entry(child).unwrap().add_component(ChildOf(parent));
+        Ok(())
+    }
+
+    /// Detaches `child` from its parent. Returns the previous parent, if any.
+    ///
+    /// Removing the `ChildOf` component directly has the same effect.
+    pub fn remove_parent(&mut self, child: Entity) -> Option<Entity> {
+        let mut entry = self.entry(child)?;
+        let parent = entry.get_component::<ChildOf>().ok()?.parent();
+        // the entry detaches the child from its parent's `Children`
+        entry.remove_component::<ChildOf>();
+        Some(parent)
+    }
+
+    /// Returns the parent of an entity, if any.
+    pub fn parent(&self, entity: Entity) -> Option<Entity> {
+        self.entry_ref(entity)
+            .ok()?
+            .get_component::<ChildOf>()
+            .ok()
+            .map(|child_of| child_of.parent())
+    }
+
+    /// Removes `child` from the `Children` of `parent`.
+    fn detach_child(&mut self, parent: Entity, child: Entity) {
+        if let Some(mut entry) = self.entry(parent) {
+            let empty = if let Ok(children) = entry.get_component_mut::<Children>() {
+                children.0.retain(|entity| *entity != child);
+                children.0.is_empty()
+            } else {
+                false
+            };
+
+            if empty {
+                entry.remove_component::<Children>();
+            }
+        }
+    }
+
+    /// Returns the relationship which will be broken by removing a component of the given type
+    /// from an entity, if the component is a relationship component.
+    pub(crate) fn relation(&self, entity: Entity, type_id: ComponentTypeId) -> Option<Relation> {
+        if type_id == ComponentTypeId::of::<ChildOf>() {
+            self.parent(entity).map(Relation::Parent)
+        } else if type_id == ComponentTypeId::of::<Children>() {
+            let entry = self.entry_ref(entity).ok()?;
+            let children = entry.get_component::<Children>().ok()?;
+            Some(Relation::Children(children.0.clone()))
+        } else {
+            None
+        }
+    }
+
+    /// Updates the other side of a relationship after its component was removed from `entity`.
+    pub(crate) fn unlink(&mut self, entity: Entity, relation: Relation) {
+        match relation {
+            Relation::Parent(parent) => self.detach_child(parent, entity),
+            Relation::Children(children) => {
+                for child in children {
+                    if self.parent(child) == Some(entity) {
+                        self.entry(child).unwrap().remove_component::<ChildOf>();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Detaches an entity which is about to be removed from the world from its parent.
+    /// Returns the entity's children, which must also be removed.
+    pub(crate) fn take_relations(&mut self, entity: Entity) -> Vec<Entity> {
+        let (parent, children) = match self.entry(entity) {
+            Some(entry) => {
+                let layout = entry.archetype().layout();
+                if !layout.has_component::<ChildOf>() && !layout.has_component::<Children>() {
+                    return Vec::new();
+                }
+                (
+                    entry.get_component::<ChildOf>().ok().map(ChildOf::parent),
+                    entry
+                        .get_component::<Children>()
+                        .map(|children| children.0.clone())
+                        .unwrap_or_default(),
+                )
+            }
+            None => return Vec::new(),
+        };
+
+        if let Some(parent) = parent {
+            self.detach_child(parent, entity);
+        }
+
+        children
+    }
+}
+
+impl<V: IntoView, F: EntityFilter> Query<V, F> {
+    /// Iterates through all entities which match the query and have a parent, pairing
+    /// each with the components of its parent as matched by the `parents` query.
+    ///
+    /// The parent's components are `None` if the parent does not match `parents`.
+    /// The children and parents may be read from the same world or from two halves of a
+    /// [split](../world/struct.World.html#method.split) world.
+    ///
+    /// Returns [EntityAccessError::AccessDenied](../world/enum.EntityAccessError.html) if `world`
+    /// does not allow `ChildOf` to be read, such as a split world which excludes it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use legion::*;
+    /// # struct Local(f32);
+    /// let mut world = World::default();
+    /// let parent = world.push((Local(1.0),));
+    /// let child = world.push((Local(2.0),));
+    /// world.set_parent(child, parent).unwrap();
+    ///
+    /// let mut children = <&Local>::query();
+    /// let mut parents = <&Local>::query();
+    /// children
+    ///     .for_each_with_parent(&world, &mut parents, &world, |local, parent| {
+    ///         assert_eq!(local.0 + parent.unwrap().0, 3.0);
+    ///     })
+    ///     .unwrap();
+    /// ```
+    pub fn for_each_with_parent<'a, T, PV, PF, PT, Body>(
+        &mut self,
+        world: &'a T,
+        parents: &mut Query<PV, PF>,
+        parent_world: &'a PT,
+        f: Body,
+    ) -> Result<(), EntityAccessError>
+    where
+        T: EntityStore,
+        PT: EntityStore,
+        PV: IntoView,
+        PF: EntityFilter,
+        <V::View as View<'a>>::Fetch: ReadOnlyFetch,
+        <PV::View as View<'a>>::Fetch: ReadOnlyFetch,
+        Body: FnMut(<V::View as View<'a>>::Element, Option<<PV::View as View<'a>>::Element>),
+    {
+        // safety: the views are readonly - they cannot create mutable aliases
+        unsafe { self.for_each_with_parent_unchecked(world, parents, parent_world, f) }
+    }
+
+    /// Iterates through all entities which match the query and have a parent, pairing
+    /// each with the components of its parent as matched by the `parents` query.
+    ///
+    /// The parent's components are `None` if the parent does not match `parents`.
+    /// The parents must be read from a different world, such as the other half of a
+    /// [split](../world/struct.World.html#method.split) world.
+    ///
+    /// Returns [EntityAccessError::AccessDenied](../world/enum.EntityAccessError.html) if `world`
+    /// does not allow `ChildOf` to be read.
+    pub fn for_each_with_parent_mut<'a, T, PV, PF, PT, Body>(
+        &mut self,
+        world: &'a mut T,
+        parents: &mut Query<PV, PF>,
+        parent_world: &'a PT,
+        f: Body,
+    ) -> Result<(), EntityAccessError>
+    where
+        T: EntityStore,
+        PT: EntityStore,
+        PV: IntoView,
+        PF: EntityFilter,
+        <PV::View as View<'a>>::Fetch: ReadOnlyFetch,
+        Body: FnMut(<V::View as View<'a>>::Element, Option<<PV::View as View<'a>>::Element>),
+    {
+        // safety: we have exclusive access to the world, and the parent view is readonly
+        unsafe { self.for_each_with_parent_unchecked(world, parents, parent_world, f) }
+    }
+
+    /// Iterates through all entities which match the query and have a parent, pairing
+    /// each with the components of its parent as matched by the `parents` query.
+    ///
+    /// Returns [EntityAccessError::AccessDenied](../world/enum.EntityAccessError.html) if `world`
+    /// does not allow `ChildOf` to be read.
+    ///
+    /// # Safety
+    /// This function allows mutable access via a shared world reference. The caller is responsible for
+    /// ensuring that no component accesses may create mutable aliases.
+    pub unsafe fn for_each_with_parent_unchecked<'a, T, PV, PF, PT, Body>(
+        &mut self,
+        world: &'a T,
+        parents: &mut Query<PV, PF>,
+        parent_world: &'a PT,
+        mut f: Body,
+    ) -> Result<(), EntityAccessError>
+    where
+        T: EntityStore,
+        PT: EntityStore,
+        PV: IntoView,
+        PF: EntityFilter,
+        Body: FnMut(<V::View as View<'a>>::Element, Option<<PV::View as View<'a>>::Element>),
+    {
+        assert!(
+            !<V::View as View<'a>>::writes::<ChildOf>(),
+            "queries joined by parent may not write to ChildOf"
+        );
+
+        let relations = world.get_component_storage::<Read<ChildOf>>()?;
+        let relations = match relations.components().get_downcast::<ChildOf>() {
+            Some(relations) => relations,
+            None => return Ok(()),
+        };
+
+        self.for_each_chunk_unchecked(world, |chunk| {
+            if let Some(targets) = relations.get(chunk.archetype().index()) {
+                for (child_of, components) in chunk.into_iter_zip(targets.into_slice()) {
+                    let parent = parents.get_unchecked(parent_world, child_of.parent()).ok();
+                    f(components, parent);
+                }
+            }
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::internals::{
+        query::{
+            filter::filter_fns::any,
+            view::{read::Read, write::Write},
+            IntoQuery,
+        },
+        systems::command::CommandBuffer,
+        world::Duplicate,
+    };
+
+    fn children(world: &World, entity: Entity) -> Vec<Entity> {
+        world
+            .entry_ref(entity)
+            .unwrap()
+            .get_component::<Children>()
+            .map(|children| children.iter().collect())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn set_parent() {
+        let mut world = World::default();
+        let parent = world.push((1usize,));
+        let a = world.push((2usize,));
+        let b = world.push((3usize,));
+
+        world.set_parent(a, parent).unwrap();
+        world.set_parent(b, parent).unwrap();
+        world.set_parent(b, parent).unwrap();
+
+        assert_eq!(children(&world, parent), vec![a, b]);
+        assert_eq!(world.parent(a), Some(parent));
+        assert_eq!(world.parent(parent), None);
+
+        // reparenting moves the child
+        world.set_parent(b, a).unwrap();
+        assert_eq!(children(&world, parent), vec![a]);
+        assert_eq!(children(&world, a), vec![b]);
+
+        assert_eq!(world.remove_parent(a), Some(parent));
+        assert_eq!(world.remove_parent(a), None);
+        assert!(world
+            .entry_ref(parent)
+            .unwrap()
+            .get_component::<Children>()
+            .is_err());
+    }
+
+    #[test]
+    fn set_parent_cycle() {
+        let mut world = World::default();
+        let a = world.push((1usize,));
+        let b = world.push((2usize,));
+        let c = world.push((3usize,));
+
+        world.set_parent(b, a).unwrap();
+        world.set_parent(c, b).unwrap();
+
+        assert_eq!(world.set_parent(a, c), Err(HierarchyError::Cycle));
+        assert_eq!(world.set_parent(a, a), Err(HierarchyError::Cycle));
+        world.remove(c);
+        assert_eq!(world.set_parent(a, c), Err(HierarchyError::EntityNotFound));
+    }
+
+    #[test]
+    fn remove_cascades() {
+        let mut world = World::default();
+        let root = world.push((1usize,));
+        let a = world.push((2usize,));
+        let b = world.push((3usize,));
+        let c = world.push((4usize,));
+        let other = world.push((5usize,));
+
+        world.set_parent(a, root).unwrap();
+        world.set_parent(b, a).unwrap();
+        world.set_parent(c, root).unwrap();
+
+        // removing a child detaches it from its parent
+        world.remove(c);
+        assert_eq!(children(&world, root), vec![a]);
+
+        world.remove(root);
+        assert!(!world.contains(root));
+        assert!(!world.contains(a));
+        assert!(!world.contains(b));
+        assert!(world.contains(other));
+        assert_eq!(world.len(), 1);
+    }
+
+    #[test]
+    fn remove_components_directly() {
+        let mut world = World::default();
+        let parent = world.push((1usize,));
+        let a = world.push((2usize,));
+        let b = world.push((3usize,));
+        world.set_parent(a, parent).unwrap();
+        world.set_parent(b, parent).unwrap();
+
+        // removing `ChildOf` detaches the child from its parent
+        world.entry(a).unwrap().remove_component::<ChildOf>();
+        let entry = world.entry(parent).unwrap();
+        let children = entry.get_component::<Children>().unwrap();
+        assert_eq!(children.iter().collect::<Vec<_>>(), vec![b]);
+
+        // removing `Children` detaches all of the children
+        let mut entry = world.entry(parent).unwrap();
+        entry.remove_component::<Children>();
+        assert_eq!(entry.get_component::<usize>(), Ok(&1));
+        assert_eq!(world.parent(a), None);
+        assert_eq!(world.parent(b), None);
+        assert!(world.remove(parent));
+        assert!(world.contains(b));
+    }
+
+    #[test]
+    fn command_buffer() {
+        let mut world = World::default();
+        let parent = world.push((1usize,));
+
+        let mut cmd = CommandBuffer::new(&world);
+        let child = cmd.push((2usize,));
+        cmd.set_parent(child, parent);
+        cmd.flush(&mut world);
+        assert_eq!(children(&world, parent), vec![child]);
+
+        cmd.remove(parent);
+        cmd.flush(&mut world);
+        assert!(!world.contains(child));
+    }
+
+    #[test]
+    fn join_parent() {
+        #[derive(Debug, PartialEq)]
+        struct Local(f32);
+        #[derive(Debug, PartialEq)]
+        struct Global(f32);
+
+        let mut world = World::default();
+        let root = world.push((Local(1.0), Global(1.0), true));
+        let child = world.push((Local(2.0), Global(0.0)));
+        let grandchild = world.push((Local(3.0), Global(0.0), false));
+        let orphan = world.push((Local(4.0), Global(0.0)));
+        world.set_parent(child, root).unwrap();
+        world.set_parent(grandchild, child).unwrap();
+
+        // write to children while reading from their parents in the other half of the world
+        let mut query = <(Read<Local>, Write<Global>)>::query();
+        let mut parents = Read::<Local>::query();
+        let (mut left, right) = world.split::<(Write<Global>, Read<Local>, Read<ChildOf>)>();
+        let mut visited = 0;
+        query
+            .for_each_with_parent_mut(
+                &mut left,
+                &mut parents,
+                &right,
+                |(local, global), parent| {
+                    global.0 = local.0 + parent.unwrap().0;
+                    visited += 1;
+                },
+            )
+            .unwrap();
+        assert_eq!(visited, 2);
+
+        let global = |entity| {
+            world
+                .entry_ref(entity)
+                .unwrap()
+                .into_component::<Global>()
+                .unwrap()
+                .0
+        };
+        assert_eq!(global(root), 1.0);
+        assert_eq!(global(child), 3.0);
+        assert_eq!(global(grandchild), 5.0);
+        assert_eq!(global(orphan), 0.0);
+
+        // parents which do not match the parent query are not provided
+        let mut query = Read::<Global>::query();
+        let mut parents = <(Read<Global>, Read<bool>)>::query();
+        let mut results = Vec::new();
+        query
+            .for_each_with_parent(&world, &mut parents, &world, |global, parent| {
+                results.push((global.0, parent.map(|(global, _)| global.0)));
+            })
+            .unwrap();
+        results.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(results, vec![(3.0, Some(1.0)), (5.0, None)]);
+
+        // a split world which excludes ChildOf cannot be joined by parent
+        let mut query = <(Read<Local>, Write<Global>)>::query();
+        let mut parents = Read::<Local>::query();
+        let (mut left, right) = world.split::<(Write<Global>, Read<Local>)>();
+        assert_eq!(
+            query.for_each_with_parent_mut(&mut left, &mut parents, &right, |_, _| {}),
+            Err(EntityAccessError::AccessDenied)
+        );
+    }
+
+    #[test]
+    fn clone_remaps_relations() {
+        let mut a = World::default();
+        let mut b = World::default();
+        b.push((0usize,));
+
+        let parent = a.push((1usize,));
+        let child = a.push((2usize,));
+        a.set_parent(child, parent).unwrap();
+
+        let mut merger = Duplicate::default();
+        merger.register_copy::<usize>();
+        merger.register_clone::<ChildOf>();
+        merger.register_clone::<Children>();
+
+        let map = b.clone_from(&a, &any(), &mut merger);
+        assert_ne!(map[&parent], parent);
+        assert_eq!(b.parent(map[&child]), Some(map[&parent]));
+        assert_eq!(children(&b, map[&parent]), vec![map[&child]]);
+    }
+
+    #[test]
+    #[cfg(feature = "serialize")]
+    fn serialize_relations() {
+        use crate::internals::serialize::Registry;
+        use serde::de::DeserializeSeed;
+
+        let mut world = World::default();
+        let parent = world.push((1usize,));
+        let child = world.push((2usize,));
+        world.set_parent(child, parent).unwrap();
+
+        let mut registry = Registry::<String>::default();
+        registry.register::<usize>("usize".to_string());
+        registry.register::<ChildOf>("child_of".to_string());
+        registry.register::<Children>("children".to_string());
+
+        let json = serde_json::to_value(&world.as_serializable(any(), &registry)).unwrap();
+        let world: World = registry.as_deserialize().deserialize(json).unwrap();
+
+        assert_eq!(world.parent(child), Some(parent));
+        assert_eq!(children(&world, parent), vec![child]);
+    }
+}
@@ -0,0 +1,171 @@
+//! Contains the callbacks which a world runs as components of a given type are added to or
+//! removed from entities.
+
+use super::{
+    entity::Entity,
+    hash::ComponentTypeIdHasher,
+    storage::{
+        archetype::ArchetypeIndex,
+        component::{Component, ComponentTypeId},
+        sparse::{SparseStorage, UnknownSparseStorage},
+        ComponentIndex, ComponentStorage, UnknownComponentStorage,
+    },
+};
+use downcast_rs::{impl_downcast, Downcast};
+use std::{collections::HashMap, fmt::Debug, hash::BuildHasherDefault};
+
+type Hook<T> = Box<dyn FnMut(Entity, &mut T) + Send + Sync>;
+
+/// Selects which hooks to run.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum HookKind {
+    Add,
+    Remove,
+}
+
+struct Hooks<T: Component> {
+    on_add: Vec<Hook<T>>,
+    on_remove: Vec<Hook<T>>,
+}
+
+impl<T: Component> Default for Hooks<T> {
+    fn default() -> Self {
+        Self {
+            on_add: Vec::new(),
+            on_remove: Vec::new(),
+        }
+    }
+}
+
+impl<T: Component> Hooks<T> {
+    fn get_mut(&mut self, kind: HookKind) -> &mut Vec<Hook<T>> {
+        match kind {
+            HookKind::Add => &mut self.on_add,
+            HookKind::Remove => &mut self.on_remove,
+        }
+    }
+}
+
+/// The hooks for a component type which is not known statically.
+trait UnknownHooks: Downcast + Send + Sync {
+    fn run_packed(
+        &mut self,
+        kind: HookKind,
+        storage: &mut dyn UnknownComponentStorage,
+        archetype: ArchetypeIndex,
+        first: ComponentIndex,
+        entities: &[Entity],
+    );
+
+    fn run_sparse(
+        &mut self,
+        kind: HookKind,
+        storage: &mut dyn UnknownSparseStorage,
+        entity: Entity,
+    );
+}
+
+impl_downcast!(UnknownHooks);
+
+impl<T: Component> UnknownHooks for Hooks<T> {
+    fn run_packed(
+        &mut self,
+        kind: HookKind,
+        storage: &mut dyn UnknownComponentStorage,
+        archetype: ArchetypeIndex,
+        ComponentIndex(first): ComponentIndex,
+        entities: &[Entity],
+    ) {
+        let hooks = self.get_mut(kind);
+        if hooks.is_empty() {
+            return;
+        }
+
+        let storage = storage.downcast_mut::<T::Storage>().unwrap();
+        // safety: we have exclusive access to the storage
+        let mut slice = unsafe { storage.get_mut(archetype) }.unwrap();
+        for (i, entity) in entities.iter().enumerate() {
+            let component = &mut slice[ComponentIndex(first + i)];
+            for hook in hooks.iter_mut() {
+                hook(*entity, component);
+            }
+        }
+    }
+
+    fn run_sparse(
+        &mut self,
+        kind: HookKind,
+        storage: &mut dyn UnknownSparseStorage,
+        entity: Entity,
+    ) {
+        let hooks = self.get_mut(kind);
+        if hooks.is_empty() {
+            return;
+        }
+
+        let storage = storage.downcast_mut::<SparseStorage<T>>().unwrap();
+        if let Some(component) = storage.get_mut(entity) {
+            for hook in hooks.iter_mut() {
+                hook(entity, component);
+            }
+        }
+    }
+}
+
+/// The hooks registered with a world, by component type.
+#[derive(Default)]
+pub(crate) struct ComponentHooks {
+    hooks:
+        HashMap<ComponentTypeId, Box<dyn UnknownHooks>, BuildHasherDefault<ComponentTypeIdHasher>>,
+}
+
+impl ComponentHooks {
+    pub(crate) fn push<T: Component>(&mut self, kind: HookKind, hook: Hook<T>) {
+        self.hooks
+            .entry(ComponentTypeId::of::<T>())
+            .or_insert_with(|| Box::new(Hooks::<T>::default()))
+            .downcast_mut::<Hooks<T>>()
+            .unwrap()
+            .get_mut(kind)
+            .push(hook);
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+
+    /// Runs the hooks for the components of the given entities, which are stored contiguously in
+    /// an archetype slice starting at `first`.
+    pub(crate) fn run_packed(
+        &mut self,
+        kind: HookKind,
+        type_id: ComponentTypeId,
+        storage: &mut dyn UnknownComponentStorage,
+        archetype: ArchetypeIndex,
+        first: ComponentIndex,
+        entities: &[Entity],
+    ) {
+        if let Some(hooks) = self.hooks.get_mut(&type_id) {
+            hooks.run_packed(kind, storage, archetype, first, entities);
+        }
+    }
+
+    /// Runs the hooks for an entity's sparse component, if it has one.
+    pub(crate) fn run_sparse(
+        &mut self,
+        kind: HookKind,
+        type_id: ComponentTypeId,
+        storage: &mut dyn UnknownSparseStorage,
+        entity: Entity,
+    ) {
+        if let Some(hooks) = self.hooks.get_mut(&type_id) {
+            hooks.run_sparse(kind, storage, entity);
+        }
+    }
+}
+
+impl Debug for ComponentHooks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.hooks.keys()).finish()
+    }
+}
@@ -0,0 +1,231 @@
+use super::{storage::archetype::ArchetypeIndex, world::World};
+use std::time::{Duration, Instant};
+
+/// The number of entities moved between checks of a [time budget](enum.LoadBudget.html#variant.Time).
+const TIME_BATCH_SIZE: usize = 64;
+
+/// Limits the work done by a single call to [WorldLoader::load_into](struct.WorldLoader.html#method.load_into).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LoadBudget {
+    /// Moves at most the given number of entities.
+    Entities(usize),
+    /// Moves entities in small batches until the given time has elapsed. The time is checked
+    /// between batches, so the budget may be slightly exceeded.
+    Time(Duration),
+    /// Moves all remaining entities.
+    Unlimited,
+}
+
+/// Merges the entities of a staging world into another world over multiple calls, such that
+/// the target world remains usable while a large world is loaded into it.
+///
+/// A loader can be deserialized through a [Registry](../serialize/struct.Registry.html) with
+/// [as_deserialize_loader](../serialize/struct.Registry.html#method.as_deserialize_loader).
+/// Further serialized batches can be staged at any time with
+/// [as_deserialize_batch](../serialize/struct.Registry.html#method.as_deserialize_batch), so a
+/// world which has been serialized as several documents, such as the chunks of a streamed level,
+/// is deserialized one document per call while earlier batches are still being merged. Each
+/// document is parsed in full when it is staged, as serde cannot suspend a deserializer part way
+/// through a document.
+///
+/// Deserialization into the staging world does not touch the target world, so it can also be
+/// performed on a background thread before the loader is handed to the thread which owns the
+/// target world.
+///
+/// The loaded entities are new to the target world, so [on_add](struct.World.html#method.on_add)
+/// hooks are run for their components.
+///
+/// Entities are moved archetype by archetype, keeping their IDs. Any entity in the target world
+/// with the same ID as a loaded entity is replaced.
+///
+/// # Examples
+///
+/// ```
+/// # use legion::*;
+/// # use legion::world::{LoadBudget, WorldLoader};
+/// let mut staging = World::default();
+/// staging.extend((0..100usize).map(|i| (i,)));
+///
+/// let mut world = World::default();
+/// let mut loader = WorldLoader::new(staging);
+/// while !loader.load_into(&mut world, LoadBudget::Entities(30)) {
+///     // the world can be used between calls
+///     assert!(world.len() < 100);
+/// }
+/// assert_eq!(world.len(), 100);
+/// ```
+#[derive(Debug)]
+pub struct WorldLoader {
+    staging: World,
+    archetype: usize,
+}
+
+impl WorldLoader {
+    /// Constructs a loader which moves the entities of the given staging world.
+    pub fn new(staging: World) -> Self {
+        Self {
+            staging,
+            archetype: 0,
+        }
+    }
+
+    /// Returns the number of entities which have not yet been loaded.
+    pub fn remaining(&self) -> usize {
+        self.staging.len()
+    }
+
+    /// Returns `true` if all entities have been loaded.
+    pub fn is_complete(&self) -> bool {
+        self.staging.is_empty()
+    }
+
+    /// Returns the staging world, so that another batch of entities can be added to it. Entities
+    /// may be added to archetypes which have already been merged, so the merge starts again from
+    /// the first archetype.
+    pub(crate) fn stage(&mut self) -> &mut World {
+        self.archetype = 0;
+        &mut self.staging
+    }
+
+    /// Moves entities into `world`, until either all entities have been loaded or the budget
+    /// is exhausted. Returns `true` if all entities have been loaded.
+    pub fn load_into(&mut self, world: &mut World, budget: LoadBudget) -> bool {
+        match budget {
+            LoadBudget::Entities(count) => {
+                self.load_entities(world, count);
+            }
+            LoadBudget::Time(duration) => {
+                let start = Instant::now();
+                while !self.is_complete() && start.elapsed() < duration {
+                    self.load_entities(world, TIME_BATCH_SIZE);
+                }
+            }
+            LoadBudget::Unlimited => {
+                self.load_entities(world, usize::MAX);
+            }
+        }
+        self.is_complete()
+    }
+
+    fn load_entities(&mut self, world: &mut World, mut count: usize) {
+        while count > 0 && self.archetype < self.staging.archetypes().len() {
+            let moved = world.move_batch_from(
+                &mut self.staging,
+                ArchetypeIndex(self.archetype as u32),
+                count,
+            );
+            if moved < count {
+                self.archetype += 1;
+            }
+            count -= moved;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::internals::{
+        entity::Entity,
+        query::IntoQuery,
+        world::{EntityStore, World},
+    };
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn load_in_batches() {
+        let mut staging = World::default();
+        let a = staging.extend((0..10usize).map(|i| (i, i as f32))).to_vec();
+        let b = staging.extend((0..5usize).map(|i| (i,))).to_vec();
+        staging.register_sparse::<u16>();
+        let sparse = staging.push((1u8,));
+        staging.entry(sparse).unwrap().add_component(2u16);
+
+        let mut world = World::default();
+        let existing = world.push((100usize,));
+        // a loaded entity replaces any entity with the same ID
+        world.push_with_id(a[0], (false,));
+
+        let mut loader = WorldLoader::new(staging);
+        assert_eq!(loader.remaining(), 16);
+        assert!(!loader.load_into(&mut world, LoadBudget::Entities(7)));
+        assert_eq!(loader.remaining(), 9);
+        assert!(!loader.load_into(&mut world, LoadBudget::Entities(7)));
+        assert!(loader.load_into(&mut world, LoadBudget::Entities(7)));
+        assert!(loader.is_complete());
+
+        assert_eq!(world.len(), 17);
+        assert!(world.contains(existing));
+        for (i, entity) in a.iter().enumerate() {
+            let entry = world.entry_ref(*entity).unwrap();
+            assert_eq!(entry.get_component::<usize>(), Ok(&i));
+            assert_eq!(entry.get_component::<f32>(), Ok(&(i as f32)));
+            assert!(entry.get_component::<bool>().is_err());
+            assert!(entry.get_component::<u16>().is_err());
+        }
+        for (i, entity) in b.iter().enumerate() {
+            let entry = world.entry_ref(*entity).unwrap();
+            assert_eq!(entry.get_component::<usize>(), Ok(&i));
+        }
+        assert_eq!(
+            world.entry_ref(sparse).unwrap().get_component::<u16>(),
+            Ok(&2)
+        );
+        assert_eq!(Entity::query().iter(&world).count(), 17);
+    }
+
+    #[test]
+    fn time_budget() {
+        let mut staging = World::default();
+        staging.extend((0..1000usize).map(|i| (i,)));
+
+        let mut world = World::default();
+        let mut loader = WorldLoader::new(staging);
+        assert!(!loader.load_into(&mut world, LoadBudget::Time(Duration::from_secs(0))));
+        assert!(loader.load_into(&mut world, LoadBudget::Time(Duration::from_secs(60))));
+        assert_eq!(world.len(), 1000);
+    }
+
+    #[test]
+    fn load_runs_hooks() {
+        let mut staging = World::default();
+        staging.extend((0..10usize).map(|i| (i,)));
+
+        let mut world = World::default();
+        let added = Arc::new(Mutex::new(0));
+        let added_ref = added.clone();
+        world.on_add::<usize, _>(move |_, value| *added_ref.lock().unwrap() += *value);
+
+        WorldLoader::new(staging).load_into(&mut world, LoadBudget::Unlimited);
+        assert_eq!(*added.lock().unwrap(), 45);
+
+        // sparse components which are moved with their entities are added too
+        let mut staging = World::default();
+        staging.register_sparse::<u16>();
+        let entity = staging.push((1usize,));
+        staging.entry(entity).unwrap().add_component(7u16);
+
+        let sparse_added = Arc::new(Mutex::new(0));
+        let sparse_added_ref = sparse_added.clone();
+        world.on_add::<u16, _>(move |_, value| *sparse_added_ref.lock().unwrap() += *value);
+        WorldLoader::new(staging).load_into(&mut world, LoadBudget::Unlimited);
+        assert_eq!(*added.lock().unwrap(), 46);
+        assert_eq!(*sparse_added.lock().unwrap(), 7);
+    }
+
+    #[test]
+    fn stage_while_loading() {
+        let mut staging = World::default();
+        staging.extend((0..10usize).map(|i| (i,)));
+
+        let mut world = World::default();
+        let mut loader = WorldLoader::new(staging);
+        assert!(!loader.load_into(&mut world, LoadBudget::Entities(5)));
+
+        // the new entities join an archetype which has already been partially merged
+        loader.stage().extend((10..15usize).map(|i| (i,)));
+        assert_eq!(loader.remaining(), 10);
+        assert!(loader.load_into(&mut world, LoadBudget::Unlimited));
+        assert_eq!(world.len(), 15);
+    }
+}
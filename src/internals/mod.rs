@@ -1,14 +1,23 @@
+#[macro_use]
+pub mod trace;
+
 pub mod cons;
+pub mod diff;
 pub mod entity;
 pub mod entry;
 pub mod event;
 pub mod hash;
+pub mod hierarchy;
+pub mod hooks;
 pub mod insert;
 pub mod iter;
+pub mod loader;
 pub mod permissions;
+pub mod prefab;
 pub mod query;
 #[cfg(feature = "serialize")]
 pub mod serialize;
+pub mod snapshot;
 pub mod storage;
 pub mod subworld;
 pub mod systems;
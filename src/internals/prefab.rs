@@ -0,0 +1,468 @@
+use super::{
+    entity::{Allocate, Entity, EntityHasher, ID_CLONE_MAPPINGS},
+    entry::Entry,
+    insert::ArchetypeWriter,
+    query::filter::filter_fns::any,
+    storage::{
+        archetype::{Archetype, EntityLayout},
+        component::Component,
+        Components,
+    },
+    world::{Duplicate, EntityRewrite, Merger, World},
+};
+use std::{collections::HashMap, fmt::Debug, ops::Range, sync::Arc};
+
+type Override = Arc<dyn Fn(&mut Entry) + Send + Sync>;
+
+/// A template of entities which can be [instantiated](struct.World.html#method.instantiate)
+/// into a world any number of times.
+///
+/// Each instance is given fresh entity IDs, and `Entity` references between the prefab's
+/// entities are rewritten to refer to the entities of the same instance. A prefab may also
+/// [nest](#method.nest) instances of other prefabs, which are instantiated along with it.
+/// Each nested instance is identified by the [NestedPrefab](struct.NestedPrefab.html) returned
+/// from `nest`, so the same prefab can be nested more than once.
+///
+/// Prefabs can be loaded through a [Registry](../serialize/struct.Registry.html) with
+/// [as_deserialize_prefab](../serialize/struct.Registry.html#method.as_deserialize_prefab).
+pub struct Prefab {
+    world: World,
+    nested: Vec<(Arc<Prefab>, PrefabOverrides, NestedPrefab)>,
+}
+
+impl Prefab {
+    /// Constructs a prefab from the entities in a world.
+    pub fn new(world: World) -> Self {
+        Self {
+            world,
+            nested: Vec::new(),
+        }
+    }
+
+    /// Returns the world containing the prefab's own entities. The IDs of these entities are
+    /// used to identify them in [overrides](struct.PrefabOverrides.html) and in the mappings
+    /// returned from [World::instantiate](struct.World.html#method.instantiate).
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    /// Returns the world containing the prefab's own entities mutably, such as to add entities
+    /// which refer to the entities of a [nested](#method.nest) prefab.
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.world
+    }
+
+    /// Nests an instance of another prefab inside this prefab, with the given overrides applied
+    /// to each of its instances.
+    ///
+    /// The nested instance's entities are identified within this prefab by new IDs, which are
+    /// looked up with [NestedPrefab::entity](struct.NestedPrefab.html#method.entity). These IDs
+    /// are used by `Entity` references from this prefab's entities, which are rewritten to refer
+    /// to the nested instance, by this prefab's [overrides](struct.PrefabOverrides.html), and in
+    /// the mappings returned from [World::instantiate](struct.World.html#method.instantiate).
+    pub fn nest(&mut self, prefab: Arc<Prefab>, overrides: PrefabOverrides) -> NestedPrefab {
+        let allocate = self.world.allocate_mut();
+        let ids = prefab
+            .ids()
+            .map(|entity| (entity, allocate.next().unwrap()))
+            .collect();
+        let nested = NestedPrefab { ids: Arc::new(ids) };
+        self.nested.push((prefab, overrides, nested.clone()));
+        nested
+    }
+
+    /// Returns the IDs by which the prefab identifies its entities, including those of nested
+    /// instances.
+    fn ids(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.world
+            .archetypes()
+            .iter()
+            .flat_map(|arch| arch.entities().iter().copied())
+            .chain(
+                self.nested
+                    .iter()
+                    .flat_map(|(_, _, nested)| nested.ids.values().copied()),
+            )
+    }
+
+    fn instantiate_into(
+        &self,
+        world: &mut World,
+        overrides: &PrefabOverrides,
+        duplicate: &mut Duplicate,
+        mappings: &mut HashMap<Entity, Entity, EntityHasher>,
+    ) {
+        for (prefab, nested_overrides, nested) in &self.nested {
+            let mut instances = HashMap::default();
+            prefab.instantiate_into(world, nested_overrides, duplicate, &mut instances);
+            mappings.extend(
+                instances
+                    .into_iter()
+                    .filter_map(|(entity, instance)| Some((nested.entity(entity)?, instance))),
+            );
+        }
+
+        let mut merger = Instantiate {
+            duplicate,
+            references: mappings.clone(),
+        };
+        let instances = world.clone_from(&self.world, &any(), &mut merger);
+        mappings.extend(instances);
+
+        overrides.apply(world, mappings);
+    }
+}
+
+impl Debug for Prefab {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Prefab")
+            .field("len", &self.world.len())
+            .field("nested", &self.nested.len())
+            .finish()
+    }
+}
+
+/// An instance of a [prefab](struct.Prefab.html) which has been [nested](struct.Prefab.html#method.nest)
+/// inside another prefab.
+#[derive(Clone)]
+pub struct NestedPrefab {
+    ids: Arc<HashMap<Entity, Entity, EntityHasher>>,
+}
+
+impl NestedPrefab {
+    /// Returns the ID which identifies the nested instance of one of the nested prefab's
+    /// entities, given the ID of the entity in the nested prefab.
+    pub fn entity(&self, entity: Entity) -> Option<Entity> {
+        self.ids.get(&entity).copied()
+    }
+}
+
+impl Debug for NestedPrefab {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.ids.iter()).finish()
+    }
+}
+
+/// Component values which replace those of a [prefab](struct.Prefab.html)'s entities when it is
+/// instantiated.
+///
+/// `Entity` references inside override values are rewritten in the same way as those inside
+/// the prefab's own components.
+#[derive(Clone, Default)]
+pub struct PrefabOverrides {
+    overrides: Vec<(Entity, Override)>,
+}
+
+impl PrefabOverrides {
+    /// Constructs an empty set of overrides.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the value of a component on the instance of the given prefab entity, adding the
+    /// component if the prefab entity does not have it.
+    pub fn set<T: Component + Clone>(&mut self, entity: Entity, component: T) {
+        self.overrides.push((
+            entity,
+            Arc::new(move |entry: &mut Entry| entry.add_component(component.clone())),
+        ));
+    }
+
+    /// Returns `true` if there are no overrides.
+    pub fn is_empty(&self) -> bool {
+        self.overrides.is_empty()
+    }
+
+    fn apply(&self, world: &mut World, mappings: &HashMap<Entity, Entity, EntityHasher>) {
+        if self.overrides.is_empty() {
+            return;
+        }
+
+        // set the entity mappings as context for Entity::clone
+        let mut previous = mappings.clone();
+        ID_CLONE_MAPPINGS.with(|cell| std::mem::swap(&mut *cell.borrow_mut(), &mut previous));
+
+        for (entity, apply) in &self.overrides {
+            if let Some(mut entry) = mappings.get(entity).and_then(|e| world.entry(*e)) {
+                apply(&mut entry);
+            }
+        }
+
+        ID_CLONE_MAPPINGS.with(|cell| *cell.borrow_mut() = previous);
+    }
+}
+
+impl Debug for PrefabOverrides {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.overrides.iter().map(|(entity, _)| entity))
+            .finish()
+    }
+}
+
+/// A merger which clones a prefab's entities with fresh IDs, rewriting references to the
+/// entities of nested instances.
+struct Instantiate<'a> {
+    duplicate: &'a mut Duplicate,
+    references: HashMap<Entity, Entity, EntityHasher>,
+}
+
+impl<'a> Merger for Instantiate<'a> {
+    fn entity_map(&mut self) -> EntityRewrite {
+        EntityRewrite::Auto(Some(std::mem::take(&mut self.references)))
+    }
+
+    fn assign_id(&mut self, _: Entity, allocator: &mut Allocate) -> Entity {
+        allocator.next().unwrap()
+    }
+
+    fn convert_layout(&mut self, source_layout: EntityLayout) -> EntityLayout {
+        self.duplicate.convert_layout(source_layout)
+    }
+
+    fn merge_archetype(
+        &mut self,
+        src_entity_range: Range<usize>,
+        src_arch: &Archetype,
+        src_components: &Components,
+        dst: &mut ArchetypeWriter,
+    ) {
+        self.duplicate
+            .merge_archetype(src_entity_range, src_arch, src_components, dst)
+    }
+}
+
+impl World {
+    /// Instantiates a [prefab](struct.Prefab.html) into the world, using `duplicate` to clone
+    /// its components. Returns a map from the IDs of the prefab's entities to the IDs of the new
+    /// instances. The entities of nested prefabs are keyed by the IDs given by
+    /// [NestedPrefab::entity](struct.NestedPrefab.html#method.entity).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use legion::*;
+    /// # use legion::world::{Duplicate, Prefab};
+    /// let mut template = World::default();
+    /// let root = template.push((1usize,));
+    /// let prefab = Prefab::new(template);
+    ///
+    /// let mut duplicate = Duplicate::new();
+    /// duplicate.register_copy::<usize>();
+    ///
+    /// let mut world = World::default();
+    /// let a = world.instantiate(&prefab, &mut duplicate)[&root];
+    /// let b = world.instantiate(&prefab, &mut duplicate)[&root];
+    /// assert_ne!(a, b);
+    /// assert_eq!(world.len(), 2);
+    /// ```
+    pub fn instantiate(
+        &mut self,
+        prefab: &Prefab,
+        duplicate: &mut Duplicate,
+    ) -> HashMap<Entity, Entity, EntityHasher> {
+        self.instantiate_with(prefab, &PrefabOverrides::default(), duplicate)
+    }
+
+    /// Instantiates a [prefab](struct.Prefab.html) into the world, replacing component values
+    /// with those in `overrides`. Overrides are applied after those of nested prefabs, and so
+    /// take precedence over them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use legion::*;
+    /// # use legion::world::{Duplicate, Prefab, PrefabOverrides};
+    /// let mut template = World::default();
+    /// let root = template.push((1usize,));
+    /// let prefab = Prefab::new(template);
+    ///
+    /// let mut duplicate = Duplicate::new();
+    /// duplicate.register_copy::<usize>();
+    ///
+    /// let mut overrides = PrefabOverrides::new();
+    /// overrides.set(root, 5usize);
+    ///
+    /// let mut world = World::default();
+    /// let instance = world.instantiate_with(&prefab, &overrides, &mut duplicate)[&root];
+    /// assert_eq!(world.entry(instance).unwrap().get_component::<usize>(), Ok(&5));
+    /// ```
+    pub fn instantiate_with(
+        &mut self,
+        prefab: &Prefab,
+        overrides: &PrefabOverrides,
+        duplicate: &mut Duplicate,
+    ) -> HashMap<Entity, Entity, EntityHasher> {
+        let mut mappings = HashMap::default();
+        prefab.instantiate_into(self, overrides, duplicate, &mut mappings);
+        mappings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::internals::world::EntityStore;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Target(Entity);
+
+    fn duplicate() -> Duplicate {
+        let mut duplicate = Duplicate::new();
+        duplicate.register_copy::<usize>();
+        duplicate.register_clone::<Target>();
+        duplicate
+    }
+
+    #[test]
+    fn remap_references() {
+        let mut template = World::default();
+        let a = template.push((1usize,));
+        let b = template.push((2usize, Target(a)));
+        let prefab = Prefab::new(template);
+
+        let mut duplicate = duplicate();
+        let mut world = World::default();
+        let first = world.instantiate(&prefab, &mut duplicate);
+        let second = world.instantiate(&prefab, &mut duplicate);
+        assert_eq!(world.len(), 4);
+        assert_ne!(first[&a], second[&a]);
+
+        for instance in &[first, second] {
+            let entry = world.entry_ref(instance[&b]).unwrap();
+            assert_eq!(entry.get_component::<Target>(), Ok(&Target(instance[&a])));
+            assert_eq!(entry.get_component::<usize>(), Ok(&2));
+        }
+
+        // cloning outside of instantiation does not remap
+        assert_eq!(a.clone(), a);
+    }
+
+    #[test]
+    fn overrides() {
+        let mut template = World::default();
+        let a = template.push((1usize,));
+        let b = template.push((2usize,));
+        let prefab = Prefab::new(template);
+
+        let mut overrides = PrefabOverrides::new();
+        overrides.set(a, 10usize);
+        overrides.set(b, Target(a));
+
+        let mut duplicate = duplicate();
+        let mut world = World::default();
+        let instance = world.instantiate_with(&prefab, &overrides, &mut duplicate);
+
+        let entry = world.entry_ref(instance[&a]).unwrap();
+        assert_eq!(entry.get_component::<usize>(), Ok(&10));
+        let entry = world.entry_ref(instance[&b]).unwrap();
+        assert_eq!(entry.get_component::<usize>(), Ok(&2));
+        assert_eq!(entry.get_component::<Target>(), Ok(&Target(instance[&a])));
+    }
+
+    #[test]
+    fn nested() {
+        let mut wheel = World::default();
+        let hub = wheel.push((1usize,));
+        let wheel = Arc::new(Prefab::new(wheel));
+
+        let mut car = Prefab::new(World::default());
+        let mut wheel_overrides = PrefabOverrides::new();
+        wheel_overrides.set(hub, 2usize);
+        let hub = car.nest(wheel, wheel_overrides).entity(hub).unwrap();
+        let body = car.world_mut().push((Target(hub),));
+
+        let mut duplicate = duplicate();
+        let mut world = World::default();
+        let first = world.instantiate(&car, &mut duplicate);
+        let mut overrides = PrefabOverrides::new();
+        overrides.set(hub, 3usize);
+        let second = world.instantiate_with(&car, &overrides, &mut duplicate);
+        assert_eq!(world.len(), 4);
+
+        for (instance, value) in &[(first, 2usize), (second, 3usize)] {
+            let entry = world.entry_ref(instance[&hub]).unwrap();
+            assert_eq!(entry.get_component::<usize>(), Ok(value));
+            let entry = world.entry_ref(instance[&body]).unwrap();
+            assert_eq!(entry.get_component::<Target>(), Ok(&Target(instance[&hub])));
+        }
+    }
+
+    #[test]
+    fn nested_twice() {
+        let mut wheel = World::default();
+        let hub = wheel.push((1usize,));
+        let tyre = wheel.push((2usize, Target(hub)));
+        let wheel = Arc::new(Prefab::new(wheel));
+
+        let mut axle = Prefab::new(World::default());
+        let left = axle.nest(wheel.clone(), PrefabOverrides::new());
+        let right = axle.nest(wheel, PrefabOverrides::new());
+        let right_hub = right.entity(hub).unwrap();
+        let rod = axle.world_mut().push((Target(right_hub),));
+        let mut overrides = PrefabOverrides::new();
+        overrides.set(right_hub, 3usize);
+
+        // nest the axle in turn, so that IDs are mapped through each level of nesting
+        let mut car = Prefab::new(World::default());
+        let axle = car.nest(Arc::new(axle), overrides);
+        let nested =
+            |slot: &NestedPrefab, entity| axle.entity(slot.entity(entity).unwrap()).unwrap();
+        let wheels = [
+            (nested(&left, hub), nested(&left, tyre), 1usize),
+            (nested(&right, hub), nested(&right, tyre), 3usize),
+        ];
+        let rod = axle.entity(rod).unwrap();
+
+        let mut duplicate = duplicate();
+        let mut world = World::default();
+        let instance = world.instantiate(&car, &mut duplicate);
+        assert_eq!(world.len(), 5);
+        assert_eq!(instance.len(), 5);
+        assert_ne!(instance[&wheels[0].0], instance[&wheels[1].0]);
+
+        for (hub, tyre, value) in &wheels {
+            let entry = world.entry_ref(instance[hub]).unwrap();
+            assert_eq!(entry.get_component::<usize>(), Ok(value));
+            let entry = world.entry_ref(instance[tyre]).unwrap();
+            assert_eq!(entry.get_component::<Target>(), Ok(&Target(instance[hub])));
+        }
+        let entry = world.entry_ref(instance[&rod]).unwrap();
+        assert_eq!(
+            entry.get_component::<Target>(),
+            Ok(&Target(instance[&wheels[1].0]))
+        );
+    }
+
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn load_through_registry() {
+        use crate::internals::{query::IntoQuery, serialize::Registry};
+        use serde::de::DeserializeSeed;
+
+        #[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+        struct Link(Entity);
+
+        let mut registry = Registry::<String>::default();
+        registry.register::<usize>("usize".to_string());
+        registry.register::<Link>("link".to_string());
+
+        let mut template = World::default();
+        let a = template.push((1usize,));
+        template.push((2usize, Link(a)));
+        let json = serde_json::to_value(&template.as_serializable(any(), &registry)).unwrap();
+        let prefab = registry.as_deserialize_prefab().deserialize(json).unwrap();
+
+        let mut duplicate = duplicate();
+        duplicate.register_clone::<Link>();
+        let mut world = World::default();
+        let instance = world.instantiate(&prefab, &mut duplicate);
+        assert_eq!(world.len(), 2);
+
+        let a = instance[&a];
+        let mut query = <(&usize, &Link)>::query();
+        let links = query.iter(&world).collect::<Vec<_>>();
+        assert_eq!(links, vec![(&2, &Link(a))]);
+        assert_eq!(world.entry_ref(a).unwrap().get_component::<usize>(), Ok(&1));
+    }
+}
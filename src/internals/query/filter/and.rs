@@ -70,6 +70,20 @@ macro_rules! impl_and_filter {
                 $( result = result.coalesce_and($ty.matches_archetype(fetch)); )*
                 result
             }
+
+            #[inline]
+            fn matches_entity<Fet: Fetch>(&mut self, fetch: &Fet, index: usize) -> FilterResult {
+                #![allow(non_snake_case)]
+                let ($( $ty, )*) = &mut self.filters;
+                let mut result = FilterResult::Defer;
+                $( result = result.coalesce_and($ty.matches_entity(fetch, index)); )*
+                result
+            }
+
+            #[inline]
+            fn filters_entities() -> bool {
+                $( $ty::filters_entities() )||*
+            }
         }
 
         impl<$( $ty ),*> std::ops::Not for And<($( $ty, )*)> {
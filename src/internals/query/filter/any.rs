@@ -29,6 +29,10 @@ impl DynamicFilter for Any {
     fn matches_archetype<F: Fetch>(&mut self, _: &F) -> FilterResult {
         FilterResult::Match(true)
     }
+
+    fn matches_entity<F: Fetch>(&mut self, _: &F, _: usize) -> FilterResult {
+        FilterResult::Match(true)
+    }
 }
 
 impl std::ops::Not for Any {
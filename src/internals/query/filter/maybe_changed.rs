@@ -64,6 +64,14 @@ impl<T: Component> DynamicFilter for ComponentChangedFilter<T> {
             FilterResult::Defer
         }
     }
+
+    fn matches_entity<Fet: Fetch>(&mut self, fetch: &Fet, _: usize) -> FilterResult {
+        if let Some(version) = fetch.version::<T>() {
+            FilterResult::Match(version > self.threshold)
+        } else {
+            FilterResult::Defer
+        }
+    }
 }
 
 impl<T: Component> std::ops::Not for ComponentChangedFilter<T> {
@@ -3,8 +3,10 @@
 use super::view::Fetch;
 use crate::internals::{storage::component::ComponentTypeId, world::WorldId};
 
+pub mod added;
 pub mod and;
 pub mod any;
+pub mod changed;
 pub mod component;
 pub mod maybe_changed;
 pub mod not;
@@ -14,7 +16,8 @@ pub mod try_component;
 
 pub mod filter_fns {
     use super::{
-        any::Any, component::ComponentFilter, maybe_changed::ComponentChangedFilter,
+        added::EntityAddedFilter, any::Any, changed::EntityChangedFilter,
+        component::ComponentFilter, maybe_changed::ComponentChangedFilter,
         passthrough::Passthrough, try_component::TryComponentFilter, EntityFilterTuple,
     };
     use crate::internals::storage::component::Component;
@@ -33,6 +36,26 @@ pub mod filter_fns {
         Default::default()
     }
 
+    /// Constructs a filter which requires that the component has been written since the query
+    /// last ran.
+    ///
+    /// Unlike `maybe_changed`, this check is performed for each individual entity, using the
+    /// change tick recorded when the entity's component was accessed via `Write` or `TryWrite`.
+    /// The component type must be included in the query's view.
+    pub fn changed<T: Component>(
+    ) -> EntityFilterTuple<TryComponentFilter<T>, EntityChangedFilter<T>> {
+        Default::default()
+    }
+
+    /// Constructs a filter which requires that the component has been added to the entity since
+    /// the query last ran, either by inserting the entity or by adding the component to it.
+    ///
+    /// Like `changed`, this check is performed for each individual entity, and the component type
+    /// must be included in the query's view.
+    pub fn added<T: Component>() -> EntityFilterTuple<TryComponentFilter<T>, EntityAddedFilter<T>> {
+        Default::default()
+    }
+
     /// Constructs a filter which passes all entities.
     pub fn any() -> EntityFilterTuple<Any, Any> {
         Default::default()
@@ -116,6 +139,25 @@ pub trait DynamicFilter: Default + Send + Sync {
 
     /// Calculates the filter's result for the given archetype data.
     fn matches_archetype<F: Fetch>(&mut self, fetch: &F) -> FilterResult;
+
+    /// Calculates the filter's result for a single entity, given the data of an archetype which
+    /// was accepted by `matches_archetype` and the index of the entity within that archetype.
+    ///
+    /// This is only called if `filters_entities` returns `true`. Filters which only consider
+    /// whole archetypes should return the same result as they did for the archetype, so that they
+    /// combine correctly with filters that do consider individual entities.
+    #[inline]
+    #[allow(unused_variables)]
+    fn matches_entity<F: Fetch>(&mut self, fetch: &F, index: usize) -> FilterResult {
+        FilterResult::Defer
+    }
+
+    /// Returns `true` if the filter may reject individual entities within an archetype which
+    /// it has accepted.
+    #[inline]
+    fn filters_entities() -> bool {
+        false
+    }
 }
 
 /// A marker trait for filters that are not no-ops.
@@ -162,6 +204,15 @@ impl<T: EntityFilter> DynamicFilter for T {
         let (_, dynamic_filter) = self.filters();
         dynamic_filter.matches_archetype(fetch)
     }
+
+    fn matches_entity<Fet: Fetch>(&mut self, fetch: &Fet, index: usize) -> FilterResult {
+        let (_, dynamic_filter) = self.filters();
+        dynamic_filter.matches_entity(fetch, index)
+    }
+
+    fn filters_entities() -> bool {
+        T::Dynamic::filters_entities()
+    }
 }
 
 impl<T: EntityFilter> GroupMatcher for T {
@@ -37,10 +37,24 @@ impl<F: DynamicFilter> DynamicFilter for Not<F> {
 
     fn matches_archetype<T: Fetch>(&mut self, fetch: &T) -> FilterResult {
         match self.filter.matches_archetype(fetch) {
+            // an archetype accepted by an entity filter may still contain rejected entities,
+            // so the negation can only be decided per entity
+            FilterResult::Match(true) if F::filters_entities() => FilterResult::Defer,
             FilterResult::Match(success) => FilterResult::Match(!success),
             FilterResult::Defer => FilterResult::Defer,
         }
     }
+
+    fn matches_entity<T: Fetch>(&mut self, fetch: &T, index: usize) -> FilterResult {
+        match self.filter.matches_entity(fetch, index) {
+            FilterResult::Match(success) => FilterResult::Match(!success),
+            FilterResult::Defer => FilterResult::Defer,
+        }
+    }
+
+    fn filters_entities() -> bool {
+        F::filters_entities()
+    }
 }
 
 impl<'a, F, Rhs: ActiveFilter> std::ops::BitAnd<Rhs> for Not<F> {
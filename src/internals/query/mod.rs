@@ -1,6 +1,7 @@
 use super::world::EntityAccessError;
 use crate::internals::{
     entity::Entity,
+    iter::indexed::{IndexedIter, TrustedRandomAccess},
     storage::{
         archetype::{Archetype, ArchetypeIndex},
         component::Component,
@@ -10,6 +11,7 @@ use crate::internals::{
 };
 use filter::{DynamicFilter, EntityFilter, GroupMatcher};
 use parking_lot::Mutex;
+use smallvec::SmallVec;
 use std::{collections::HashMap, marker::PhantomData, ops::Range, slice::Iter};
 use view::{DefaultFilter, Fetch, IntoIndexableIter, IntoView, ReadOnlyFetch, View};
 
@@ -286,10 +288,8 @@ impl<V: IntoView, F: EntityFilter> Query<V, F> {
         // accept the fetch to trigger version increments
         fetch.accepted();
 
-        // construct a chunk view for the archetype, then index the entity we want
-        let view = ChunkView::new(&accessor.archetypes()[location.archetype()], fetch);
-        let mut iter = view.into_iter();
-        use crate::internals::iter::indexed::TrustedRandomAccess;
+        // index the entity we want
+        let mut iter = fetch.into_indexable_iter();
         Ok(iter.get_unchecked(location.component().0))
     }
 
@@ -402,6 +402,7 @@ impl<V: IntoView, F: EntityFilter> Query<V, F> {
     ) -> par_iter::ParChunkIter<'a, V::View, F> {
         let accessor = world.get_component_storage::<V::View>().unwrap();
         let (filter, result) = self.evaluate_query(&accessor);
+        filter.get_mut().prepare(world.id());
         par_iter::ParChunkIter::new(accessor, result, filter)
     }
 
@@ -673,9 +674,17 @@ impl<V: IntoView, F: EntityFilter> Query<V, F> {
         Body: FnMut(<V::View as View<'world>>::Element),
     {
         // we use a nested loop because it is significantly faster than .flatten()
-        for chunk in self.iter_chunks_unchecked(world) {
-            for entities in chunk {
-                f(entities);
+        if F::filters_entities() {
+            for chunk in self.iter_chunks_unchecked(world) {
+                for entities in chunk {
+                    f(entities);
+                }
+            }
+        } else {
+            for chunk in self.iter_chunks_unchecked(world) {
+                for entities in chunk.fetch.into_indexable_iter() {
+                    f(entities);
+                }
             }
         }
     }
@@ -753,17 +762,115 @@ impl<V: IntoView, F: EntityFilter> Query<V, F> {
     }
 }
 
+/// The entities of an accepted archetype which were selected by a query's filter.
+enum Selection {
+    /// All entities were selected.
+    All,
+    /// No entities were selected.
+    Empty,
+    /// Only the entities in the mask were selected.
+    Some(EntityMask),
+}
+
+/// Evaluates a filter against each entity in an accepted archetype.
+///
+/// The mask is only built once the filter's result differs between entities, so chunks whose
+/// entities are all accepted or all rejected do not need one.
+fn select_entities<D: DynamicFilter, F: Fetch>(filter: &mut D, fetch: &F, len: usize) -> Selection {
+    if !D::filters_entities() || len == 0 {
+        return Selection::All;
+    }
+
+    let first = filter.matches_entity(fetch, 0).is_pass();
+    let mut i = 1;
+    while i < len && filter.matches_entity(fetch, i).is_pass() == first {
+        i += 1;
+    }
+    if i == len {
+        return if first {
+            Selection::All
+        } else {
+            Selection::Empty
+        };
+    }
+
+    let mut mask = EntityMask::new(len);
+    if first {
+        for j in 0..i {
+            mask.insert(j);
+        }
+    } else {
+        mask.insert(i);
+    }
+    for j in (i + 1)..len {
+        if filter.matches_entity(fetch, j).is_pass() {
+            mask.insert(j);
+        }
+    }
+    Selection::Some(mask)
+}
+
+/// A bitset of the entities in a chunk which were selected by a query's filter.
+#[derive(Clone)]
+struct EntityMask {
+    bits: SmallVec<[u64; 2]>,
+}
+
+impl EntityMask {
+    fn new(len: usize) -> Self {
+        let mut bits = SmallVec::new();
+        bits.resize(Self::words(len), 0);
+        Self { bits }
+    }
+
+    /// The number of words needed to hold `len` bits, with room to spare.
+    fn words(len: usize) -> usize {
+        len / 64 + 1
+    }
+
+    #[inline]
+    fn insert(&mut self, index: usize) {
+        self.bits[index / 64] |= 1 << (index % 64);
+    }
+
+    #[inline]
+    unsafe fn contains_unchecked(&self, index: usize) -> bool {
+        *self.bits.get_unchecked(index / 64) & (1 << (index % 64)) != 0
+    }
+
+    /// Splits the mask at the given index, returning the mask of entities from `index` to `len`.
+    fn split_off(&mut self, index: usize, len: usize) -> Self {
+        let mut right = Self::new(len - index);
+        for i in index..len {
+            if unsafe { self.contains_unchecked(i) } {
+                right.insert(i - index);
+            }
+        }
+        self.bits.truncate(Self::words(index));
+        right
+    }
+}
+
 /// Provides access to slices of components for entities which have the same component layout.
 ///
 /// A single index in any of the slices contained in a chunk belong to the same entity.
+///
+/// If the query's filter selects individual entities (such as [changed](fn.changed.html)),
+/// iterating through the chunk only yields the selected entities. The slices returned by
+/// chunk accessors always contain all entities in the chunk.
 pub struct ChunkView<'a, F: Fetch> {
     archetype: &'a Archetype,
     fetch: F,
+    mask: Option<EntityMask>,
 }
 
 impl<'a, F: Fetch> ChunkView<'a, F> {
-    fn new(archetype: &'a Archetype, fetch: F) -> Self {
-        Self { archetype, fetch }
+    fn new(archetype: &'a Archetype, fetch: F, mask: Option<EntityMask>) -> Self {
+        Self {
+            archetype,
+            fetch,
+            mask,
+        }
     }
 
     /// Returns the archetype that all entities in the chunk belong to.
@@ -836,28 +943,184 @@ impl<'a, F: Fetch> ChunkView<'a, F> {
         <F as IntoIndexableIter>::IntoIter: 'a,
     {
         let iter = self.fetch.into_indexable_iter();
-        self.archetype.entities().iter().copied().zip(iter)
+        let entities = self.archetype.entities();
+        ChunkViewIter::new((entities, iter), self.mask)
+            .map(|(entity, components)| (*entity, components))
+    }
+
+    /// Converts the chunk into an iterator which pairs the components of each entity with
+    /// the element at the same index in `data`.
+    pub(crate) fn into_iter_zip<'b, T>(
+        self,
+        data: &'b [T],
+    ) -> impl Iterator<Item = (&'b T, <F as IntoIndexableIter>::Item)>
+    where
+        <F as IntoIndexableIter>::IntoIter: 'b,
+    {
+        debug_assert_eq!(data.len(), self.archetype.entities().len());
+        let iter = self.fetch.into_indexable_iter();
+        ChunkViewIter::new((data, iter), self.mask)
     }
 }
 
 impl<'a, F: Fetch> IntoIterator for ChunkView<'a, F> {
-    type IntoIter = <F as IntoIndexableIter>::IntoIter;
+    type IntoIter = ChunkViewIter<<F as IntoIndexableIter>::IntoIter>;
     type Item = <F as IntoIndexableIter>::Item;
     fn into_iter(self) -> Self::IntoIter {
-        self.fetch.into_indexable_iter()
+        ChunkViewIter::new(self.fetch.into_indexable_iter(), self.mask)
     }
 }
 
 #[cfg(feature = "parallel")]
 impl<'a, F: Fetch> rayon::iter::IntoParallelIterator for ChunkView<'a, F> {
-    type Iter = crate::internals::iter::indexed::par_iter::Par<<F as IntoIndexableIter>::IntoIter>;
-    type Item = <<F as IntoIndexableIter>::IntoIter as crate::internals::iter::indexed::TrustedRandomAccess>::Item;
+    type Iter = rayon::iter::Flatten<
+        crate::internals::iter::indexed::par_iter::Par<Masked<<F as IntoIndexableIter>::IntoIter>>,
+    >;
+    type Item = <<F as IntoIndexableIter>::IntoIter as TrustedRandomAccess>::Item;
     fn into_par_iter(self) -> Self::Iter {
         use crate::internals::iter::indexed::par_iter::Par;
-        Par::new(self.fetch.into_indexable_iter())
+        use rayon::iter::ParallelIterator;
+        Par::new(Masked::new(self.fetch.into_indexable_iter(), self.mask)).flatten()
+    }
+}
+
+/// Random access into the entities of a chunk, which yields `None` for entities that were
+/// rejected by the query's filter.
+#[doc(hidden)]
+pub struct Masked<I> {
+    inner: I,
+    mask: Option<EntityMask>,
+}
+
+impl<I: TrustedRandomAccess> Masked<I> {
+    fn new(inner: I, mask: Option<EntityMask>) -> Self {
+        Self { inner, mask }
+    }
+}
+
+unsafe impl<I: TrustedRandomAccess> TrustedRandomAccess for Masked<I> {
+    type Item = Option<I::Item>;
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    #[inline]
+    unsafe fn get_unchecked(&mut self, i: usize) -> Self::Item {
+        match &self.mask {
+            Some(mask) if !mask.contains_unchecked(i) => None,
+            _ => Some(self.inner.get_unchecked(i)),
+        }
+    }
+
+    fn split_at(self, index: usize) -> (Self, Self) {
+        let len = self.inner.len();
+        let (left, right) = self.inner.split_at(index);
+        let (left_mask, right_mask) = match self.mask {
+            Some(mut mask) => {
+                let right_mask = mask.split_off(index, len);
+                (Some(mask), Some(right_mask))
+            }
+            None => (None, None),
+        };
+        (Self::new(left, left_mask), Self::new(right, right_mask))
+    }
+}
+
+/// An iterator which yields the components of each entity in a chunk which was selected by
+/// the query's filter.
+pub struct ChunkViewIter<I> {
+    inner: ChunkViewIterInner<I>,
+}
+
+enum ChunkViewIterInner<I> {
+    All(IndexedIter<I>),
+    Masked {
+        inner: I,
+        mask: EntityMask,
+        index: usize,
+        len: usize,
+    },
+}
+
+impl<I: TrustedRandomAccess> ChunkViewIter<I> {
+    fn new(inner: I, mask: Option<EntityMask>) -> Self {
+        let inner = match mask {
+            None => ChunkViewIterInner::All(IndexedIter::new(inner)),
+            Some(mask) => {
+                let len = inner.len();
+                ChunkViewIterInner::Masked {
+                    inner,
+                    mask,
+                    index: 0,
+                    len,
+                }
+            }
+        };
+        Self { inner }
+    }
+}
+
+impl<I: TrustedRandomAccess> Iterator for ChunkViewIter<I> {
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.inner {
+            ChunkViewIterInner::All(iter) => iter.next(),
+            ChunkViewIterInner::Masked {
+                inner,
+                mask,
+                index,
+                len,
+            } => {
+                while *index < *len {
+                    let i = *index;
+                    *index += 1;
+                    if unsafe { mask.contains_unchecked(i) } {
+                        return Some(unsafe { inner.get_unchecked(i) });
+                    }
+                }
+                None
+            }
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.inner {
+            ChunkViewIterInner::All(iter) => iter.size_hint(),
+            ChunkViewIterInner::Masked { index, len, .. } => (0, Some(len - index)),
+        }
     }
 }
 
+impl<I: TrustedRandomAccess> DoubleEndedIterator for ChunkViewIter<I> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        match &mut self.inner {
+            ChunkViewIterInner::All(iter) => iter.next_back(),
+            ChunkViewIterInner::Masked {
+                inner,
+                mask,
+                index,
+                len,
+            } => {
+                while *index < *len {
+                    *len -= 1;
+                    if unsafe { mask.contains_unchecked(*len) } {
+                        return Some(unsafe { inner.get_unchecked(*len) });
+                    }
+                }
+                None
+            }
+        }
+    }
+}
+
+impl<I: TrustedRandomAccess> std::iter::FusedIterator for ChunkViewIter<I> {}
+
 /// An iterator which yields entity chunks from a query.
 #[must_use = "iterator adaptors are lazy and do nothing unless consumed"]
 pub struct ChunkIter<'data, 'index, V, D>
@@ -885,8 +1148,14 @@ where
             let mut fetch = fetch.unwrap();
             let idx = self.indices.next().unwrap();
             if self.filter.matches_archetype(&fetch).is_pass() {
+                let archetype = &self.archetypes[*idx];
+                let mask = match select_entities(self.filter, &fetch, archetype.entities().len()) {
+                    Selection::All => None,
+                    Selection::Empty => continue,
+                    Selection::Some(mask) => Some(mask),
+                };
                 fetch.accepted();
-                return Some(ChunkView::new(&self.archetypes[*idx], fetch));
+                return Some(ChunkView::new(archetype, fetch, mask));
             }
         }
         None
@@ -956,8 +1225,15 @@ pub mod par_iter {
                 let mut fetch = fetch.unwrap();
                 let idx = self.indices.next().unwrap();
                 if filter.matches_archetype(&fetch).is_pass() {
+                    let archetype = &self.archetypes[*idx];
+                    let len = archetype.entities().len();
+                    let mask = match select_entities(&mut *filter, &fetch, len) {
+                        Selection::All => None,
+                        Selection::Empty => continue,
+                        Selection::Some(mask) => Some(mask),
+                    };
                     fetch.accepted();
-                    return Some(ChunkView::new(&self.archetypes[*idx], fetch));
+                    return Some(ChunkView::new(archetype, fetch, mask));
                 }
             }
             None
@@ -1229,4 +1505,181 @@ mod test {
                 .par_for_each(&w, |(e, x, y)| println!("{:?} {} {:?}", e, x, y));
         }
     }
+
+    #[test]
+    fn changed_filter_selects_entities() {
+        use crate::internals::query::filter::filter_fns::changed;
+        use crate::Entity;
+
+        let mut world = World::default();
+        let entities = world
+            .extend(vec![(1usize, true), (2usize, true), (3usize, false)])
+            .to_vec();
+
+        let mut query = <(Entity, Read<usize>)>::query().filter(changed::<usize>());
+        assert_eq!(query.iter(&world).count(), 3);
+        assert_eq!(query.iter(&world).count(), 0);
+
+        *world
+            .entry(entities[1])
+            .unwrap()
+            .get_component_mut::<usize>()
+            .unwrap() = 20;
+
+        let changed: Vec<_> = query.iter(&world).map(|(e, x)| (*e, *x)).collect();
+        assert_eq!(changed, vec![(entities[1], 20)]);
+        assert_eq!(query.iter(&world).count(), 0);
+    }
+
+    #[test]
+    fn changed_filter_selects_entities_in_large_chunk() {
+        use crate::internals::query::filter::filter_fns::changed;
+
+        let mut world = World::default();
+        let entities = world.extend((0..300usize).map(|i| (i, true))).to_vec();
+
+        let mut query = Read::<usize>::query().filter(changed::<usize>());
+        assert_eq!(query.iter(&world).count(), 300);
+
+        let selected = [0, 63, 64, 150, 299];
+        let mut change = |world: &mut World| {
+            for i in &selected {
+                *world
+                    .entry(entities[*i])
+                    .unwrap()
+                    .get_component_mut::<usize>()
+                    .unwrap() += 1;
+            }
+        };
+
+        change(&mut world);
+        let forward: Vec<_> = query.iter(&world).map(|x| x - 1).collect();
+        assert_eq!(forward, selected);
+
+        change(&mut world);
+        let backward: Vec<_> = query
+            .iter_chunks(&world)
+            .flat_map(|chunk| chunk.into_iter().rev())
+            .map(|x| x - 2)
+            .collect();
+        assert_eq!(backward, vec![299, 150, 64, 63, 0]);
+
+        #[cfg(feature = "parallel")]
+        {
+            use rayon::iter::ParallelIterator;
+            use std::sync::atomic::{AtomicUsize, Ordering};
+
+            change(&mut world);
+            let sum = AtomicUsize::new(0);
+            query.par_iter(&world).for_each(|x| {
+                sum.fetch_add(*x - 3, Ordering::SeqCst);
+            });
+            assert_eq!(sum.load(Ordering::SeqCst), 576);
+        }
+    }
+
+    #[test]
+    fn changed_filter_ignores_unvisited_entities() {
+        use crate::internals::query::filter::filter_fns::changed;
+        use crate::Entity;
+
+        let mut world = World::default();
+        let entities = world
+            .extend(vec![(1usize, true), (2usize, true), (3usize, false)])
+            .to_vec();
+
+        let mut query = Read::<usize>::query().filter(changed::<usize>());
+        assert_eq!(query.iter(&world).count(), 3);
+
+        // only entities yielded by a mutable iterator are marked as changed
+        let mut writer = <(Entity, Write<usize>)>::query();
+        for (entity, x) in writer.iter_mut(&mut world).take(1) {
+            assert_eq!(*entity, entities[0]);
+            *x += 10;
+        }
+
+        let changed: Vec<_> = query.iter(&world).copied().collect();
+        assert_eq!(changed, vec![11]);
+    }
+
+    #[test]
+    fn or_changed_filter() {
+        use crate::internals::query::filter::filter_fns::changed;
+
+        let mut world = World::default();
+        let entities = world
+            .extend(vec![(1usize, 1f32), (2usize, 2f32), (3usize, 3f32)])
+            .to_vec();
+
+        let mut query =
+            <(Read<usize>, Read<f32>)>::query().filter(changed::<usize>() | changed::<f32>());
+        assert_eq!(query.iter(&world).count(), 3);
+
+        let mut entry = world.entry(entities[0]).unwrap();
+        *entry.get_component_mut::<usize>().unwrap() = 10;
+        let mut entry = world.entry(entities[2]).unwrap();
+        *entry.get_component_mut::<f32>().unwrap() = 30f32;
+
+        let mut changed: Vec<_> = query.iter(&world).map(|(x, _)| *x).collect();
+        changed.sort_unstable();
+        assert_eq!(changed, vec![3, 10]);
+    }
+
+    #[test]
+    fn added_filter() {
+        use crate::internals::query::filter::filter_fns::added;
+        use crate::Entity;
+
+        let mut world = World::default();
+        let entities = world.extend(vec![(1usize,), (2usize,)]).to_vec();
+        let untagged = world.push((3f32,));
+
+        let mut query = <(Entity, Read<usize>)>::query().filter(added::<usize>());
+        assert_eq!(query.iter(&world).count(), 2);
+        assert_eq!(query.iter(&world).count(), 0);
+
+        // writing or moving the component to a new archetype does not count as adding it
+        *world
+            .entry(entities[0])
+            .unwrap()
+            .get_component_mut::<usize>()
+            .unwrap() = 10;
+        world.entry(entities[1]).unwrap().add_component(true);
+        assert_eq!(query.iter(&world).count(), 0);
+
+        world.entry(untagged).unwrap().add_component(4usize);
+        let pushed = world.push((5usize, false));
+
+        let mut added: Vec<_> = query.iter(&world).map(|(e, x)| (*e, *x)).collect();
+        added.sort_by_key(|(_, x)| *x);
+        assert_eq!(added, vec![(untagged, 4), (pushed, 5)]);
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn par_changed_filter() {
+        use crate::internals::query::filter::filter_fns::changed;
+        use std::sync::atomic::*;
+
+        let mut world = World::default();
+        let entities = world.extend((0..100usize).map(|i| (i,))).to_vec();
+
+        let mut query = Read::<usize>::query().filter(changed::<usize>());
+        query.iter(&world).count();
+
+        for entity in entities.iter().step_by(10) {
+            *world
+                .entry(*entity)
+                .unwrap()
+                .get_component_mut::<usize>()
+                .unwrap() += 1000;
+        }
+
+        let count = AtomicUsize::new(0);
+        query.par_for_each(&world, |x| {
+            assert!(*x >= 1000);
+            count.fetch_add(1, Ordering::SeqCst);
+        });
+        assert_eq!(count.load(Ordering::SeqCst), 10);
+    }
 }
@@ -12,7 +12,7 @@ use crate::internals::{
     storage::{
         archetype::{Archetype, ArchetypeIndex},
         component::{Component, ComponentTypeId},
-        Components,
+        ComponentTicks, Components,
     },
     subworld::ComponentAccess,
 };
@@ -151,6 +151,11 @@ impl<'a> Fetch for EntityFetch<'a> {
         None
     }
 
+    #[inline]
+    fn ticks<C: Component>(&self) -> Option<&[ComponentTicks]> {
+        None
+    }
+
     #[inline]
     fn accepted(&mut self) {}
 }
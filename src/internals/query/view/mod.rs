@@ -14,7 +14,7 @@ use crate::internals::{
     storage::{
         archetype::Archetype,
         component::{Component, ComponentTypeId},
-        Components,
+        ComponentTicks, Components,
     },
     subworld::ComponentAccess,
 };
@@ -135,6 +135,10 @@ pub trait Fetch: IntoIndexableIter + Send + Sync {
     /// if this fetch contains the requested component type.
     fn version<T: Component>(&self) -> Option<u64>;
 
+    /// Tries to find the change tick of each component in the slice of a component type,
+    /// if this fetch contains the requested component type.
+    fn ticks<T: Component>(&self) -> Option<&[ComponentTicks]>;
+
     /// Indicates that the archetype is going to be provided to the user.
     /// Component slice versions are incremented here.
     fn accepted(&mut self);
@@ -362,6 +366,17 @@ macro_rules! impl_view_tuple {
                 result
             }
 
+            #[inline]
+            fn ticks<Comp: Component>(&self) -> Option<&[ComponentTicks]> {
+                #[allow(non_snake_case)]
+                let ($( $ty, )*) = &self.fetches;
+                let mut result = None;
+                $(
+                    result = result.or_else(|| $ty.ticks::<Comp>());
+                )*
+                result
+            }
+
             #[inline]
             fn accepted(&mut self) {
                 #[allow(non_snake_case)]
@@ -11,7 +11,7 @@ use crate::internals::{
     storage::{
         archetype::{Archetype, ArchetypeIndex},
         component::{Component, ComponentTypeId},
-        ComponentSlice, ComponentStorage, Components,
+        ComponentSlice, ComponentStorage, ComponentTicks, Components,
     },
     subworld::ComponentAccess,
 };
@@ -138,6 +138,7 @@ impl<'a, T: Component> Iterator for ReadIter<'a, T> {
 pub struct ReadFetch<'a, T: Component> {
     version: &'a u64,
     components: &'a [T],
+    ticks: &'a [ComponentTicks],
 }
 
 impl<'a, T: Component> From<ComponentSlice<'a, T>> for ReadFetch<'a, T> {
@@ -145,6 +146,7 @@ impl<'a, T: Component> From<ComponentSlice<'a, T>> for ReadFetch<'a, T> {
         ReadFetch {
             components: slice.components,
             version: slice.version,
+            ticks: slice.ticks,
         }
     }
 }
@@ -211,6 +213,15 @@ impl<'a, T: Component> Fetch for ReadFetch<'a, T> {
         }
     }
 
+    #[inline]
+    fn ticks<C: Component>(&self) -> Option<&[ComponentTicks]> {
+        if TypeId::of::<C>() == TypeId::of::<T>() {
+            Some(self.ticks)
+        } else {
+            None
+        }
+    }
+
     #[inline]
     fn accepted(&mut self) {}
 }
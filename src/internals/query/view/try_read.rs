@@ -2,6 +2,7 @@
 
 use super::{DefaultFilter, Fetch, IntoIndexableIter, IntoView, ReadOnly, ReadOnlyFetch, View};
 use crate::internals::{
+    entity::Entity,
     iter::indexed::{IndexedIter, TrustedRandomAccess},
     permissions::Permissions,
     query::{
@@ -11,7 +12,8 @@ use crate::internals::{
     storage::{
         archetype::{Archetype, ArchetypeIndex},
         component::{Component, ComponentTypeId},
-        ComponentSlice, ComponentStorage, Components,
+        sparse::SparseStorage,
+        ComponentSlice, ComponentStorage, ComponentTicks, Components,
     },
     subworld::ComponentAccess,
 };
@@ -82,10 +84,12 @@ impl<'data, T: Component> View<'data> for TryRead<T> {
         archetypes: &'data [Archetype],
         query: QueryResult<'data>,
     ) -> Self::Iter {
+        let sparse = components.sparse().get_downcast::<T>();
         let components = components.get_downcast::<T>();
         let archetype_indexes = query.index().iter();
         TryReadIter {
             components,
+            sparse,
             archetypes,
             archetype_indexes,
         }
@@ -95,6 +99,7 @@ impl<'data, T: Component> View<'data> for TryRead<T> {
 #[doc(hidden)]
 pub struct TryReadIter<'a, T: Component> {
     components: Option<&'a T::Storage>,
+    sparse: Option<&'a SparseStorage<T>>,
     archetype_indexes: std::slice::Iter<'a, ArchetypeIndex>,
     archetypes: &'a [Archetype],
 }
@@ -105,6 +110,11 @@ impl<'a, T: Component> Iterator for TryReadIter<'a, T> {
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
         self.archetype_indexes.next().map(|i| {
+            if let Some(storage) = self.sparse {
+                let entities = self.archetypes[*i].entities();
+                return Some(Slice::Sparse { entities, storage });
+            }
+
             let slice = self
                 .components
                 .and_then(|components| components.get(*i))
@@ -122,6 +132,11 @@ pub enum Slice<'a, T: Component> {
     Occupied {
         version: &'a u64,
         components: &'a [T],
+        ticks: &'a [ComponentTicks],
+    },
+    Sparse {
+        entities: &'a [Entity],
+        storage: &'a SparseStorage<T>,
     },
     Empty(usize),
 }
@@ -131,6 +146,7 @@ impl<'a, T: Component> From<ComponentSlice<'a, T>> for Slice<'a, T> {
         Slice::Occupied {
             components: slice.components,
             version: slice.version,
+            ticks: slice.ticks,
         }
     }
 }
@@ -142,6 +158,7 @@ impl<'a, T: Component> IntoIndexableIter for Slice<'a, T> {
     fn into_indexable_iter(self) -> Self::IntoIter {
         let data = match self {
             Self::Occupied { components, .. } => Data::Occupied(components),
+            Self::Sparse { entities, storage } => Data::Sparse(entities, storage),
             Self::Empty(count) => Data::Empty(count),
         };
         IndexedIter::new(data)
@@ -161,7 +178,7 @@ unsafe impl<'a, T: Component> ReadOnlyFetch for Slice<'a, T> {
     fn get_components(&self) -> Self::Data {
         match self {
             Self::Occupied { components, .. } => Some(components),
-            Self::Empty(_) => None,
+            Self::Sparse { .. } | Self::Empty(_) => None,
         }
     }
 }
@@ -173,7 +190,7 @@ impl<'a, T: Component> Fetch for Slice<'a, T> {
     fn into_components(self) -> Self::Data {
         match self {
             Self::Occupied { components, .. } => Some(components),
-            Self::Empty(_) => None,
+            Self::Sparse { .. } | Self::Empty(_) => None,
         }
     }
 
@@ -185,7 +202,7 @@ impl<'a, T: Component> Fetch for Slice<'a, T> {
                 Self::Occupied { components, .. } => Some(unsafe {
                     std::slice::from_raw_parts(components.as_ptr() as *const C, components.len())
                 }),
-                Self::Empty(_) => None,
+                Self::Sparse { .. } | Self::Empty(_) => None,
             }
         } else {
             None
@@ -202,7 +219,19 @@ impl<'a, T: Component> Fetch for Slice<'a, T> {
         if TypeId::of::<C>() == TypeId::of::<T>() {
             match self {
                 Self::Occupied { version, .. } => Some(**version),
-                Self::Empty(_) => None,
+                Self::Sparse { .. } | Self::Empty(_) => None,
+            }
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn ticks<C: Component>(&self) -> Option<&[ComponentTicks]> {
+        if TypeId::of::<C>() == TypeId::of::<T>() {
+            match self {
+                Self::Occupied { ticks, .. } => Some(ticks),
+                Self::Sparse { .. } | Self::Empty(_) => None,
             }
         } else {
             None
@@ -216,6 +245,7 @@ impl<'a, T: Component> Fetch for Slice<'a, T> {
 #[doc(hidden)]
 pub enum Data<'a, T: Component> {
     Occupied(&'a [T]),
+    Sparse(&'a [Entity], &'a SparseStorage<T>),
     Empty(usize),
 }
 
@@ -226,6 +256,7 @@ unsafe impl<'a, T: Component> TrustedRandomAccess for Data<'a, T> {
     fn len(&self) -> usize {
         match self {
             Self::Occupied(slice) => slice.len(),
+            Self::Sparse(entities, _) => entities.len(),
             Self::Empty(len) => *len,
         }
     }
@@ -234,6 +265,7 @@ unsafe impl<'a, T: Component> TrustedRandomAccess for Data<'a, T> {
     unsafe fn get_unchecked(&mut self, i: usize) -> Self::Item {
         match self {
             Self::Occupied(slice) => Some(slice.get_unchecked(i)),
+            Self::Sparse(entities, storage) => storage.get(*entities.get_unchecked(i)),
             Self::Empty(_) => None,
         }
     }
@@ -245,6 +277,10 @@ unsafe impl<'a, T: Component> TrustedRandomAccess for Data<'a, T> {
                 let (left, right) = slice.split_at(index);
                 (Self::Occupied(left), Self::Occupied(right))
             }
+            Self::Sparse(entities, storage) => {
+                let (left, right) = entities.split_at(index);
+                (Self::Sparse(left, storage), Self::Sparse(right, storage))
+            }
             Self::Empty(count) => {
                 debug_assert!(index < count);
                 (Self::Empty(index), Self::Empty(count - index))
@@ -1,7 +1,8 @@
 #![doc(hidden)]
 
-use super::{DefaultFilter, Fetch, IntoIndexableIter, IntoView, View};
+use super::{write::WriteSlice, DefaultFilter, Fetch, IntoIndexableIter, IntoView, View};
 use crate::internals::{
+    entity::Entity,
     iter::indexed::{IndexedIter, TrustedRandomAccess},
     permissions::Permissions,
     query::{
@@ -11,7 +12,9 @@ use crate::internals::{
     storage::{
         archetype::{Archetype, ArchetypeIndex},
         component::{Component, ComponentTypeId},
-        next_component_version, ComponentSliceMut, ComponentStorage, Components,
+        next_component_version,
+        sparse::SparseStorage,
+        ComponentSliceMut, ComponentStorage, ComponentTicks, Components,
     },
     subworld::ComponentAccess,
 };
@@ -81,10 +84,12 @@ impl<'data, T: Component> View<'data> for TryWrite<T> {
         archetypes: &'data [Archetype],
         query: QueryResult<'data>,
     ) -> Self::Iter {
+        let sparse = components.sparse().get_downcast::<T>();
         let components = components.get_downcast::<T>();
         let archetype_indexes = query.index().iter();
         TryWriteIter {
             components,
+            sparse,
             archetypes,
             archetype_indexes,
         }
@@ -94,6 +99,7 @@ impl<'data, T: Component> View<'data> for TryWrite<T> {
 #[doc(hidden)]
 pub struct TryWriteIter<'a, T: Component> {
     components: Option<&'a T::Storage>,
+    sparse: Option<&'a SparseStorage<T>>,
     archetype_indexes: std::slice::Iter<'a, ArchetypeIndex>,
     archetypes: &'a [Archetype],
 }
@@ -104,6 +110,11 @@ impl<'a, T: Component> Iterator for TryWriteIter<'a, T> {
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
         self.archetype_indexes.next().map(|i| unsafe {
+            if let Some(storage) = self.sparse {
+                let entities = self.archetypes[*i].entities();
+                return Some(Slice::Sparse { entities, storage });
+            }
+
             let slice = self
                 .components
                 .and_then(|components| components.get_mut(*i))
@@ -121,8 +132,13 @@ pub enum Slice<'a, T: Component> {
     Occupied {
         version: &'a mut u64,
         components: &'a mut [T],
+        ticks: &'a mut [ComponentTicks],
         next_version: u64,
     },
+    Sparse {
+        entities: &'a [Entity],
+        storage: &'a SparseStorage<T>,
+    },
     Empty(usize),
 }
 
@@ -131,18 +147,40 @@ impl<'a, T: Component> From<ComponentSliceMut<'a, T>> for Slice<'a, T> {
         Slice::Occupied {
             components: slice.components,
             version: slice.version,
+            ticks: slice.ticks,
             next_version: next_component_version(),
         }
     }
 }
 
+impl<'a, T: Component> Slice<'a, T> {
+    fn mark_all_changed(&mut self) {
+        if let Self::Occupied {
+            ticks,
+            next_version,
+            ..
+        } = self
+        {
+            for tick in ticks.iter_mut() {
+                tick.changed = *next_version;
+            }
+        }
+    }
+}
+
 impl<'a, T: Component> IntoIndexableIter for Slice<'a, T> {
     type Item = Option<&'a mut T>;
     type IntoIter = IndexedIter<Data<'a, T>>;
 
     fn into_indexable_iter(self) -> Self::IntoIter {
         let data = match self {
-            Self::Occupied { components, .. } => Data::Occupied(components),
+            Self::Occupied {
+                components,
+                ticks,
+                next_version,
+                ..
+            } => Data::Occupied(WriteSlice::new(components, ticks, next_version)),
+            Self::Sparse { entities, storage } => Data::Sparse(entities, storage),
             Self::Empty(count) => Data::Empty(count),
         };
         IndexedIter::new(data)
@@ -162,10 +200,11 @@ impl<'a, T: Component> Fetch for Slice<'a, T> {
     type Data = Option<&'a mut [T]>;
 
     #[inline]
-    fn into_components(self) -> Self::Data {
+    fn into_components(mut self) -> Self::Data {
+        self.mark_all_changed();
         match self {
             Self::Occupied { components, .. } => Some(components),
-            Self::Empty(_) => None,
+            Self::Sparse { .. } | Self::Empty(_) => None,
         }
     }
 
@@ -177,7 +216,7 @@ impl<'a, T: Component> Fetch for Slice<'a, T> {
                 Self::Occupied { components, .. } => Some(unsafe {
                     std::slice::from_raw_parts(components.as_ptr() as *const C, components.len())
                 }),
-                Self::Empty(_) => None,
+                Self::Sparse { .. } | Self::Empty(_) => None,
             }
         } else {
             None
@@ -187,6 +226,8 @@ impl<'a, T: Component> Fetch for Slice<'a, T> {
     #[inline]
     fn find_mut<C: 'static>(&mut self) -> Option<&mut [C]> {
         if TypeId::of::<C>() == TypeId::of::<T>() {
+            self.mark_all_changed();
+
             // safety: C and T are the same type
             match self {
                 Self::Occupied { components, .. } => Some(unsafe {
@@ -195,7 +236,7 @@ impl<'a, T: Component> Fetch for Slice<'a, T> {
                         components.len(),
                     )
                 }),
-                Self::Empty(_) => None,
+                Self::Sparse { .. } | Self::Empty(_) => None,
             }
         } else {
             None
@@ -207,7 +248,19 @@ impl<'a, T: Component> Fetch for Slice<'a, T> {
         if TypeId::of::<C>() == TypeId::of::<T>() {
             match self {
                 Self::Occupied { version, .. } => Some(**version),
-                Self::Empty(_) => None,
+                Self::Sparse { .. } | Self::Empty(_) => None,
+            }
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn ticks<C: Component>(&self) -> Option<&[ComponentTicks]> {
+        if TypeId::of::<C>() == TypeId::of::<T>() {
+            match self {
+                Self::Occupied { ticks, .. } => Some(ticks),
+                Self::Sparse { .. } | Self::Empty(_) => None,
             }
         } else {
             None
@@ -229,7 +282,8 @@ impl<'a, T: Component> Fetch for Slice<'a, T> {
 
 #[doc(hidden)]
 pub enum Data<'a, T: Component> {
-    Occupied(&'a mut [T]),
+    Occupied(WriteSlice<'a, T>),
+    Sparse(&'a [Entity], &'a SparseStorage<T>),
     Empty(usize),
 }
 
@@ -240,6 +294,7 @@ unsafe impl<'a, T: Component> TrustedRandomAccess for Data<'a, T> {
     fn len(&self) -> usize {
         match self {
             Self::Occupied(slice) => slice.len(),
+            Self::Sparse(entities, _) => entities.len(),
             Self::Empty(len) => *len,
         }
     }
@@ -248,6 +303,7 @@ unsafe impl<'a, T: Component> TrustedRandomAccess for Data<'a, T> {
     unsafe fn get_unchecked(&mut self, i: usize) -> Self::Item {
         match self {
             Self::Occupied(slice) => Some(slice.get_unchecked(i)),
+            Self::Sparse(entities, storage) => storage.get_unchecked(*entities.get_unchecked(i)),
             Self::Empty(_) => None,
         }
     }
@@ -256,9 +312,13 @@ unsafe impl<'a, T: Component> TrustedRandomAccess for Data<'a, T> {
     fn split_at(self, index: usize) -> (Self, Self) {
         match self {
             Self::Occupied(slice) => {
-                let (left, right) = slice.split_at_mut(index);
+                let (left, right) = slice.split_at(index);
                 (Self::Occupied(left), Self::Occupied(right))
             }
+            Self::Sparse(entities, storage) => {
+                let (left, right) = entities.split_at(index);
+                (Self::Sparse(left, storage), Self::Sparse(right, storage))
+            }
             Self::Empty(count) => (Self::Empty(index), Self::Empty(count - index)),
         }
     }
@@ -2,7 +2,7 @@
 
 use super::{DefaultFilter, Fetch, IntoIndexableIter, IntoView, View};
 use crate::internals::{
-    iter::indexed::IndexedIter,
+    iter::indexed::{IndexedIter, TrustedRandomAccess},
     permissions::Permissions,
     query::{
         filter::{component::ComponentFilter, passthrough::Passthrough, EntityFilterTuple},
@@ -11,7 +11,8 @@ use crate::internals::{
     storage::{
         archetype::{Archetype, ArchetypeIndex},
         component::{Component, ComponentTypeId},
-        next_component_version, ComponentSliceMut, ComponentStorage, Components,
+        next_component_version, ComponentSliceMut, ComponentStorage, ComponentTicks, Components,
+        Version,
     },
     subworld::ComponentAccess,
 };
@@ -137,6 +138,7 @@ impl<'a, T: Component> Iterator for WriteIter<'a, T> {
 pub struct WriteFetch<'a, T: Component> {
     version: &'a mut u64,
     components: &'a mut [T],
+    ticks: &'a mut [ComponentTicks],
     next_version: u64,
 }
 
@@ -145,17 +147,30 @@ impl<'a, T: Component> From<ComponentSliceMut<'a, T>> for WriteFetch<'a, T> {
         WriteFetch {
             components: slice.components,
             version: slice.version,
+            ticks: slice.ticks,
             next_version: next_component_version(),
         }
     }
 }
 
+impl<'a, T: Component> WriteFetch<'a, T> {
+    fn mark_all_changed(&mut self) {
+        for tick in self.ticks.iter_mut() {
+            tick.changed = self.next_version;
+        }
+    }
+}
+
 impl<'a, T: Component> IntoIndexableIter for WriteFetch<'a, T> {
     type Item = &'a mut T;
-    type IntoIter = IndexedIter<&'a mut [T]>;
+    type IntoIter = IndexedIter<WriteSlice<'a, T>>;
 
     fn into_indexable_iter(self) -> Self::IntoIter {
-        IndexedIter::new(self.components)
+        IndexedIter::new(WriteSlice::new(
+            self.components,
+            self.ticks,
+            self.next_version,
+        ))
     }
 }
 
@@ -172,7 +187,8 @@ impl<'a, T: Component> Fetch for WriteFetch<'a, T> {
     type Data = &'a mut [T];
 
     #[inline]
-    fn into_components(self) -> Self::Data {
+    fn into_components(mut self) -> Self::Data {
+        self.mark_all_changed();
         self.components
     }
 
@@ -194,6 +210,8 @@ impl<'a, T: Component> Fetch for WriteFetch<'a, T> {
     #[inline]
     fn find_mut<C: 'static>(&mut self) -> Option<&mut [C]> {
         if TypeId::of::<C>() == TypeId::of::<T>() {
+            self.mark_all_changed();
+
             // safety: C and T are the same type
             Some(unsafe {
                 std::slice::from_raw_parts_mut(
@@ -215,8 +233,65 @@ impl<'a, T: Component> Fetch for WriteFetch<'a, T> {
         }
     }
 
+    #[inline]
+    fn ticks<C: Component>(&self) -> Option<&[ComponentTicks]> {
+        if TypeId::of::<C>() == TypeId::of::<T>() {
+            Some(self.ticks)
+        } else {
+            None
+        }
+    }
+
     #[inline]
     fn accepted(&mut self) {
         *self.version = self.next_version;
     }
 }
+
+/// A mutable slice of components which records a change tick for each component as it is accessed.
+#[doc(hidden)]
+pub struct WriteSlice<'a, T> {
+    components: &'a mut [T],
+    ticks: &'a mut [ComponentTicks],
+    tick: Version,
+}
+
+impl<'a, T> WriteSlice<'a, T> {
+    pub(crate) fn new(
+        components: &'a mut [T],
+        ticks: &'a mut [ComponentTicks],
+        tick: Version,
+    ) -> Self {
+        debug_assert_eq!(components.len(), ticks.len());
+        Self {
+            components,
+            ticks,
+            tick,
+        }
+    }
+}
+
+unsafe impl<'a, T> TrustedRandomAccess for WriteSlice<'a, T> {
+    type Item = &'a mut T;
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.components.len()
+    }
+
+    #[inline]
+    unsafe fn get_unchecked(&mut self, i: usize) -> Self::Item {
+        self.ticks.get_unchecked_mut(i).changed = self.tick;
+        &mut *self.components.as_mut_ptr().add(i)
+    }
+
+    #[inline]
+    fn split_at(self, index: usize) -> (Self, Self) {
+        let (left, right) = self.components.split_at_mut(index);
+        let (left_ticks, right_ticks) = self.ticks.split_at_mut(index);
+        (
+            Self::new(left, left_ticks, self.tick),
+            Self::new(right, right_ticks, self.tick),
+        )
+    }
+}
@@ -1,12 +1,8 @@
 pub mod ser {
     use crate::internals::{
         query::filter::LayoutFilter,
-        serialize::{ser::WorldSerializer, UnknownType},
-        storage::{
-            archetype::{Archetype, ArchetypeIndex},
-            component::ComponentTypeId,
-            UnknownComponentStorage,
-        },
+        serialize::{id::serialized_order, ser::WorldSerializer, EntityGroup, UnknownType},
+        storage::component::ComponentTypeId,
         world::World,
     };
     use itertools::Itertools;
@@ -20,6 +16,7 @@ pub mod ser {
         pub world_serializer: &'a W,
         pub world: &'a World,
         pub filter: &'a F,
+        pub canonical: bool,
     }
 
     impl<'a, W: WorldSerializer, F: LayoutFilter> Serialize for ArchetypeLayoutSerializer<'a, W, F> {
@@ -27,20 +24,18 @@ pub mod ser {
         where
             S: Serializer,
         {
-            let archetypes = self
-                .world
-                .archetypes()
-                .iter()
-                .filter(|arch| {
-                    self.filter
-                        .matches_layout(arch.layout().component_types())
-                        .is_pass()
+            let mut archetypes = EntityGroup::split(self.world, self.filter)
+                .into_iter()
+                .filter(|group| !self.canonical || group.len() > 0)
+                .map(|group| {
+                    let order = group.indices.clone();
+                    (group, order)
                 })
                 .collect_vec();
 
             let component_types = archetypes
                 .iter()
-                .flat_map(|arch| arch.layout().component_types())
+                .flat_map(|(group, _)| group.component_types())
                 .unique();
             let mut type_mappings = HashMap::new();
             for id in component_types {
@@ -60,14 +55,60 @@ pub mod ser {
                 }
             }
 
+            if self.canonical {
+                // order entities within each archetype, then order archetypes by their type keys,
+                // breaking ties between archetypes with the same keys by their first entity
+                for (group, order) in &mut archetypes {
+                    let indices = group.indices();
+                    let entities = group.archetype.entities();
+                    let ids = indices.iter().map(|i| entities[*i]).collect_vec();
+                    *order = Some(
+                        serialized_order(&ids)
+                            .into_iter()
+                            .map(|i| indices[i])
+                            .collect(),
+                    );
+                }
+                let first_entities = archetypes
+                    .iter()
+                    .map(|(group, order)| group.archetype.entities()[order.as_ref().unwrap()[0]])
+                    .collect_vec();
+                let mut rank = vec![0; archetypes.len()];
+                for (i, index) in serialized_order(&first_entities).into_iter().enumerate() {
+                    rank[index] = i;
+                }
+                let mut keyed = archetypes
+                    .into_iter()
+                    .zip(rank)
+                    .map(|(archetype, rank)| {
+                        let keys = archetype
+                            .0
+                            .component_types()
+                            .filter_map(|type_id| type_mappings.get(type_id))
+                            .sorted()
+                            .collect_vec();
+                        (keys, rank, archetype)
+                    })
+                    .collect_vec();
+                keyed.sort_by(|(a_keys, a_rank, _), (b_keys, b_rank, _)| {
+                    a_keys.cmp(b_keys).then(a_rank.cmp(b_rank))
+                });
+                archetypes = keyed
+                    .into_iter()
+                    .map(|(_, _, archetype)| archetype)
+                    .collect_vec();
+            }
+
             let mut root = serializer.serialize_seq(Some(archetypes.len()))?;
 
-            for archetype in archetypes {
+            for (group, order) in &archetypes {
                 root.serialize_element(&SerializableArchetype {
                     world_serializer: self.world_serializer,
                     world: self.world,
                     type_mappings: &type_mappings,
-                    archetype,
+                    group,
+                    order: order.as_deref(),
+                    canonical: self.canonical,
                 })?;
             }
 
@@ -79,7 +120,9 @@ pub mod ser {
         world_serializer: &'a W,
         world: &'a World,
         type_mappings: &'a HashMap<ComponentTypeId, W::TypeId>,
-        archetype: &'a Archetype,
+        group: &'a EntityGroup<'a>,
+        order: Option<&'a [usize]>,
+        canonical: bool,
     }
 
     impl<'a, W: WorldSerializer> Serialize for SerializableArchetype<'a, W> {
@@ -89,17 +132,30 @@ pub mod ser {
         {
             let mut root = serializer.serialize_struct("archetype", 3)?;
 
-            let mut component_type_ids = Vec::new();
-            let mut component_external_ids = Vec::new();
-            for type_id in self.archetype.layout().component_types() {
-                if let Some(mapped) = self.type_mappings.get(type_id) {
-                    component_type_ids.push(*type_id);
-                    component_external_ids.push(mapped);
-                }
+            let mut components = self
+                .group
+                .component_types()
+                .filter_map(|type_id| {
+                    self.type_mappings
+                        .get(type_id)
+                        .map(|mapped| (*type_id, mapped))
+                })
+                .collect_vec();
+            if self.canonical {
+                components.sort_by_key(|(_, a)| *a);
             }
+            let (component_type_ids, component_external_ids): (Vec<_>, Vec<_>) =
+                components.into_iter().unzip();
 
             root.serialize_field("_layout", &component_external_ids)?;
-            root.serialize_field("entities", self.archetype.entities())?;
+            match self.order {
+                Some(order) => {
+                    let entities = self.group.archetype.entities();
+                    let entities = order.iter().map(|i| entities[*i]).collect_vec();
+                    root.serialize_field("entities", &entities)?;
+                }
+                None => root.serialize_field("entities", self.group.archetype.entities())?,
+            }
             root.serialize_field(
                 "components",
                 &SerializableArchetypeComponents {
@@ -107,7 +163,8 @@ pub mod ser {
                     world: self.world,
                     component_external_ids,
                     component_type_ids,
-                    archetype: self.archetype.index(),
+                    group: self.group,
+                    order: self.order,
                 },
             )?;
 
@@ -120,7 +177,8 @@ pub mod ser {
         world: &'a World,
         component_type_ids: Vec<ComponentTypeId>,
         component_external_ids: Vec<&'a W::TypeId>,
-        archetype: ArchetypeIndex,
+        group: &'a EntityGroup<'a>,
+        order: Option<&'a [usize]>,
     }
 
     impl<'a, W: WorldSerializer> Serialize for SerializableArchetypeComponents<'a, W> {
@@ -128,8 +186,6 @@ pub mod ser {
         where
             S: Serializer,
         {
-            let components = self.world.components();
-
             let mut root = serializer.serialize_map(Some(self.component_external_ids.len()))?;
 
             for (i, external_id) in self.component_external_ids.iter().enumerate() {
@@ -138,9 +194,10 @@ pub mod ser {
                     external_id,
                     &SerializableComponentSlice {
                         world_serializer: self.world_serializer,
-                        storage: components.get(type_id).unwrap(),
-                        archetype: self.archetype,
+                        world: self.world,
+                        group: self.group,
                         type_id,
+                        order: self.order,
                     },
                 )?;
             }
@@ -151,23 +208,61 @@ pub mod ser {
 
     struct SerializableComponentSlice<'a, W: WorldSerializer> {
         world_serializer: &'a W,
-        storage: &'a dyn UnknownComponentStorage,
-        archetype: ArchetypeIndex,
+        world: &'a World,
+        group: &'a EntityGroup<'a>,
         type_id: ComponentTypeId,
+        order: Option<&'a [usize]>,
     }
 
     impl<'a, W: WorldSerializer> Serialize for SerializableComponentSlice<'a, W> {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            match self.order {
+                None if self.group.is_slice(self.type_id) => unsafe {
+                    self.world_serializer.serialize_component_slice(
+                        self.type_id,
+                        self.world.components().get(self.type_id).unwrap(),
+                        self.group.archetype.index(),
+                        serializer,
+                    )
+                },
+                order => {
+                    // serialize each component individually in entity order, which is
+                    // equivalent to serializing the reordered slice
+                    let indices = match order {
+                        Some(order) => order.to_vec(),
+                        None => self.group.indices(),
+                    };
+                    let mut seq = serializer.serialize_seq(Some(indices.len()))?;
+                    for index in indices {
+                        seq.serialize_element(&SerializableComponent {
+                            world_serializer: self.world_serializer,
+                            type_id: self.type_id,
+                            ptr: self.group.component_ptr(self.world, self.type_id, index),
+                        })?;
+                    }
+                    seq.end()
+                }
+            }
+        }
+    }
+
+    struct SerializableComponent<'a, W: WorldSerializer> {
+        world_serializer: &'a W,
+        type_id: ComponentTypeId,
+        ptr: *const u8,
+    }
+
+    impl<'a, W: WorldSerializer> Serialize for SerializableComponent<'a, W> {
         fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
         where
             S: Serializer,
         {
             unsafe {
-                self.world_serializer.serialize_component_slice(
-                    self.type_id,
-                    self.storage,
-                    self.archetype,
-                    serializer,
-                )
+                self.world_serializer
+                    .serialize_component(self.type_id, self.ptr, serializer)
             }
         }
     }
@@ -176,7 +271,7 @@ pub mod ser {
 pub mod de {
     use crate::{
         internals::{
-            serialize::de::WorldDeserializer,
+            serialize::{de::WorldDeserializer, MAX_PREALLOCATION},
             storage::{archetype::EntityLayout, component::ComponentTypeId},
             world::World,
         },
@@ -537,7 +632,7 @@ pub mod de {
                     V: SeqAccess<'de>,
                 {
                     if let Some(len) = seq.size_hint() {
-                        self.writer.reserve(len);
+                        self.writer.reserve(len.min(MAX_PREALLOCATION));
                     }
 
                     while let Some(entity) = seq.next_element()? {
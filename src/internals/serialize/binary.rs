@@ -0,0 +1,1211 @@
+//! A compact world format for binary serializers, which writes the components of
+//! [plain old data](../struct.Registry.html#method.register_pod) types as raw byte blocks.
+
+use super::{
+    de::WorldDeserializer, id::run_as_context, schema::run_with_versions, ser::WorldSerializer,
+    CustomEntitySerializer, EntityGroup, Registry, TypeKey, UnknownType,
+};
+use crate::internals::{
+    entity::Entity,
+    insert::{
+        ArchetypeSource, ArchetypeWriter, ComponentSource, IntoComponentSource,
+        UnknownComponentWriter,
+    },
+    query::filter::LayoutFilter,
+    storage::{
+        archetype::{Archetype, ArchetypeIndex, EntityLayout},
+        component::ComponentTypeId,
+    },
+    world::World,
+};
+use serde::{
+    de::{DeserializeSeed, IgnoredAny, SeqAccess, Visitor},
+    ser::{SerializeSeq, SerializeStruct},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::{alloc::Layout, collections::HashMap, marker::PhantomData, rc::Rc};
+
+/// The version of the binary world format written by this version of legion.
+const FORMAT_VERSION: u32 = 2;
+
+/// The memory layout of a plain old data component type.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct PodLayout {
+    pub(crate) size: u64,
+    pub(crate) align: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Header {
+    format_version: u32,
+    little_endian: bool,
+}
+
+impl Header {
+    fn native() -> Self {
+        Self {
+            format_version: FORMAT_VERSION,
+            little_endian: cfg!(target_endian = "little"),
+        }
+    }
+}
+
+/// Describes one column of components in a serialized archetype.
+#[derive(Serialize)]
+#[serde(rename = "Column")]
+struct ColumnRef<'a, K> {
+    key: &'a K,
+    version: u32,
+    pod: Option<PodLayout>,
+}
+
+#[derive(Deserialize)]
+struct Column<K> {
+    key: K,
+    version: u32,
+    pod: Option<PodLayout>,
+}
+
+/// A representation of a world in legion's binary world format, returned by
+/// [Registry::as_serializable_binary](struct.Registry.html#method.as_serializable_binary).
+///
+/// The format begins with a header which records the format version and the endianness of the
+/// machine which wrote it, followed by each archetype's layout, entities and component slices.
+/// Slices of components registered with
+/// [Registry::register_pod](struct.Registry.html#method.register_pod) are written as raw byte
+/// blocks, which are copied directly into the world's storage when loaded. All other components
+/// are serialized through their serde implementations.
+///
+/// Each column records the [schema version](struct.Registry.html#method.register_versioned) of
+/// its component type. Serde columns written with a different version are upgraded with the
+/// registered migrations. Raw blocks cannot be migrated, so they fail to load if their version
+/// differs from the registered version.
+///
+/// The format is intended for non-self-describing binary serializers, such as bincode, which
+/// write byte blocks contiguously.
+pub struct SerializableBinaryWorld<
+    'a,
+    F: LayoutFilter,
+    T: TypeKey,
+    S: CustomEntitySerializer + 'static,
+> {
+    registry: &'a Registry<T, S>,
+    world: &'a World,
+    filter: F,
+}
+
+impl<'a, F: LayoutFilter, T: TypeKey, S: CustomEntitySerializer + 'static>
+    SerializableBinaryWorld<'a, F, T, S>
+{
+    pub(crate) fn new(registry: &'a Registry<T, S>, world: &'a World, filter: F) -> Self {
+        Self {
+            registry,
+            world,
+            filter,
+        }
+    }
+}
+
+impl<'a, F: LayoutFilter, T: TypeKey, S: CustomEntitySerializer + 'static> Serialize
+    for SerializableBinaryWorld<'a, F, T, S>
+{
+    fn serialize<Ser>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error>
+    where
+        Ser: Serializer,
+    {
+        use serde::ser::Error;
+
+        let mut archetypes = Vec::new();
+        for group in EntityGroup::split(self.world, &self.filter) {
+            let mut columns = Vec::new();
+            for type_id in group.component_types() {
+                match self.registry.map_id(*type_id) {
+                    Ok(_) => columns.push(*type_id),
+                    Err(UnknownType::Ignore) => {}
+                    Err(UnknownType::Error) => {
+                        return Err(Ser::Error::custom(format!(
+                            "unknown component type {:?}",
+                            type_id
+                        )));
+                    }
+                }
+            }
+            archetypes.push((group, columns));
+        }
+
+        let canon = &self.registry.canon;
+        run_as_context(&canon, || {
+            let mut root = serializer.serialize_struct("BinaryWorld", 2)?;
+            root.serialize_field("header", &Header::native())?;
+            root.serialize_field(
+                "archetypes",
+                &SerializableArchetypes {
+                    registry: self.registry,
+                    world: self.world,
+                    archetypes: &archetypes,
+                },
+            )?;
+            root.end()
+        })
+    }
+}
+
+struct SerializableArchetypes<'a, T: TypeKey, S: CustomEntitySerializer + 'static> {
+    registry: &'a Registry<T, S>,
+    world: &'a World,
+    archetypes: &'a [(EntityGroup<'a>, Vec<ComponentTypeId>)],
+}
+
+impl<'a, T: TypeKey, S: CustomEntitySerializer + 'static> Serialize
+    for SerializableArchetypes<'a, T, S>
+{
+    fn serialize<Ser>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error>
+    where
+        Ser: Serializer,
+    {
+        let mut seq = serializer.serialize_seq(Some(self.archetypes.len()))?;
+        for (group, columns) in self.archetypes {
+            seq.serialize_element(&SerializableArchetype {
+                registry: self.registry,
+                world: self.world,
+                group,
+                columns,
+            })?;
+        }
+        seq.end()
+    }
+}
+
+struct SerializableArchetype<'a, T: TypeKey, S: CustomEntitySerializer + 'static> {
+    registry: &'a Registry<T, S>,
+    world: &'a World,
+    group: &'a EntityGroup<'a>,
+    columns: &'a [ComponentTypeId],
+}
+
+impl<'a, T: TypeKey, S: CustomEntitySerializer + 'static> Serialize
+    for SerializableArchetype<'a, T, S>
+{
+    fn serialize<Ser>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error>
+    where
+        Ser: Serializer,
+    {
+        let layout = self
+            .columns
+            .iter()
+            .map(|type_id| ColumnRef {
+                key: &self.registry.serialize_fns[type_id].0,
+                version: self.registry.component_version(*type_id),
+                pod: self.registry.pod.get(type_id).copied(),
+            })
+            .collect::<Vec<_>>();
+
+        let mut root = serializer.serialize_struct("Archetype", 3)?;
+        root.serialize_field("layout", &layout)?;
+        match &self.group.indices {
+            Some(indices) => {
+                let entities = self.group.archetype.entities();
+                let entities = indices.iter().map(|i| entities[*i]).collect::<Vec<_>>();
+                root.serialize_field("entities", &entities)?;
+            }
+            None => root.serialize_field("entities", self.group.archetype.entities())?,
+        }
+        root.serialize_field(
+            "components",
+            &SerializableColumns {
+                registry: self.registry,
+                world: self.world,
+                group: self.group,
+                columns: self.columns,
+            },
+        )?;
+        root.end()
+    }
+}
+
+struct SerializableColumns<'a, T: TypeKey, S: CustomEntitySerializer + 'static> {
+    registry: &'a Registry<T, S>,
+    world: &'a World,
+    group: &'a EntityGroup<'a>,
+    columns: &'a [ComponentTypeId],
+}
+
+impl<'a, T: TypeKey, S: CustomEntitySerializer + 'static> Serialize
+    for SerializableColumns<'a, T, S>
+{
+    fn serialize<Ser>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error>
+    where
+        Ser: Serializer,
+    {
+        let mut seq = serializer.serialize_seq(Some(self.columns.len()))?;
+        for type_id in self.columns {
+            let slice = self.group.is_slice(*type_id);
+            if let Some(pod) = self.registry.pod.get(type_id) {
+                // safety: pod components contain no padding or pointers, so their bytes are
+                // fully initialized and meaningful outside of this process
+                if slice {
+                    let storage = self.world.components().get(*type_id).unwrap();
+                    let (ptr, len) = storage.get_raw(self.group.archetype.index()).unwrap();
+                    let bytes = unsafe { std::slice::from_raw_parts(ptr, len * pod.size as usize) };
+                    seq.serialize_element(&RawBlock(bytes))?;
+                } else {
+                    let mut bytes = Vec::with_capacity(self.group.len() * pod.size as usize);
+                    for index in self.group.indices() {
+                        let ptr = self.group.component_ptr(self.world, *type_id, index);
+                        bytes.extend_from_slice(unsafe {
+                            std::slice::from_raw_parts(ptr, pod.size as usize)
+                        });
+                    }
+                    seq.serialize_element(&RawBlock(&bytes))?;
+                }
+            } else if slice {
+                seq.serialize_element(&ComponentSlice {
+                    registry: self.registry,
+                    storage: self.world.components().get(*type_id).unwrap(),
+                    arch: self.group.archetype,
+                    type_id: *type_id,
+                })?;
+            } else {
+                seq.serialize_element(&ComponentElements {
+                    registry: self.registry,
+                    world: self.world,
+                    group: self.group,
+                    type_id: *type_id,
+                })?;
+            }
+        }
+        seq.end()
+    }
+}
+
+struct RawBlock<'a>(&'a [u8]);
+
+impl<'a> Serialize for RawBlock<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(self.0)
+    }
+}
+
+struct ComponentSlice<'a, T: TypeKey, S: CustomEntitySerializer + 'static> {
+    registry: &'a Registry<T, S>,
+    storage: &'a dyn crate::internals::storage::UnknownComponentStorage,
+    arch: &'a Archetype,
+    type_id: ComponentTypeId,
+}
+
+impl<'a, T: TypeKey, S: CustomEntitySerializer + 'static> Serialize for ComponentSlice<'a, T, S> {
+    fn serialize<Ser>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error>
+    where
+        Ser: Serializer,
+    {
+        unsafe {
+            self.registry.serialize_component_slice(
+                self.type_id,
+                self.storage,
+                self.arch.index(),
+                serializer,
+            )
+        }
+    }
+}
+
+/// Serializes a group's components one at a time, in the same format as a slice of them.
+struct ComponentElements<'a, T: TypeKey, S: CustomEntitySerializer + 'static> {
+    registry: &'a Registry<T, S>,
+    world: &'a World,
+    group: &'a EntityGroup<'a>,
+    type_id: ComponentTypeId,
+}
+
+impl<'a, T: TypeKey, S: CustomEntitySerializer + 'static> Serialize
+    for ComponentElements<'a, T, S>
+{
+    fn serialize<Ser>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error>
+    where
+        Ser: Serializer,
+    {
+        let mut seq = serializer.serialize_seq(Some(self.group.len()))?;
+        for index in self.group.indices() {
+            seq.serialize_element(&Element {
+                registry: self.registry,
+                type_id: self.type_id,
+                ptr: self.group.component_ptr(self.world, self.type_id, index),
+            })?;
+        }
+        seq.end()
+    }
+}
+
+struct Element<'a, T: TypeKey, S: CustomEntitySerializer + 'static> {
+    registry: &'a Registry<T, S>,
+    type_id: ComponentTypeId,
+    ptr: *const u8,
+}
+
+impl<'a, T: TypeKey, S: CustomEntitySerializer + 'static> Serialize for Element<'a, T, S> {
+    fn serialize<Ser>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error>
+    where
+        Ser: Serializer,
+    {
+        unsafe {
+            self.registry
+                .serialize_component(self.type_id, self.ptr, serializer)
+        }
+    }
+}
+
+/// Wraps a [Registry](struct.Registry.html) and implements `serde::DeserializeSeed` for
+/// deserializing a world written in the [binary world format](struct.SerializableBinaryWorld.html)
+/// into a new world.
+///
+/// Raw component blocks can only be loaded on a machine with the same endianness as the one
+/// which wrote them, and only if the component's size and alignment have not changed.
+pub struct DeserializeBinaryWorld<'a, T: TypeKey, S: CustomEntitySerializer + 'static>(
+    pub &'a Registry<T, S>,
+);
+
+impl<'a, 'de, T: TypeKey, S: CustomEntitySerializer + 'static> DeserializeSeed<'de>
+    for DeserializeBinaryWorld<'a, T, S>
+{
+    type Value = World;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let mut world = World::default();
+        DeserializeBinaryIntoWorld(self.0, &mut world).deserialize(deserializer)?;
+        Ok(world)
+    }
+}
+
+/// Wraps a [Registry](struct.Registry.html) and implements `serde::DeserializeSeed` for
+/// deserializing a world written in the [binary world format](struct.SerializableBinaryWorld.html)
+/// into an existing world.
+///
+/// Components of types which the world has registered as
+/// [sparse](../world/struct.World.html#method.register_sparse) are moved into sparse storage.
+pub struct DeserializeBinaryIntoWorld<'a, T: TypeKey, S: CustomEntitySerializer + 'static>(
+    pub &'a Registry<T, S>,
+    pub &'a mut World,
+);
+
+impl<'a, 'de, T: TypeKey, S: CustomEntitySerializer + 'static> DeserializeSeed<'de>
+    for DeserializeBinaryIntoWorld<'a, T, S>
+{
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let canon = &self.0.canon;
+        run_as_context(&canon, || {
+            deserializer.deserialize_struct("BinaryWorld", &["header", "archetypes"], self)
+        })
+    }
+}
+
+impl<'a, 'de, T: TypeKey, S: CustomEntitySerializer + 'static> Visitor<'de>
+    for DeserializeBinaryIntoWorld<'a, T, S>
+{
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("struct BinaryWorld")
+    }
+
+    fn visit_seq<V>(self, mut seq: V) -> Result<Self::Value, V::Error>
+    where
+        V: SeqAccess<'de>,
+    {
+        use serde::de::Error;
+        let header = seq
+            .next_element::<Header>()?
+            .ok_or_else(|| V::Error::invalid_length(0, &self))?;
+        if header.format_version != FORMAT_VERSION {
+            return Err(V::Error::custom(format!(
+                "unsupported binary world format version {}",
+                header.format_version
+            )));
+        }
+
+        seq.next_element_seed(ArchetypeSeq {
+            registry: self.0,
+            world: self.1,
+            native_endian: header.little_endian == cfg!(target_endian = "little"),
+        })?
+        .ok_or_else(|| V::Error::invalid_length(1, &self))?;
+        Ok(())
+    }
+}
+
+struct ArchetypeSeq<'a, T: TypeKey, S: CustomEntitySerializer + 'static> {
+    registry: &'a Registry<T, S>,
+    world: &'a mut World,
+    native_endian: bool,
+}
+
+impl<'a, 'de, T: TypeKey, S: CustomEntitySerializer + 'static> DeserializeSeed<'de>
+    for ArchetypeSeq<'a, T, S>
+{
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'a, 'de, T: TypeKey, S: CustomEntitySerializer + 'static> Visitor<'de>
+    for ArchetypeSeq<'a, T, S>
+{
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("archetype sequence")
+    }
+
+    fn visit_seq<V>(self, mut seq: V) -> Result<Self::Value, V::Error>
+    where
+        V: SeqAccess<'de>,
+    {
+        while seq
+            .next_element_seed(DeserializableArchetype {
+                registry: self.registry,
+                world: self.world,
+                native_endian: self.native_endian,
+            })?
+            .is_some()
+        {}
+        Ok(())
+    }
+}
+
+/// How a serialized column of components is loaded.
+#[derive(Copy, Clone)]
+enum ColumnPlan {
+    Raw(ComponentTypeId, PodLayout),
+    Serde(ComponentTypeId),
+    SkipRaw,
+    SkipSerde,
+}
+
+struct DeserializableArchetype<'a, T: TypeKey, S: CustomEntitySerializer + 'static> {
+    registry: &'a Registry<T, S>,
+    world: &'a mut World,
+    native_endian: bool,
+}
+
+/// The components and column plan of a serialized archetype, along with the schema versions with
+/// which its components were written.
+type ArchetypePlan = (EntityLayout, Vec<ColumnPlan>, HashMap<ComponentTypeId, u32>);
+
+impl<'a, T: TypeKey, S: CustomEntitySerializer + 'static> DeserializableArchetype<'a, T, S> {
+    fn plan(&self, columns: Vec<Column<T>>) -> Result<ArchetypePlan, String> {
+        let mut layout = EntityLayout::new();
+        let mut plan = Vec::with_capacity(columns.len());
+        let mut versions = HashMap::new();
+        for Column { key, version, pod } in columns {
+            let type_id = match self.registry.unmap_id(&key) {
+                Ok(type_id) => type_id,
+                Err(UnknownType::Ignore) => {
+                    plan.push(if pod.is_some() {
+                        ColumnPlan::SkipRaw
+                    } else {
+                        ColumnPlan::SkipSerde
+                    });
+                    continue;
+                }
+                Err(UnknownType::Error) => return Err("unknown component type".to_string()),
+            };
+            if layout.has_component_by_id(type_id) {
+                return Err(format!(
+                    "component {} is serialized more than once",
+                    type_id
+                ));
+            }
+
+            if let Some(pod) = pod {
+                if !self.native_endian {
+                    return Err(format!(
+                        "component {} was written with a different endianness",
+                        type_id
+                    ));
+                }
+                if self.registry.pod.get(&type_id) != Some(&pod) {
+                    return Err(format!(
+                        "the layout of component {} does not match the serialized layout",
+                        type_id
+                    ));
+                }
+                let current = self.registry.component_version(type_id);
+                if version != current {
+                    return Err(format!(
+                        "component {} was written with schema version {}, but raw blocks can \
+                         only be loaded with the registered version {}",
+                        type_id, version, current
+                    ));
+                }
+                plan.push(ColumnPlan::Raw(type_id, pod));
+            } else {
+                plan.push(ColumnPlan::Serde(type_id));
+            }
+            if version != 0 {
+                versions.insert(type_id, version);
+            }
+            self.registry.register_component(key, &mut layout);
+        }
+        Ok((layout, plan, versions))
+    }
+}
+
+impl<'a, 'de, T: TypeKey, S: CustomEntitySerializer + 'static> DeserializeSeed<'de>
+    for DeserializableArchetype<'a, T, S>
+{
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_struct("Archetype", &["layout", "entities", "components"], self)
+    }
+}
+
+impl<'a, 'de, T: TypeKey, S: CustomEntitySerializer + 'static> Visitor<'de>
+    for DeserializableArchetype<'a, T, S>
+{
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("struct Archetype")
+    }
+
+    fn visit_seq<V>(self, mut seq: V) -> Result<Self::Value, V::Error>
+    where
+        V: SeqAccess<'de>,
+    {
+        use serde::de::Error;
+        let columns = seq
+            .next_element::<Vec<Column<T>>>()?
+            .ok_or_else(|| V::Error::custom("expected archetype layout"))?;
+        let (layout, plan, versions) = self.plan(columns).map_err(V::Error::custom)?;
+        let layout = Rc::new(layout);
+        let entities = seq
+            .next_element::<Vec<Entity>>()?
+            .ok_or_else(|| V::Error::custom("expected entity seq"))?;
+
+        // load every column into a scratch world first, so that a malformed archetype
+        // leaves nothing behind in the destination world
+        let mut staging = World::default();
+        let mut result = None;
+        run_with_versions(versions, || {
+            staging.extend(StageColumns {
+                registry: self.registry,
+                seq: &mut seq,
+                plan,
+                layout: layout.clone(),
+                count: entities.len(),
+                result: &mut result,
+                _phantom: PhantomData,
+            })
+        });
+        let archetype = result.unwrap().map_err(V::Error::custom)?;
+        for type_id in layout.component_types() {
+            let len = staging
+                .components()
+                .get(*type_id)
+                .and_then(|storage| storage.get_raw(archetype))
+                .map(|(_, len)| len)
+                .unwrap_or(0);
+            if len != entities.len() {
+                return Err(V::Error::invalid_length(
+                    len,
+                    &"a component for each entity",
+                ));
+            }
+        }
+
+        self.world.extend(InsertArchetype {
+            staging: &mut staging,
+            archetype,
+            entities,
+            layout,
+        });
+        Ok(())
+    }
+}
+
+/// Deserializes an archetype's component columns into a staging world, without any entities.
+struct StageColumns<'a, 'de, T: TypeKey, S: CustomEntitySerializer + 'static, V: SeqAccess<'de>> {
+    registry: &'a Registry<T, S>,
+    seq: V,
+    plan: Vec<ColumnPlan>,
+    layout: Rc<EntityLayout>,
+    count: usize,
+    result: &'a mut Option<Result<ArchetypeIndex, String>>,
+    _phantom: PhantomData<&'de ()>,
+}
+
+impl<'a, 'de, T: TypeKey, S: CustomEntitySerializer + 'static, V: SeqAccess<'de>> ArchetypeSource
+    for StageColumns<'a, 'de, T, S, V>
+{
+    type Filter = Rc<EntityLayout>;
+
+    fn filter(&self) -> Self::Filter {
+        self.layout.clone()
+    }
+
+    fn layout(&mut self) -> EntityLayout {
+        (*self.layout).clone()
+    }
+}
+
+impl<'a, 'de, T: TypeKey, S: CustomEntitySerializer + 'static, V: SeqAccess<'de>> ComponentSource
+    for StageColumns<'a, 'de, T, S, V>
+{
+    fn push_components<'c>(
+        &mut self,
+        writer: &mut ArchetypeWriter<'c>,
+        _: impl Iterator<Item = Entity>,
+    ) {
+        use serde::de::Error;
+
+        // claim every column up front, so that the writer is satisfied even if loading fails
+        let storages = self
+            .plan
+            .iter()
+            .map(|plan| match *plan {
+                ColumnPlan::Raw(type_id, _) | ColumnPlan::Serde(type_id) => {
+                    Some(writer.claim_components_unknown(type_id))
+                }
+                ColumnPlan::SkipRaw | ColumnPlan::SkipSerde => None,
+            })
+            .collect();
+
+        let loaded = self
+            .seq
+            .next_element_seed(Columns {
+                registry: self.registry,
+                storages,
+                plan: &self.plan,
+                count: self.count,
+            })
+            .and_then(|loaded| loaded.ok_or_else(|| V::Error::custom("expected component seq")));
+        *self.result = Some(match loaded {
+            Ok(_) => Ok(writer.archetype().index()),
+            Err(err) => Err(err.to_string()),
+        });
+    }
+}
+
+impl<'a, 'de, T: TypeKey, S: CustomEntitySerializer + 'static, V: SeqAccess<'de>>
+    IntoComponentSource for StageColumns<'a, 'de, T, S, V>
+{
+    type Source = Self;
+
+    fn into(self) -> Self::Source {
+        self
+    }
+}
+
+/// Moves fully loaded component columns out of a staging world and into their entities.
+struct InsertArchetype<'a> {
+    staging: &'a mut World,
+    archetype: ArchetypeIndex,
+    entities: Vec<Entity>,
+    layout: Rc<EntityLayout>,
+}
+
+impl<'a> ArchetypeSource for InsertArchetype<'a> {
+    type Filter = Rc<EntityLayout>;
+
+    fn filter(&self) -> Self::Filter {
+        self.layout.clone()
+    }
+
+    fn layout(&mut self) -> EntityLayout {
+        (*self.layout).clone()
+    }
+}
+
+impl<'a> ComponentSource for InsertArchetype<'a> {
+    fn push_components<'c>(
+        &mut self,
+        writer: &mut ArchetypeWriter<'c>,
+        _: impl Iterator<Item = Entity>,
+    ) {
+        writer.reserve(self.entities.len());
+        for entity in &self.entities {
+            writer.push(*entity);
+        }
+
+        let components = self.staging.components_mut();
+        for type_id in self.layout.component_types() {
+            let src = components.get_mut(*type_id).unwrap();
+            writer
+                .claim_components_unknown(*type_id)
+                .move_archetype_from(self.archetype, src);
+        }
+    }
+}
+
+impl<'a> IntoComponentSource for InsertArchetype<'a> {
+    type Source = Self;
+
+    fn into(self) -> Self::Source {
+        self
+    }
+}
+
+struct Columns<'a, 'b, T: TypeKey, S: CustomEntitySerializer + 'static> {
+    registry: &'a Registry<T, S>,
+    storages: Vec<Option<UnknownComponentWriter<'b>>>,
+    plan: &'a [ColumnPlan],
+    count: usize,
+}
+
+impl<'a, 'b, 'de, T: TypeKey, S: CustomEntitySerializer + 'static> DeserializeSeed<'de>
+    for Columns<'a, 'b, T, S>
+{
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'a, 'b, 'de, T: TypeKey, S: CustomEntitySerializer + 'static> Visitor<'de>
+    for Columns<'a, 'b, T, S>
+{
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("component seq")
+    }
+
+    fn visit_seq<V>(self, mut seq: V) -> Result<Self::Value, V::Error>
+    where
+        V: SeqAccess<'de>,
+    {
+        use serde::de::Error;
+        for (i, (plan, storage)) in self.plan.iter().zip(self.storages).enumerate() {
+            let loaded = match *plan {
+                ColumnPlan::Raw(_, pod) => seq.next_element_seed(RawBlockSeed {
+                    storage,
+                    pod,
+                    count: self.count,
+                })?,
+                ColumnPlan::Serde(type_id) => seq.next_element_seed(ComponentSliceSeed {
+                    registry: self.registry,
+                    storage: storage.unwrap(),
+                    type_id,
+                })?,
+                ColumnPlan::SkipRaw => seq.next_element_seed(RawBlockSeed {
+                    storage: None,
+                    pod: PodLayout { size: 0, align: 1 },
+                    count: 0,
+                })?,
+                ColumnPlan::SkipSerde => seq.next_element::<IgnoredAny>()?.map(|_| ()),
+            };
+            loaded.ok_or_else(|| V::Error::invalid_length(i, &"a block for each column"))?;
+        }
+        Ok(())
+    }
+}
+
+/// Copies a raw byte block into a component storage, or discards it if there is no storage.
+struct RawBlockSeed<'a> {
+    storage: Option<UnknownComponentWriter<'a>>,
+    pod: PodLayout,
+    count: usize,
+}
+
+impl<'a> RawBlockSeed<'a> {
+    fn write<E: serde::de::Error>(self, bytes: &[u8]) -> Result<(), E> {
+        let mut storage = match self.storage {
+            Some(storage) => storage,
+            None => return Ok(()),
+        };
+        let size = self.pod.size as usize;
+        if bytes.len() != size * self.count {
+            return Err(E::invalid_length(bytes.len(), &"a block of components"));
+        }
+
+        let align = self.pod.align as usize;
+        if bytes.as_ptr().align_offset(align) == 0 {
+            // safety: the block contains `count` valid, aligned pod components
+            unsafe { storage.extend_memcopy_raw(bytes.as_ptr(), self.count) };
+        } else if !bytes.is_empty() {
+            // the serializer does not guarantee alignment, so copy into an aligned buffer first
+            let layout = Layout::from_size_align(bytes.len(), align).unwrap();
+            unsafe {
+                let aligned = std::alloc::alloc(layout);
+                if aligned.is_null() {
+                    std::alloc::handle_alloc_error(layout);
+                }
+                std::ptr::copy_nonoverlapping(bytes.as_ptr(), aligned, bytes.len());
+                storage.extend_memcopy_raw(aligned, self.count);
+                std::alloc::dealloc(aligned, layout);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a, 'de> DeserializeSeed<'de> for RawBlockSeed<'a> {
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_bytes(self)
+    }
+}
+
+impl<'a, 'de> Visitor<'de> for RawBlockSeed<'a> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("component bytes")
+    }
+
+    fn visit_bytes<E: serde::de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        self.write(v)
+    }
+
+    fn visit_byte_buf<E: serde::de::Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+        self.write(&v)
+    }
+}
+
+struct ComponentSliceSeed<'a, 'b, T: TypeKey, S: CustomEntitySerializer + 'static> {
+    registry: &'a Registry<T, S>,
+    storage: UnknownComponentWriter<'b>,
+    type_id: ComponentTypeId,
+}
+
+impl<'a, 'b, 'de, T: TypeKey, S: CustomEntitySerializer + 'static> DeserializeSeed<'de>
+    for ComponentSliceSeed<'a, 'b, T, S>
+{
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        self.registry
+            .deserialize_component_slice(self.type_id, self.storage, deserializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::internals::{
+        entity::Entity,
+        query::{filter::filter_fns::any, IntoQuery},
+        serialize::{Registry, UnknownType},
+        world::{EntityStore, World},
+    };
+    use serde::de::DeserializeSeed;
+
+    #[derive(serde::Serialize, serde::Deserialize, Copy, Clone, Debug, PartialEq)]
+    #[repr(C)]
+    struct Position {
+        x: f32,
+        y: f32,
+        z: f32,
+    }
+
+    fn registry() -> Registry<String> {
+        let mut registry = Registry::<String>::default();
+        unsafe {
+            registry.register_pod::<Position>("position".to_string());
+            registry.register_pod::<u64>("u64".to_string());
+        }
+        registry.register::<String>("string".to_string());
+        registry.register::<Entity>("entity".to_string());
+        registry
+    }
+
+    fn deserialize(registry: &Registry<String>, encoded: &[u8]) -> Result<World, bincode::Error> {
+        use bincode::Options;
+        registry
+            .as_deserialize_binary()
+            .deserialize(&mut bincode::Deserializer::from_slice(
+                encoded,
+                bincode::DefaultOptions::new()
+                    .with_fixint_encoding()
+                    .allow_trailing_bytes(),
+            ))
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut world = World::default();
+        let entities = world
+            .extend((0..100u64).map(|i| {
+                (
+                    Position {
+                        x: i as f32,
+                        y: 0.0,
+                        z: -(i as f32),
+                    },
+                    i,
+                )
+            }))
+            .to_vec();
+        let named = world.push((
+            Position {
+                x: 1.0,
+                y: 2.0,
+                z: 3.0,
+            },
+            "named".to_string(),
+            entities[5],
+        ));
+
+        let registry = registry();
+        let encoded = bincode::serialize(&registry.as_serializable_binary(&world, any())).unwrap();
+        let loaded = deserialize(&registry, &encoded).unwrap();
+
+        assert_eq!(loaded.len(), 101);
+        for (i, entity) in entities.iter().enumerate() {
+            let entry = loaded.entry_ref(*entity).unwrap();
+            assert_eq!(entry.get_component::<u64>(), Ok(&(i as u64)));
+            assert_eq!(entry.get_component::<Position>().unwrap().z, -(i as f32));
+        }
+        let entry = loaded.entry_ref(named).unwrap();
+        assert_eq!(entry.get_component::<String>().unwrap(), "named");
+        assert_eq!(entry.get_component::<Entity>(), Ok(&entities[5]));
+        assert_eq!(<&Position>::query().iter(&loaded).count(), 101);
+    }
+
+    #[test]
+    fn sparse() {
+        let mut world = World::default();
+        world.register_sparse::<u64>();
+        world.register_sparse::<String>();
+        let entities = world
+            .extend((0..6u64).map(|i| {
+                (Position {
+                    x: i as f32,
+                    y: 0.0,
+                    z: 0.0,
+                },)
+            }))
+            .to_vec();
+        for (i, entity) in entities.iter().enumerate() {
+            let mut entry = world.entry(*entity).unwrap();
+            if i % 2 == 0 {
+                entry.add_component(i as u64);
+            }
+            if i % 3 == 0 {
+                entry.add_component(i.to_string());
+            }
+        }
+
+        let registry = registry();
+        let encoded = bincode::serialize(&registry.as_serializable_binary(&world, any())).unwrap();
+        let mut loaded = World::default();
+        loaded.register_sparse::<u64>();
+        loaded.register_sparse::<String>();
+        {
+            use bincode::Options;
+            registry
+                .as_deserialize_binary_into_world(&mut loaded)
+                .deserialize(&mut bincode::Deserializer::from_slice(
+                    &encoded,
+                    bincode::DefaultOptions::new()
+                        .with_fixint_encoding()
+                        .allow_trailing_bytes(),
+                ))
+                .unwrap();
+        }
+
+        assert_eq!(loaded.len(), 6);
+        assert_eq!(
+            loaded
+                .archetypes()
+                .iter()
+                .filter(|arch| !arch.entities().is_empty())
+                .count(),
+            1
+        );
+        let sparse = loaded.sparse_components();
+        for (i, entity) in entities.iter().enumerate() {
+            let entry = loaded.entry_ref(*entity).unwrap();
+            assert_eq!(entry.get_component::<Position>().unwrap().x, i as f32);
+            let number = sparse.get_downcast::<u64>().unwrap().get(*entity);
+            assert_eq!(number, Some(&(i as u64)).filter(|_| i % 2 == 0));
+            let string = sparse.get_downcast::<String>().unwrap().get(*entity);
+            assert_eq!(string, Some(&i.to_string()).filter(|_| i % 3 == 0));
+        }
+
+        // a new world stores the components in archetypes
+        let loaded = deserialize(&registry, &encoded).unwrap();
+        assert_eq!(<&u64>::query().iter(&loaded).count(), 3);
+        assert_eq!(<&String>::query().iter(&loaded).count(), 2);
+        assert_consistent(&loaded);
+    }
+
+    #[test]
+    fn layout_mismatch() {
+        let mut world = World::default();
+        world.push((Position {
+            x: 1.0,
+            y: 2.0,
+            z: 3.0,
+        },));
+        let encoded =
+            bincode::serialize(&registry().as_serializable_binary(&world, any())).unwrap();
+
+        // the type is no longer registered as plain old data
+        let mut registry = Registry::<String>::default();
+        registry.register::<Position>("position".to_string());
+        assert!(deserialize(&registry, &encoded).is_err());
+
+        // unknown raw blocks can be skipped
+        let mut registry = Registry::<String>::default();
+        registry.on_unknown(UnknownType::Ignore);
+        let loaded = deserialize(&registry, &encoded).unwrap();
+        assert_eq!(loaded.len(), 1);
+    }
+
+    #[test]
+    fn schema_versions() {
+        #[derive(serde::Serialize, serde::Deserialize)]
+        struct OldName(String);
+
+        #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+        struct Name {
+            first: String,
+            last: String,
+        }
+
+        let mut world = World::default();
+        world.push((
+            OldName("Ada".to_string()),
+            Position {
+                x: 1.0,
+                y: 2.0,
+                z: 3.0,
+            },
+        ));
+        let mut old_registry = Registry::<String>::default();
+        unsafe { old_registry.register_pod::<Position>("position".to_string()) };
+        old_registry.register_versioned::<OldName>("name".to_string(), 1);
+        let encoded =
+            bincode::serialize(&old_registry.as_serializable_binary(&world, any())).unwrap();
+
+        // serde columns are migrated from the version they were written with
+        let mut registry = Registry::<String>::default();
+        unsafe { registry.register_pod::<Position>("position".to_string()) };
+        registry.register_versioned::<Name>("name".to_string(), 2);
+        registry.register_migration::<Name, String, _>(1, |first| Name {
+            first,
+            last: String::new(),
+        });
+        let loaded = deserialize(&registry, &encoded).unwrap();
+        let mut query = <(&Name, &Position)>::query();
+        let (name, position) = query.iter(&loaded).next().unwrap();
+        assert_eq!(name.first, "Ada");
+        assert_eq!(position.z, 3.0);
+
+        // without a migration, the world does not load
+        let mut registry = Registry::<String>::default();
+        unsafe { registry.register_pod::<Position>("position".to_string()) };
+        registry.register_versioned::<Name>("name".to_string(), 2);
+        assert!(deserialize(&registry, &encoded).is_err());
+
+        // raw blocks cannot be migrated
+        let mut registry = Registry::<String>::default();
+        unsafe { registry.register_pod::<Position>("position".to_string()) };
+        registry.register_versioned::<Position>("position".to_string(), 1);
+        registry.register_versioned::<OldName>("name".to_string(), 1);
+        assert!(deserialize(&registry, &encoded).is_err());
+    }
+
+    fn mixed_world() -> World {
+        let mut world = World::default();
+        world.extend((0..4u64).map(|i| {
+            (
+                Position {
+                    x: i as f32,
+                    y: 0.0,
+                    z: 0.0,
+                },
+                i,
+            )
+        }));
+        world.extend(vec![("a".to_string(),), ("b".to_string(),)]);
+        world
+    }
+
+    fn assert_consistent(world: &World) {
+        for arch in world.archetypes() {
+            for type_id in arch.layout().component_types() {
+                let (_, len) = world
+                    .components()
+                    .get(*type_id)
+                    .and_then(|storage| storage.get_raw(arch.index()))
+                    .unwrap();
+                assert_eq!(len, arch.entities().len());
+            }
+        }
+    }
+
+    #[test]
+    fn truncated() {
+        let registry = registry();
+        let encoded =
+            bincode::serialize(&registry.as_serializable_binary(&mixed_world(), any())).unwrap();
+
+        for len in 0..encoded.len() {
+            assert!(deserialize(&registry, &encoded[..len]).is_err());
+        }
+    }
+
+    #[test]
+    fn corrupt() {
+        let registry = registry();
+        let encoded =
+            bincode::serialize(&registry.as_serializable_binary(&mixed_world(), any())).unwrap();
+
+        for i in 0..encoded.len() {
+            let mut corrupt = encoded.clone();
+            corrupt[i] ^= 0xff;
+            if let Ok(loaded) = deserialize(&registry, &corrupt) {
+                assert_consistent(&loaded);
+            }
+        }
+    }
+
+    #[test]
+    fn short_column() {
+        let registry = registry();
+        let encoded =
+            bincode::serialize(&registry.as_serializable_binary(&mixed_world(), any())).unwrap();
+
+        // claim that the string column holds only one of the archetype's two components
+        let column = [&2u64.to_le_bytes()[..], &1u64.to_le_bytes()[..], &b"a"[..]].concat();
+        let at = encoded
+            .windows(column.len())
+            .position(|window| window == &column[..])
+            .unwrap();
+        let mut corrupt = encoded.clone();
+        corrupt[at..at + 8].copy_from_slice(&1u64.to_le_bytes());
+
+        assert!(deserialize(&registry, &corrupt).is_err());
+    }
+}
@@ -1,8 +1,11 @@
 //! World deserialization types.
 
 use super::{
-    archetypes::de::ArchetypeLayoutDeserializer, entities::de::EntitiesLayoutDeserializer,
-    id::run_as_context, EntitySerializer, UnknownType, WorldField,
+    archetypes::de::ArchetypeLayoutDeserializer,
+    entities::de::EntitiesLayoutDeserializer,
+    id::run_as_context,
+    schema::{run_with_versions, DeserializeSchemaVersions},
+    EntitySerializer, UnknownType, WorldField,
 };
 use crate::{
     internals::{
@@ -15,6 +18,7 @@ use serde::{
     de::{MapAccess, Visitor},
     Deserialize, Deserializer,
 };
+use std::collections::HashMap;
 
 /// Describes a type which knows how to deserialize the components in a world.
 pub trait WorldDeserializer {
@@ -67,18 +71,33 @@ impl<'a, 'de, W: WorldDeserializer> Visitor<'de> for WorldVisitor<'a, W> {
             world: &'a mut World,
             mut map: V,
         ) -> Result<(), V::Error> {
+            // worlds without recorded versions were serialized with version 0 of every schema
+            let mut versions = HashMap::new();
             while let Some(key) = map.next_key()? {
                 match key {
+                    WorldField::Versions => {
+                        let saved =
+                            map.next_value_seed(DeserializeSchemaVersions::<W::TypeId>::new())?;
+                        for (mapped_type_id, version) in saved {
+                            if let Ok(type_id) = world_deserializer.unmap_id(&mapped_type_id) {
+                                versions.insert(type_id, version);
+                            }
+                        }
+                    }
                     WorldField::Packed => {
-                        map.next_value_seed(ArchetypeLayoutDeserializer {
-                            world_deserializer,
-                            world,
+                        run_with_versions(versions.clone(), || {
+                            map.next_value_seed(ArchetypeLayoutDeserializer {
+                                world_deserializer,
+                                world,
+                            })
                         })?;
                     }
                     WorldField::Entities => {
-                        map.next_value_seed(EntitiesLayoutDeserializer {
-                            world_deserializer,
-                            world,
+                        run_with_versions(versions.clone(), || {
+                            map.next_value_seed(EntitiesLayoutDeserializer {
+                                world_deserializer,
+                                world,
+                            })
                         })?;
                     }
                 }
@@ -1,12 +1,8 @@
 pub mod ser {
     use crate::internals::{
         query::filter::LayoutFilter,
-        serialize::{ser::WorldSerializer, UnknownType},
-        storage::{
-            archetype::{Archetype, ArchetypeIndex},
-            component::ComponentTypeId,
-            ComponentIndex,
-        },
+        serialize::{id::serialized_order, ser::WorldSerializer, EntityGroup, UnknownType},
+        storage::component::ComponentTypeId,
         world::World,
     };
     use itertools::Itertools;
@@ -17,6 +13,7 @@ pub mod ser {
         pub world_serializer: &'a W,
         pub world: &'a World,
         pub filter: &'a F,
+        pub canonical: bool,
     }
 
     impl<'a, W: WorldSerializer, F: LayoutFilter> Serialize for EntitiesLayoutSerializer<'a, W, F> {
@@ -24,22 +21,11 @@ pub mod ser {
         where
             S: Serializer,
         {
-            let archetypes = self
-                .world
-                .archetypes()
-                .iter()
-                .enumerate()
-                .filter(|(_, arch)| {
-                    self.filter
-                        .matches_layout(arch.layout().component_types())
-                        .is_pass()
-                })
-                .map(|(i, arch)| (ArchetypeIndex(i as u32), arch))
-                .collect::<Vec<_>>();
+            let groups = EntityGroup::split(self.world, self.filter);
 
-            let component_types = archetypes
+            let component_types = groups
                 .iter()
-                .flat_map(|(_, arch)| arch.layout().component_types())
+                .flat_map(|group| group.component_types())
                 .unique();
             let mut type_mappings = HashMap::new();
             for id in component_types {
@@ -59,27 +45,39 @@ pub mod ser {
                 }
             }
 
-            let mut map = serializer.serialize_map(Some(
-                archetypes
+            let mut map =
+                serializer.serialize_map(Some(groups.iter().map(|group| group.len()).sum()))?;
+
+            let mut entities = groups
+                .iter()
+                .flat_map(|group| {
+                    group
+                        .indices()
+                        .into_iter()
+                        .map(move |index| (group.archetype.entities()[index], index, group))
+                })
+                .collect::<Vec<_>>();
+            if self.canonical {
+                let ids = entities
                     .iter()
-                    .map(|(_, arch)| arch.entities().len())
-                    .sum(),
-            ))?;
-
-            for (arch_index, arch) in archetypes {
-                for (i, entity) in arch.entities().iter().enumerate() {
-                    map.serialize_entry(
-                        entity,
-                        &EntitySerializer {
-                            component_index: ComponentIndex(i),
-                            arch_index,
-                            arch,
-                            world: self.world,
-                            world_serializer: self.world_serializer,
-                            type_mappings: &type_mappings,
-                        },
-                    )?;
-                }
+                    .map(|(entity, ..)| *entity)
+                    .collect::<Vec<_>>();
+                let order = serialized_order(&ids);
+                entities = order.into_iter().map(|i| entities[i]).collect();
+            }
+
+            for (entity, index, group) in entities {
+                map.serialize_entry(
+                    &entity,
+                    &EntitySerializer {
+                        index,
+                        group,
+                        world: self.world,
+                        world_serializer: self.world_serializer,
+                        type_mappings: &type_mappings,
+                        canonical: self.canonical,
+                    },
+                )?;
             }
 
             map.end()
@@ -87,12 +85,12 @@ pub mod ser {
     }
 
     struct EntitySerializer<'a, W: WorldSerializer> {
-        component_index: ComponentIndex,
-        arch_index: ArchetypeIndex,
-        arch: &'a Archetype,
+        index: usize,
+        group: &'a EntityGroup<'a>,
         world: &'a World,
         world_serializer: &'a W,
         type_mappings: &'a HashMap<ComponentTypeId, W::TypeId>,
+        canonical: bool,
     }
 
     impl<'a, W: WorldSerializer> Serialize for EntitySerializer<'a, W> {
@@ -100,32 +98,31 @@ pub mod ser {
         where
             S: Serializer,
         {
-            let layout = self.arch.layout().component_types();
-            let components = self.world.components();
-            let type_count = layout
-                .iter()
-                .filter_map(|t| self.type_mappings.get(t))
-                .count();
-
-            let mut map = serializer.serialize_map(Some(type_count))?;
-
-            for type_id in self.arch.layout().component_types() {
-                if let Some(mapped_type_id) = self.type_mappings.get(type_id) {
-                    let storage = components.get(*type_id).unwrap();
-                    let (ptr, len) = storage.get_raw(self.arch_index).unwrap();
-                    assert!(self.component_index.0 < len);
-                    map.serialize_entry(
-                        mapped_type_id,
-                        &ComponentSerializer {
-                            type_id: *type_id,
-                            world_serializer: self.world_serializer,
-                            ptr: unsafe {
-                                ptr.add(self.component_index.0 * storage.element_vtable().size())
-                            },
-                            _phantom: PhantomData,
-                        },
-                    )?;
-                }
+            let mut layout = self
+                .group
+                .component_types()
+                .filter_map(|type_id| {
+                    self.type_mappings
+                        .get(type_id)
+                        .map(|mapped| (type_id, mapped))
+                })
+                .collect::<Vec<_>>();
+            if self.canonical {
+                layout.sort_by_key(|(_, a)| *a);
+            }
+
+            let mut map = serializer.serialize_map(Some(layout.len()))?;
+
+            for (type_id, mapped_type_id) in layout {
+                map.serialize_entry(
+                    mapped_type_id,
+                    &ComponentSerializer {
+                        type_id: *type_id,
+                        world_serializer: self.world_serializer,
+                        ptr: self.group.component_ptr(self.world, *type_id, self.index),
+                        _phantom: PhantomData,
+                    },
+                )?;
             }
 
             map.end()
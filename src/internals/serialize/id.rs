@@ -1,10 +1,15 @@
 use crate::{
-    internals::entity::EntityHasher, internals::serialize::CustomEntitySerializer, world::Allocate,
+    internals::entity::EntityHasher,
+    internals::serialize::CustomEntitySerializer,
+    world::{Allocate, EntityAllocator},
     Entity,
 };
 use serde::{Deserialize, Serialize, Serializer};
 use std::cell::RefCell;
 use std::collections::{hash_map::Entry, HashMap};
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::sync::Arc;
 use thiserror::Error;
 use uuid::Uuid;
 
@@ -19,6 +24,14 @@ pub trait EntitySerializer {
         &self,
         deserializer: &mut dyn erased_serde::Deserializer,
     ) -> Result<Entity, erased_serde::Error>;
+
+    /// Returns the indices of `entities` in the order of their serialized representations.
+    ///
+    /// This is used to sort entities in [canonical](ser/struct.SerializableWorld.html#method.canonical)
+    /// output. The default implementation keeps the entities in the order given.
+    fn serialized_order(&self, entities: &[Entity]) -> Vec<usize> {
+        (0..entities.len()).collect()
+    }
 }
 
 thread_local! {
@@ -58,6 +71,17 @@ pub fn run_as_context<F: FnOnce() -> R, R>(context: &dyn EntitySerializer, f: F)
     })
 }
 
+/// Returns the indices of `entities` in the order of their serialized representations, using the
+/// entity serializer of the current context.
+pub(crate) fn serialized_order(entities: &[Entity]) -> Vec<usize> {
+    SERIALIZER.with(|cell| {
+        cell.borrow()
+            .as_ref()
+            .expect("No entity serializer set")
+            .serialized_order(entities)
+    })
+}
+
 impl Serialize for Entity {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -105,65 +129,232 @@ impl<'de> Deserialize<'de> for Entity {
 /// A 16 byte UUID which uniquely identifies an entity.
 pub type EntityName = [u8; 16];
 
+/// Generates the names which a [Canon](struct.Canon.html) assigns to entities which have not
+/// yet been named.
+pub trait EntityNames {
+    /// The name of an entity.
+    type Name: Clone + Eq + Hash + Debug;
+
+    /// The serialized representation of a name.
+    type Serialized: Serialize + for<'a> Deserialize<'a>;
+
+    /// Generates a name for the given entity. The canon will generate another name if the
+    /// returned name is already bound to a different entity.
+    fn generate(&mut self, entity: Entity) -> Self::Name;
+
+    /// Converts a name into its serialized representation.
+    fn to_serialized(name: Self::Name) -> Self::Serialized;
+
+    /// Converts a serialized representation back into a name.
+    fn from_serialized(serialized: Self::Serialized) -> Self::Name;
+}
+
+/// Names entities with randomly generated (v4) UUIDs, which are serialized as
+/// [Uuid](https://docs.rs/uuid)s.
+#[derive(Default, Debug, Copy, Clone)]
+pub struct UuidNames;
+
+impl EntityNames for UuidNames {
+    type Name = EntityName;
+    type Serialized = Uuid;
+
+    fn generate(&mut self, _: Entity) -> Self::Name {
+        *Uuid::new_v4().as_bytes()
+    }
+
+    fn to_serialized(name: Self::Name) -> Self::Serialized {
+        Uuid::from_bytes(name)
+    }
+
+    fn from_serialized(serialized: Self::Serialized) -> Self::Name {
+        *serialized.as_bytes()
+    }
+}
+
+/// Names entities with string paths.
+///
+/// Entities can be given human readable names with [Canon::canonize](struct.Canon.html#method.canonize).
+/// Entities which have not been named are assigned paths of the form `prefix/n`.
+#[derive(Debug, Clone)]
+pub struct PathNames {
+    prefix: String,
+    next: u64,
+}
+
+impl PathNames {
+    /// Constructs a new path name generator which generates names under the given prefix.
+    pub fn new<P: Into<String>>(prefix: P) -> Self {
+        Self {
+            prefix: prefix.into(),
+            next: 0,
+        }
+    }
+}
+
+impl Default for PathNames {
+    fn default() -> Self {
+        Self::new("entity")
+    }
+}
+
+impl EntityNames for PathNames {
+    type Name = String;
+    type Serialized = String;
+
+    fn generate(&mut self, _: Entity) -> Self::Name {
+        let name = format!("{}/{}", self.prefix, self.next);
+        self.next += 1;
+        name
+    }
+
+    fn to_serialized(name: Self::Name) -> Self::Serialized {
+        name
+    }
+
+    fn from_serialized(serialized: Self::Serialized) -> Self::Name {
+        serialized
+    }
+}
+
+/// Names entities with sequential integers.
+#[derive(Default, Debug, Copy, Clone)]
+pub struct SequentialNames {
+    next: u32,
+}
+
+impl SequentialNames {
+    /// Constructs a new sequential name generator which generates names starting at `first`.
+    pub fn starting_at(first: u32) -> Self {
+        Self { next: first }
+    }
+}
+
+impl EntityNames for SequentialNames {
+    type Name = u32;
+    type Serialized = u32;
+
+    fn generate(&mut self, _: Entity) -> Self::Name {
+        let name = self.next;
+        self.next = self
+            .next
+            .checked_add(1)
+            .expect("sequential entity names exhausted");
+        name
+    }
+
+    fn to_serialized(name: Self::Name) -> Self::Serialized {
+        name
+    }
+
+    fn from_serialized(serialized: Self::Serialized) -> Self::Name {
+        serialized
+    }
+}
+
 /// Error returned on unsucessful attempt to canonize an entity.
 #[derive(Error, Debug, Copy, Clone, PartialEq, Hash)]
-pub enum CanonizeError {
+pub enum CanonizeError<N = EntityName> {
     /// The entity already exists bound to a different name.
-    #[error("the entity is already bound to name {0:?}")]
-    EntityAlreadyBound(Entity, EntityName),
+    #[error("the entity is already bound to name {1:?}")]
+    EntityAlreadyBound(Entity, N),
     /// The name already exists bound to a different entity.
     #[error("the name is already bound to a different entity")]
-    NameAlreadyBound(Entity, EntityName),
+    NameAlreadyBound(Entity, N),
 }
 
 /// Contains the canon names of entities.
+///
+/// The type of the names is determined by the [EntityNames](trait.EntityNames.html) generator,
+/// which defaults to random UUIDs. [PathNames](struct.PathNames.html) and
+/// [SequentialNames](struct.SequentialNames.html) name entities with strings and integers.
+///
+/// # Examples
+///
+/// ```
+/// # use legion::*;
+/// # use legion::serialize::{Canon, PathNames};
+/// let registry = Registry::<String, Canon<PathNames>>::default();
+///
+/// let mut world = World::default();
+/// let door = world.push(());
+/// registry.entity_serializer().canonize(door, "level/door".to_string()).unwrap();
+///
+/// let json = serde_json::to_value(&world.as_serializable(any(), &registry)).unwrap();
+/// assert!(json["entities"].get("level/door").is_some());
+/// ```
 #[derive(Default, Debug)]
-pub struct Canon {
-    to_name: HashMap<Entity, EntityName, EntityHasher>,
-    to_id: HashMap<EntityName, Entity>,
+pub struct Canon<N: EntityNames = UuidNames> {
+    to_name: HashMap<Entity, N::Name, EntityHasher>,
+    to_id: HashMap<N::Name, Entity>,
     allocator: Allocate,
+    names: N,
 }
 
-impl Canon {
-    /// Returns the [Entity](struct.Entity.html) ID associated with the given [EntityName](struct.EntityName.html).
-    pub fn get_id(&self, name: &EntityName) -> Option<Entity> {
+impl<N: EntityNames + Default> Canon<N> {
+    /// Constructs a new canon which draws the IDs of newly named entities from the given
+    /// [allocator](../world/trait.EntityAllocator.html).
+    ///
+    /// This should usually be the allocator of the world being deserialized into.
+    pub fn with_allocator(allocator: Arc<dyn EntityAllocator>) -> Self {
+        Self::with_names(N::default(), allocator)
+    }
+}
+
+impl<N: EntityNames> Canon<N> {
+    /// Constructs a new canon which generates names with the given generator, and draws the IDs
+    /// of newly named entities from the given [allocator](../world/trait.EntityAllocator.html).
+    pub fn with_names(names: N, allocator: Arc<dyn EntityAllocator>) -> Self {
+        Self {
+            to_name: HashMap::default(),
+            to_id: HashMap::default(),
+            allocator: Allocate::with_allocator(allocator),
+            names,
+        }
+    }
+
+    /// Returns the [Entity](struct.Entity.html) ID associated with the given name.
+    pub fn get_id(&self, name: &N::Name) -> Option<Entity> {
         self.to_id.get(name).copied()
     }
 
-    /// Returns the [EntityName](struct.EntityName.html) associated with the given [Entity](struct.Entity.html) ID.
-    pub fn get_name(&self, entity: Entity) -> Option<EntityName> {
-        self.to_name.get(&entity).copied()
+    /// Returns the name associated with the given [Entity](struct.Entity.html) ID.
+    pub fn get_name(&self, entity: Entity) -> Option<N::Name> {
+        self.to_name.get(&entity).cloned()
     }
 
-    /// Canonizes a given [EntityName](struct.EntityName.html) and returns the associated [Entity](struct.Entity.html) ID.
-    pub fn canonize_name(&mut self, name: &EntityName) -> Entity {
-        match self.to_id.entry(*name) {
-            Entry::Occupied(occupied) => *occupied.get(),
-            Entry::Vacant(vacant) => {
-                let entity = self.allocator.next().unwrap();
-                vacant.insert(entity);
-                self.to_name.insert(entity, *name);
-                entity
-            }
+    /// Canonizes a given name and returns the associated [Entity](struct.Entity.html) ID.
+    pub fn canonize_name(&mut self, name: &N::Name) -> Entity {
+        if let Some(entity) = self.to_id.get(name) {
+            return *entity;
         }
+        let entity = self.allocator.next().unwrap();
+        self.to_id.insert(name.clone(), entity);
+        self.to_name.insert(entity, name.clone());
+        entity
     }
 
-    /// Canonizes a given [Entity](struct.Entity.html) ID and returns the associated [EntityName](struct.EntityName.html).
-    pub fn canonize_id(&mut self, entity: Entity) -> EntityName {
+    /// Canonizes a given [Entity](struct.Entity.html) ID and returns the associated name.
+    pub fn canonize_id(&mut self, entity: Entity) -> N::Name {
         match self.to_name.entry(entity) {
-            Entry::Occupied(occupied) => *occupied.get(),
+            Entry::Occupied(occupied) => occupied.get().clone(),
             Entry::Vacant(vacant) => {
-                let uuid = Uuid::new_v4();
-                let name = *uuid.as_bytes();
-                vacant.insert(name);
-                self.to_id.insert(name, entity);
+                let mut name = self.names.generate(entity);
+                while self.to_id.contains_key(&name) {
+                    name = self.names.generate(entity);
+                }
+                vacant.insert(name.clone());
+                self.to_id.insert(name.clone(), entity);
                 name
             }
         }
     }
 
     /// Canonizes the given entity and name pair.
-    pub fn canonize(&mut self, entity: Entity, name: EntityName) -> Result<(), CanonizeError> {
+    pub fn canonize(
+        &mut self,
+        entity: Entity,
+        name: N::Name,
+    ) -> Result<(), CanonizeError<N::Name>> {
         if let Some(existing) = self.to_id.get(&name) {
             if existing != &entity {
                 return Err(CanonizeError::NameAlreadyBound(*existing, name));
@@ -174,11 +365,14 @@ impl Canon {
                 if occupied.get() == &name {
                     Ok(())
                 } else {
-                    Err(CanonizeError::EntityAlreadyBound(entity, *occupied.get()))
+                    Err(CanonizeError::EntityAlreadyBound(
+                        entity,
+                        occupied.get().clone(),
+                    ))
                 }
             }
             Entry::Vacant(vacant) => {
-                vacant.insert(name);
+                vacant.insert(name.clone());
                 self.to_id.insert(name, entity);
                 Ok(())
             }
@@ -186,15 +380,137 @@ impl Canon {
     }
 }
 
-impl CustomEntitySerializer for Canon {
-    type SerializedID = uuid::Uuid;
+impl<N: EntityNames> CustomEntitySerializer for Canon<N> {
+    type SerializedID = N::Serialized;
     /// Constructs the serializable representation of `Entity`
     fn to_serialized(&mut self, entity: Entity) -> Self::SerializedID {
-        uuid::Uuid::from_bytes(self.canonize_id(entity))
+        N::to_serialized(self.canonize_id(entity))
     }
 
     /// Convert a `SerializedEntity` to an `Entity`.
     fn from_serialized(&mut self, serialized: Self::SerializedID) -> Entity {
-        self.canonize_name(serialized.as_bytes())
+        self.canonize_name(&N::from_serialized(serialized))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::internals::{
+        query::filter::filter_fns::any,
+        serialize::Registry,
+        world::{EntityStore, World},
+    };
+    use serde::de::DeserializeSeed;
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+    struct Target(Entity);
+
+    #[test]
+    fn uuid_names() {
+        let mut registry = Registry::<String>::default();
+        registry.register::<usize>("usize".to_string());
+        registry.register::<Target>("target".to_string());
+
+        let mut world = World::default();
+        let door = world.push((1usize,));
+        let other = world.push((2usize, Target(door)));
+        let name: EntityName = *Uuid::from_u128(1).as_bytes();
+        registry.entity_serializer().canonize(door, name).unwrap();
+
+        // names are serialized as uuids
+        let json = serde_json::to_value(&world.as_serializable(any(), &registry)).unwrap();
+        let uuid = "00000000-0000-0000-0000-000000000001";
+        assert_eq!(json["entities"][uuid]["usize"], serde_json::json!(1));
+        let other_name = registry.entity_serializer().get_name(other).unwrap();
+        assert_eq!(
+            json["entities"][Uuid::from_bytes(other_name).to_string()]["target"],
+            serde_json::json!(uuid)
+        );
+
+        let world: World = registry.as_deserialize().deserialize(json).unwrap();
+        assert_eq!(registry.entity_serializer().get_id(&name), Some(door));
+        let entry = world.entry_ref(other).unwrap();
+        assert_eq!(entry.get_component::<Target>(), Ok(&Target(door)));
+    }
+
+    #[test]
+    fn path_names() {
+        let mut registry = Registry::<String, Canon<PathNames>>::default();
+        registry.register::<usize>("usize".to_string());
+        registry.register::<Target>("target".to_string());
+
+        let mut world = World::default();
+        let door = world.push((1usize,));
+        let other = world.push((2usize, Target(door)));
+        registry
+            .entity_serializer()
+            .canonize(door, "entity/0".to_string())
+            .unwrap();
+        assert_eq!(
+            registry
+                .entity_serializer()
+                .canonize(other, "entity/0".to_string()),
+            Err(CanonizeError::NameAlreadyBound(
+                door,
+                "entity/0".to_string()
+            ))
+        );
+
+        // generated names skip names which are already bound
+        let json = serde_json::to_value(&world.as_serializable(any(), &registry)).unwrap();
+        assert_eq!(json["entities"]["entity/0"]["usize"], serde_json::json!(1));
+        assert_eq!(
+            json["entities"]["entity/1"]["target"],
+            serde_json::json!("entity/0")
+        );
+
+        let world: World = registry.as_deserialize().deserialize(json).unwrap();
+        let entry = world.entry_ref(other).unwrap();
+        assert_eq!(entry.get_component::<Target>(), Ok(&Target(door)));
+    }
+
+    #[test]
+    fn sequential_names() {
+        use bincode::Options;
+
+        let mut server = Registry::<u32, Canon<SequentialNames>>::new(Canon::with_names(
+            SequentialNames::starting_at(100),
+            Arc::new(crate::internals::entity::SharedAllocator),
+        ));
+        server.register::<usize>(1);
+        server.register::<Target>(2);
+
+        let mut world = World::default();
+        let first = world.push((1usize,));
+        let second = world.push((2usize, Target(first)));
+        let encoded = bincode::serialize(&world.as_serializable(any(), &server)).unwrap();
+        assert_eq!(server.entity_serializer().get_name(first), Some(100));
+        assert_eq!(server.entity_serializer().get_name(second), Some(101));
+
+        // the client assigns its own entity IDs to the server's names
+        let mut client = Registry::<u32, Canon<SequentialNames>>::default();
+        client.register::<usize>(1);
+        client.register::<Target>(2);
+        let world: World = client
+            .as_deserialize()
+            .deserialize(&mut bincode::Deserializer::from_slice(
+                &encoded,
+                bincode::DefaultOptions::new()
+                    .with_fixint_encoding()
+                    .allow_trailing_bytes(),
+            ))
+            .unwrap();
+        let canon = client.entity_serializer();
+        let first = canon.get_id(&100).unwrap();
+        let second = canon.get_id(&101).unwrap();
+        assert_eq!(
+            world.entry_ref(second).unwrap().get_component::<Target>(),
+            Ok(&Target(first))
+        );
+        assert_eq!(
+            world.entry_ref(first).unwrap().get_component::<usize>(),
+            Ok(&1)
+        );
     }
 }
@@ -2,26 +2,44 @@
 
 use crate::{
     internals::{
+        loader::WorldLoader,
+        prefab::Prefab,
+        query::filter::LayoutFilter,
         storage::{
-            archetype::{ArchetypeIndex, EntityLayout},
+            archetype::{Archetype, ArchetypeIndex, EntityLayout},
             component::{Component, ComponentTypeId},
             UnknownComponentStorage,
         },
+        systems::resources::{Resource, ResourceTypeId, Resources},
         world::World,
     },
     storage::UnknownComponentWriter,
     Entity,
 };
+use binary::{
+    DeserializeBinaryIntoWorld, DeserializeBinaryWorld, PodLayout, SerializableBinaryWorld,
+};
 use de::{WorldDeserializer, WorldVisitor};
 use id::{Canon, EntitySerializer};
+use patch::DeserializePatch;
+use resources::{
+    DeserializeIntoResources, DeserializeResourceFn, DeserializeWorldAndResources,
+    SerializableResources, SerializableWorldAndResources, SerializeResourceFn,
+};
+use schema::Migration;
 use ser::WorldSerializer;
 use serde::{de::DeserializeSeed, Serializer};
 use std::{collections::HashMap, hash::Hash, marker::PhantomData};
 
 pub mod archetypes;
+pub mod binary;
 pub mod de;
 mod entities;
 pub mod id;
+pub mod patch;
+pub mod reflect;
+pub mod resources;
+mod schema;
 pub mod ser;
 
 /// A (de)serializable type which can represent a component type in a serialized world.
@@ -42,6 +60,127 @@ pub trait AutoTypeKey<T: Component>: TypeKey {
     fn new() -> Self;
 }
 
+/// The most elements reserved ahead of time for a deserialized sequence, so that a corrupt length
+/// prefix cannot trigger a huge allocation.
+pub(crate) const MAX_PREALLOCATION: usize = 4096;
+
+/// The entities of an archetype which have the same set of
+/// [sparse](../world/struct.World.html#method.register_sparse) components.
+///
+/// Worlds are serialized as groups rather than archetypes, with each group's sparse component
+/// types written as part of its layout. Sparse components are therefore loaded along with their
+/// entities, and are moved back into sparse storage by worlds which register their types as
+/// sparse.
+pub(crate) struct EntityGroup<'a> {
+    pub(crate) archetype: &'a Archetype,
+    pub(crate) sparse: Vec<ComponentTypeId>,
+    /// The indices of the group's entities within the archetype, or `None` if the group contains
+    /// all of the archetype's entities.
+    pub(crate) indices: Option<Vec<usize>>,
+}
+
+impl<'a> EntityGroup<'a> {
+    /// Splits the archetypes of a world into groups, keeping those whose layout, including their
+    /// sparse component types, passes the filter.
+    pub(crate) fn split<F: LayoutFilter>(world: &'a World, filter: &F) -> Vec<Self> {
+        let sparse = world.sparse_components();
+        let mut groups = Vec::new();
+        for archetype in world.archetypes() {
+            if sparse.is_empty()
+                || !archetype
+                    .entities()
+                    .iter()
+                    .any(|entity| sparse.has_components(*entity))
+            {
+                if filter
+                    .matches_layout(archetype.layout().component_types())
+                    .is_pass()
+                {
+                    groups.push(Self {
+                        archetype,
+                        sparse: Vec::new(),
+                        indices: None,
+                    });
+                }
+                continue;
+            }
+
+            let mut split: Vec<Self> = Vec::new();
+            for (index, entity) in archetype.entities().iter().enumerate() {
+                let types = sparse.component_types(*entity);
+                match split.iter_mut().find(|group| group.sparse == types) {
+                    Some(group) => group.indices.as_mut().unwrap().push(index),
+                    None => split.push(Self {
+                        archetype,
+                        sparse: types,
+                        indices: Some(vec![index]),
+                    }),
+                }
+            }
+            groups.extend(split.into_iter().filter(|group| {
+                filter
+                    .matches_layout(&group.component_types().copied().collect::<Vec<_>>())
+                    .is_pass()
+            }));
+        }
+        groups
+    }
+
+    /// Returns the types of the group's components, followed by those of its sparse components.
+    pub(crate) fn component_types(&self) -> impl Iterator<Item = &ComponentTypeId> {
+        self.archetype
+            .layout()
+            .component_types()
+            .iter()
+            .chain(self.sparse.iter())
+    }
+
+    /// Returns the number of entities in the group.
+    pub(crate) fn len(&self) -> usize {
+        match &self.indices {
+            Some(indices) => indices.len(),
+            None => self.archetype.entities().len(),
+        }
+    }
+
+    /// Returns the indices of the group's entities within the archetype.
+    pub(crate) fn indices(&self) -> Vec<usize> {
+        match &self.indices {
+            Some(indices) => indices.clone(),
+            None => (0..self.archetype.entities().len()).collect(),
+        }
+    }
+
+    /// Returns `true` if the group's components of the given type are stored contiguously in
+    /// the archetype's slice.
+    pub(crate) fn is_slice(&self, type_id: ComponentTypeId) -> bool {
+        self.indices.is_none() && !self.sparse.contains(&type_id)
+    }
+
+    /// Returns a pointer to the component of the given type for the entity at `index` within
+    /// the archetype.
+    pub(crate) fn component_ptr(
+        &self,
+        world: &World,
+        type_id: ComponentTypeId,
+        index: usize,
+    ) -> *const u8 {
+        if self.sparse.contains(&type_id) {
+            let entity = self.archetype.entities()[index];
+            return world
+                .sparse_components()
+                .get(type_id)
+                .and_then(|storage| storage.get_raw(entity))
+                .unwrap();
+        }
+
+        let storage = world.components().get(type_id).unwrap();
+        let (ptr, len) = storage.get_raw(self.archetype.index()).unwrap();
+        assert!(index < len);
+        unsafe { ptr.add(index * storage.element_vtable().size()) }
+    }
+}
+
 type SerializeFn = fn(*const u8, &mut dyn FnMut(&dyn erased_serde::Serialize));
 type SerializeSliceFn =
     fn(&dyn UnknownComponentStorage, ArchetypeIndex, &mut dyn FnMut(&dyn erased_serde::Serialize));
@@ -63,6 +202,9 @@ pub enum UnknownType {
 
 /// Describes how to serialize and deserialize a runtime `Entity` ID.
 pub trait CustomEntitySerializer {
+    /// The serializable representation of an entity. If it implements `Ord`,
+    /// [canonical](ser/struct.SerializableWorld.html#method.canonical) output sorts entities
+    /// by it.
     type SerializedID: serde::Serialize + for<'a> serde::Deserialize<'a>;
     /// Constructs the serializable representation of `Entity`
     fn to_serialized(&mut self, entity: Entity) -> Self::SerializedID;
@@ -98,6 +240,11 @@ where
         ),
     >,
     constructors: HashMap<T, (ComponentTypeId, fn(&mut EntityLayout))>,
+    resource_fns: HashMap<ResourceTypeId, (T, SerializeResourceFn)>,
+    resource_constructors: HashMap<T, DeserializeResourceFn>,
+    versions: HashMap<ComponentTypeId, u32>,
+    migrations: HashMap<(ComponentTypeId, u32), Migration>,
+    pod: HashMap<ComponentTypeId, PodLayout>,
     canon: parking_lot::Mutex<S>,
 }
 
@@ -112,12 +259,23 @@ where
             missing: UnknownType::Error,
             serialize_fns: HashMap::new(),
             constructors: HashMap::new(),
+            resource_fns: HashMap::new(),
+            resource_constructors: HashMap::new(),
+            versions: HashMap::new(),
+            migrations: HashMap::new(),
+            pod: HashMap::new(),
             canon: parking_lot::Mutex::new(entity_serializer),
             _phantom_t: PhantomData,
             _phantom_s: PhantomData,
         }
     }
 
+    /// Locks and returns the registry's entity serializer, for example to name entities in its
+    /// [Canon](struct.Canon.html) before serializing a world.
+    pub fn entity_serializer(&self) -> impl std::ops::DerefMut<Target = S> + '_ {
+        self.canon.lock()
+    }
+
     /// Sets the behavior to use when a component type is unknown.
     pub fn on_unknown(&mut self, unknown: UnknownType) {
         self.missing = unknown;
@@ -189,6 +347,249 @@ where
         self.register::<C>(<T as AutoTypeKey<C>>::new())
     }
 
+    /// Registers a component type and its key with the registry, along with the version of the
+    /// component's schema.
+    ///
+    /// Serialized worlds record the schema version of each component type with a non-zero
+    /// version. When a world is deserialized, components which were serialized with a different
+    /// version, including those serialized before the type was versioned (as version `0`), are
+    /// upgraded with the migration [registered](#method.register_migration) for their version.
+    pub fn register_versioned<
+        C: Component + serde::Serialize + for<'de> serde::Deserialize<'de>,
+    >(
+        &mut self,
+        mapped_type_id: T,
+        version: u32,
+    ) {
+        self.register::<C>(mapped_type_id);
+        self.versions.insert(ComponentTypeId::of::<C>(), version);
+    }
+
+    /// Registers a migration which upgrades components of type `C` which were serialized with
+    /// schema version `from_version`. The serialized component is deserialized as `Old`, which
+    /// describes the component's old schema, and converted into a `C` with `migrate`.
+    ///
+    /// `Old` can be any deserializable type, such as a copy of the old component type, or an
+    /// intermediate value type such as `serde_json::Value` for self-describing formats.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use legion::*;
+    /// # use serde::de::DeserializeSeed;
+    /// #[derive(serde::Serialize, serde::Deserialize)]
+    /// struct Health {
+    ///     current: u32,
+    ///     max: u32,
+    /// }
+    ///
+    /// let mut registry = Registry::<String>::default();
+    /// registry.register_versioned::<Health>("health".to_string(), 1);
+    /// registry.register_migration::<Health, serde_json::Value, _>(0, |old| {
+    ///     let hp = old["hp"].as_u64().unwrap() as u32;
+    ///     Health { current: hp, max: hp }
+    /// });
+    ///
+    /// // a world saved before `hp` was split into `current` and `max`
+    /// let json = serde_json::json!({
+    ///     "entities": {
+    ///         "e8fa2fc6-9ed9-4eb4-ae03-da3b6b5b4c44": { "health": { "hp": 10 } }
+    ///     }
+    /// });
+    /// let world = registry.as_deserialize().deserialize(json).unwrap();
+    /// let health = <&Health>::query().iter(&world).next().unwrap();
+    /// assert_eq!(health.max, 10);
+    /// ```
+    pub fn register_migration<C, Old, F>(&mut self, from_version: u32, migrate: F)
+    where
+        C: Component,
+        Old: for<'de> serde::Deserialize<'de>,
+        F: Fn(Old) -> C + Send + Sync + 'static,
+    {
+        self.migrations.insert(
+            (ComponentTypeId::of::<C>(), from_version),
+            Migration::new(migrate),
+        );
+    }
+
+    /// Returns the migration which must be used to deserialize a component, or `None` if the
+    /// component was serialized with the current version of its schema.
+    fn migration(&self, type_id: ComponentTypeId) -> Result<Option<&Migration>, String> {
+        let current = self.versions.get(&type_id).copied().unwrap_or(0);
+        match schema::saved_version(type_id) {
+            Some(saved) if saved != current => self
+                .migrations
+                .get(&(type_id, saved))
+                .map(Some)
+                .ok_or_else(|| {
+                    format!(
+                        "no migration for component {} from schema version {}",
+                        type_id, saved
+                    )
+                }),
+            _ => Ok(None),
+        }
+    }
+
+    /// Registers a plain old data component type and its key with the registry. In the
+    /// [binary world format](struct.SerializableBinaryWorld.html), slices of the component are
+    /// written and loaded as raw bytes rather than through serde. Other formats serialize the
+    /// component through serde as usual.
+    ///
+    /// Raw bytes cannot be [migrated](#method.register_migration), so the binary format refuses
+    /// to load components which were written with a different schema version.
+    ///
+    /// # Safety
+    /// The component's memory must be fully described by its bytes: it must not contain any
+    /// padding, pointers or references, and any bit pattern written by another process must be a
+    /// valid value of the type. A `#[repr(C)]` struct of integers and floats, without padding
+    /// between its fields, satisfies these requirements.
+    pub unsafe fn register_pod<
+        C: Component + Copy + serde::Serialize + for<'de> serde::Deserialize<'de>,
+    >(
+        &mut self,
+        mapped_type_id: T,
+    ) {
+        self.register::<C>(mapped_type_id);
+        self.pod.insert(
+            ComponentTypeId::of::<C>(),
+            PodLayout {
+                size: std::mem::size_of::<C>() as u64,
+                align: std::mem::align_of::<C>() as u64,
+            },
+        );
+    }
+
+    /// Constructs a serializable representation of the entities in a world which match the
+    /// given filter, in legion's [binary world format](struct.SerializableBinaryWorld.html).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use legion::*;
+    /// let mut registry = Registry::<String>::default();
+    /// // safety: `u64` contains no padding or pointers
+    /// unsafe { registry.register_pod::<u64>("u64".to_string()) };
+    ///
+    /// let mut world = World::default();
+    /// world.extend((0..1000u64).map(|i| (i,)));
+    ///
+    /// let encoded = bincode::serialize(&registry.as_serializable_binary(&world, passthrough())).unwrap();
+    /// use bincode::Options;
+    /// let world = bincode::DefaultOptions::new()
+    ///     .with_fixint_encoding()
+    ///     .deserialize_seed(registry.as_deserialize_binary(), &encoded)
+    ///     .unwrap();
+    /// assert_eq!(world.len(), 1000);
+    /// ```
+    pub fn as_serializable_binary<'a, F: LayoutFilter>(
+        &'a self,
+        world: &'a World,
+        filter: F,
+    ) -> SerializableBinaryWorld<'a, F, T, S> {
+        SerializableBinaryWorld::new(self, world, filter)
+    }
+
+    /// Constructs a serde::DeserializeSeed which will deserialize a world written in the
+    /// [binary world format](struct.SerializableBinaryWorld.html) into a new world.
+    pub fn as_deserialize_binary(&self) -> DeserializeBinaryWorld<'_, T, S> {
+        DeserializeBinaryWorld(self)
+    }
+
+    /// Constructs a serde::DeserializeSeed which will deserialize a world written in the
+    /// [binary world format](struct.SerializableBinaryWorld.html) into an existing world.
+    pub fn as_deserialize_binary_into_world<'a>(
+        &'a self,
+        world: &'a mut World,
+    ) -> DeserializeBinaryIntoWorld<'a, T, S> {
+        DeserializeBinaryIntoWorld(self, world)
+    }
+
+    /// Registers a resource type and its key with the registry.
+    pub fn register_resource<R: Resource + serde::Serialize + for<'de> serde::Deserialize<'de>>(
+        &mut self,
+        mapped_type_id: T,
+    ) {
+        let serialize_fn =
+            |resources: &Resources, serialize: &mut dyn FnMut(&dyn erased_serde::Serialize)| {
+                let resource = resources.get::<R>().unwrap();
+                (serialize)(&*resource);
+            };
+        let deserialize_fn =
+            |resources: &mut Resources, deserializer: &mut dyn erased_serde::Deserializer| {
+                resources.insert(erased_serde::deserialize::<R>(deserializer)?);
+                Ok(())
+            };
+        self.resource_fns.insert(
+            ResourceTypeId::of::<R>(),
+            (mapped_type_id.clone(), serialize_fn),
+        );
+        self.resource_constructors
+            .insert(mapped_type_id, deserialize_fn);
+    }
+
+    /// Constructs a serializable representation of the resources in a resource collection.
+    /// Resources are serialized in the order of their type keys.
+    pub fn as_serializable_resources<'a>(
+        &'a self,
+        resources: &'a Resources,
+    ) -> SerializableResources<'a, T, S> {
+        SerializableResources::new(self, resources)
+    }
+
+    /// Constructs a serializable representation of the entities in a world which match the
+    /// given filter, together with the resources in a resource collection.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use legion::*;
+    /// # use serde::de::DeserializeSeed;
+    /// #[derive(serde::Serialize, serde::Deserialize)]
+    /// struct Score(u32);
+    ///
+    /// let mut registry = Registry::<String>::default();
+    /// registry.register::<usize>("usize".to_string());
+    /// registry.register_resource::<Score>("score".to_string());
+    ///
+    /// let mut world = World::default();
+    /// world.push((1usize,));
+    /// let mut resources = Resources::default();
+    /// resources.insert(Score(10));
+    ///
+    /// let json = serde_json::to_value(
+    ///     &registry.as_serializable_with_resources(&world, passthrough(), &resources),
+    /// )
+    /// .unwrap();
+    ///
+    /// let (world, resources) = registry.as_deserialize_with_resources().deserialize(json).unwrap();
+    /// assert_eq!(world.len(), 1);
+    /// assert_eq!(resources.get::<Score>().unwrap().0, 10);
+    /// ```
+    pub fn as_serializable_with_resources<'a, F: LayoutFilter>(
+        &'a self,
+        world: &'a World,
+        filter: F,
+        resources: &'a Resources,
+    ) -> SerializableWorldAndResources<'a, F, T, S> {
+        SerializableWorldAndResources::new(self, world, filter, resources)
+    }
+
+    /// Constructs a serde::DeserializeSeed which will deserialize resources into an existing
+    /// resource collection.
+    pub fn as_deserialize_resources<'a>(
+        &'a self,
+        resources: &'a mut Resources,
+    ) -> DeserializeIntoResources<'a, T, S> {
+        DeserializeIntoResources(self, resources)
+    }
+
+    /// Constructs a serde::DeserializeSeed which will deserialize a world and its resources
+    /// into a new world and resource collection.
+    pub fn as_deserialize_with_resources(&self) -> DeserializeWorldAndResources<'_, T, S> {
+        DeserializeWorldAndResources(self)
+    }
+
     /// Constructs a serde::DeserializeSeed which will deserialize into an existing world.
     pub fn as_deserialize_into_world<'a>(
         &'a self,
@@ -201,6 +602,69 @@ where
     pub fn as_deserialize(&self) -> DeserializeNewWorld<'_, Self> {
         DeserializeNewWorld(&self)
     }
+
+    /// Constructs a serde::DeserializeSeed which will deserialize a serialized world into a
+    /// [WorldLoader](../world/struct.WorldLoader.html), which can then be merged into a world
+    /// incrementally.
+    ///
+    /// The whole document is parsed in a single call, into a staging world which does not touch
+    /// the target world. Only merging the staged entities into the target world is spread across
+    /// calls. To spread deserialization across calls too, serialize the world as several documents
+    /// and stage each one with [as_deserialize_batch](#method.as_deserialize_batch).
+    pub fn as_deserialize_loader(&self) -> DeserializeLoader<'_, Self> {
+        DeserializeLoader(self)
+    }
+
+    /// Constructs a serde::DeserializeSeed which will deserialize a serialized world into the
+    /// staging world of a [WorldLoader](../world/struct.WorldLoader.html), to be merged into the
+    /// target world along with any entities which the loader has not yet merged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use legion::*;
+    /// # use legion::world::{LoadBudget, WorldLoader};
+    /// # use serde::de::DeserializeSeed;
+    /// let mut registry = Registry::<String>::default();
+    /// registry.register::<usize>("usize".to_string());
+    ///
+    /// // a level which was saved in chunks
+    /// let chunks = (0..4usize)
+    ///     .map(|i| {
+    ///         let mut chunk = World::default();
+    ///         chunk.extend((0..25).map(|j| (i * 25 + j,)));
+    ///         serde_json::to_value(&chunk.as_serializable(any(), &registry)).unwrap()
+    ///     })
+    ///     .collect::<Vec<_>>();
+    ///
+    /// let mut world = World::default();
+    /// let mut loader = WorldLoader::new(World::default());
+    /// for chunk in chunks {
+    ///     // each frame stages the next chunk and merges a few entities
+    ///     registry.as_deserialize_batch(&mut loader).deserialize(chunk).unwrap();
+    ///     loader.load_into(&mut world, LoadBudget::Entities(20));
+    /// }
+    /// while !loader.load_into(&mut world, LoadBudget::Entities(20)) {}
+    /// assert_eq!(world.len(), 100);
+    /// ```
+    pub fn as_deserialize_batch<'a>(
+        &'a self,
+        loader: &'a mut WorldLoader,
+    ) -> DeserializeBatch<'a, Self> {
+        DeserializeBatch(self, loader)
+    }
+
+    /// Constructs a serde::DeserializeSeed which will deserialize a serialized world into a
+    /// [Prefab](../world/struct.Prefab.html).
+    pub fn as_deserialize_prefab(&self) -> DeserializePrefab<'_, Self> {
+        DeserializePrefab(self)
+    }
+
+    /// Constructs a serde::DeserializeSeed which will deserialize a serialized
+    /// [WorldDiff](../world/struct.WorldDiff.html) into a [WorldPatch](struct.WorldPatch.html).
+    pub fn as_deserialize_patch(&self) -> DeserializePatch<'_, Self> {
+        DeserializePatch(self)
+    }
 }
 
 impl<T, S> Default for Registry<T, S>
@@ -237,6 +701,57 @@ impl<S: CustomEntitySerializer + 'static> EntitySerializer for &parking_lot::Mut
     }
 }
 
+/// An entity serializer which orders entities by their serialized IDs, used for
+/// [canonical](ser/struct.SerializableWorld.html#method.canonical) output.
+struct OrderedCanon<'a, S>(&'a parking_lot::Mutex<S>);
+
+impl<'a, S> EntitySerializer for OrderedCanon<'a, S>
+where
+    S: CustomEntitySerializer + 'static,
+    S::SerializedID: Ord,
+{
+    fn serialize(
+        &self,
+        entity: Entity,
+        serialize_fn: &mut dyn FnMut(&dyn erased_serde::Serialize),
+    ) {
+        EntitySerializer::serialize(&self.0, entity, serialize_fn)
+    }
+
+    fn deserialize(
+        &self,
+        deserializer: &mut dyn erased_serde::Deserializer,
+    ) -> Result<Entity, erased_serde::Error> {
+        EntitySerializer::deserialize(&self.0, deserializer)
+    }
+
+    fn serialized_order(&self, entities: &[Entity]) -> Vec<usize> {
+        let mut canon = self.0.lock();
+        let serialized = entities
+            .iter()
+            .map(|entity| canon.to_serialized(*entity))
+            .collect::<Vec<_>>();
+        let mut order = (0..entities.len()).collect::<Vec<_>>();
+        order.sort_by(|a, b| serialized[*a].cmp(&serialized[*b]));
+        order
+    }
+}
+
+impl<T, S> Registry<T, S>
+where
+    T: TypeKey,
+    S: CustomEntitySerializer + 'static,
+    S::SerializedID: Ord,
+{
+    /// Calls `callback` with an entity serializer which orders entities by their serialized IDs.
+    pub(crate) fn with_ordered_entity_serializer(
+        &self,
+        callback: &mut dyn FnMut(&dyn EntitySerializer),
+    ) {
+        callback(&OrderedCanon(&self.canon));
+    }
+}
+
 impl<T, S> WorldSerializer for Registry<T, S>
 where
     T: TypeKey,
@@ -268,6 +783,10 @@ where
         }
     }
 
+    fn component_version(&self, type_id: ComponentTypeId) -> u32 {
+        self.versions.get(&type_id).copied().unwrap_or(0)
+    }
+
     fn map_id(&self, type_id: ComponentTypeId) -> Result<Self::TypeId, UnknownType> {
         if let Some(type_id) = self
             .serialize_fns
@@ -339,8 +858,12 @@ where
     ) -> Result<(), D::Error> {
         if let Some((_, _, _, deserialize, _)) = self.serialize_fns.get(&type_id) {
             use serde::de::Error;
-            let mut deserializer = erased_serde::Deserializer::erase(deserializer);
-            (deserialize)(storage, &mut deserializer).map_err(D::Error::custom)
+            let mut deserializer = <dyn erased_serde::Deserializer>::erase(deserializer);
+            match self.migration(type_id).map_err(D::Error::custom)? {
+                Some(migration) => (migration.deserialize_slice)(storage, &mut deserializer),
+                None => (deserialize)(storage, &mut deserializer),
+            }
+            .map_err(D::Error::custom)
         } else {
             //Err(D::Error::custom("unrecognized component type"))
             panic!()
@@ -354,8 +877,12 @@ where
     ) -> Result<Box<[u8]>, D::Error> {
         if let Some((_, _, _, _, deserialize)) = self.serialize_fns.get(&type_id) {
             use serde::de::Error;
-            let mut deserializer = erased_serde::Deserializer::erase(deserializer);
-            (deserialize)(&mut deserializer).map_err(D::Error::custom)
+            let mut deserializer = <dyn erased_serde::Deserializer>::erase(deserializer);
+            match self.migration(type_id).map_err(D::Error::custom)? {
+                Some(migration) => (migration.deserialize_single)(&mut deserializer),
+                None => (deserialize)(&mut deserializer),
+            }
+            .map_err(D::Error::custom)
         } else {
             //Err(D::Error::custom("unrecognized component type"))
             panic!()
@@ -400,7 +927,7 @@ impl<'a, 'de, T: Component + for<'b> serde::de::Deserialize<'b>> serde::de::Dese
                 V: serde::de::SeqAccess<'de>,
             {
                 if let Some(len) = seq.size_hint() {
-                    self.storage.ensure_capacity(len);
+                    self.storage.ensure_capacity(len.min(MAX_PREALLOCATION));
                 }
 
                 while let Some(component) = seq.next_element::<C>()? {
@@ -456,11 +983,76 @@ impl<'a, 'de, W: WorldDeserializer> DeserializeSeed<'de> for DeserializeNewWorld
     }
 }
 
+/// Wraps a [WorldDeserializer](de/trait.WorldDeserializer.html) and implements
+/// `serde::DeserializeSeed` for deserializing a world into a staging world, held by a
+/// [WorldLoader](../world/struct.WorldLoader.html) which merges it into a target world over
+/// multiple calls.
+///
+/// The document is parsed in full by a single call to `deserialize`, as serde cannot suspend a
+/// deserializer part way through a document. To avoid stalling the thread which owns the target
+/// world, deserialize on another thread and send the loader back, or split a large world into
+/// several documents which are each staged with
+/// [DeserializeBatch](struct.DeserializeBatch.html) and loaded in turn.
+pub struct DeserializeLoader<'a, T: WorldDeserializer>(pub &'a T);
+
+impl<'a, 'de, W: WorldDeserializer> DeserializeSeed<'de> for DeserializeLoader<'a, W> {
+    type Value = WorldLoader;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let mut loader = WorldLoader::new(World::default());
+        DeserializeBatch(self.0, &mut loader).deserialize(deserializer)?;
+        Ok(loader)
+    }
+}
+
+/// Wraps a [WorldDeserializer](de/trait.WorldDeserializer.html) and a
+/// [WorldLoader](../world/struct.WorldLoader.html) and implements `serde::DeserializeSeed` for
+/// deserializing another batch of entities into the loader's staging world.
+pub struct DeserializeBatch<'a, T: WorldDeserializer>(pub &'a T, pub &'a mut WorldLoader);
+
+impl<'a, 'de, W: WorldDeserializer> DeserializeSeed<'de> for DeserializeBatch<'a, W> {
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_map(WorldVisitor {
+            world_deserializer: self.0,
+            world: self.1.stage(),
+        })
+    }
+}
+
+/// Wraps a [WorldDeserializer](de/trait.WorldDeserializer.html) and implements
+/// `serde::DeserializeSeed` for deserializing a world into a
+/// [Prefab](../world/struct.Prefab.html).
+pub struct DeserializePrefab<'a, T: WorldDeserializer>(pub &'a T);
+
+impl<'a, 'de, W: WorldDeserializer> DeserializeSeed<'de> for DeserializePrefab<'a, W> {
+    type Value = Prefab;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let world = DeserializeNewWorld(self.0).deserialize(deserializer)?;
+        Ok(Prefab::new(world))
+    }
+}
+
 #[derive(serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "lowercase")]
 enum WorldField {
     Packed,
     Entities,
+    // appended to keep the variant indices of the other fields stable in binary formats, and
+    // named to sort before the other fields in formats which order map keys
+    #[serde(rename = "_versions")]
+    Versions,
 }
 
 #[cfg(test)]
@@ -565,4 +1157,306 @@ mod test {
 
         assert_eq!(8, world.len());
     }
+
+    #[test]
+    fn deserialize_bincode_without_versions() {
+        use crate::internals::query::IntoQuery;
+        use bincode::config::Options;
+        use serde::de::DeserializeSeed;
+
+        // `(1usize, false)` and `(2usize, true)`, as serialized before schema versions existed
+        const ENCODED: &[u8] = &[
+            1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 1,
+            0, 0, 0, 2, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 16, 0, 0, 0, 0, 0, 0, 0, 168, 115, 197, 6,
+            218, 183, 68, 31, 162, 213, 242, 171, 246, 74, 95, 244, 16, 0, 0, 0, 0, 0, 0, 0, 114,
+            89, 161, 142, 161, 110, 69, 72, 181, 125, 194, 69, 65, 149, 181, 27, 2, 0, 0, 0, 0, 0,
+            0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0,
+            0, 2, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 1,
+        ];
+
+        let mut registry = Registry::<i32>::default();
+        registry.register::<usize>(1);
+        registry.register::<bool>(2);
+
+        let mut deserializer = bincode::de::Deserializer::from_slice(
+            ENCODED,
+            bincode::config::DefaultOptions::new()
+                .with_fixint_encoding()
+                .allow_trailing_bytes(),
+        );
+        let world: World = registry
+            .as_deserialize()
+            .deserialize(&mut deserializer)
+            .unwrap();
+
+        let mut components = <(&usize, &bool)>::query()
+            .iter(&world)
+            .map(|(a, b)| (*a, *b))
+            .collect::<Vec<_>>();
+        components.sort_unstable();
+        assert_eq!(components, vec![(1, false), (2, true)]);
+    }
+
+    #[test]
+    fn deserialize_twice_and_remove() {
+        use serde::de::DeserializeSeed;
+
+        let mut registry = Registry::<String>::default();
+        registry.register::<usize>("usize".to_string());
+
+        let mut world = World::default();
+        let entity = world.push((1usize,));
+        let json = serde_json::to_value(&world.as_serializable(any(), &registry)).unwrap();
+
+        // both worlds contain the same entity ID, and both release it
+        let mut a: World = registry.as_deserialize().deserialize(json.clone()).unwrap();
+        let mut b: World = registry.as_deserialize().deserialize(json).unwrap();
+        assert!(a.remove(entity));
+        assert!(b.remove(entity));
+
+        let mut pushed = (0..64usize).map(|i| a.push((i,))).collect::<Vec<_>>();
+        assert_eq!(a.len(), 64);
+        pushed.sort_by_key(|e| (e.index(), e.generation()));
+        pushed.dedup();
+        assert_eq!(pushed.len(), 64);
+    }
+
+    #[test]
+    fn deserialize_into_recycled_index() {
+        use crate::internals::{
+            entity::GenerationalAllocator, query::IntoQuery, world::WorldOptions,
+        };
+        use serde::de::DeserializeSeed;
+        use std::sync::Arc;
+
+        let mut registry = Registry::<String>::default();
+        registry.register::<usize>("usize".to_string());
+
+        let mut world = World::new(WorldOptions {
+            allocator: Arc::new(GenerationalAllocator::new()),
+            ..Default::default()
+        });
+        let entity = world.push((1usize,));
+        let json = serde_json::to_value(&world.as_serializable(any(), &registry)).unwrap();
+
+        // a new entity takes the removed entity's index
+        world.remove(entity);
+        let recycled = (2..64usize)
+            .map(|i| world.push((i,)))
+            .find(|pushed| pushed.index() == entity.index())
+            .unwrap();
+
+        // the loaded entity displaces it
+        registry
+            .as_deserialize_into_world(&mut world)
+            .deserialize(json)
+            .unwrap();
+        assert!(!world.contains(recycled));
+        let entry = world.entry_ref(entity).unwrap();
+        assert_eq!(entry.get_component::<usize>(), Ok(&1usize));
+        assert_eq!(<&usize>::query().iter(&world).count(), world.len());
+    }
+
+    #[test]
+    fn serialize_sparse() {
+        use crate::internals::query::IntoQuery;
+        use bincode::config::Options;
+        use serde::de::DeserializeSeed;
+
+        let mut registry = Registry::<String>::default();
+        registry.register::<usize>("usize".to_string());
+        registry.register::<u16>("u16".to_string());
+
+        let mut world = World::default();
+        world.register_sparse::<u16>();
+        let a = world.push((1usize, 1u16));
+        let b = world.push((2usize,));
+        let c = world.push((3usize, 3u16));
+
+        let json = serde_json::to_value(&world.as_serializable(any(), &registry)).unwrap();
+        assert_eq!(json["entities"].as_object().unwrap().len(), 3);
+        let components = json["entities"]
+            .as_object()
+            .unwrap()
+            .values()
+            .filter(|components| components.get("u16").is_some())
+            .count();
+        assert_eq!(components, 2);
+
+        let assert_loaded = |loaded: &World| {
+            assert_eq!(loaded.len(), 3);
+            let sparse = loaded.sparse_components().get_downcast::<u16>().unwrap();
+            assert_eq!(sparse.get(a), Some(&1));
+            assert_eq!(sparse.get(b), None);
+            assert_eq!(sparse.get(c), Some(&3));
+            let archetype = |entity| loaded.entry_ref(entity).unwrap().location().archetype();
+            assert_eq!(archetype(a), archetype(b));
+            assert_eq!(archetype(b), archetype(c));
+            assert_eq!(
+                loaded.entry_ref(c).unwrap().get_component::<usize>(),
+                Ok(&3)
+            );
+        };
+
+        let mut loaded = World::default();
+        loaded.register_sparse::<u16>();
+        registry
+            .as_deserialize_into_world(&mut loaded)
+            .deserialize(json.clone())
+            .unwrap();
+        assert_loaded(&loaded);
+
+        // worlds which do not store the type sparsely load it into archetypes
+        let loaded: World = registry.as_deserialize().deserialize(json).unwrap();
+        assert_eq!(<&u16>::query().iter(&loaded).count(), 2);
+
+        for canonical in &[false, true] {
+            let serializable = world.as_serializable(any(), &registry);
+            let serializable = if *canonical {
+                serializable.canonical()
+            } else {
+                serializable
+            };
+            let encoded = bincode::serialize(&serializable).unwrap();
+            let mut deserializer = bincode::de::Deserializer::from_slice(
+                &encoded[..],
+                bincode::config::DefaultOptions::new()
+                    .with_fixint_encoding()
+                    .allow_trailing_bytes(),
+            );
+            let mut loaded = World::default();
+            loaded.register_sparse::<u16>();
+            registry
+                .as_deserialize_into_world(&mut loaded)
+                .deserialize(&mut deserializer)
+                .unwrap();
+            assert_loaded(&loaded);
+        }
+    }
+
+    #[test]
+    fn serialize_canonical() {
+        use bincode::config::Options;
+        use serde::de::DeserializeSeed;
+
+        let mut registry = Registry::<String>::default();
+        registry.register::<usize>("usize".to_string());
+        registry.register::<bool>("bool".to_string());
+        registry.register::<isize>("isize".to_string());
+
+        let mut a = World::default();
+        let entities = a
+            .extend(vec![(1usize, false), (2usize, true), (3usize, false)])
+            .to_vec();
+        let removed = a.push((4usize, 4isize));
+        let single = a.push((5usize, 5isize));
+        a.remove(entities[0]);
+        a.remove(removed);
+
+        // the same entities, built in a different order
+        let mut b = World::default();
+        b.push_with_id(single, (5isize, 5usize));
+        b.push_with_id(entities[2], (false, 3usize));
+        b.push_with_id(entities[1], (true, 2usize));
+
+        let json_a = serde_json::to_string(&a.as_serializable(any(), &registry).canonical());
+        let json_b = serde_json::to_string(&b.as_serializable(any(), &registry).canonical());
+        assert_eq!(json_a.unwrap(), json_b.unwrap());
+
+        let bincode_a = bincode::serialize(&a.as_serializable(any(), &registry).canonical());
+        let bincode_b = bincode::serialize(&b.as_serializable(any(), &registry).canonical());
+        let encoded = bincode_a.unwrap();
+        assert_eq!(encoded, bincode_b.unwrap());
+
+        let mut deserializer = bincode::de::Deserializer::from_slice(
+            &encoded[..],
+            bincode::config::DefaultOptions::new()
+                .with_fixint_encoding()
+                .allow_trailing_bytes(),
+        );
+        let world: World = registry
+            .as_deserialize()
+            .deserialize(&mut deserializer)
+            .unwrap();
+        assert_eq!(3, world.len());
+        let entry = world.entry_ref(entities[1]).unwrap();
+        assert_eq!(entry.get_component::<usize>(), Ok(&2usize));
+        assert_eq!(entry.get_component::<bool>(), Ok(&true));
+        let entry = world.entry_ref(single).unwrap();
+        assert_eq!(entry.get_component::<isize>(), Ok(&5isize));
+    }
+
+    #[test]
+    fn serialize_unordered_ids() {
+        use super::CustomEntitySerializer;
+        use serde::de::DeserializeSeed;
+
+        // serialized IDs only need to be ordered for canonical output
+        #[derive(Default)]
+        struct Indexed(Vec<Entity>);
+
+        impl CustomEntitySerializer for Indexed {
+            type SerializedID = f64;
+
+            fn to_serialized(&mut self, entity: Entity) -> f64 {
+                let index = match self.0.iter().position(|e| *e == entity) {
+                    Some(index) => index,
+                    None => {
+                        self.0.push(entity);
+                        self.0.len() - 1
+                    }
+                };
+                index as f64
+            }
+
+            fn from_serialized(&mut self, serialized: f64) -> Entity {
+                self.0[serialized as usize]
+            }
+        }
+
+        let mut world = World::default();
+        let entity = world.push((1usize,));
+
+        let mut registry = Registry::<String, Indexed>::default();
+        registry.register::<usize>("usize".to_string());
+
+        let json = serde_json::to_value(&world.as_serializable(any(), &registry)).unwrap();
+        let world: World = registry.as_deserialize().deserialize(json).unwrap();
+        let entry = world.entry_ref(entity).unwrap();
+        assert_eq!(entry.get_component::<usize>(), Ok(&1usize));
+    }
+
+    #[test]
+    fn deserialize_loader() {
+        use crate::internals::loader::LoadBudget;
+        use serde::de::DeserializeSeed;
+
+        let mut world = World::default();
+        let entities = world.extend((0..20usize).map(|i| (i, i % 2 == 0))).to_vec();
+
+        let mut registry = Registry::<String>::default();
+        registry.register::<usize>("usize".to_string());
+        registry.register::<bool>("bool".to_string());
+
+        let json = serde_json::to_value(&world.as_serializable(any(), &registry)).unwrap();
+        // the document is parsed in full up front, so parse it away from the target world
+        let mut loader = std::thread::scope(|scope| {
+            scope
+                .spawn(|| registry.as_deserialize_loader().deserialize(json).unwrap())
+                .join()
+                .unwrap()
+        });
+        assert_eq!(loader.remaining(), 20);
+
+        let mut target = World::default();
+        assert!(!loader.load_into(&mut target, LoadBudget::Entities(15)));
+        assert_eq!(target.len(), 15);
+        assert!(loader.load_into(&mut target, LoadBudget::Entities(15)));
+
+        for (i, entity) in entities.iter().enumerate() {
+            let entry = target.entry_ref(*entity).unwrap();
+            assert_eq!(entry.get_component::<usize>(), Ok(&i));
+            assert_eq!(entry.get_component::<bool>(), Ok(&(i % 2 == 0)));
+        }
+    }
 }
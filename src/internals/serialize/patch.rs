@@ -0,0 +1,1003 @@
+//! World diff serialization, and the patches which serialized diffs deserialize into.
+
+use super::{de::WorldDeserializer, id::run_as_context, ser::WorldSerializer, UnknownType};
+use crate::internals::{
+    diff::WorldDiff,
+    entity::{Entity, EntityHasher, EntityLocation},
+    entry::DynamicArchetype,
+    hooks::HookKind,
+    storage::{
+        archetype::EntityLayout, component::ComponentTypeId, next_component_version, ComponentMeta,
+        UnknownComponentStorage,
+    },
+    world::{EntityStore, World},
+};
+use serde::{
+    de::{DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor},
+    ser::{SerializeMap, SerializeStruct},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::{collections::HashMap, fmt::Debug};
+
+/// A serializable representation of a [WorldDiff](../world/struct.WorldDiff.html).
+pub struct SerializableWorldDiff<'a, W: WorldSerializer> {
+    diff: &'a WorldDiff<'a>,
+    world_serializer: &'a W,
+}
+
+impl<'a, W: WorldSerializer> SerializableWorldDiff<'a, W> {
+    pub(crate) fn new(diff: &'a WorldDiff<'a>, world_serializer: &'a W) -> Self {
+        Self {
+            diff,
+            world_serializer,
+        }
+    }
+}
+
+/// Groups `(entity, component)` pairs by entity, in order of each entity's first appearance.
+fn group_by_entity<'a>(
+    pairs: impl Iterator<Item = &'a (Entity, ComponentTypeId)>,
+) -> Vec<(Entity, Vec<ComponentTypeId>)> {
+    let mut groups = Vec::<(Entity, Vec<ComponentTypeId>)>::new();
+    let mut indices = HashMap::<Entity, usize, EntityHasher>::default();
+    for (entity, type_id) in pairs {
+        let index = *indices.entry(*entity).or_insert_with(|| {
+            groups.push((*entity, Vec::new()));
+            groups.len() - 1
+        });
+        groups[index].1.push(*type_id);
+    }
+    groups
+}
+
+impl<'a, W: WorldSerializer> Serialize for SerializableWorldDiff<'a, W> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let diff = self.diff;
+        let mut type_mappings = HashMap::new();
+        for (_, type_id) in diff
+            .added()
+            .iter()
+            .chain(diff.removed())
+            .chain(diff.changed())
+        {
+            if type_mappings.contains_key(type_id) {
+                continue;
+            }
+            match self.world_serializer.map_id(*type_id) {
+                Ok(mapped) => {
+                    type_mappings.insert(*type_id, mapped);
+                }
+                Err(UnknownType::Ignore) => {}
+                Err(UnknownType::Error) => {
+                    return Err(serde::ser::Error::custom(format!(
+                        "unknown component type {:?}",
+                        type_id
+                    )));
+                }
+            }
+        }
+
+        let removed = group_by_entity(diff.removed().iter());
+        let components = group_by_entity(diff.added().iter().chain(diff.changed()));
+
+        let mut serializer = Some(serializer);
+        let mut hoist = None;
+        let hoist_ref = &mut hoist;
+        self.world_serializer.with_entity_serializer(&mut |canon| {
+            let serializer = serializer.take().unwrap();
+            *hoist_ref = Some(run_as_context(canon, || {
+                let mut root = serializer.serialize_struct("WorldDiff", 4)?;
+                root.serialize_field("despawned", diff.despawned())?;
+                root.serialize_field("spawned", diff.spawned())?;
+                root.serialize_field(
+                    "removed",
+                    &RemovedSerializer {
+                        removed: &removed,
+                        type_mappings: &type_mappings,
+                    },
+                )?;
+                root.serialize_field(
+                    "components",
+                    &ComponentsSerializer {
+                        components: &components,
+                        type_mappings: &type_mappings,
+                        world: diff.world(),
+                        world_serializer: self.world_serializer,
+                    },
+                )?;
+                root.end()
+            }));
+        });
+        hoist.unwrap()
+    }
+}
+
+struct RemovedSerializer<'a, T: Serialize> {
+    removed: &'a [(Entity, Vec<ComponentTypeId>)],
+    type_mappings: &'a HashMap<ComponentTypeId, T>,
+}
+
+impl<'a, T: Serialize> Serialize for RemovedSerializer<'a, T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(self.removed.len()))?;
+        for (entity, type_ids) in self.removed {
+            let mapped = type_ids
+                .iter()
+                .filter_map(|type_id| self.type_mappings.get(type_id))
+                .collect::<Vec<_>>();
+            map.serialize_entry(entity, &mapped)?;
+        }
+        map.end()
+    }
+}
+
+struct ComponentsSerializer<'a, W: WorldSerializer> {
+    components: &'a [(Entity, Vec<ComponentTypeId>)],
+    type_mappings: &'a HashMap<ComponentTypeId, W::TypeId>,
+    world: &'a World,
+    world_serializer: &'a W,
+}
+
+impl<'a, W: WorldSerializer> Serialize for ComponentsSerializer<'a, W> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(self.components.len()))?;
+        for (entity, type_ids) in self.components {
+            let location = self.world.entry_ref(*entity).unwrap().location();
+            map.serialize_entry(
+                entity,
+                &EntityComponentsSerializer {
+                    location,
+                    type_ids,
+                    type_mappings: self.type_mappings,
+                    world: self.world,
+                    world_serializer: self.world_serializer,
+                },
+            )?;
+        }
+        map.end()
+    }
+}
+
+struct EntityComponentsSerializer<'a, W: WorldSerializer> {
+    location: EntityLocation,
+    type_ids: &'a [ComponentTypeId],
+    type_mappings: &'a HashMap<ComponentTypeId, W::TypeId>,
+    world: &'a World,
+    world_serializer: &'a W,
+}
+
+impl<'a, W: WorldSerializer> Serialize for EntityComponentsSerializer<'a, W> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let type_count = self
+            .type_ids
+            .iter()
+            .filter(|type_id| self.type_mappings.contains_key(type_id))
+            .count();
+        let mut map = serializer.serialize_map(Some(type_count))?;
+        let entity = self.world.archetypes()[self.location.archetype()].entities()
+            [self.location.component().0];
+        for type_id in self.type_ids {
+            if let Some(mapped) = self.type_mappings.get(type_id) {
+                let ptr = match self.world.sparse_components().get(*type_id) {
+                    Some(storage) => storage.get_raw(entity).unwrap(),
+                    None => {
+                        let storage = self.world.components().get(*type_id).unwrap();
+                        let (ptr, len) = storage.get_raw(self.location.archetype()).unwrap();
+                        assert!(self.location.component().0 < len);
+                        unsafe {
+                            ptr.add(self.location.component().0 * storage.element_vtable().size())
+                        }
+                    }
+                };
+                map.serialize_entry(
+                    mapped,
+                    &ComponentSerializer {
+                        type_id: *type_id,
+                        ptr,
+                        world_serializer: self.world_serializer,
+                    },
+                )?;
+            }
+        }
+        map.end()
+    }
+}
+
+struct ComponentSerializer<'a, W: WorldSerializer> {
+    type_id: ComponentTypeId,
+    ptr: *const u8,
+    world_serializer: &'a W,
+}
+
+impl<'a, W: WorldSerializer> Serialize for ComponentSerializer<'a, W> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        unsafe {
+            self.world_serializer
+                .serialize_component(self.type_id, self.ptr, serializer)
+        }
+    }
+}
+
+/// A deserialized [WorldDiff](../world/struct.WorldDiff.html), which can be applied to a world
+/// with [World::apply_patch](../world/struct.World.html#method.apply_patch).
+#[derive(Debug, Default)]
+pub struct WorldPatch {
+    despawned: Vec<Entity>,
+    spawned: Vec<Entity>,
+    removed: Vec<(Entity, Vec<ComponentTypeId>)>,
+    components: Vec<(Entity, Vec<PatchComponent>)>,
+}
+
+impl WorldPatch {
+    /// Returns the entities which the patch despawns.
+    pub fn despawned(&self) -> &[Entity] {
+        &self.despawned
+    }
+
+    /// Returns the entities which the patch spawns.
+    pub fn spawned(&self) -> &[Entity] {
+        &self.spawned
+    }
+
+    /// Returns `true` if applying the patch would not modify a world.
+    pub fn is_empty(&self) -> bool {
+        self.despawned.is_empty()
+            && self.spawned.is_empty()
+            && self.removed.is_empty()
+            && self.components.is_empty()
+    }
+}
+
+/// A deserialized component value which has not yet been moved into a world.
+pub(crate) struct PatchComponent {
+    type_id: ComponentTypeId,
+    constructor: fn() -> Box<dyn UnknownComponentStorage>,
+    meta: ComponentMeta,
+    data: Option<Box<[u8]>>,
+}
+
+impl PatchComponent {
+    /// Wraps a component value which was read into `data`, taking ownership of the value.
+    pub(crate) fn new(
+        type_id: ComponentTypeId,
+        constructor: fn() -> Box<dyn UnknownComponentStorage>,
+        data: Box<[u8]>,
+    ) -> Self {
+        Self {
+            type_id,
+            constructor,
+            meta: constructor().element_vtable(),
+            data: Some(data),
+        }
+    }
+
+    /// Takes the component's data, which the caller is responsible for moving into a storage.
+    fn take(&mut self) -> Box<[u8]> {
+        self.data.take().unwrap()
+    }
+}
+
+impl Drop for PatchComponent {
+    fn drop(&mut self) {
+        if let Some(mut data) = self.data.take() {
+            // safety: the data has not been moved into a world, so we still own the component
+            unsafe { self.meta.drop(data.as_mut_ptr()) };
+        }
+    }
+}
+
+impl Debug for PatchComponent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("PatchComponent")
+            .field(&self.type_id)
+            .finish()
+    }
+}
+
+/// Wraps a [WorldDeserializer](de/trait.WorldDeserializer.html) and implements
+/// `serde::DeserializeSeed` for deserializing a serialized
+/// [WorldDiff](../world/struct.WorldDiff.html) into a [WorldPatch](struct.WorldPatch.html).
+pub struct DeserializePatch<'a, T: WorldDeserializer>(pub &'a T);
+
+impl<'a, 'de, W: WorldDeserializer> DeserializeSeed<'de> for DeserializePatch<'a, W> {
+    type Value = WorldPatch;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        const FIELDS: &[&str] = &["despawned", "spawned", "removed", "components"];
+
+        let world_deserializer = self.0;
+        let mut deserializer = Some(deserializer);
+        let mut hoist = None;
+        let hoist_ref = &mut hoist;
+        world_deserializer.with_entity_serializer(&mut |canon| {
+            let deserializer = deserializer.take().unwrap();
+            *hoist_ref = Some(run_as_context(canon, || {
+                deserializer.deserialize_struct(
+                    "WorldDiff",
+                    FIELDS,
+                    PatchVisitor { world_deserializer },
+                )
+            }));
+        });
+        hoist.unwrap()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "lowercase")]
+enum PatchField {
+    Despawned,
+    Spawned,
+    Removed,
+    Components,
+}
+
+struct PatchVisitor<'a, W: WorldDeserializer> {
+    world_deserializer: &'a W,
+}
+
+impl<'a, 'de, W: WorldDeserializer> Visitor<'de> for PatchVisitor<'a, W> {
+    type Value = WorldPatch;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("struct WorldDiff")
+    }
+
+    fn visit_seq<V>(self, mut seq: V) -> Result<Self::Value, V::Error>
+    where
+        V: SeqAccess<'de>,
+    {
+        use serde::de::Error;
+        let mut constructors = HashMap::new();
+        let despawned = seq
+            .next_element()?
+            .ok_or_else(|| V::Error::invalid_length(0, &self))?;
+        let spawned = seq
+            .next_element()?
+            .ok_or_else(|| V::Error::invalid_length(1, &self))?;
+        let removed = seq
+            .next_element_seed(RemovedDeserializer {
+                world_deserializer: self.world_deserializer,
+            })?
+            .ok_or_else(|| V::Error::invalid_length(2, &self))?;
+        let components = seq
+            .next_element_seed(ComponentsDeserializer {
+                world_deserializer: self.world_deserializer,
+                constructors: &mut constructors,
+            })?
+            .ok_or_else(|| V::Error::invalid_length(3, &self))?;
+        Ok(WorldPatch {
+            despawned,
+            spawned,
+            removed,
+            components,
+        })
+    }
+
+    fn visit_map<V>(self, mut map: V) -> Result<Self::Value, V::Error>
+    where
+        V: MapAccess<'de>,
+    {
+        let mut constructors = HashMap::new();
+        let mut patch = WorldPatch::default();
+        while let Some(key) = map.next_key()? {
+            match key {
+                PatchField::Despawned => patch.despawned = map.next_value()?,
+                PatchField::Spawned => patch.spawned = map.next_value()?,
+                PatchField::Removed => {
+                    patch.removed = map.next_value_seed(RemovedDeserializer {
+                        world_deserializer: self.world_deserializer,
+                    })?
+                }
+                PatchField::Components => {
+                    patch.components = map.next_value_seed(ComponentsDeserializer {
+                        world_deserializer: self.world_deserializer,
+                        constructors: &mut constructors,
+                    })?
+                }
+            }
+        }
+        Ok(patch)
+    }
+}
+
+struct RemovedDeserializer<'a, W: WorldDeserializer> {
+    world_deserializer: &'a W,
+}
+
+impl<'a, 'de, W: WorldDeserializer> DeserializeSeed<'de> for RemovedDeserializer<'a, W> {
+    type Value = Vec<(Entity, Vec<ComponentTypeId>)>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'a, 'de, W: WorldDeserializer> Visitor<'de> for RemovedDeserializer<'a, W> {
+    type Value = Vec<(Entity, Vec<ComponentTypeId>)>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("removed component map")
+    }
+
+    fn visit_map<V>(self, mut map: V) -> Result<Self::Value, V::Error>
+    where
+        V: MapAccess<'de>,
+    {
+        let mut removed = Vec::new();
+        while let Some(entity) = map.next_key()? {
+            let mut type_ids = Vec::new();
+            for mapped_type_id in map.next_value::<Vec<W::TypeId>>()? {
+                match self.world_deserializer.unmap_id(&mapped_type_id) {
+                    Ok(type_id) => type_ids.push(type_id),
+                    Err(UnknownType::Ignore) => {}
+                    Err(UnknownType::Error) => {
+                        return Err(serde::de::Error::custom("unknown component type"));
+                    }
+                }
+            }
+            removed.push((entity, type_ids));
+        }
+        Ok(removed)
+    }
+}
+
+type Constructors =
+    HashMap<ComponentTypeId, (fn() -> Box<dyn UnknownComponentStorage>, ComponentMeta)>;
+
+struct ComponentsDeserializer<'a, W: WorldDeserializer> {
+    world_deserializer: &'a W,
+    constructors: &'a mut Constructors,
+}
+
+impl<'a, 'de, W: WorldDeserializer> DeserializeSeed<'de> for ComponentsDeserializer<'a, W> {
+    type Value = Vec<(Entity, Vec<PatchComponent>)>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'a, 'de, W: WorldDeserializer> Visitor<'de> for ComponentsDeserializer<'a, W> {
+    type Value = Vec<(Entity, Vec<PatchComponent>)>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("entity component map")
+    }
+
+    fn visit_map<V>(self, mut map: V) -> Result<Self::Value, V::Error>
+    where
+        V: MapAccess<'de>,
+    {
+        let mut components = Vec::new();
+        while let Some(entity) = map.next_key()? {
+            let entity_components = map.next_value_seed(EntityComponentsDeserializer {
+                world_deserializer: self.world_deserializer,
+                constructors: self.constructors,
+            })?;
+            components.push((entity, entity_components));
+        }
+        Ok(components)
+    }
+}
+
+struct EntityComponentsDeserializer<'a, W: WorldDeserializer> {
+    world_deserializer: &'a W,
+    constructors: &'a mut Constructors,
+}
+
+impl<'a, 'de, W: WorldDeserializer> DeserializeSeed<'de> for EntityComponentsDeserializer<'a, W> {
+    type Value = Vec<PatchComponent>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'a, 'de, W: WorldDeserializer> Visitor<'de> for EntityComponentsDeserializer<'a, W> {
+    type Value = Vec<PatchComponent>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("component map")
+    }
+
+    fn visit_map<V>(self, mut map: V) -> Result<Self::Value, V::Error>
+    where
+        V: MapAccess<'de>,
+    {
+        use serde::de::Error;
+        let mut components = Vec::new();
+        while let Some(mapped_type_id) = map.next_key::<W::TypeId>()? {
+            let type_id = match self.world_deserializer.unmap_id(&mapped_type_id) {
+                Ok(type_id) => type_id,
+                Err(UnknownType::Ignore) => {
+                    map.next_value::<IgnoredAny>()?;
+                    continue;
+                }
+                Err(UnknownType::Error) => {
+                    return Err(V::Error::custom("unknown component type"));
+                }
+            };
+
+            let (constructor, meta) = if let Some(entry) = self.constructors.get(&type_id) {
+                *entry
+            } else {
+                let mut layout = EntityLayout::new();
+                self.world_deserializer
+                    .register_component(mapped_type_id, &mut layout);
+                let constructor = *layout
+                    .component_constructors()
+                    .first()
+                    .ok_or_else(|| V::Error::custom("unknown component type"))?;
+                let meta = constructor().element_vtable();
+                self.constructors.insert(type_id, (constructor, meta));
+                (constructor, meta)
+            };
+
+            let data = map.next_value_seed(ComponentDeserializer {
+                type_id,
+                world_deserializer: self.world_deserializer,
+            })?;
+            components.push(PatchComponent {
+                type_id,
+                constructor,
+                meta,
+                data: Some(data),
+            });
+        }
+        Ok(components)
+    }
+}
+
+struct ComponentDeserializer<'a, W: WorldDeserializer> {
+    type_id: ComponentTypeId,
+    world_deserializer: &'a W,
+}
+
+impl<'a, 'de, W: WorldDeserializer> DeserializeSeed<'de> for ComponentDeserializer<'a, W> {
+    type Value = Box<[u8]>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        self.world_deserializer
+            .deserialize_component(self.type_id, deserializer)
+    }
+}
+
+impl World {
+    /// Applies a [patch](../serialize/struct.WorldPatch.html), deserialized from a
+    /// [WorldDiff](struct.WorldDiff.html), to the world.
+    ///
+    /// Despawned entities are removed as with [remove](#method.remove), which also removes their
+    /// [children](../hierarchy/struct.Children.html) and detaches them from their parents, and
+    /// spawned entities are inserted with their original IDs, replacing any existing entity with
+    /// the same ID. The IDs of despawned entities are not released, as they belong to the world
+    /// from which the diff was computed. Components are then removed from, added to or
+    /// overwritten on each entity. Changes to entities which do not exist in the world are
+    /// ignored. Component [hooks](#method.on_add) run as they would for the equivalent
+    /// [Entry](struct.Entry.html) operations.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use legion::*;
+    /// # use legion::world::Duplicate;
+    /// # use serde::de::DeserializeSeed;
+    /// let mut registry = Registry::<String>::default();
+    /// registry.register::<usize>("usize".to_string());
+    ///
+    /// let mut server = World::default();
+    /// let entity = server.push((1usize,));
+    ///
+    /// // replicate the world to a client
+    /// let mut client = World::default();
+    /// let json = serde_json::to_value(&server.diff(&client, 0).as_serializable(&registry)).unwrap();
+    /// client.apply_patch(registry.as_deserialize_patch().deserialize(json).unwrap());
+    /// let version = server.diff(&client, 0).version();
+    ///
+    /// // send the changes made to the server since the last update
+    /// *server.entry(entity).unwrap().get_component_mut::<usize>().unwrap() = 2;
+    /// let json = serde_json::to_value(&server.diff(&client, version).as_serializable(&registry)).unwrap();
+    /// client.apply_patch(registry.as_deserialize_patch().deserialize(json).unwrap());
+    ///
+    /// assert_eq!(client.entry(entity).unwrap().get_component::<usize>(), Ok(&2));
+    /// ```
+    pub fn apply_patch(&mut self, patch: WorldPatch) {
+        let WorldPatch {
+            despawned,
+            spawned,
+            removed,
+            components,
+        } = patch;
+
+        for entity in despawned {
+            self.remove_with_children(entity, false);
+        }
+        for entity in spawned {
+            self.push_with_id(entity, ());
+        }
+        for (entity, type_ids) in removed {
+            self.patch_entity(entity, &type_ids, Vec::new());
+        }
+        for (entity, components) in components {
+            self.patch_entity(entity, &[], components);
+        }
+    }
+
+    /// Removes the `remove` component types from an entity, then inserts `components`,
+    /// overwriting any components of the same types. Does nothing if the entity does not exist.
+    pub(crate) fn patch_entity(
+        &mut self,
+        entity: Entity,
+        remove: &[ComponentTypeId],
+        components: Vec<PatchComponent>,
+    ) {
+        let location = match self.entry_ref(entity) {
+            Ok(entry) => entry.location(),
+            Err(_) => return,
+        };
+        let layout = self.archetypes()[location.archetype()].layout().clone();
+
+        // overwrite the components which the entity already has in place
+        let mut added = Vec::new();
+        for mut component in components {
+            if !layout.has_component_by_id(component.type_id) {
+                // sparse components are replaced as they are moved into sparse storage
+                if self.has_sparse_component(entity, component.type_id) {
+                    self.run_hooks(HookKind::Remove, component.type_id, location);
+                }
+                added.push(component);
+                continue;
+            }
+            self.run_hooks(HookKind::Remove, component.type_id, location);
+            let data = component.take();
+            unsafe {
+                self.components_mut()
+                    .get_mut(component.type_id)
+                    .unwrap()
+                    .replace_raw(location.archetype(), location.component(), data.as_ptr())
+            };
+            self.run_hooks(HookKind::Add, component.type_id, location);
+        }
+
+        for type_id in remove {
+            if !layout.has_component_by_id(*type_id) {
+                self.remove_sparse_component(location, *type_id);
+            }
+        }
+        let remove = remove
+            .iter()
+            .copied()
+            .filter(|type_id| layout.has_component_by_id(*type_id))
+            .collect::<Vec<_>>();
+        if added.is_empty() && remove.is_empty() {
+            return;
+        }
+
+        let add = added.iter().map(|c| c.type_id).collect::<Vec<_>>();
+        let add_constructors = added.iter().map(|c| c.constructor).collect::<Vec<_>>();
+        let target_arch = self.get_archetype_for_components(&mut DynamicArchetype {
+            base: layout,
+            add: &add,
+            add_constructors: &add_constructors,
+            remove: &remove,
+        });
+        let location = unsafe {
+            let idx =
+                self.transfer_archetype(location.archetype(), target_arch, location.component());
+            for component in &mut added {
+                let data = component.take();
+                self.components_mut()
+                    .get_mut(component.type_id)
+                    .unwrap()
+                    .extend_memcopy_raw(target_arch, data.as_ptr(), 1);
+            }
+            EntityLocation::new(target_arch, idx)
+        };
+
+        // components which this world stores sparsely are moved out of the archetype
+        self.flush_sparse(target_arch);
+        let location = self
+            .entry_ref(entity)
+            .map_or(location, |entry| entry.location());
+
+        for type_id in add {
+            self.run_hooks(HookKind::Add, type_id, location);
+        }
+        let version = next_component_version();
+        for type_id in remove {
+            self.record_removal_at(type_id, entity, version);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::internals::{
+        hierarchy::Children,
+        query::IntoQuery,
+        serialize::Registry,
+        world::{Duplicate, EntityStore},
+    };
+    use std::sync::{Arc, Mutex};
+
+    fn registry() -> Registry<String> {
+        let mut registry = Registry::<String>::default();
+        registry.register::<usize>("usize".to_string());
+        registry.register::<bool>("bool".to_string());
+        registry.register::<String>("string".to_string());
+        registry.register::<Entity>("entity".to_string());
+        registry
+    }
+
+    fn duplicate() -> Duplicate {
+        let mut duplicate = Duplicate::new();
+        duplicate.register_copy::<usize>();
+        duplicate.register_copy::<bool>();
+        duplicate.register_clone::<String>();
+        duplicate.register_copy::<Entity>();
+        duplicate
+    }
+
+    fn assert_replicated(server: &World, client: &World) {
+        assert_eq!(server.len(), client.len());
+        let mut query = <(Entity, Option<&usize>, Option<&bool>, Option<&String>)>::query();
+        for (entity, a, b, c) in query.iter(server) {
+            let entry = client.entry_ref(*entity).unwrap();
+            assert_eq!(entry.get_component::<usize>().ok(), a);
+            assert_eq!(entry.get_component::<bool>().ok(), b);
+            assert_eq!(entry.get_component::<String>().ok(), c);
+        }
+    }
+
+    #[test]
+    fn patch_json() {
+        let registry = registry();
+        let mut duplicate = duplicate();
+
+        let mut server = World::default();
+        let a = server.push((1usize, false, "a".to_string()));
+        let b = server.push((2usize, true));
+        let c = server.push((3usize, b));
+
+        let mut client = World::default();
+        let json =
+            serde_json::to_value(&server.diff(&client, 0).as_serializable(&registry)).unwrap();
+        let patch = registry.as_deserialize_patch().deserialize(json).unwrap();
+        assert_eq!(patch.spawned().len(), 3);
+        client.apply_patch(patch);
+        assert_replicated(&server, &client);
+        assert_eq!(
+            client.entry_ref(c).unwrap().get_component::<Entity>(),
+            Ok(&b)
+        );
+
+        let snapshot = server.snapshot(&mut duplicate);
+        *server
+            .entry(a)
+            .unwrap()
+            .get_component_mut::<String>()
+            .unwrap() = "changed".to_string();
+        {
+            let mut entry = server.entry(b).unwrap();
+            entry.remove_component::<bool>();
+            entry.add_component("b".to_string());
+        }
+        server.remove(c);
+        server.push((4usize,));
+
+        let json =
+            serde_json::to_value(&server.diff_snapshot(&snapshot).as_serializable(&registry))
+                .unwrap();
+        let patch = registry.as_deserialize_patch().deserialize(json).unwrap();
+        assert_eq!(patch.despawned(), &[c]);
+        client.apply_patch(patch);
+        assert_replicated(&server, &client);
+    }
+
+    #[test]
+    fn patch_sparse() {
+        let registry = registry();
+        let mut duplicate = duplicate();
+
+        let mut server = World::default();
+        server.register_sparse::<String>();
+        let a = server.push((1usize, "a".to_string()));
+        let b = server.push((2usize,));
+
+        let mut client = World::default();
+        client.register_sparse::<String>();
+        let removed = Arc::new(Mutex::new(Vec::new()));
+        let removed_ref = removed.clone();
+        client.on_remove::<String, _>(move |_, value| {
+            removed_ref.lock().unwrap().push(value.clone())
+        });
+
+        let json =
+            serde_json::to_value(&server.diff(&client, 0).as_serializable(&registry)).unwrap();
+        client.apply_patch(registry.as_deserialize_patch().deserialize(json).unwrap());
+        assert_replicated(&server, &client);
+
+        let snapshot = server.snapshot(&mut duplicate);
+        assert!(server.diff_snapshot(&snapshot).is_empty());
+        *server
+            .entry(a)
+            .unwrap()
+            .get_component_mut::<String>()
+            .unwrap() = "changed".to_string();
+        server.entry(b).unwrap().add_component("b".to_string());
+
+        let json =
+            serde_json::to_value(&server.diff_snapshot(&snapshot).as_serializable(&registry))
+                .unwrap();
+        client.apply_patch(registry.as_deserialize_patch().deserialize(json).unwrap());
+        assert_replicated(&server, &client);
+        assert_eq!(*removed.lock().unwrap(), vec!["a".to_string()]);
+
+        let snapshot = server.snapshot(&mut duplicate);
+        server.entry(a).unwrap().remove_component::<String>();
+        let json =
+            serde_json::to_value(&server.diff_snapshot(&snapshot).as_serializable(&registry))
+                .unwrap();
+        client.apply_patch(registry.as_deserialize_patch().deserialize(json).unwrap());
+        assert_replicated(&server, &client);
+        assert_eq!(removed.lock().unwrap().len(), 2);
+
+        // the components never entered the client's archetypes
+        let sparse = client.sparse_components().get_downcast::<String>().unwrap();
+        assert_eq!(sparse.len(), 1);
+        assert_eq!(<&String>::query().iter(&client).count(), 0);
+    }
+
+    #[test]
+    fn patch_bincode() {
+        let registry = registry();
+
+        let mut server = World::default();
+        let a = server.push((1usize, false));
+        let mut client = World::default();
+
+        let diff = server.diff(&client, 0);
+        let version = diff.version();
+        let encoded = bincode::serialize(&diff.as_serializable(&registry)).unwrap();
+        use bincode::Options;
+        let patch = registry
+            .as_deserialize_patch()
+            .deserialize(&mut bincode::Deserializer::from_slice(
+                &encoded,
+                bincode::DefaultOptions::new()
+                    .with_fixint_encoding()
+                    .allow_trailing_bytes(),
+            ))
+            .unwrap();
+        client.apply_patch(patch);
+        assert_replicated(&server, &client);
+
+        *server
+            .entry(a)
+            .unwrap()
+            .get_component_mut::<usize>()
+            .unwrap() = 2;
+        let diff = server.diff(&client, version);
+        assert_eq!(diff.changed(), &[(a, ComponentTypeId::of::<usize>())]);
+        let encoded = bincode::serialize(&diff.as_serializable(&registry)).unwrap();
+        let patch = registry
+            .as_deserialize_patch()
+            .deserialize(&mut bincode::Deserializer::from_slice(
+                &encoded,
+                bincode::DefaultOptions::new()
+                    .with_fixint_encoding()
+                    .allow_trailing_bytes(),
+            ))
+            .unwrap();
+        client.apply_patch(patch);
+        assert_replicated(&server, &client);
+    }
+
+    #[test]
+    fn patch_runs_hooks() {
+        let registry = registry();
+        let mut server = World::default();
+        let entity = server.push((1usize,));
+
+        let mut client = World::default();
+        let added = Arc::new(Mutex::new(Vec::new()));
+        let removed = Arc::new(Mutex::new(Vec::new()));
+        let added_ref = added.clone();
+        client.on_add::<usize, _>(move |_, value| added_ref.lock().unwrap().push(*value));
+        let removed_ref = removed.clone();
+        client.on_remove::<usize, _>(move |_, value| removed_ref.lock().unwrap().push(*value));
+
+        let json =
+            serde_json::to_value(&server.diff(&client, 0).as_serializable(&registry)).unwrap();
+        client.apply_patch(registry.as_deserialize_patch().deserialize(json).unwrap());
+        *server
+            .entry(entity)
+            .unwrap()
+            .get_component_mut::<usize>()
+            .unwrap() = 2;
+        let json =
+            serde_json::to_value(&server.diff(&client, 0).as_serializable(&registry)).unwrap();
+        client.apply_patch(registry.as_deserialize_patch().deserialize(json).unwrap());
+
+        assert_eq!(*added.lock().unwrap(), vec![1, 2]);
+        assert_eq!(*removed.lock().unwrap(), vec![1]);
+    }
+
+    #[test]
+    fn patch_despawns_children() {
+        let registry = registry();
+        let mut duplicate = duplicate();
+        let mut server = World::default();
+        let parent = server.push((1usize,));
+        let child = server.push((2usize,));
+        let grandchild = server.push((3usize,));
+
+        let mut client = World::default();
+        let json =
+            serde_json::to_value(&server.diff(&client, 0).as_serializable(&registry)).unwrap();
+        client.apply_patch(registry.as_deserialize_patch().deserialize(json).unwrap());
+        // the hierarchy is only built on the client
+        client.set_parent(child, parent).unwrap();
+        client.set_parent(grandchild, child).unwrap();
+
+        let snapshot = server.snapshot(&mut duplicate);
+        server.remove(child);
+        let json =
+            serde_json::to_value(&server.diff_snapshot(&snapshot).as_serializable(&registry))
+                .unwrap();
+        client.apply_patch(registry.as_deserialize_patch().deserialize(json).unwrap());
+
+        assert!(!client.contains(child));
+        assert!(!client.contains(grandchild));
+        assert!(client
+            .entry_ref(parent)
+            .unwrap()
+            .get_component::<Children>()
+            .is_err());
+    }
+
+    #[test]
+    fn drop_unapplied_patch() {
+        let registry = registry();
+        let mut server = World::default();
+        server.push(("dropped".to_string(),));
+
+        let json =
+            serde_json::to_value(&server.diff(&World::default(), 0).as_serializable(&registry))
+                .unwrap();
+        let patch = registry.as_deserialize_patch().deserialize(json).unwrap();
+        assert!(!patch.is_empty());
+        drop(patch);
+    }
+}
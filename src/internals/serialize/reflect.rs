@@ -0,0 +1,350 @@
+//! Dynamic access to the components of an entity through the types registered with a
+//! [Registry](../struct.Registry.html).
+
+use super::{
+    de::WorldDeserializer, id::run_as_context, patch::PatchComponent, ser::WorldSerializer,
+    CustomEntitySerializer, Registry, TypeKey,
+};
+use crate::internals::{
+    entity::Entity,
+    storage::{archetype::EntityLayout, component::ComponentTypeId},
+    world::{EntityStore, World},
+};
+use serde::{Deserializer, Serialize, Serializer};
+use std::marker::PhantomData;
+use thiserror::Error;
+
+/// An error type which describes why dynamic access to a component failed.
+#[derive(Error, Clone, Debug, PartialEq, Eq)]
+pub enum DynamicComponentError {
+    /// The entity does not exist.
+    #[error("the entity does not exist")]
+    EntityNotFound,
+    /// The type key is not registered with the registry.
+    #[error("the type key is not registered")]
+    UnknownType,
+    /// The entity does not have the component.
+    #[error("the component was not found on the entity")]
+    NotFound,
+    /// The component value could not be deserialized.
+    #[error("failed to deserialize the component: {0}")]
+    Deserialize(String),
+}
+
+/// A serializable view of a single component of an entity, returned by
+/// [Registry::get_component](struct.Registry.html#method.get_component).
+///
+/// As a `serde::Serialize` type, this can also be used as an `erased_serde::Serialize`.
+pub struct SerializableComponent<'a, T: TypeKey, S: CustomEntitySerializer + 'static> {
+    registry: &'a Registry<T, S>,
+    type_id: ComponentTypeId,
+    ptr: *const u8,
+    _world: PhantomData<&'a World>,
+}
+
+impl<'a, T: TypeKey, S: CustomEntitySerializer + 'static> SerializableComponent<'a, T, S> {
+    /// Returns the type ID of the component.
+    pub fn type_id(&self) -> ComponentTypeId {
+        self.type_id
+    }
+}
+
+impl<'a, T: TypeKey, S: CustomEntitySerializer + 'static> Serialize
+    for SerializableComponent<'a, T, S>
+{
+    fn serialize<Ser>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error>
+    where
+        Ser: Serializer,
+    {
+        let canon = &self.registry.canon;
+        run_as_context(&canon, || unsafe {
+            self.registry
+                .serialize_component(self.type_id, self.ptr, serializer)
+        })
+    }
+}
+
+impl<T, S> Registry<T, S>
+where
+    T: TypeKey,
+    S: CustomEntitySerializer + 'static,
+{
+    /// Returns the type keys of an entity's components which are registered with the registry,
+    /// including its [sparse](../world/struct.World.html#method.register_sparse) components.
+    pub fn component_keys(
+        &self,
+        world: &World,
+        entity: Entity,
+    ) -> Result<Vec<T>, DynamicComponentError> {
+        let location = world
+            .entry_ref(entity)
+            .map_err(|_| DynamicComponentError::EntityNotFound)?
+            .location();
+        let layout = world.archetypes()[location.archetype()].layout();
+        Ok(layout
+            .component_types()
+            .iter()
+            .copied()
+            .chain(world.sparse_components().component_types(entity))
+            .filter_map(|type_id| self.map_id(type_id).ok())
+            .collect())
+    }
+
+    /// Returns the component of the given registered type on an entity, as a value which can be
+    /// serialized with any serde serializer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use legion::*;
+    /// let mut registry = Registry::<String>::default();
+    /// registry.register::<usize>("usize".to_string());
+    ///
+    /// let mut world = World::default();
+    /// let entity = world.push((5usize,));
+    ///
+    /// let component = registry.get_component(&world, entity, &"usize".to_string()).unwrap();
+    /// let value = serde_json::to_value(&component).unwrap();
+    /// assert_eq!(value, serde_json::json!(5));
+    ///
+    /// registry.set_component(&mut world, entity, &"usize".to_string(), serde_json::json!(6)).unwrap();
+    /// assert_eq!(world.entry(entity).unwrap().get_component::<usize>(), Ok(&6));
+    /// ```
+    pub fn get_component<'a>(
+        &'a self,
+        world: &'a World,
+        entity: Entity,
+        key: &T,
+    ) -> Result<SerializableComponent<'a, T, S>, DynamicComponentError> {
+        let type_id = self.type_id_of(key)?;
+        let location = world
+            .entry_ref(entity)
+            .map_err(|_| DynamicComponentError::EntityNotFound)?
+            .location();
+        if let Some(storage) = world.sparse_components().get(type_id) {
+            return match storage.get_raw(entity) {
+                Some(ptr) => Ok(SerializableComponent {
+                    registry: self,
+                    type_id,
+                    ptr,
+                    _world: PhantomData,
+                }),
+                None => Err(DynamicComponentError::NotFound),
+            };
+        }
+        if !world.archetypes()[location.archetype()]
+            .layout()
+            .has_component_by_id(type_id)
+        {
+            return Err(DynamicComponentError::NotFound);
+        }
+
+        let storage = world.components().get(type_id).unwrap();
+        let (base, _) = storage.get_raw(location.archetype()).unwrap();
+        let size = storage.element_vtable().size();
+        // safety: the entity's index lies within the archetype's slice
+        let ptr = unsafe { base.add(location.component().0 * size) };
+        Ok(SerializableComponent {
+            registry: self,
+            type_id,
+            ptr,
+            _world: PhantomData,
+        })
+    }
+
+    /// Deserializes a component of the given registered type and inserts it into an entity,
+    /// replacing any existing component of the same type. Component
+    /// [hooks](../world/struct.World.html#method.on_add) run as they would for
+    /// `Entry::add_component`.
+    pub fn set_component<'de, D: Deserializer<'de>>(
+        &self,
+        world: &mut World,
+        entity: Entity,
+        key: &T,
+        deserializer: D,
+    ) -> Result<(), DynamicComponentError> {
+        let type_id = self.type_id_of(key)?;
+        if !world.contains(entity) {
+            return Err(DynamicComponentError::EntityNotFound);
+        }
+
+        let mut layout = EntityLayout::new();
+        self.register_component(key.clone(), &mut layout);
+        let constructor = layout.component_constructors()[0];
+
+        let canon = &self.canon;
+        let data = run_as_context(&canon, || self.deserialize_component(type_id, deserializer))
+            .map_err(|err| DynamicComponentError::Deserialize(err.to_string()))?;
+
+        world.patch_entity(
+            entity,
+            &[],
+            vec![PatchComponent::new(type_id, constructor, data)],
+        );
+        Ok(())
+    }
+
+    fn type_id_of(&self, key: &T) -> Result<ComponentTypeId, DynamicComponentError> {
+        self.constructors
+            .get(key)
+            .map(|(type_id, _)| *type_id)
+            .ok_or(DynamicComponentError::UnknownType)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+    struct Position {
+        x: f32,
+        y: f32,
+    }
+
+    fn registry() -> Registry<String> {
+        let mut registry = Registry::<String>::default();
+        registry.register::<Position>("position".to_string());
+        registry.register::<usize>("usize".to_string());
+        registry.register::<Entity>("entity".to_string());
+        registry
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let registry = registry();
+        let mut world = World::default();
+        let target = world.push((1usize,));
+        let entity = world.push((Position { x: 1.0, y: 2.0 }, target, false));
+
+        let mut keys = registry.component_keys(&world, entity).unwrap();
+        keys.sort();
+        assert_eq!(keys, vec!["entity".to_string(), "position".to_string()]);
+
+        let position = "position".to_string();
+        let mut value =
+            serde_json::to_value(registry.get_component(&world, entity, &position).unwrap())
+                .unwrap();
+        assert_eq!(value, json!({ "x": 1.0, "y": 2.0 }));
+
+        value["x"] = json!(5.0);
+        registry
+            .set_component(&mut world, entity, &position, value)
+            .unwrap();
+        assert_eq!(
+            world.entry_ref(entity).unwrap().get_component::<Position>(),
+            Ok(&Position { x: 5.0, y: 2.0 })
+        );
+
+        // entity references go through the registry's entity serializer
+        let entity_key = "entity".to_string();
+        let value =
+            serde_json::to_value(registry.get_component(&world, entity, &entity_key).unwrap())
+                .unwrap();
+        registry
+            .set_component(&mut world, target, &entity_key, value)
+            .unwrap();
+        assert_eq!(
+            world.entry_ref(target).unwrap().get_component::<Entity>(),
+            Ok(&target)
+        );
+    }
+
+    #[test]
+    fn add_component() {
+        let registry = registry();
+        let mut world = World::default();
+        let entity = world.push((false,));
+
+        let added = Arc::new(Mutex::new(0));
+        let added_ref = added.clone();
+        world.on_add::<usize, _>(move |_, value| *added_ref.lock().unwrap() += *value);
+
+        let key = "usize".to_string();
+        assert_eq!(
+            registry.get_component(&world, entity, &key).err(),
+            Some(DynamicComponentError::NotFound)
+        );
+        registry
+            .set_component(&mut world, entity, &key, json!(3))
+            .unwrap();
+        let entry = world.entry_ref(entity).unwrap();
+        assert_eq!(entry.get_component::<usize>(), Ok(&3));
+        assert_eq!(entry.get_component::<bool>(), Ok(&false));
+        assert_eq!(*added.lock().unwrap(), 3);
+    }
+
+    #[test]
+    fn errors() {
+        let registry = registry();
+        let mut world = World::default();
+        let entity = world.push((1usize,));
+        let key = "usize".to_string();
+
+        assert_eq!(
+            registry
+                .get_component(&world, entity, &"bool".to_string())
+                .err(),
+            Some(DynamicComponentError::UnknownType)
+        );
+        assert!(matches!(
+            registry.set_component(&mut world, entity, &key, json!("text")),
+            Err(DynamicComponentError::Deserialize(_))
+        ));
+        assert_eq!(
+            world.entry_ref(entity).unwrap().get_component::<usize>(),
+            Ok(&1)
+        );
+
+        world.remove(entity);
+        assert_eq!(
+            registry.component_keys(&world, entity),
+            Err(DynamicComponentError::EntityNotFound)
+        );
+        assert_eq!(
+            registry.set_component(&mut world, entity, &key, json!(2)),
+            Err(DynamicComponentError::EntityNotFound)
+        );
+    }
+
+    #[test]
+    fn sparse_components() {
+        let registry = registry();
+        let mut world = World::default();
+        world.register_sparse::<usize>();
+        let with = world.push((true, 1usize));
+        let without = world.push((false,));
+        let key = "usize".to_string();
+
+        assert_eq!(
+            registry.component_keys(&world, with).unwrap(),
+            vec!["usize".to_string()]
+        );
+        let value = serde_json::to_value(registry.get_component(&world, with, &key).unwrap());
+        assert_eq!(value.unwrap(), json!(1));
+        assert_eq!(
+            registry.get_component(&world, without, &key).err(),
+            Some(DynamicComponentError::NotFound)
+        );
+
+        let archetype = world.entry_ref(without).unwrap().location().archetype();
+        for entity in &[with, without] {
+            registry
+                .set_component(&mut world, *entity, &key, json!(2))
+                .unwrap();
+            let entry = world.entry_ref(*entity).unwrap();
+            assert_eq!(entry.get_component::<usize>(), Ok(&2));
+            assert_eq!(entry.location().archetype(), archetype);
+        }
+        assert_eq!(
+            world
+                .sparse_components()
+                .get_downcast::<usize>()
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+}
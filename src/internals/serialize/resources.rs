@@ -0,0 +1,411 @@
+//! Resource serialization, and (de)serialization of a world together with its resources.
+
+use super::{
+    id::run_as_context, ser::SerializableWorld, CustomEntitySerializer, DeserializeNewWorld,
+    Registry, TypeKey, UnknownType,
+};
+use crate::internals::{query::filter::LayoutFilter, systems::resources::Resources, world::World};
+use serde::{
+    de::{DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor},
+    ser::{SerializeMap, SerializeStruct},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+pub(crate) type SerializeResourceFn = fn(&Resources, &mut dyn FnMut(&dyn erased_serde::Serialize));
+pub(crate) type DeserializeResourceFn =
+    fn(&mut Resources, &mut dyn erased_serde::Deserializer) -> Result<(), erased_serde::Error>;
+
+/// A serializable representation of the resources in a
+/// [Resources](../systems/struct.Resources.html) collection, serialized as a map from each
+/// resource's type key to its value.
+pub struct SerializableResources<'a, T: TypeKey, S: CustomEntitySerializer + 'static> {
+    registry: &'a Registry<T, S>,
+    resources: &'a Resources,
+}
+
+impl<'a, T: TypeKey, S: CustomEntitySerializer + 'static> SerializableResources<'a, T, S> {
+    pub(crate) fn new(registry: &'a Registry<T, S>, resources: &'a Resources) -> Self {
+        Self {
+            registry,
+            resources,
+        }
+    }
+}
+
+impl<'a, T: TypeKey, S: CustomEntitySerializer + 'static> Serialize
+    for SerializableResources<'a, T, S>
+{
+    fn serialize<Ser>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error>
+    where
+        Ser: Serializer,
+    {
+        use serde::ser::Error;
+
+        let mut entries = Vec::new();
+        for type_id in self.resources.type_ids() {
+            match self.registry.resource_fns.get(&type_id) {
+                Some((key, serialize_fn)) => entries.push((key, *serialize_fn)),
+                None => {
+                    if let UnknownType::Error = self.registry.missing {
+                        return Err(Ser::Error::custom(format!(
+                            "unknown resource type {}",
+                            type_id
+                        )));
+                    }
+                }
+            }
+        }
+        entries.sort_by_key(|(key, _)| *key);
+
+        let canon = &self.registry.canon;
+        run_as_context(&canon, || {
+            let mut map = serializer.serialize_map(Some(entries.len()))?;
+            for (key, serialize_fn) in entries {
+                map.serialize_entry(
+                    key,
+                    &ResourceSerializer {
+                        resources: self.resources,
+                        serialize_fn,
+                    },
+                )?;
+            }
+            map.end()
+        })
+    }
+}
+
+struct ResourceSerializer<'a> {
+    resources: &'a Resources,
+    serialize_fn: SerializeResourceFn,
+}
+
+impl<'a> Serialize for ResourceSerializer<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut serializer = Some(serializer);
+        let mut result = None;
+        let result_ref = &mut result;
+        (self.serialize_fn)(self.resources, &mut move |serializable| {
+            *result_ref = Some(erased_serde::serialize(
+                serializable,
+                serializer
+                    .take()
+                    .expect("serialize can only be called once"),
+            ));
+        });
+        result.unwrap()
+    }
+}
+
+/// A serializable representation of a world and its resources, serialized as a struct with a
+/// `world` and a `resources` field. It can be deserialized with
+/// [Registry::as_deserialize_with_resources](struct.Registry.html#method.as_deserialize_with_resources).
+pub struct SerializableWorldAndResources<
+    'a,
+    F: LayoutFilter,
+    T: TypeKey,
+    S: CustomEntitySerializer + 'static,
+> {
+    world: SerializableWorld<'a, F, Registry<T, S>>,
+    resources: SerializableResources<'a, T, S>,
+}
+
+impl<'a, F: LayoutFilter, T: TypeKey, S: CustomEntitySerializer + 'static>
+    SerializableWorldAndResources<'a, F, T, S>
+{
+    pub(crate) fn new(
+        registry: &'a Registry<T, S>,
+        world: &'a World,
+        filter: F,
+        resources: &'a Resources,
+    ) -> Self {
+        Self {
+            world: world.as_serializable(filter, registry),
+            resources: SerializableResources::new(registry, resources),
+        }
+    }
+}
+
+impl<'a, F: LayoutFilter, T: TypeKey, S: CustomEntitySerializer + 'static> Serialize
+    for SerializableWorldAndResources<'a, F, T, S>
+{
+    fn serialize<Ser>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error>
+    where
+        Ser: Serializer,
+    {
+        let mut root = serializer.serialize_struct("WorldAndResources", 2)?;
+        root.serialize_field("world", &self.world)?;
+        root.serialize_field("resources", &self.resources)?;
+        root.end()
+    }
+}
+
+/// Wraps a [Registry](struct.Registry.html) and a resource collection and implements
+/// `serde::DeserializeSeed` for deserializing
+/// [serialized resources](struct.SerializableResources.html) into the collection. Existing
+/// resources of the same types are replaced.
+pub struct DeserializeIntoResources<'a, T: TypeKey, S: CustomEntitySerializer + 'static>(
+    pub &'a Registry<T, S>,
+    pub &'a mut Resources,
+);
+
+impl<'a, 'de, T: TypeKey, S: CustomEntitySerializer + 'static> DeserializeSeed<'de>
+    for DeserializeIntoResources<'a, T, S>
+{
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let canon = &self.0.canon;
+        run_as_context(&canon, || deserializer.deserialize_map(self))
+    }
+}
+
+impl<'a, 'de, T: TypeKey, S: CustomEntitySerializer + 'static> Visitor<'de>
+    for DeserializeIntoResources<'a, T, S>
+{
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("resource map")
+    }
+
+    fn visit_map<V>(self, mut map: V) -> Result<Self::Value, V::Error>
+    where
+        V: MapAccess<'de>,
+    {
+        use serde::de::Error;
+        while let Some(key) = map.next_key::<T>()? {
+            match self.0.resource_constructors.get(&key) {
+                Some(deserialize_fn) => {
+                    map.next_value_seed(ResourceDeserializer {
+                        resources: &mut *self.1,
+                        deserialize_fn: *deserialize_fn,
+                    })?;
+                }
+                None => match self.0.missing {
+                    UnknownType::Ignore => {
+                        map.next_value::<IgnoredAny>()?;
+                    }
+                    UnknownType::Error => {
+                        return Err(V::Error::custom("unknown resource type"));
+                    }
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+struct ResourceDeserializer<'a> {
+    resources: &'a mut Resources,
+    deserialize_fn: DeserializeResourceFn,
+}
+
+impl<'a, 'de> DeserializeSeed<'de> for ResourceDeserializer<'a> {
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        use serde::de::Error;
+        let mut deserializer = <dyn erased_serde::Deserializer>::erase(deserializer);
+        (self.deserialize_fn)(self.resources, &mut deserializer).map_err(D::Error::custom)
+    }
+}
+
+/// Wraps a [Registry](struct.Registry.html) and implements `serde::DeserializeSeed` for
+/// deserializing a [serialized world and its resources](struct.SerializableWorldAndResources.html)
+/// into a new world and resource collection.
+pub struct DeserializeWorldAndResources<'a, T: TypeKey, S: CustomEntitySerializer + 'static>(
+    pub &'a Registry<T, S>,
+);
+
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "lowercase")]
+enum Field {
+    World,
+    Resources,
+}
+
+impl<'a, 'de, T: TypeKey, S: CustomEntitySerializer + 'static> DeserializeSeed<'de>
+    for DeserializeWorldAndResources<'a, T, S>
+{
+    type Value = (World, Resources);
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_struct("WorldAndResources", &["world", "resources"], self)
+    }
+}
+
+impl<'a, 'de, T: TypeKey, S: CustomEntitySerializer + 'static> Visitor<'de>
+    for DeserializeWorldAndResources<'a, T, S>
+{
+    type Value = (World, Resources);
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("struct WorldAndResources")
+    }
+
+    fn visit_seq<V>(self, mut seq: V) -> Result<Self::Value, V::Error>
+    where
+        V: SeqAccess<'de>,
+    {
+        use serde::de::Error;
+        let world = seq
+            .next_element_seed(DeserializeNewWorld(self.0))?
+            .ok_or_else(|| V::Error::invalid_length(0, &self))?;
+        let mut resources = Resources::default();
+        seq.next_element_seed(DeserializeIntoResources(self.0, &mut resources))?
+            .ok_or_else(|| V::Error::invalid_length(1, &self))?;
+        Ok((world, resources))
+    }
+
+    fn visit_map<V>(self, mut map: V) -> Result<Self::Value, V::Error>
+    where
+        V: MapAccess<'de>,
+    {
+        use serde::de::Error;
+        let mut world = None;
+        let mut resources = Resources::default();
+        while let Some(field) = map.next_key()? {
+            match field {
+                Field::World => {
+                    if world.is_some() {
+                        return Err(V::Error::duplicate_field("world"));
+                    }
+                    world = Some(map.next_value_seed(DeserializeNewWorld(self.0))?);
+                }
+                Field::Resources => {
+                    map.next_value_seed(DeserializeIntoResources(self.0, &mut resources))?;
+                }
+            }
+        }
+        let world = world.ok_or_else(|| V::Error::missing_field("world"))?;
+        Ok((world, resources))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::internals::{
+        entity::Entity,
+        query::filter::filter_fns::any,
+        serialize::{Registry, UnknownType},
+        systems::resources::Resources,
+        world::{EntityStore, World},
+    };
+    use serde::de::DeserializeSeed;
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+    struct Score(u32);
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+    struct Player(Entity);
+
+    struct Unregistered;
+
+    fn registry() -> Registry<String> {
+        let mut registry = Registry::<String>::default();
+        registry.register::<usize>("usize".to_string());
+        registry.register_resource::<Score>("score".to_string());
+        registry.register_resource::<Player>("player".to_string());
+        registry
+    }
+
+    fn state() -> (World, Resources, Entity) {
+        let mut world = World::default();
+        let player = world.push((1usize,));
+        world.push((2usize,));
+        let mut resources = Resources::default();
+        resources.insert(Score(10));
+        resources.insert(Player(player));
+        (world, resources, player)
+    }
+
+    fn check(world: &World, resources: &Resources, player: Entity) {
+        assert_eq!(world.len(), 2);
+        assert_eq!(
+            world.entry_ref(player).unwrap().get_component::<usize>(),
+            Ok(&1)
+        );
+        assert_eq!(*resources.get::<Score>().unwrap(), Score(10));
+        assert_eq!(*resources.get::<Player>().unwrap(), Player(player));
+    }
+
+    #[test]
+    fn round_trip_json() {
+        let registry = registry();
+        let (world, resources, player) = state();
+
+        let json = serde_json::to_value(registry.as_serializable_with_resources(
+            &world,
+            any(),
+            &resources,
+        ))
+        .unwrap();
+        assert_eq!(json["resources"]["score"], serde_json::json!(10));
+
+        let (world, resources) = registry
+            .as_deserialize_with_resources()
+            .deserialize(json)
+            .unwrap();
+        check(&world, &resources, player);
+    }
+
+    #[test]
+    fn round_trip_bincode() {
+        let registry = registry();
+        let (world, resources, player) = state();
+
+        let encoded =
+            bincode::serialize(&registry.as_serializable_with_resources(&world, any(), &resources))
+                .unwrap();
+
+        use bincode::Options;
+        let options = bincode::DefaultOptions::new()
+            .with_fixint_encoding()
+            .allow_trailing_bytes();
+        let mut deserializer = bincode::de::Deserializer::from_slice(&encoded, options);
+        let (world, resources) = registry
+            .as_deserialize_with_resources()
+            .deserialize(&mut deserializer)
+            .unwrap();
+        check(&world, &resources, player);
+    }
+
+    #[test]
+    fn unknown_resources() {
+        let mut registry = registry();
+        let mut resources = Resources::default();
+        resources.insert(Score(1));
+        resources.insert(Unregistered);
+        assert!(serde_json::to_value(registry.as_serializable_resources(&resources)).is_err());
+
+        registry.on_unknown(UnknownType::Ignore);
+        let json = serde_json::to_value(registry.as_serializable_resources(&resources)).unwrap();
+        assert_eq!(json, serde_json::json!({ "score": 1 }));
+
+        let json = serde_json::json!({ "score": 2, "unknown": true });
+        let mut resources = Resources::default();
+        registry
+            .as_deserialize_resources(&mut resources)
+            .deserialize(json.clone())
+            .unwrap();
+        assert_eq!(*resources.get::<Score>().unwrap(), Score(2));
+
+        registry.on_unknown(UnknownType::Error);
+        assert!(registry
+            .as_deserialize_resources(&mut resources)
+            .deserialize(json)
+            .is_err());
+    }
+}
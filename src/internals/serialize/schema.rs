@@ -0,0 +1,337 @@
+//! Component schema versions, and the migrations which upgrade components which were serialized
+//! with an older version of their schema.
+
+use crate::internals::{
+    insert::UnknownComponentWriter,
+    serialize::MAX_PREALLOCATION,
+    storage::component::{Component, ComponentTypeId},
+};
+use serde::{
+    de::{DeserializeSeed, MapAccess, SeqAccess, Visitor},
+    ser::Serializer,
+    Deserialize, Deserializer, Serialize,
+};
+use std::{cell::RefCell, collections::HashMap, marker::PhantomData, sync::Arc};
+
+thread_local! {
+    static SAVED_VERSIONS: RefCell<Option<HashMap<ComponentTypeId, u32>>> = const { RefCell::new(None) };
+}
+
+/// Runs `f` with the schema versions recorded in the world which is being deserialized.
+/// Component types which are not in `versions` were serialized with version `0`.
+pub(crate) fn run_with_versions<F: FnOnce() -> R, R>(
+    versions: HashMap<ComponentTypeId, u32>,
+    f: F,
+) -> R {
+    let prev = SAVED_VERSIONS.with(|cell| cell.borrow_mut().replace(versions));
+    let result = (f)();
+    SAVED_VERSIONS.with(|cell| *cell.borrow_mut() = prev);
+    result
+}
+
+/// Returns the schema version with which a component type was serialized, or `None` if the
+/// component is not being deserialized as part of a world.
+pub(crate) fn saved_version(type_id: ComponentTypeId) -> Option<u32> {
+    SAVED_VERSIONS.with(|cell| {
+        cell.borrow()
+            .as_ref()
+            .map(|versions| versions.get(&type_id).copied().unwrap_or(0))
+    })
+}
+
+type MigrateSliceFn = dyn Fn(
+        UnknownComponentWriter,
+        &mut dyn erased_serde::Deserializer,
+    ) -> Result<(), erased_serde::Error>
+    + Send
+    + Sync;
+type MigrateSingleBoxedFn = dyn Fn(&mut dyn erased_serde::Deserializer) -> Result<Box<[u8]>, erased_serde::Error>
+    + Send
+    + Sync;
+
+/// Deserializes components in an old schema and converts them into the current component type.
+pub(crate) struct Migration {
+    pub(crate) deserialize_slice: Box<MigrateSliceFn>,
+    pub(crate) deserialize_single: Box<MigrateSingleBoxedFn>,
+}
+
+impl Migration {
+    pub(crate) fn new<C, Old, F>(migrate: F) -> Self
+    where
+        C: Component,
+        Old: for<'de> Deserialize<'de>,
+        F: Fn(Old) -> C + Send + Sync + 'static,
+    {
+        let migrate = Arc::new(migrate);
+        let migrate_single = migrate.clone();
+        Self {
+            deserialize_slice: Box::new(move |storage, deserializer| {
+                MigrateSeq {
+                    storage,
+                    migrate: &*migrate,
+                    _phantom: PhantomData,
+                }
+                .deserialize(deserializer)
+            }),
+            deserialize_single: Box::new(move |deserializer| {
+                let component = migrate_single(erased_serde::deserialize::<Old>(deserializer)?);
+                unsafe {
+                    let vec = std::slice::from_raw_parts(
+                        &component as *const C as *const u8,
+                        std::mem::size_of::<C>(),
+                    )
+                    .to_vec();
+                    std::mem::forget(component);
+                    Ok(vec.into_boxed_slice())
+                }
+            }),
+        }
+    }
+}
+
+struct MigrateSeq<'a, C, Old, F> {
+    storage: UnknownComponentWriter<'a>,
+    migrate: &'a F,
+    _phantom: PhantomData<fn(Old) -> C>,
+}
+
+impl<'a, 'de, C, Old, F> DeserializeSeed<'de> for MigrateSeq<'a, C, Old, F>
+where
+    C: Component,
+    Old: for<'b> Deserialize<'b>,
+    F: Fn(Old) -> C,
+{
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'a, 'de, C, Old, F> Visitor<'de> for MigrateSeq<'a, C, Old, F>
+where
+    C: Component,
+    Old: for<'b> Deserialize<'b>,
+    F: Fn(Old) -> C,
+{
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("component seq")
+    }
+
+    fn visit_seq<V>(mut self, mut seq: V) -> Result<Self::Value, V::Error>
+    where
+        V: SeqAccess<'de>,
+    {
+        if let Some(len) = seq.size_hint() {
+            self.storage.ensure_capacity(len.min(MAX_PREALLOCATION));
+        }
+
+        while let Some(old) = seq.next_element::<Old>()? {
+            let component = (self.migrate)(old);
+            unsafe {
+                let ptr = &component as *const C as *const u8;
+                self.storage.extend_memcopy_raw(ptr, 1);
+                std::mem::forget(component)
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Serializes the schema versions of a world's component types as a map from type key to
+/// version.
+pub(crate) struct SchemaVersions<'a, K: Serialize>(pub(crate) &'a [(K, u32)]);
+
+impl<'a, K: Serialize> Serialize for SchemaVersions<'a, K> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_map(self.0.iter().map(|(key, version)| (key, version)))
+    }
+}
+
+/// Deserializes a map of schema versions, keeping them in the order in which they were written.
+pub(crate) struct DeserializeSchemaVersions<K>(PhantomData<K>);
+
+impl<K> DeserializeSchemaVersions<K> {
+    pub(crate) fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<'de, K: Deserialize<'de>> DeserializeSeed<'de> for DeserializeSchemaVersions<K> {
+    type Value = Vec<(K, u32)>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de, K: Deserialize<'de>> Visitor<'de> for DeserializeSchemaVersions<K> {
+    type Value = Vec<(K, u32)>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("schema version map")
+    }
+
+    fn visit_map<V>(self, mut map: V) -> Result<Self::Value, V::Error>
+    where
+        V: MapAccess<'de>,
+    {
+        let mut versions = Vec::new();
+        while let Some(entry) = map.next_entry()? {
+            versions.push(entry);
+        }
+        Ok(versions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::internals::{
+        query::{filter::filter_fns::any, IntoQuery},
+        serialize::Registry,
+        world::World,
+    };
+    use serde::de::DeserializeSeed;
+
+    mod v0 {
+        #[derive(serde::Serialize, serde::Deserialize)]
+        pub struct Health {
+            pub hp: u32,
+        }
+    }
+
+    mod v1 {
+        #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+        pub struct Health {
+            pub current: u32,
+            pub max: u32,
+        }
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+    struct Health {
+        current: u32,
+        max: u32,
+        regen: f32,
+    }
+
+    fn old_world() -> World {
+        let mut world = World::default();
+        world.extend(vec![
+            (v0::Health { hp: 10 }, 1usize),
+            (v0::Health { hp: 20 }, 2usize),
+        ]);
+        world
+    }
+
+    fn registry() -> Registry<String> {
+        let mut registry = Registry::<String>::default();
+        registry.register::<usize>("usize".to_string());
+        registry.register_versioned::<Health>("health".to_string(), 2);
+        registry.register_migration::<Health, v0::Health, _>(0, |old| Health {
+            current: old.hp,
+            max: old.hp,
+            regen: 0.0,
+        });
+        registry.register_migration::<Health, v1::Health, _>(1, |old| Health {
+            current: old.current,
+            max: old.max,
+            regen: 0.0,
+        });
+        registry
+    }
+
+    fn check(world: &World) {
+        let mut health = <&Health>::query()
+            .iter(world)
+            .map(|health| health.current)
+            .collect::<Vec<_>>();
+        health.sort_unstable();
+        assert_eq!(health, vec![10, 20]);
+    }
+
+    #[test]
+    fn migrate_json() {
+        let mut old_registry = Registry::<String>::default();
+        old_registry.register::<usize>("usize".to_string());
+        old_registry.register::<v0::Health>("health".to_string());
+        let json = serde_json::to_value(old_world().as_serializable(any(), &old_registry)).unwrap();
+        assert!(json.get("_versions").is_none());
+
+        let registry = registry();
+        let world = registry.as_deserialize().deserialize(json).unwrap();
+        check(&world);
+
+        // the current version is recorded, so the world loads without migrating
+        let json = serde_json::to_value(world.as_serializable(any(), &registry)).unwrap();
+        assert_eq!(json["_versions"], serde_json::json!({ "health": 2 }));
+        let world = registry.as_deserialize().deserialize(json).unwrap();
+        check(&world);
+    }
+
+    #[test]
+    fn migrate_bincode() {
+        let mut world = World::default();
+        world.extend(vec![
+            (
+                v1::Health {
+                    current: 10,
+                    max: 30,
+                },
+                1usize,
+            ),
+            (
+                v1::Health {
+                    current: 20,
+                    max: 30,
+                },
+                2usize,
+            ),
+        ]);
+        let mut old_registry = Registry::<String>::default();
+        old_registry.register::<usize>("usize".to_string());
+        old_registry.register_versioned::<v1::Health>("health".to_string(), 1);
+        let encoded = bincode::serialize(&world.as_serializable(any(), &old_registry)).unwrap();
+
+        use bincode::Options;
+        let world = registry()
+            .as_deserialize()
+            .deserialize(&mut bincode::Deserializer::from_slice(
+                &encoded,
+                bincode::DefaultOptions::new()
+                    .with_fixint_encoding()
+                    .allow_trailing_bytes(),
+            ))
+            .unwrap();
+        check(&world);
+        let max = <&Health>::query()
+            .iter(&world)
+            .map(|health| health.max)
+            .collect::<Vec<_>>();
+        assert_eq!(max, vec![30, 30]);
+    }
+
+    #[test]
+    fn missing_migration() {
+        let mut old_registry = Registry::<String>::default();
+        old_registry.register::<usize>("usize".to_string());
+        old_registry.register::<v0::Health>("health".to_string());
+        let json = serde_json::to_value(old_world().as_serializable(any(), &old_registry)).unwrap();
+
+        let mut registry = Registry::<String>::default();
+        registry.register::<usize>("usize".to_string());
+        registry.register_versioned::<Health>("health".to_string(), 2);
+        assert!(registry.as_deserialize().deserialize(json).is_err());
+    }
+}
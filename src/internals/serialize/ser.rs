@@ -2,12 +2,14 @@
 
 use super::{
     archetypes::ser::ArchetypeLayoutSerializer, entities::ser::EntitiesLayoutSerializer,
-    id::run_as_context, EntitySerializer, UnknownType, WorldField,
+    id::run_as_context, schema::SchemaVersions, CustomEntitySerializer, EntitySerializer, Registry,
+    TypeKey, UnknownType, WorldField,
 };
 use crate::{
     internals::{query::filter::LayoutFilter, storage::component::ComponentTypeId, world::World},
     storage::{ArchetypeIndex, UnknownComponentStorage},
 };
+use itertools::Itertools;
 use serde::ser::{Serialize, SerializeMap, Serializer};
 
 /// Describes a type which knows how to deserialize the components in a world.
@@ -15,6 +17,12 @@ pub trait WorldSerializer {
     /// The stable type ID used to identify each component type in the serialized data.
     type TypeId: Serialize + Ord;
 
+    /// Returns the version of the component type's schema. Non-zero versions are recorded in the
+    /// serialized world.
+    fn component_version(&self, _type_id: ComponentTypeId) -> u32 {
+        0
+    }
+
     /// Converts a runtime component type ID into the serialized type ID.
     fn map_id(&self, type_id: ComponentTypeId) -> Result<Self::TypeId, UnknownType>;
 
@@ -47,11 +55,15 @@ pub trait WorldSerializer {
     fn with_entity_serializer(&self, callback: &mut dyn FnMut(&dyn EntitySerializer));
 }
 
+/// Calls a callback with the entity serializer to use for a world serializer.
+type WithEntitySerializer<W> = fn(&W, &mut dyn FnMut(&dyn EntitySerializer));
+
 /// A serializable representation of a world.
 pub struct SerializableWorld<'a, F: LayoutFilter, W: WorldSerializer> {
     world: &'a World,
     filter: F,
     world_serializer: &'a W,
+    canonical: Option<WithEntitySerializer<W>>,
 }
 
 impl<'a, F: LayoutFilter, W: WorldSerializer> SerializableWorld<'a, F, W> {
@@ -60,16 +72,65 @@ impl<'a, F: LayoutFilter, W: WorldSerializer> SerializableWorld<'a, F, W> {
             world,
             filter,
             world_serializer,
+            canonical: None,
         }
     }
 }
 
+impl<'a, F, T, S> SerializableWorld<'a, F, Registry<T, S>>
+where
+    F: LayoutFilter,
+    T: TypeKey,
+    S: CustomEntitySerializer + 'static,
+    S::SerializedID: Ord,
+{
+    /// Serializes the world in canonical order, such that worlds which contain the same
+    /// entities and components serialize to the same output regardless of the order in which
+    /// they were built.
+    ///
+    /// Archetypes are ordered by their sets of type keys, components by type key, and entities
+    /// by their serialized IDs. This is slower than the default order, which follows the
+    /// world's internal layout.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use legion::*;
+    /// let mut registry = Registry::<String>::default();
+    /// registry.register::<usize>("usize".to_string());
+    /// registry.register::<bool>("bool".to_string());
+    ///
+    /// let mut a = World::default();
+    /// let first = a.push((1usize,));
+    /// let second = a.push((2usize, false));
+    ///
+    /// // the same entities, built in a different order
+    /// let mut b = World::default();
+    /// b.push_with_id(second, (false, 2usize));
+    /// b.push_with_id(first, (1usize,));
+    ///
+    /// let a = serde_json::to_string(&a.as_serializable(any(), &registry).canonical()).unwrap();
+    /// let b = serde_json::to_string(&b.as_serializable(any(), &registry).canonical()).unwrap();
+    /// assert_eq!(a, b);
+    /// ```
+    pub fn canonical(mut self) -> Self {
+        self.canonical = Some(Registry::with_ordered_entity_serializer);
+        self
+    }
+}
+
 impl<'a, F: LayoutFilter, W: WorldSerializer> Serialize for SerializableWorld<'a, F, W> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        serialize_world(serializer, self.world, &self.filter, self.world_serializer)
+        serialize_world(
+            serializer,
+            self.world,
+            &self.filter,
+            self.world_serializer,
+            self.canonical,
+        )
     }
 }
 
@@ -78,19 +139,51 @@ fn serialize_world<S, F, W>(
     world: &World,
     filter: &F,
     world_serializer: &W,
+    canonical: Option<WithEntitySerializer<W>>,
 ) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
     F: LayoutFilter,
     W: WorldSerializer,
 {
+    let mut versions = world
+        .archetypes()
+        .iter()
+        .filter(|arch| {
+            filter
+                .matches_layout(arch.layout().component_types())
+                .is_pass()
+        })
+        .flat_map(|arch| arch.layout().component_types().iter().copied())
+        .chain(
+            world
+                .sparse_components()
+                .iter()
+                .filter(|(_, storage)| !storage.is_empty())
+                .map(|(type_id, _)| type_id),
+        )
+        .unique()
+        .filter_map(
+            |type_id| match world_serializer.component_version(type_id) {
+                0 => None,
+                version => Some((world_serializer.map_id(type_id).ok()?, version)),
+            },
+        )
+        .collect::<Vec<_>>();
+    versions.sort_by(|(a, _), (b, _)| a.cmp(b));
+
     let human_readable = serializer.is_human_readable();
-    let mut root = serializer.serialize_map(Some(1))?;
+    let mut root = serializer.serialize_map(Some(if versions.is_empty() { 1 } else { 2 }))?;
+    if !versions.is_empty() {
+        root.serialize_entry(&WorldField::Versions, &SchemaVersions(&versions))?;
+    }
 
     let mut hoist = core::cell::Cell::new(None);
     let hoist_ref = &mut hoist;
     let root_ref = &mut root;
-    world_serializer.with_entity_serializer(&mut |canon| {
+    let with_entity_serializer = canonical.unwrap_or(W::with_entity_serializer);
+    let canonical = canonical.is_some();
+    with_entity_serializer(world_serializer, &mut |canon| {
         run_as_context(canon, || {
             let result = if human_readable {
                 // serialize per-entity representation
@@ -100,6 +193,7 @@ where
                         world_serializer,
                         world,
                         filter,
+                        canonical,
                     },
                 )
             } else {
@@ -110,6 +204,7 @@ where
                         world_serializer,
                         world,
                         filter,
+                        canonical,
                     },
                 )
             };
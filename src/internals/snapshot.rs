@@ -0,0 +1,534 @@
+use super::{
+    entity::{Allocate, Entity, EntityAllocator, EntityHasher},
+    insert::ArchetypeWriter,
+    query::filter::filter_fns::any,
+    storage::{
+        archetype::{Archetype, EntityLayout},
+        next_component_version,
+        sparse::SparseComponents,
+        Components, Version,
+    },
+    world::{Duplicate, EntityRewrite, Merger, World},
+};
+use parking_lot::Mutex;
+use std::{
+    any::Any,
+    collections::HashSet,
+    fmt::Debug,
+    ops::Range,
+    sync::{Arc, Weak},
+};
+
+/// A copy of the entities in a [world](struct.World.html) and their components, taken with
+/// [World::snapshot](struct.World.html#method.snapshot) and later restored with
+/// [World::restore](struct.World.html#method.restore).
+///
+/// Snapshots are immutable. Cloning a snapshot is cheap, as all clones share the same copy of
+/// the world's data.
+#[derive(Clone)]
+pub struct WorldSnapshot(Arc<SnapshotData>);
+
+struct SnapshotData {
+    world: World,
+    version: Version,
+    allocator: Option<Box<dyn Any + Send + Sync>>,
+    reserved: Vec<Entity>,
+}
+
+impl WorldSnapshot {
+    /// Returns the number of entities in the snapshot.
+    pub fn len(&self) -> usize {
+        self.0.world.len()
+    }
+
+    /// Returns `true` if the snapshot contains no entities.
+    pub fn is_empty(&self) -> bool {
+        self.0.world.is_empty()
+    }
+
+    /// Returns `true` if the snapshot contains an entity with the given ID.
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.0.world.contains(entity)
+    }
+
+    pub(crate) fn world(&self) -> &World {
+        &self.0.world
+    }
+
+    /// Returns the component version at which the snapshot was taken.
+    pub fn version(&self) -> Version {
+        self.0.version
+    }
+}
+
+/// The IDs of entities which have been removed from a world while a
+/// [snapshot](struct.WorldSnapshot.html) which contains them is alive.
+///
+/// Snapshots which do not capture the state of the world's allocator cannot rewind it, so the
+/// world holds on to these IDs instead of releasing them to the allocator, where they could be
+/// handed out again to new entities in this or any other world before the snapshot is restored.
+/// Held IDs are released once no live snapshot contains them.
+#[derive(Default)]
+pub(crate) struct HeldIds {
+    snapshots: Mutex<Vec<Weak<SnapshotData>>>,
+    held: HashSet<Entity, EntityHasher>,
+}
+
+impl HeldIds {
+    fn track(&self, snapshot: &Arc<SnapshotData>) {
+        self.snapshots.lock().push(Arc::downgrade(snapshot));
+    }
+
+    /// Releases the ID of an entity which has been removed from the world, unless a live
+    /// snapshot contains the entity.
+    pub(crate) fn release(&mut self, allocator: &dyn EntityAllocator, entity: Entity) {
+        self.prune(allocator);
+        let snapshots = self.snapshots.get_mut();
+        if snapshots
+            .iter()
+            .filter_map(Weak::upgrade)
+            .any(|snapshot| snapshot.world.contains(entity))
+        {
+            self.held.insert(entity);
+        } else {
+            allocator.release(entity);
+        }
+    }
+
+    /// Takes back a held ID, returning `true` if the ID was held.
+    pub(crate) fn take(&mut self, entity: Entity) -> bool {
+        self.held.remove(&entity)
+    }
+
+    /// Forgets snapshots which have been dropped, and releases the held IDs which are no longer
+    /// contained by any live snapshot.
+    fn prune(&mut self, allocator: &dyn EntityAllocator) {
+        let snapshots = self.snapshots.get_mut();
+        let count = snapshots.len();
+        snapshots.retain(|snapshot| snapshot.strong_count() > 0);
+        if snapshots.len() == count {
+            return;
+        }
+
+        let live = snapshots
+            .iter()
+            .filter_map(Weak::upgrade)
+            .collect::<Vec<_>>();
+        self.held.retain(|entity| {
+            let contained = live.iter().any(|snapshot| snapshot.world.contains(*entity));
+            if !contained {
+                allocator.release(*entity);
+            }
+            contained
+        });
+    }
+}
+
+impl Debug for HeldIds {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HeldIds")
+            .field("snapshots", &self.snapshots.lock().len())
+            .field("held", &self.held)
+            .finish()
+    }
+}
+
+impl Debug for WorldSnapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WorldSnapshot")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+/// A merger which clones components with a `Duplicate` while keeping every entity's ID.
+struct PreserveIds<'a>(&'a mut Duplicate);
+
+impl<'a> Merger for PreserveIds<'a> {
+    fn entity_map(&mut self) -> EntityRewrite {
+        EntityRewrite::Explicit(Default::default())
+    }
+
+    fn assign_id(&mut self, existing: Entity, _: &mut Allocate) -> Entity {
+        existing
+    }
+
+    fn convert_layout(&mut self, source_layout: EntityLayout) -> EntityLayout {
+        self.0.convert_layout(source_layout)
+    }
+
+    fn merge_archetype(
+        &mut self,
+        src_entity_range: Range<usize>,
+        src_arch: &Archetype,
+        src_components: &Components,
+        dst: &mut ArchetypeWriter,
+    ) {
+        self.0
+            .merge_archetype(src_entity_range, src_arch, src_components, dst)
+    }
+
+    fn merge_sparse(
+        &mut self,
+        src_entity: Entity,
+        src_components: &SparseComponents,
+        dst_entity: Entity,
+        dst_components: &mut SparseComponents,
+    ) {
+        self.0
+            .merge_sparse(src_entity, src_components, dst_entity, dst_components)
+    }
+}
+
+impl World {
+    /// Captures the world's entities, with all of their components which are registered with
+    /// `duplicate`, such that the world can later be rewound with [restore](#method.restore).
+    ///
+    /// Entity IDs are preserved exactly. Components of types which are not registered with
+    /// `duplicate` are not captured. [Sparse](#method.register_sparse) components are captured
+    /// in the snapshot's own sparse storage.
+    ///
+    /// If the world's [allocator](struct.WorldOptions.html#structfield.allocator) can be rewound,
+    /// such as a [GenerationalAllocator](struct.GenerationalAllocator.html) owned by the world,
+    /// the allocator's state is captured too, so that the same entity IDs are assigned again
+    /// after the world is restored. The allocator should not be shared with other worlds.
+    ///
+    /// Otherwise, such as for worlds which use the
+    /// [SharedAllocator](struct.SharedAllocator.html), the IDs of entities which are removed
+    /// from the world while the snapshot is alive are not released to the allocator, so that
+    /// they cannot be assigned to other entities before the snapshot is restored. The IDs are
+    /// released once they are removed with no live snapshot containing them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use legion::*;
+    /// # use legion::world::{Duplicate, GenerationalAllocator, WorldOptions};
+    /// # use std::sync::Arc;
+    /// let mut world = World::new(WorldOptions {
+    ///     allocator: Arc::new(GenerationalAllocator::new()),
+    ///     ..Default::default()
+    /// });
+    /// let entity = world.push((1usize, 2.0f32));
+    ///
+    /// let mut duplicate = Duplicate::new();
+    /// duplicate.register_copy::<usize>();
+    /// duplicate.register_copy::<f32>();
+    /// let snapshot = world.snapshot(&mut duplicate);
+    ///
+    /// world.remove(entity);
+    /// world.push((3usize,));
+    ///
+    /// world.restore(&snapshot, &mut duplicate);
+    /// assert_eq!(world.len(), 1);
+    /// assert_eq!(world.entry(entity).unwrap().get_component::<usize>(), Ok(&1));
+    /// ```
+    pub fn snapshot(&self, duplicate: &mut Duplicate) -> WorldSnapshot {
+        let version = next_component_version();
+        let mut world = World::default();
+        world.clone_from(self, &any(), &mut PreserveIds(duplicate));
+
+        let allocator = self.entity_allocator().save_state();
+        let reserved = if allocator.is_some() {
+            self.allocate().reserved().to_vec()
+        } else {
+            Vec::new()
+        };
+
+        let data = Arc::new(SnapshotData {
+            world,
+            version,
+            allocator,
+            reserved,
+        });
+        if data.allocator.is_none() {
+            self.held_ids().track(&data);
+        }
+        WorldSnapshot(data)
+    }
+
+    /// Resets the world in place to the state captured in a [snapshot](#method.snapshot).
+    ///
+    /// All entities are removed from the world, and the snapshot's entities are cloned back into
+    /// it with their original IDs, using `duplicate` to clone their components. Component
+    /// [hooks](#method.on_remove) and [events](../world/enum.Event.html) run as they would for
+    /// removing and cloning the entities, but the removals are not recorded in the
+    /// [removal log](../storage/struct.RemovalLog.html).
+    ///
+    /// If the snapshot captured the state of the world's allocator, the allocator is rewound.
+    /// Otherwise, the IDs of entities which are not in the snapshot are released, unless another
+    /// live snapshot contains them, and the world takes back the IDs of the snapshot's entities
+    /// which it has held since they were removed.
+    ///
+    /// Entities which left the world without being removed, such as by being
+    /// [moved](#method.move_from) into another world, keep their IDs in the world they moved to.
+    /// Restoring them will [reclaim](trait.EntityAllocator.html#method.reclaim) their IDs from
+    /// the allocator if they have since been released, and otherwise restores them as copies
+    /// which share the IDs of the moved entities.
+    pub fn restore(&mut self, snapshot: &WorldSnapshot, duplicate: &mut Duplicate) {
+        let data = &*snapshot.0;
+
+        if data.allocator.is_none() {
+            let removed = data
+                .world
+                .archetypes()
+                .iter()
+                .flat_map(|arch| arch.entities().iter().copied())
+                .filter(|entity| !self.contains(*entity))
+                .collect::<Vec<_>>();
+            for entity in removed {
+                if !self.take_held_id(entity) {
+                    self.allocate_mut().unreserve_index(entity);
+                    self.entity_allocator().reclaim(entity);
+                }
+            }
+        }
+
+        let entities = self
+            .archetypes()
+            .iter()
+            .flat_map(|arch| arch.entities().iter().copied())
+            .collect::<Vec<_>>();
+        for entity in entities {
+            self.remove_retain_id(entity);
+            if data.allocator.is_none() && !data.world.contains(entity) {
+                self.release_id(entity);
+            }
+        }
+
+        if let Some(state) = &data.allocator {
+            self.entity_allocator().restore_state(state.as_ref());
+            self.allocate_mut().set_reserved(data.reserved.clone());
+        }
+
+        self.clone_from(&data.world, &any(), &mut PreserveIds(duplicate));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::internals::{
+        entity::{EntityAllocator, GenerationalAllocator},
+        query::IntoQuery,
+        storage::component::ComponentTypeId,
+        world::{EntityStore, WorldOptions},
+    };
+
+    fn duplicate() -> Duplicate {
+        let mut duplicate = Duplicate::new();
+        duplicate.register_copy::<usize>();
+        duplicate.register_clone::<String>();
+        duplicate.register_copy::<Entity>();
+        duplicate
+    }
+
+    #[test]
+    fn restore_entities() {
+        // a private allocator, so that other tests cannot take the IDs of removed entities
+        let allocator = Arc::new(GenerationalAllocator::new());
+        let mut world = shared_world(&allocator);
+        let a = world.push((1usize, "a".to_string()));
+        let b = world.push((2usize,));
+        let c = world.push((3usize, a));
+        world.push((false,));
+
+        let mut duplicate = duplicate();
+        let snapshot = world.snapshot(&mut duplicate);
+        assert_eq!(snapshot.len(), 4);
+
+        *world
+            .entry(a)
+            .unwrap()
+            .get_component_mut::<usize>()
+            .unwrap() = 10;
+        world.entry(b).unwrap().add_component("b".to_string());
+        world.remove(c);
+        let d = world.push((4usize,));
+
+        world.restore(&snapshot, &mut duplicate);
+
+        assert_eq!(world.len(), 4);
+        assert!(!world.contains(d));
+        let entry = world.entry_ref(a).unwrap();
+        assert_eq!(entry.get_component::<usize>(), Ok(&1));
+        assert_eq!(entry.get_component::<String>().unwrap(), "a");
+        let entry = world.entry_ref(b).unwrap();
+        assert!(entry.get_component::<String>().is_err());
+        assert_eq!(
+            world.entry_ref(c).unwrap().get_component::<Entity>(),
+            Ok(&a)
+        );
+
+        // unregistered components are not captured
+        assert_eq!(<&bool>::query().iter(&world).count(), 0);
+
+        let mut values = <&usize>::query().iter(&world).copied().collect::<Vec<_>>();
+        values.sort_unstable();
+        assert_eq!(values, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn restore_sparse() {
+        let allocator = Arc::new(GenerationalAllocator::new());
+        let mut world = shared_world(&allocator);
+        world.register_sparse::<String>();
+        let a = world.push((1usize, "a".to_string()));
+        let b = world.push((2usize,));
+
+        let mut duplicate = duplicate();
+        let snapshot = world.snapshot(&mut duplicate);
+
+        world.entry(a).unwrap().remove_component::<String>();
+        world.entry(b).unwrap().add_component("b".to_string());
+        let diff = world.diff_snapshot(&snapshot);
+        let string_id = ComponentTypeId::of::<String>();
+        assert_eq!(diff.removed(), &[(a, string_id)]);
+        assert_eq!(diff.added(), &[(b, string_id)]);
+
+        world.restore(&snapshot, &mut duplicate);
+        let sparse = world.sparse_components().get_downcast::<String>().unwrap();
+        assert_eq!(sparse.get(a).map(String::as_str), Some("a"));
+        assert_eq!(sparse.get(b), None);
+        assert_eq!(sparse.len(), 1);
+    }
+
+    #[test]
+    fn rewind_allocator() {
+        let mut world = World::new(WorldOptions {
+            allocator: Arc::new(GenerationalAllocator::new()),
+            ..Default::default()
+        });
+        let a = world.push((1usize,));
+
+        let mut duplicate = duplicate();
+        let snapshot = world.snapshot(&mut duplicate);
+
+        world.remove(a);
+        let b = world.push((2usize,));
+        let c = world.push((3usize,));
+
+        // the same IDs are assigned when the same operations are replayed
+        world.restore(&snapshot, &mut duplicate);
+        assert_eq!(world.entry_ref(a).unwrap().get_component::<usize>(), Ok(&1));
+        world.remove(a);
+        assert_eq!(world.push((2usize,)), b);
+        assert_eq!(world.push((3usize,)), c);
+
+        world.restore(&snapshot, &mut duplicate);
+        assert_eq!(world.len(), 1);
+        assert!(world.contains(a));
+    }
+
+    /// Shares a `GenerationalAllocator` without allowing it to be rewound, like the
+    /// `SharedAllocator`.
+    struct Shared(Arc<GenerationalAllocator>);
+
+    impl EntityAllocator for Shared {
+        fn reserve(&self, count: usize, ids: &mut Vec<Entity>) {
+            self.0.reserve(count, ids)
+        }
+
+        fn unreserve(&self, ids: &[Entity]) {
+            self.0.unreserve(ids)
+        }
+
+        fn release(&self, entity: Entity) {
+            self.0.release(entity)
+        }
+
+        fn reclaim(&self, entity: Entity) -> bool {
+            self.0.reclaim(entity)
+        }
+    }
+
+    fn shared_world(allocator: &Arc<GenerationalAllocator>) -> World {
+        World::new(WorldOptions {
+            allocator: Arc::new(Shared(allocator.clone())),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn restore_reclaims_ids() {
+        let allocator = Arc::new(GenerationalAllocator::new());
+        let mut world = shared_world(&allocator);
+        let a = world.push((1usize,));
+        let b = world.push((2usize,));
+
+        let mut duplicate = duplicate();
+        let snapshot = world.snapshot(&mut duplicate);
+
+        world.remove(a);
+        world.remove(b);
+        world.restore(&snapshot, &mut duplicate);
+
+        // pushing new entities, into this world or another, must not reuse the restored IDs
+        let mut other = shared_world(&allocator);
+        let mut pushed = (0..40).map(|i| world.push((i,))).collect::<Vec<_>>();
+        pushed.extend((0..40).map(|i| other.push((i,))));
+        assert!(pushed
+            .iter()
+            .all(|entity| entity.index() != a.index() && entity.index() != b.index()));
+        assert_eq!(world.entry_ref(a).unwrap().get_component::<usize>(), Ok(&1));
+        assert_eq!(world.entry_ref(b).unwrap().get_component::<usize>(), Ok(&2));
+    }
+
+    #[test]
+    fn restore_after_other_world_allocates() {
+        let allocator = Arc::new(GenerationalAllocator::new());
+        let mut world = shared_world(&allocator);
+        let a = world.push((1usize,));
+
+        let mut duplicate = duplicate();
+        let snapshot = world.snapshot(&mut duplicate);
+
+        world.remove(a);
+        // another world reserves a block of IDs, which must not include the removed ID
+        let mut other = shared_world(&allocator);
+        let b = other.push((2usize,));
+        assert_ne!(a.index(), b.index());
+
+        world.restore(&snapshot, &mut duplicate);
+        assert_eq!(world.entry_ref(a).unwrap().get_component::<usize>(), Ok(&1));
+    }
+
+    #[test]
+    fn restore_default_allocator() {
+        let mut world = World::default();
+        let a = world.push((1usize,));
+
+        let mut duplicate = duplicate();
+        let snapshot = world.snapshot(&mut duplicate);
+
+        world.remove(a);
+        for i in 0..5000usize {
+            world.push((i,));
+        }
+
+        world.restore(&snapshot, &mut duplicate);
+        assert_eq!(world.len(), 1);
+        assert_eq!(world.entry_ref(a).unwrap().get_component::<usize>(), Ok(&1));
+    }
+
+    #[test]
+    fn release_held_ids() {
+        let allocator = Arc::new(GenerationalAllocator::new());
+        let mut world = shared_world(&allocator);
+        let a = world.push((1usize,));
+        let b = world.push((2usize,));
+
+        let mut duplicate = duplicate();
+        let snapshot = world.snapshot(&mut duplicate);
+
+        // the ID is held while the snapshot is alive
+        world.remove(a);
+        assert!(!allocator.reclaim(a));
+
+        // and released once the world next releases an ID after the snapshot is dropped
+        drop(snapshot);
+        world.remove(b);
+        assert!(allocator.reclaim(a));
+        assert!(allocator.reclaim(b));
+    }
+}
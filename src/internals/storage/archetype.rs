@@ -114,6 +114,15 @@ impl Archetype {
         removed
     }
 
+    /// Returns `true` if any subscriber is interested in events about this archetype.
+    pub(crate) fn has_subscribers(&self) -> bool {
+        !self.subscribers.is_empty()
+    }
+
+    pub(crate) fn notify(&mut self, event: Event) {
+        self.subscribers.send(event);
+    }
+
     pub(crate) fn subscribe(&mut self, subscriber: Subscriber) {
         subscriber.send(Event::ArchetypeCreated(self.index));
         for entity in &self.entities {
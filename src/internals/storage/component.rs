@@ -11,7 +11,6 @@ use std::{
 #[derive(Copy, Clone, Debug, Eq, PartialOrd, Ord)]
 pub struct ComponentTypeId {
     pub(crate) type_id: TypeId,
-    #[cfg(debug_assertions)]
     name: &'static str,
 }
 
@@ -20,7 +19,6 @@ impl ComponentTypeId {
     pub fn of<T: Component>() -> Self {
         Self {
             type_id: TypeId::of::<T>(),
-            #[cfg(debug_assertions)]
             name: std::any::type_name::<T>(),
         }
     }
@@ -29,6 +27,11 @@ impl ComponentTypeId {
     pub fn type_id(&self) -> TypeId {
         self.type_id
     }
+
+    /// Returns the name of the component type.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
 }
 
 impl std::hash::Hash for ComponentTypeId {
@@ -44,15 +47,9 @@ impl PartialEq for ComponentTypeId {
 }
 
 impl Display for ComponentTypeId {
-    #[cfg(debug_assertions)]
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.name)
     }
-
-    #[cfg(not(debug_assertions))]
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?}", self.type_id)
-    }
 }
 
 /// A marker trait for all types which can be attached to an entity.
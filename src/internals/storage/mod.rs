@@ -1,7 +1,8 @@
-use crate::internals::hash::ComponentTypeIdHasher;
+use crate::internals::{entity::Entity, hash::ComponentTypeIdHasher};
 use archetype::ArchetypeIndex;
 use component::{Component, ComponentTypeId};
 use downcast_rs::{impl_downcast, Downcast};
+use sparse::SparseComponents;
 use std::{
     collections::{HashMap, HashSet},
     hash::BuildHasherDefault,
@@ -14,7 +15,9 @@ pub mod component;
 pub mod group;
 pub mod index;
 pub mod packed;
+pub mod removed;
 pub mod slicevec;
+pub mod sparse;
 
 /// Contains information about the type of a component.
 #[derive(Copy, Clone, PartialEq)]
@@ -70,9 +73,32 @@ pub type Epoch = u64;
 
 /// The version of a component slice. Versions are incremented when the sliace is
 /// accessed mutably.
+///
+/// Each component also records the versions at which it was added and last written in its
+/// [ComponentTicks](struct.ComponentTicks.html).
 pub type Version = u64;
 
-static COMPONENT_VERSION: AtomicU64 = AtomicU64::new(0);
+/// The versions at which a single entity's component was added and last accessed mutably.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ComponentTicks {
+    /// The version at which the component was added to the entity.
+    pub added: Version,
+    /// The version at which the component was last accessed mutably.
+    pub changed: Version,
+}
+
+impl ComponentTicks {
+    /// Constructs ticks for a component which was added at the given version.
+    pub fn new(version: Version) -> Self {
+        Self {
+            added: version,
+            changed: version,
+        }
+    }
+}
+
+// versions start at 1 so that filters which have never run treat all components as changed
+static COMPONENT_VERSION: AtomicU64 = AtomicU64::new(1);
 pub(crate) fn next_component_version() -> u64 {
     COMPONENT_VERSION.fetch_add(1, Ordering::SeqCst)
 }
@@ -132,6 +158,25 @@ pub trait UnknownComponentStorage: Downcast + Send + Sync {
     /// The caller is responsible for ensuring that they have exclusive access to the given archetype's slice.
     unsafe fn get_mut_raw(&self, archetype: ArchetypeIndex) -> Option<(*mut u8, usize)>;
 
+    /// Returns the ticks of each component in the given archetype's component slice, or `None`
+    /// if the storage does not track per-component ticks.
+    fn get_ticks(&self, _archetype: ArchetypeIndex) -> Option<&[ComponentTicks]> {
+        None
+    }
+
+    /// Replaces a single component in the given archetype's component slice via a memcopy,
+    /// dropping the previous value and marking the component as changed.
+    ///
+    /// # Safety
+    /// `ptr` must point to a valid instance of the correct component type. The component is
+    /// moved into the world's internal storage, as with `extend_memcopy_raw`.
+    unsafe fn replace_raw(
+        &mut self,
+        archetype: ArchetypeIndex,
+        index: ComponentIndex,
+        ptr: *const u8,
+    );
+
     /// Writes new components into the given archetype's component slice via a memcopy.
     ///
     /// # Safety
@@ -152,16 +197,27 @@ impl_downcast!(UnknownComponentStorage);
 pub struct ComponentSlice<'a, T: Component> {
     pub(crate) components: &'a [T],
     pub(crate) version: &'a Version,
+    pub(crate) ticks: &'a [ComponentTicks],
 }
 
 impl<'a, T: Component> ComponentSlice<'a, T> {
-    pub(crate) fn new(components: &'a [T], version: &'a Version) -> Self {
+    pub(crate) fn new(
+        components: &'a [T],
+        version: &'a Version,
+        ticks: &'a [ComponentTicks],
+    ) -> Self {
         Self {
             components,
             version,
+            ticks,
         }
     }
 
+    /// Returns the versions at which each component in the slice was added and last written.
+    pub fn ticks(&self) -> &'a [ComponentTicks] {
+        self.ticks
+    }
+
     /// Converts this slice into its inner value.
     pub fn into_slice(self) -> &'a [T] {
         self.components
@@ -194,22 +250,47 @@ pub struct ComponentSliceMut<'a, T: Component> {
     // todo would be better if these were private and we controlled version increments more centrally
     pub(crate) components: &'a mut [T],
     pub(crate) version: &'a mut Version,
+    pub(crate) ticks: &'a mut [ComponentTicks],
 }
 
 impl<'a, T: Component> ComponentSliceMut<'a, T> {
-    pub(crate) fn new(components: &'a mut [T], version: &'a mut Version) -> Self {
+    pub(crate) fn new(
+        components: &'a mut [T],
+        version: &'a mut Version,
+        ticks: &'a mut [ComponentTicks],
+    ) -> Self {
         Self {
             components,
             version,
+            ticks,
         }
     }
 
+    /// Returns the versions at which each component in the slice was added and last written.
+    pub fn ticks(&self) -> &[ComponentTicks] {
+        self.ticks
+    }
+
     /// Converts this slice into its inner value.
-    /// This increments the slice's version.
+    /// This increments the slice's version, and marks every component in the slice as changed.
     pub fn into_slice(self) -> &'a mut [T] {
-        *self.version = next_component_version();
+        let version = next_component_version();
+        *self.version = version;
+        for tick in self.ticks.iter_mut() {
+            tick.changed = version;
+        }
         self.components
     }
+
+    /// Converts this slice into a reference to a single component.
+    /// This increments the slice's version, and marks only the returned component as changed.
+    pub fn into_component(self, ComponentIndex(index): ComponentIndex) -> Option<&'a mut T> {
+        let component = self.components.get_mut(index)?;
+        let version = next_component_version();
+        *self.version = version;
+        self.ticks[index].changed = version;
+        Some(component)
+    }
 }
 
 impl<'a, T: Component> Deref for ComponentSliceMut<'a, T> {
@@ -286,6 +367,7 @@ pub struct Components {
         Box<dyn UnknownComponentStorage>,
         BuildHasherDefault<ComponentTypeIdHasher>,
     >,
+    sparse: SparseComponents,
 }
 
 impl Components {
@@ -328,6 +410,32 @@ impl Components {
             .and_then(|storage| storage.downcast_mut())
     }
 
+    /// Returns the storages of all component types which use
+    /// [sparse storage](struct.SparseStorage.html).
+    pub fn sparse(&self) -> &SparseComponents {
+        &self.sparse
+    }
+
+    /// Returns mutable access to the storages of all component types which use
+    /// [sparse storage](struct.SparseStorage.html).
+    pub fn sparse_mut(&mut self) -> &mut SparseComponents {
+        &mut self.sparse
+    }
+
+    /// Moves a component out of an archetype's slice and into the component type's sparse
+    /// storage, for the given entity.
+    pub(crate) fn move_to_sparse(
+        &mut self,
+        type_id: ComponentTypeId,
+        archetype: ArchetypeIndex,
+        index: ComponentIndex,
+        entity: Entity,
+    ) {
+        let storage = self.storages.get_mut(&type_id).unwrap();
+        self.sparse
+            .move_from_packed(type_id, entity, storage.deref_mut(), archetype, index);
+    }
+
     /// Returns a writer for writing to multiple component storages.
     pub fn get_multi_mut(&mut self) -> MultiMut {
         MultiMut::new(self)
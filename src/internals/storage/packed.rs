@@ -2,7 +2,7 @@
 
 use super::{
     archetype::ArchetypeIndex, component::Component, next_component_version, ComponentIndex,
-    ComponentMeta, ComponentSlice, ComponentSliceMut, ComponentStorage, Epoch,
+    ComponentMeta, ComponentSlice, ComponentSliceMut, ComponentStorage, ComponentTicks, Epoch,
     UnknownComponentStorage,
 };
 use std::{
@@ -289,11 +289,13 @@ pub struct PackedStorage<T: Component> {
     epoch: Epoch,
     // Ordered archetype versions
     versions: Vec<UnsafeCell<u64>>,
+    // Ordered per-component added and change ticks
+    ticks: Vec<UnsafeCell<Vec<ComponentTicks>>>,
     // Ordered allocation metadata
     allocations: Vec<ComponentVec<T>>,
 }
 
-// these are needed because of the UnsafeCell in versions
+// these are needed because of the UnsafeCell in versions and ticks
 // but we write protect that ourselves
 unsafe impl<T: Component> Send for PackedStorage<T> {}
 unsafe impl<T: Component> Sync for PackedStorage<T> {}
@@ -303,13 +305,20 @@ impl<T: Component> PackedStorage<T> {
         &mut self,
         ArchetypeIndex(archetype): ArchetypeIndex,
         ComponentIndex(index): ComponentIndex,
-    ) -> T {
+    ) -> (T, ComponentTicks) {
         let slice_index = self.index[archetype as usize];
         let allocation = &mut self.allocations[slice_index];
         let component = allocation.swap_remove(self.epoch, index as usize);
+        let tick = self.ticks[slice_index].get_mut().swap_remove(index);
         self.update_slice(slice_index);
         self.entity_len -= 1;
-        component
+        (component, tick)
+    }
+
+    /// Removes a component from an archetype's slice without dropping it, swapping it with
+    /// the last component in the slice.
+    pub(crate) fn take(&mut self, archetype: ArchetypeIndex, index: ComponentIndex) -> T {
+        self.swap_remove_internal(archetype, index).0
     }
 
     #[inline]
@@ -336,6 +345,7 @@ impl<T: Component> UnknownComponentStorage for PackedStorage<T> {
         // remove component from source slice
         let src_allocation = &mut self.allocations[src_slice_index];
         let value = src_allocation.swap_remove(self.epoch, index.0 as usize);
+        let tick = self.ticks[src_slice_index].get_mut().swap_remove(index.0);
 
         // insert component into destination slice
         let dst_allocation = &mut self.allocations[dst_slice_index];
@@ -343,6 +353,7 @@ impl<T: Component> UnknownComponentStorage for PackedStorage<T> {
             dst_allocation.extend_memcopy(self.epoch, &value as *const _, 1);
             *self.versions[dst_slice_index].get() = next_component_version();
         }
+        self.ticks[dst_slice_index].get_mut().push(tick);
 
         // update slice pointers
         self.update_slice(src_slice_index);
@@ -362,6 +373,7 @@ impl<T: Component> UnknownComponentStorage for PackedStorage<T> {
         // insert archetype into collections
         self.slices.insert(index, allocation.as_raw_slice());
         self.versions.insert(index, UnsafeCell::new(0));
+        self.ticks.insert(index, UnsafeCell::new(Vec::new()));
         self.allocations.insert(index, allocation);
 
         // update index
@@ -397,6 +409,12 @@ impl<T: Component> UnknownComponentStorage for PackedStorage<T> {
                 &mut dst.allocations[dst_index],
             );
 
+            // the components keep their ticks
+            std::mem::swap(
+                self.ticks[src_index].get_mut(),
+                dst.ticks[dst_index].get_mut(),
+            );
+
             // bump destination version
             unsafe { *dst.versions[dst_index].get() = next_component_version() };
         } else {
@@ -404,6 +422,12 @@ impl<T: Component> UnknownComponentStorage for PackedStorage<T> {
             let (ptr, len) = self.get_raw(src_archetype).unwrap();
             unsafe { dst.extend_memcopy_raw(dst_archetype, ptr, len) };
 
+            // the components keep their ticks
+            let src_ticks = self.ticks[src_index].get_mut();
+            let dst_ticks = dst.ticks[dst_index].get_mut();
+            dst_ticks.truncate(dst_ticks.len() - len);
+            dst_ticks.append(src_ticks);
+
             // clear and forget source
             let mut swapped = ComponentVec::<T>::new();
             std::mem::swap(&mut self.allocations[src_index], &mut swapped);
@@ -423,9 +447,15 @@ impl<T: Component> UnknownComponentStorage for PackedStorage<T> {
         dst_archetype: ArchetypeIndex,
         dst: &mut dyn UnknownComponentStorage,
     ) {
-        let component = self.swap_remove_internal(src_archetype, src_component);
+        let (component, tick) = self.swap_remove_internal(src_archetype, src_component);
         unsafe { dst.extend_memcopy_raw(dst_archetype, &component as *const T as *const u8, 1) };
         std::mem::forget(component);
+
+        // the component keeps its ticks
+        if let Some(dst) = dst.downcast_mut::<Self>() {
+            let dst_index = dst.index(dst_archetype);
+            *dst.ticks[dst_index].get_mut().last_mut().unwrap() = tick;
+        }
     }
 
     fn swap_remove(&mut self, archetype: ArchetypeIndex, index: ComponentIndex) {
@@ -489,10 +519,36 @@ impl<T: Component> UnknownComponentStorage for PackedStorage<T> {
     ) -> Option<(*mut u8, usize)> {
         let slice_index = *self.index.get(archetype as usize)?;
         let (ptr, len) = self.slices.get(slice_index)?;
-        *self.versions.get_unchecked(slice_index).get() = next_component_version();
+        let version = next_component_version();
+        *self.versions.get_unchecked(slice_index).get() = version;
+        for tick in (*self.ticks.get_unchecked(slice_index).get()).iter_mut() {
+            tick.changed = version;
+        }
         Some((ptr.as_ptr() as *mut u8, *len))
     }
 
+    fn get_ticks(&self, ArchetypeIndex(archetype): ArchetypeIndex) -> Option<&[ComponentTicks]> {
+        let slice_index = *self.index.get(archetype as usize)?;
+        Some(unsafe { &*self.ticks.get_unchecked(slice_index).get() })
+    }
+
+    unsafe fn replace_raw(
+        &mut self,
+        ArchetypeIndex(archetype): ArchetypeIndex,
+        ComponentIndex(index): ComponentIndex,
+        ptr: *const u8,
+    ) {
+        let slice_index = self.index[archetype as usize];
+        let (slice, len) = self.slices[slice_index];
+        assert!(index < len);
+        let target = slice.as_ptr().add(index);
+        std::ptr::drop_in_place(target);
+        std::ptr::copy_nonoverlapping(ptr as *const T, target, 1);
+        let version = next_component_version();
+        *self.versions[slice_index].get_mut() = version;
+        self.ticks[slice_index].get_mut()[index].changed = version;
+    }
+
     unsafe fn extend_memcopy_raw(
         &mut self,
         ArchetypeIndex(archetype): ArchetypeIndex,
@@ -504,7 +560,10 @@ impl<T: Component> UnknownComponentStorage for PackedStorage<T> {
         allocation.extend_memcopy(self.epoch, ptr as *const T, count);
         self.slices[slice_index] = allocation.as_raw_slice();
         self.entity_len += count;
-        *self.versions[slice_index].get() = next_component_version();
+        let version = next_component_version();
+        *self.versions[slice_index].get() = version;
+        let ticks = self.ticks[slice_index].get_mut();
+        ticks.resize(ticks.len() + count, ComponentTicks::new(version));
     }
 
     fn increment_epoch(&mut self) {
@@ -524,6 +583,7 @@ impl<T: Component> Default for PackedStorage<T> {
             index: Vec::new(),
             slices: Vec::new(),
             versions: Vec::new(),
+            ticks: Vec::new(),
             allocations: Vec::new(),
             entity_len: 0,
             epoch: 0,
@@ -544,7 +604,8 @@ impl<'a, T: Component> ComponentStorage<'a, T> for PackedStorage<T> {
         let (ptr, len) = self.slices.get(slice_index)?;
         let slice = unsafe { std::slice::from_raw_parts(ptr.as_ptr(), *len as usize) };
         let version = unsafe { &*self.versions.get_unchecked(slice_index).get() };
-        Some(ComponentSlice::new(slice, version))
+        let ticks = unsafe { &*self.ticks.get_unchecked(slice_index).get() };
+        Some(ComponentSlice::new(slice, version, ticks))
     }
 
     unsafe fn get_mut(
@@ -555,14 +616,16 @@ impl<'a, T: Component> ComponentStorage<'a, T> for PackedStorage<T> {
         let (ptr, len) = self.slices.get(slice_index)?;
         let slice = std::slice::from_raw_parts_mut(ptr.as_ptr(), *len as usize);
         let version = &mut *self.versions.get_unchecked(slice_index).get();
-        Some(ComponentSliceMut::new(slice, version))
+        let ticks = &mut *self.ticks.get_unchecked(slice_index).get();
+        Some(ComponentSliceMut::new(slice, version, ticks))
     }
 
     fn iter(&'a self, start_inclusive: usize, end_exclusive: usize) -> Self::Iter {
         ComponentIter {
             slices: self.slices[start_inclusive..end_exclusive]
                 .iter()
-                .zip(self.versions[start_inclusive..end_exclusive].iter()),
+                .zip(self.versions[start_inclusive..end_exclusive].iter())
+                .zip(self.ticks[start_inclusive..end_exclusive].iter()),
         }
     }
 
@@ -570,7 +633,8 @@ impl<'a, T: Component> ComponentStorage<'a, T> for PackedStorage<T> {
         ComponentIterMut {
             slices: self.slices[start_inclusive..end_exclusive]
                 .iter()
-                .zip(self.versions[start_inclusive..end_exclusive].iter()),
+                .zip(self.versions[start_inclusive..end_exclusive].iter())
+                .zip(self.ticks[start_inclusive..end_exclusive].iter()),
         }
     }
 
@@ -579,9 +643,14 @@ impl<'a, T: Component> ComponentStorage<'a, T> for PackedStorage<T> {
     }
 }
 
+type SliceIter<'a, T> = Zip<
+    Zip<Iter<'a, (NonNull<T>, usize)>, Iter<'a, UnsafeCell<u64>>>,
+    Iter<'a, UnsafeCell<Vec<ComponentTicks>>>,
+>;
+
 #[doc(hidden)]
 pub struct ComponentIter<'a, T> {
-    slices: Zip<Iter<'a, (NonNull<T>, usize)>, Iter<'a, UnsafeCell<u64>>>,
+    slices: SliceIter<'a, T>,
 }
 
 impl<'a, T: Component> Iterator for ComponentIter<'a, T> {
@@ -589,17 +658,18 @@ impl<'a, T: Component> Iterator for ComponentIter<'a, T> {
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        self.slices.next().map(|((ptr, len), version)| {
+        self.slices.next().map(|(((ptr, len), version), ticks)| {
             let slice = unsafe { std::slice::from_raw_parts(ptr.as_ptr(), *len as usize) };
             let version = unsafe { &*version.get() };
-            ComponentSlice::new(slice, version)
+            let ticks = unsafe { &*ticks.get() };
+            ComponentSlice::new(slice, version, ticks)
         })
     }
 }
 
 #[doc(hidden)]
 pub struct ComponentIterMut<'a, T> {
-    slices: Zip<Iter<'a, (NonNull<T>, usize)>, Iter<'a, UnsafeCell<u64>>>,
+    slices: SliceIter<'a, T>,
 }
 
 impl<'a, T: Component> Iterator for ComponentIterMut<'a, T> {
@@ -607,11 +677,12 @@ impl<'a, T: Component> Iterator for ComponentIterMut<'a, T> {
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        self.slices.next().map(|((ptr, len), version)| {
+        self.slices.next().map(|(((ptr, len), version), ticks)| {
             // safety: we know each slice is disjoint
             let slice = unsafe { std::slice::from_raw_parts_mut(ptr.as_ptr(), *len as usize) };
             let version = unsafe { &mut *version.get() };
-            ComponentSliceMut::new(slice, version)
+            let ticks = unsafe { &mut *ticks.get() };
+            ComponentSliceMut::new(slice, version, ticks)
         })
     }
 }
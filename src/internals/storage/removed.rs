@@ -0,0 +1,151 @@
+//! Records of the entities which have lost components.
+
+use super::{
+    component::{Component, ComponentTypeId},
+    next_component_version, Version,
+};
+use crate::internals::{
+    entity::Entity,
+    hash::ComponentTypeIdHasher,
+    world::{EntityStore, WorldId},
+};
+use std::{collections::HashMap, hash::BuildHasherDefault, marker::PhantomData};
+
+type RecordMap =
+    HashMap<ComponentTypeId, Vec<(Entity, Version)>, BuildHasherDefault<ComponentTypeIdHasher>>;
+
+/// A log of the entities which have lost components, either because the component was removed
+/// from the entity or because the entity was removed from the world.
+///
+/// Each removal is recorded with the component version at which it occurred. The log is double
+/// buffered: removals are retained until [World::update_removals](../world/struct.World.html#method.update_removals)
+/// has been called twice, which [Schedule](../systems/struct.Schedule.html) does at the end of each
+/// execution. This gives every system a chance to observe a removal on its next run, regardless
+/// of whether it runs before or after the removal within a schedule.
+///
+/// Removals are only recorded for component types which have been registered with
+/// [World::track_removals](../world/struct.World.html#method.track_removals), so that worlds
+/// which never read removals, or are never updated, do not accumulate them.
+///
+/// Use a [RemovedReader](struct.RemovedReader.html) to read each removal only once.
+#[derive(Default, Debug)]
+pub struct RemovalLog {
+    current: RecordMap,
+    previous: RecordMap,
+}
+
+impl RemovalLog {
+    /// Starts recording the removals of the given component type.
+    pub(crate) fn track(&mut self, type_id: ComponentTypeId) {
+        self.current.entry(type_id).or_default();
+        self.previous.entry(type_id).or_default();
+    }
+
+    /// Returns `true` if removals of the given component type are being recorded.
+    pub fn is_tracked(&self, type_id: ComponentTypeId) -> bool {
+        self.current.contains_key(&type_id)
+    }
+
+    /// Records that an entity has lost a component, if the component type is tracked.
+    pub(crate) fn record(&mut self, type_id: ComponentTypeId, entity: Entity) {
+        self.record_at(type_id, entity, next_component_version());
+    }
+
+    /// Records that an entity has lost a component at the given version. Removals which happen
+    /// together, such as all of the components of a removed entity, share a single version.
+    pub(crate) fn record_at(&mut self, type_id: ComponentTypeId, entity: Entity, version: Version) {
+        if let Some(records) = self.current.get_mut(&type_id) {
+            records.push((entity, version));
+        }
+    }
+
+    /// Discards the oldest buffer of removals.
+    pub(crate) fn update(&mut self) {
+        std::mem::swap(&mut self.current, &mut self.previous);
+        for records in self.current.values_mut() {
+            records.clear();
+        }
+    }
+
+    /// Iterates through all retained removals of the given component type, oldest first.
+    /// Each item contains the entity which lost the component and the version at which the
+    /// removal was recorded.
+    pub fn iter_unknown(
+        &self,
+        type_id: ComponentTypeId,
+    ) -> impl Iterator<Item = (Entity, Version)> + '_ {
+        let previous = self.previous.get(&type_id).into_iter().flatten();
+        let current = self.current.get(&type_id).into_iter().flatten();
+        previous.chain(current).copied()
+    }
+
+    /// Iterates through all entities which have recently lost a component of type `T`,
+    /// oldest first.
+    pub fn iter<T: Component>(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.iter_unknown(ComponentTypeId::of::<T>())
+            .map(|(entity, _)| entity)
+    }
+}
+
+/// Reads the removals of component type `T` which have been recorded since the reader last ran.
+///
+/// The world must track removals of `T`, see
+/// [World::track_removals](../world/struct.World.html#method.track_removals).
+///
+/// # Examples
+///
+/// ```
+/// # use legion::*;
+/// # use legion::storage::RemovedReader;
+/// # struct Body;
+/// let mut world = World::default();
+/// world.track_removals::<Body>();
+/// let mut reader = RemovedReader::<Body>::default();
+///
+/// let entity = world.push((Body,));
+/// world.remove(entity);
+///
+/// assert_eq!(reader.read(&world).collect::<Vec<_>>(), vec![entity]);
+/// assert_eq!(reader.read(&world).count(), 0);
+/// ```
+#[derive(Debug)]
+pub struct RemovedReader<T: Component> {
+    history: HashMap<WorldId, Version>,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: Component> Default for RemovedReader<T> {
+    fn default() -> Self {
+        Self {
+            history: Default::default(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: Component> Clone for RemovedReader<T> {
+    fn clone(&self) -> Self {
+        Self {
+            history: self.history.clone(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: Component> RemovedReader<T> {
+    /// Returns the entities which have lost their `T` component since this reader last read
+    /// from the given world.
+    pub fn read<'a, S: EntityStore>(&mut self, world: &'a S) -> impl Iterator<Item = Entity> + 'a {
+        let last_read = self.history.entry(world.id()).or_insert(0);
+        let threshold = *last_read;
+
+        let log = world.removals();
+        if let Some((_, latest)) = log.iter_unknown(ComponentTypeId::of::<T>()).last() {
+            *last_read = latest.max(threshold);
+        }
+
+        log.iter_unknown(ComponentTypeId::of::<T>())
+            .filter(move |(_, version)| *version > threshold)
+            .map(|(entity, _)| entity)
+    }
+}
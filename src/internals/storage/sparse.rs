@@ -0,0 +1,525 @@
+//! A sparse set component storage which holds components outside of the archetype layout.
+
+use super::{
+    archetype::ArchetypeIndex,
+    component::{Component, ComponentTypeId},
+    next_component_version,
+    packed::PackedStorage,
+    ComponentIndex, ComponentTicks, UnknownComponentStorage,
+};
+use crate::internals::{entity::Entity, hash::ComponentTypeIdHasher};
+use downcast_rs::{impl_downcast, Downcast};
+use std::{cell::UnsafeCell, collections::HashMap, hash::BuildHasherDefault};
+
+const EMPTY: u32 = u32::MAX;
+
+/// A sparse set storage whose component type is not known statically.
+pub trait UnknownSparseStorage: Downcast + Send + Sync {
+    /// Returns `true` if the given entity has a component in this storage.
+    fn contains(&self, entity: Entity) -> bool;
+
+    /// Returns the entities which have a component in this storage.
+    fn entities(&self) -> &[Entity];
+
+    /// Returns the number of components stored.
+    fn len(&self) -> usize;
+
+    /// Returns `true` if the storage contains no components.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns a pointer to the entity's component.
+    fn get_raw(&self, entity: Entity) -> Option<*const u8>;
+
+    /// Returns the versions at which the entity's component was added and last changed.
+    fn get_ticks(&self, entity: Entity) -> Option<ComponentTicks>;
+
+    /// Removes and drops the entity's component. Returns `true` if the entity had a component.
+    fn remove_unknown(&mut self, entity: Entity) -> bool;
+
+    /// Moves the entity's component into another storage of the same component type.
+    /// Returns `true` if the entity had a component.
+    fn move_unknown(&mut self, entity: Entity, dst: &mut dyn UnknownSparseStorage) -> bool;
+
+    /// Moves a component out of an archetype's slice in a packed storage of the same component
+    /// type, and inserts it for the given entity. Returns `true` if the entity did not already
+    /// have a component.
+    fn move_from_packed(
+        &mut self,
+        entity: Entity,
+        src: &mut dyn UnknownComponentStorage,
+        archetype: ArchetypeIndex,
+        index: ComponentIndex,
+    ) -> bool;
+
+    /// Constructs a new empty storage of the same component type.
+    fn empty(&self) -> Box<dyn UnknownSparseStorage>;
+}
+
+impl_downcast!(UnknownSparseStorage);
+
+/// Stores components of a single type in a sparse set, indexed by entity.
+///
+/// Unlike [PackedStorage](struct.PackedStorage.html), components held in a sparse set are not
+/// part of their entity's [archetype](struct.Archetype.html). Adding or removing them is O(1) and
+/// does not move the entity's other components, which makes sparse storage a good fit for
+/// marker components which are frequently toggled. The trade-off is that
+/// [queries](../query/index.html) can only request sparse components as optional components,
+/// such as `Option<&T>`, and look each one up by entity.
+///
+/// Component types are opted into sparse storage per world with
+/// [World::register_sparse](../world/struct.World.html#method.register_sparse).
+pub struct SparseStorage<T: Component> {
+    indices: Vec<u32>,
+    entities: Vec<Entity>,
+    components: Vec<UnsafeCell<T>>,
+    ticks: Vec<UnsafeCell<ComponentTicks>>,
+}
+
+// Safety: components are only mutated through `&mut self`, or through `get_unchecked`
+// whose caller guarantees exclusive access.
+unsafe impl<T: Component> Send for SparseStorage<T> {}
+unsafe impl<T: Component> Sync for SparseStorage<T> {}
+
+impl<T: Component> Default for SparseStorage<T> {
+    fn default() -> Self {
+        Self {
+            indices: Vec::new(),
+            entities: Vec::new(),
+            components: Vec::new(),
+            ticks: Vec::new(),
+        }
+    }
+}
+
+impl<T: Component> std::fmt::Debug for SparseStorage<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SparseStorage")
+            .field("component", &std::any::type_name::<T>())
+            .field("entities", &self.entities)
+            .finish()
+    }
+}
+
+impl<T: Component> SparseStorage<T> {
+    fn slot(&self, entity: Entity) -> Option<usize> {
+        let slot = *self.indices.get(entity.index() as usize)?;
+        if slot != EMPTY && self.entities[slot as usize] == entity {
+            Some(slot as usize)
+        } else {
+            None
+        }
+    }
+
+    /// Returns `true` if the given entity has a component in this storage.
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.slot(entity).is_some()
+    }
+
+    /// Returns the number of components stored.
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    /// Returns `true` if the storage contains no components.
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    /// Returns the entities which have a component in this storage, in storage order.
+    pub fn entities(&self) -> &[Entity] {
+        &self.entities
+    }
+
+    /// Returns the entity's component.
+    pub fn get(&self, entity: Entity) -> Option<&T> {
+        self.slot(entity)
+            .map(|slot| unsafe { &*self.components[slot].get() })
+    }
+
+    /// Returns the entity's component mutably, marking it as changed.
+    pub fn get_mut(&mut self, entity: Entity) -> Option<&mut T> {
+        // safety: we have exclusive access to the storage
+        unsafe { self.get_unchecked(entity) }
+    }
+
+    /// Returns the entity's component mutably, marking it as changed.
+    ///
+    /// # Safety
+    /// The caller must ensure that the component is not concurrently borrowed anywhere else.
+    #[allow(clippy::mut_from_ref)]
+    pub unsafe fn get_unchecked(&self, entity: Entity) -> Option<&mut T> {
+        let slot = self.slot(entity)?;
+        (*self.ticks[slot].get()).changed = next_component_version();
+        Some(&mut *self.components[slot].get())
+    }
+
+    /// Returns the versions at which the entity's component was added and last changed.
+    pub fn ticks(&self, entity: Entity) -> Option<ComponentTicks> {
+        self.slot(entity)
+            .map(|slot| unsafe { *self.ticks[slot].get() })
+    }
+
+    /// Iterates through all entities and their components.
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, &T)> + '_ {
+        self.entities
+            .iter()
+            .copied()
+            .zip(self.components.iter().map(|cell| unsafe { &*cell.get() }))
+    }
+
+    /// Iterates through all entities and their components mutably, marking all components
+    /// as changed.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Entity, &mut T)> + '_ {
+        let version = next_component_version();
+        for ticks in self.ticks.iter_mut() {
+            ticks.get_mut().changed = version;
+        }
+        self.entities
+            .iter()
+            .copied()
+            .zip(self.components.iter_mut().map(UnsafeCell::get_mut))
+    }
+
+    /// Inserts a component for the given entity, returning the previous component if the
+    /// entity already had one.
+    pub(crate) fn insert(&mut self, entity: Entity, component: T) -> Option<T> {
+        if let Some(slot) = self.slot(entity) {
+            self.ticks[slot].get_mut().changed = next_component_version();
+            return Some(std::mem::replace(
+                self.components[slot].get_mut(),
+                component,
+            ));
+        }
+
+        let index = entity.index() as usize;
+        if index >= self.indices.len() {
+            self.indices.resize(index + 1, EMPTY);
+        }
+        self.indices[index] = self.entities.len() as u32;
+        self.entities.push(entity);
+        self.components.push(UnsafeCell::new(component));
+        self.ticks.push(UnsafeCell::new(ComponentTicks::new(
+            next_component_version(),
+        )));
+        None
+    }
+
+    /// Removes the entity's component.
+    pub(crate) fn remove(&mut self, entity: Entity) -> Option<T> {
+        let slot = self.slot(entity)?;
+        self.indices[entity.index() as usize] = EMPTY;
+        self.entities.swap_remove(slot);
+        self.ticks.swap_remove(slot);
+        let component = self.components.swap_remove(slot).into_inner();
+        if let Some(swapped) = self.entities.get(slot) {
+            self.indices[swapped.index() as usize] = slot as u32;
+        }
+        Some(component)
+    }
+}
+
+impl<T: Component> UnknownSparseStorage for SparseStorage<T> {
+    fn contains(&self, entity: Entity) -> bool {
+        SparseStorage::contains(self, entity)
+    }
+
+    fn entities(&self) -> &[Entity] {
+        &self.entities
+    }
+
+    fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    fn get_raw(&self, entity: Entity) -> Option<*const u8> {
+        self.slot(entity)
+            .map(|slot| self.components[slot].get() as *const u8)
+    }
+
+    fn get_ticks(&self, entity: Entity) -> Option<ComponentTicks> {
+        self.ticks(entity)
+    }
+
+    fn remove_unknown(&mut self, entity: Entity) -> bool {
+        self.remove(entity).is_some()
+    }
+
+    fn move_unknown(&mut self, entity: Entity, dst: &mut dyn UnknownSparseStorage) -> bool {
+        let dst = dst
+            .downcast_mut::<Self>()
+            .expect("sparse storages are of different component types");
+        if let Some(component) = self.remove(entity) {
+            dst.insert(entity, component);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn move_from_packed(
+        &mut self,
+        entity: Entity,
+        src: &mut dyn UnknownComponentStorage,
+        archetype: ArchetypeIndex,
+        index: ComponentIndex,
+    ) -> bool {
+        let src = src
+            .downcast_mut::<PackedStorage<T>>()
+            .expect("component storages are of different component types");
+        let component = src.take(archetype, index);
+        self.insert(entity, component).is_none()
+    }
+
+    fn empty(&self) -> Box<dyn UnknownSparseStorage> {
+        Box::new(Self::default())
+    }
+}
+
+/// Contains the sparse storages for all component types which a world has opted into
+/// [sparse storage](struct.SparseStorage.html).
+#[derive(Default)]
+pub struct SparseComponents {
+    storages: HashMap<
+        ComponentTypeId,
+        Box<dyn UnknownSparseStorage>,
+        BuildHasherDefault<ComponentTypeIdHasher>,
+    >,
+    counts: ComponentCounts,
+}
+
+/// The number of sparse components held by each entity, by entity index. This lets entities
+/// without sparse components skip looking through every sparse storage when they are removed.
+#[derive(Default)]
+struct ComponentCounts(Vec<u32>);
+
+impl ComponentCounts {
+    fn get(&self, entity: Entity) -> u32 {
+        self.0.get(entity.index() as usize).copied().unwrap_or(0)
+    }
+
+    fn increment(&mut self, entity: Entity) {
+        let index = entity.index() as usize;
+        if index >= self.0.len() {
+            self.0.resize(index + 1, 0);
+        }
+        self.0[index] += 1;
+    }
+
+    fn decrement(&mut self, entity: Entity) {
+        if let Some(count) = self.0.get_mut(entity.index() as usize) {
+            *count = count.saturating_sub(1);
+        }
+    }
+
+    fn clear(&mut self, entity: Entity) {
+        if let Some(count) = self.0.get_mut(entity.index() as usize) {
+            *count = 0;
+        }
+    }
+}
+
+impl SparseComponents {
+    /// Creates an empty storage for component type `T`, if one does not already exist.
+    pub(crate) fn register<T: Component>(&mut self) {
+        self.storages
+            .entry(ComponentTypeId::of::<T>())
+            .or_insert_with(|| Box::new(SparseStorage::<T>::default()));
+    }
+
+    /// Returns `true` if no component types use sparse storage.
+    pub fn is_empty(&self) -> bool {
+        self.storages.is_empty()
+    }
+
+    /// Returns `true` if the entity has any sparse components.
+    pub fn has_components(&self, entity: Entity) -> bool {
+        self.counts.get(entity) > 0
+    }
+
+    /// Returns the types of the entity's sparse components, in type ID order.
+    pub fn component_types(&self, entity: Entity) -> Vec<ComponentTypeId> {
+        if !self.has_components(entity) {
+            return Vec::new();
+        }
+        let mut types = self
+            .storages
+            .iter()
+            .filter(|(_, storage)| storage.contains(entity))
+            .map(|(type_id, _)| *type_id)
+            .collect::<Vec<_>>();
+        types.sort();
+        types
+    }
+
+    /// Inserts a component for the given entity, returning the previous component if the
+    /// entity already had one. The storage for `T` must have been registered.
+    pub(crate) fn insert<T: Component>(&mut self, entity: Entity, component: T) -> Option<T> {
+        let previous = self
+            .storages
+            .get_mut(&ComponentTypeId::of::<T>())
+            .and_then(|storage| storage.downcast_mut::<SparseStorage<T>>())
+            .expect("component type is not registered for sparse storage")
+            .insert(entity, component);
+        if previous.is_none() {
+            self.counts.increment(entity);
+        }
+        previous
+    }
+
+    /// Moves a component out of an archetype's slice in a packed storage, and inserts it for the
+    /// given entity. Does nothing if the component type does not use sparse storage.
+    pub(crate) fn move_from_packed(
+        &mut self,
+        type_id: ComponentTypeId,
+        entity: Entity,
+        src: &mut dyn UnknownComponentStorage,
+        archetype: ArchetypeIndex,
+        index: ComponentIndex,
+    ) {
+        if let Some(storage) = self.storages.get_mut(&type_id) {
+            if storage.move_from_packed(entity, src, archetype, index) {
+                self.counts.increment(entity);
+            }
+        }
+    }
+
+    /// Records that one of the entity's sparse components was removed directly from its storage.
+    pub(crate) fn removed(&mut self, entity: Entity) {
+        self.counts.decrement(entity);
+    }
+
+    /// Records that all of the entity's sparse components were removed directly from their
+    /// storages.
+    pub(crate) fn removed_all(&mut self, entity: Entity) {
+        self.counts.clear(entity);
+    }
+
+    /// Returns `true` if the given component type uses sparse storage.
+    pub fn contains_type(&self, type_id: ComponentTypeId) -> bool {
+        self.storages.contains_key(&type_id)
+    }
+
+    /// Returns the storage for the given component type.
+    pub fn get(&self, type_id: ComponentTypeId) -> Option<&dyn UnknownSparseStorage> {
+        self.storages.get(&type_id).map(|storage| storage.as_ref())
+    }
+
+    /// Returns the storage for the given component type.
+    pub fn get_downcast<T: Component>(&self) -> Option<&SparseStorage<T>> {
+        self.get(ComponentTypeId::of::<T>())
+            .and_then(|storage| storage.downcast_ref())
+    }
+
+    /// Returns the storage for the given component type.
+    pub fn get_downcast_mut<T: Component>(&mut self) -> Option<&mut SparseStorage<T>> {
+        self.storages
+            .get_mut(&ComponentTypeId::of::<T>())
+            .and_then(|storage| storage.downcast_mut())
+    }
+
+    pub(crate) fn get_mut(
+        &mut self,
+        type_id: ComponentTypeId,
+    ) -> Option<&mut dyn UnknownSparseStorage> {
+        self.storages
+            .get_mut(&type_id)
+            .map(|storage| storage.as_mut())
+    }
+
+    pub(crate) fn iter_mut(
+        &mut self,
+    ) -> impl Iterator<Item = (ComponentTypeId, &mut dyn UnknownSparseStorage)> {
+        self.storages
+            .iter_mut()
+            .map(|(type_id, storage)| (*type_id, storage.as_mut()))
+    }
+
+    /// Iterates through the sparse component types and their storages.
+    pub fn iter(&self) -> impl Iterator<Item = (ComponentTypeId, &dyn UnknownSparseStorage)> {
+        self.storages
+            .iter()
+            .map(|(type_id, storage)| (*type_id, storage.as_ref()))
+    }
+
+    /// Moves all of the entity's sparse components into another set of storages, calling
+    /// `moved` with each component type which the entity had.
+    pub(crate) fn move_entity(
+        &mut self,
+        entity: Entity,
+        dst: &mut SparseComponents,
+        mut moved: impl FnMut(ComponentTypeId),
+    ) {
+        if !self.has_components(entity) {
+            return;
+        }
+        for (type_id, storage) in self.storages.iter_mut() {
+            if !storage.contains(entity) {
+                continue;
+            }
+            let dst_storage = dst
+                .storages
+                .entry(*type_id)
+                .or_insert_with(|| storage.empty());
+            if dst_storage.contains(entity) {
+                dst.counts.decrement(entity);
+            }
+            storage.move_unknown(entity, dst_storage.as_mut());
+            dst.counts.increment(entity);
+            moved(*type_id);
+        }
+        self.counts.clear(entity);
+    }
+}
+
+impl std::fmt::Debug for SparseComponents {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.storages.keys()).finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::internals::entity::Allocate;
+
+    #[test]
+    fn insert_remove() {
+        let mut allocate = Allocate::new();
+        let entities: Vec<Entity> = (0..3).map(|_| allocate.next().unwrap()).collect();
+        let mut storage = SparseStorage::<usize>::default();
+
+        for (i, entity) in entities.iter().enumerate() {
+            assert_eq!(storage.insert(*entity, i), None);
+        }
+        assert_eq!(storage.insert(entities[1], 10), Some(1));
+        assert_eq!(storage.len(), 3);
+
+        assert_eq!(storage.remove(entities[0]), Some(0));
+        assert_eq!(storage.remove(entities[0]), None);
+        assert!(!storage.contains(entities[0]));
+        assert_eq!(storage.get(entities[1]), Some(&10));
+        assert_eq!(storage.get(entities[2]), Some(&2));
+
+        let mut items: Vec<_> = storage.iter().map(|(e, c)| (e, *c)).collect();
+        items.sort_by_key(|(_, c)| *c);
+        assert_eq!(items, vec![(entities[2], 2), (entities[1], 10)]);
+    }
+
+    #[test]
+    fn ticks() {
+        let mut allocate = Allocate::new();
+        let entity = allocate.next().unwrap();
+        let mut storage = SparseStorage::<usize>::default();
+
+        storage.insert(entity, 1);
+        let added = storage.ticks(entity).unwrap();
+        assert_eq!(added.added, added.changed);
+
+        *storage.get_mut(entity).unwrap() += 1;
+        let changed = storage.ticks(entity).unwrap();
+        assert_eq!(changed.added, added.added);
+        assert!(changed.changed > added.changed);
+    }
+}
@@ -10,7 +10,7 @@ use super::{
         view::{IntoView, View},
         Query,
     },
-    storage::{archetype::ArchetypeIndex, component::ComponentTypeId},
+    storage::{archetype::ArchetypeIndex, component::ComponentTypeId, removed::RemovalLog},
     world::{EntityAccessError, EntityStore, StorageAccessor, World, WorldId},
 };
 use bit_set::BitSet;
@@ -286,6 +286,10 @@ impl<'a> EntityStore for SubWorld<'a> {
         })
     }
 
+    fn removals(&self) -> &RemovalLog {
+        self.world.removals()
+    }
+
     fn id(&self) -> WorldId {
         self.world.id()
     }
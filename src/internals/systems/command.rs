@@ -192,6 +192,29 @@ where
     }
 }
 
+#[derive(Debug)]
+struct SetParentCommand {
+    child: Entity,
+    parent: Entity,
+}
+
+impl WorldWritable for SetParentCommand {
+    fn write(self: Arc<Self>, world: &mut World, _: &CommandBuffer) {
+        world
+            .set_parent(self.child, self.parent)
+            .expect("failed to set parent");
+    }
+}
+
+#[derive(Debug)]
+struct RemoveParentCommand(Entity);
+
+impl WorldWritable for RemoveParentCommand {
+    fn write(self: Arc<Self>, world: &mut World, _: &CommandBuffer) {
+        world.remove_parent(self.0);
+    }
+}
+
 #[allow(clippy::enum_variant_names)]
 enum Command {
     WriteWorld(Arc<dyn WorldWritable>),
@@ -236,7 +259,7 @@ impl CommandBuffer {
             world_id: world.id(),
             commands: Default::default(),
             pending_insertion: SmallVec::new(),
-            entity_allocator: Allocate::new(),
+            entity_allocator: Allocate::with_allocator(world.entity_allocator().clone()),
         }
     }
 
@@ -254,6 +277,12 @@ impl CommandBuffer {
             panic!("command buffers may only write into their parent world");
         }
 
+        trace_span!(
+            "command_buffer",
+            commands = self.len(),
+            entities = world.len(),
+        );
+
         while let Some(command) = self.commands.pop_back() {
             match command {
                 Command::WriteWorld(ptr) => ptr.write(world, self),
@@ -338,6 +367,16 @@ impl CommandBuffer {
         });
     }
 
+    /// Queues making `child` a child of `parent` in the command buffer.
+    pub fn set_parent(&mut self, child: Entity, parent: Entity) {
+        self.insert_writer(SetParentCommand { child, parent });
+    }
+
+    /// Queues detaching `child` from its parent in the command buffer.
+    pub fn remove_parent(&mut self, child: Entity) {
+        self.insert_writer(RemoveParentCommand(child));
+    }
+
     /// Returns the current number of commands already queued in this `CommandBuffer` instance.
     #[inline]
     pub fn len(&self) -> usize {
@@ -394,4 +433,41 @@ mod tests {
 
         assert_eq!(components_len, count);
     }
+
+    #[test]
+    fn flush_runs_hooks() {
+        use parking_lot::Mutex;
+        use std::sync::Arc;
+
+        let removed = Arc::new(Mutex::new(Vec::new()));
+        let mut world = World::default();
+        let log = removed.clone();
+        world.on_remove::<Pos, _>(move |entity, _| log.lock().push(entity));
+
+        let a = world.push((Pos(1., 2., 3.),));
+        let b = world.push((Pos(4., 5., 6.), Vel(0., 0., 0.)));
+
+        let mut command = CommandBuffer::new(&world);
+        command.remove(a);
+        command.remove_component::<Pos>(b);
+        command.flush(&mut world);
+
+        assert_eq!(*removed.lock(), vec![a, b]);
+    }
+
+    #[test]
+    fn push_sparse() {
+        struct Stunned;
+
+        let mut world = World::default();
+        world.register_sparse::<Stunned>();
+
+        let mut command = CommandBuffer::new(&world);
+        let entity = command.push((Pos(1., 2., 3.), Stunned));
+        command.flush(&mut world);
+
+        let entry = world.entry(entity).unwrap();
+        assert!(!entry.archetype().layout().has_component::<Stunned>());
+        assert!(entry.get_component::<Stunned>().is_ok());
+    }
 }
@@ -0,0 +1,183 @@
+//! Contains types related to deciding whether a step of a schedule should run.
+
+use super::resources::{Resource, Resources};
+use crate::internals::{
+    query::{
+        filter::EntityFilter,
+        view::{IntoView, ReadOnlyFetch, View},
+        Query,
+    },
+    world::World,
+};
+
+/// A condition which decides whether a system or thread local step of a
+/// [Schedule](struct.Schedule.html) runs during an execution of the schedule.
+///
+/// Criteria are evaluated on the main thread before the step they are attached to. Systems
+/// which do not meet their criteria are skipped entirely; they are not prepared and do not
+/// occupy the executor, although systems which depend upon them still wait for the systems
+/// that they depend on.
+///
+/// This trait is implemented for all `FnMut(&World, &Resources) -> bool` closures.
+///
+/// # Examples
+///
+/// ```
+/// # use legion::*;
+/// # use legion::systems::resource_eq;
+/// # let physics = SystemBuilder::new("physics").build(|_, _, _, _| {});
+/// #[derive(PartialEq)]
+/// enum GameState {
+///     Playing,
+///     Paused,
+/// }
+///
+/// let mut schedule = Schedule::builder()
+///     .add_system(physics)
+///     .run_if(resource_eq(GameState::Playing))
+///     .add_thread_local_fn(|_, _| println!("paused"))
+///     .run_if(|_: &World, resources: &Resources| {
+///         resources.get::<GameState>().map_or(false, |state| *state == GameState::Paused)
+///     })
+///     .build();
+/// ```
+pub trait RunCriteria: Send + Sync {
+    /// Returns `true` if the step should run in this execution of the schedule.
+    fn should_run(&mut self, world: &World, resources: &Resources) -> bool;
+}
+
+impl<F: FnMut(&World, &Resources) -> bool + Send + Sync> RunCriteria for F {
+    fn should_run(&mut self, world: &World, resources: &Resources) -> bool {
+        self(world, resources)
+    }
+}
+
+/// Combines two run criteria, both of which must be met. The second criteria is not evaluated
+/// if the first is not met.
+pub(crate) struct Both(pub Box<dyn RunCriteria>, pub Box<dyn RunCriteria>);
+
+impl RunCriteria for Both {
+    fn should_run(&mut self, world: &World, resources: &Resources) -> bool {
+        self.0.should_run(world, resources) && self.1.should_run(world, resources)
+    }
+}
+
+/// Run criteria which are met when a resource equals a given value.
+///
+/// See [resource_eq](fn.resource_eq.html).
+#[derive(Debug, Clone)]
+pub struct ResourceEq<T> {
+    value: T,
+}
+
+impl<T: Resource + PartialEq + Send + Sync> RunCriteria for ResourceEq<T> {
+    fn should_run(&mut self, _: &World, resources: &Resources) -> bool {
+        match resources.get::<T>() {
+            Some(resource) => *resource == self.value,
+            None => false,
+        }
+    }
+}
+
+/// Constructs run criteria which are met when resource `T` exists and equals the given value.
+pub fn resource_eq<T: Resource + PartialEq + Send + Sync>(value: T) -> ResourceEq<T> {
+    ResourceEq { value }
+}
+
+/// Run criteria which are met once every `n` evaluations.
+///
+/// See [every_n_ticks](fn.every_n_ticks.html).
+#[derive(Debug, Clone)]
+pub struct EveryNTicks {
+    n: u64,
+    tick: u64,
+}
+
+impl RunCriteria for EveryNTicks {
+    fn should_run(&mut self, _: &World, _: &Resources) -> bool {
+        let run = self.tick == 0;
+        self.tick = (self.tick + 1) % self.n;
+        run
+    }
+}
+
+/// Constructs run criteria which are met on the first evaluation, and then once every
+/// `n` evaluations after that.
+///
+/// # Panics
+///
+/// Panics if `n` is zero.
+pub fn every_n_ticks(n: u64) -> EveryNTicks {
+    assert!(n > 0, "run criteria cannot run every 0 ticks");
+    EveryNTicks { n, tick: 0 }
+}
+
+/// Run criteria which are met when a query matches any entity in the world.
+///
+/// See [any_match](fn.any_match.html).
+pub struct AnyMatch<V: IntoView, F: EntityFilter> {
+    query: Query<V, F>,
+}
+
+impl<V, F> RunCriteria for AnyMatch<V, F>
+where
+    V: IntoView,
+    F: EntityFilter,
+    Query<V, F>: Send + Sync,
+    for<'a> <V::View as View<'a>>::Fetch: ReadOnlyFetch,
+{
+    fn should_run(&mut self, world: &World, _: &Resources) -> bool {
+        self.query.iter(world).next().is_some()
+    }
+}
+
+/// Constructs run criteria which are met when the given read-only query matches at least
+/// one entity.
+pub fn any_match<V: IntoView, F: EntityFilter>(query: Query<V, F>) -> AnyMatch<V, F> {
+    AnyMatch { query }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::internals::query::{view::read::Read, IntoQuery};
+
+    #[test]
+    fn resource_eq_criteria() {
+        let world = World::default();
+        let mut resources = Resources::default();
+        let mut criteria = resource_eq(1usize);
+
+        assert!(!criteria.should_run(&world, &resources));
+        resources.insert(1usize);
+        assert!(criteria.should_run(&world, &resources));
+        resources.insert(2usize);
+        assert!(!criteria.should_run(&world, &resources));
+    }
+
+    #[test]
+    fn every_n_ticks_criteria() {
+        let world = World::default();
+        let resources = Resources::default();
+        let mut criteria = every_n_ticks(3);
+
+        let runs: Vec<_> = (0..7)
+            .map(|_| criteria.should_run(&world, &resources))
+            .collect();
+        assert_eq!(runs, vec![true, false, false, true, false, false, true]);
+    }
+
+    #[test]
+    fn any_match_criteria() {
+        let mut world = World::default();
+        let resources = Resources::default();
+        let mut criteria = any_match(Read::<usize>::query());
+
+        world.push((true,));
+        assert!(!criteria.should_run(&world, &resources));
+        let entity = world.push((1usize,));
+        assert!(criteria.should_run(&world, &resources));
+        world.remove(entity);
+        assert!(!criteria.should_run(&world, &resources));
+    }
+}
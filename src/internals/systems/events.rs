@@ -0,0 +1,351 @@
+//! Contains types related to sending typed events between systems.
+
+use super::{
+    resources::{Fetch, FetchMut, Resource, ResourceSet, UnsafeResources},
+    system::SystemBuilder,
+};
+use crate::internals::query::view::{read::Read, write::Write, ReadOnly};
+use std::marker::PhantomData;
+
+/// A double-buffered queue of events of type `T`, stored as a resource.
+///
+/// Events are sent with an [EventWriter](struct.EventWriter.html) and received with an
+/// [EventReader](struct.EventReader.html). Each reader tracks its own position in the queue,
+/// so every reader observes every event once.
+///
+/// Events remain readable until [update](#method.update) has been called twice after they were
+/// sent. Call `update` once per frame, for example by adding
+/// [update_system](#method.update_system) to the schedule, so that every system which runs each
+/// frame observes each event regardless of whether it runs before or after the sender.
+///
+/// # Examples
+///
+/// ```
+/// # use legion::*;
+/// # use legion::systems::Events;
+/// struct Collision(Entity, Entity);
+///
+/// let mut resources = Resources::default();
+/// resources.insert(Events::<Collision>::default());
+///
+/// let mut schedule = Schedule::builder()
+///     .add_system(
+///         SystemBuilder::new("physics")
+///             .write_events::<Collision>()
+///             .build(|_, _, collisions, _| {
+///                 // collisions.send(Collision(a, b));
+///             }),
+///     )
+///     .add_system(
+///         SystemBuilder::new("sound")
+///             .read_events::<Collision>()
+///             .build(|_, _, collisions, _| {
+///                 for Collision(a, b) in collisions.read() {
+///                     println!("{:?} collided with {:?}", a, b);
+///                 }
+///             }),
+///     )
+///     .add_system(Events::<Collision>::update_system())
+///     .build();
+/// ```
+#[derive(Debug)]
+pub struct Events<T> {
+    previous: Vec<T>,
+    current: Vec<T>,
+    event_count: usize,
+}
+
+impl<T> Default for Events<T> {
+    fn default() -> Self {
+        Self {
+            previous: Vec::new(),
+            current: Vec::new(),
+            event_count: 0,
+        }
+    }
+}
+
+impl<T> Events<T> {
+    /// Sends an event.
+    pub fn send(&mut self, event: T) {
+        self.current.push(event);
+        self.event_count += 1;
+    }
+
+    /// Sends all events in the given iterator.
+    pub fn send_batch<I: IntoIterator<Item = T>>(&mut self, events: I) {
+        for event in events {
+            self.send(event);
+        }
+    }
+
+    /// Swaps the event buffers, discarding all events which were sent before the previous
+    /// update.
+    pub fn update(&mut self) {
+        std::mem::swap(&mut self.previous, &mut self.current);
+        self.current.clear();
+    }
+
+    /// Removes and returns all events which are still readable. Readers will not observe
+    /// drained events which they had not yet read.
+    pub fn drain(&mut self) -> impl Iterator<Item = T> + '_ {
+        self.previous.drain(..).chain(self.current.drain(..))
+    }
+
+    /// Discards all events.
+    pub fn clear(&mut self) {
+        self.previous.clear();
+        self.current.clear();
+    }
+
+    /// Returns the number of events which are still readable.
+    pub fn len(&self) -> usize {
+        self.previous.len() + self.current.len()
+    }
+
+    /// Returns `true` if there are no readable events.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns a cursor which will only observe events sent after this call.
+    pub fn cursor(&self) -> EventCursor {
+        EventCursor {
+            next: self.event_count,
+        }
+    }
+
+    /// Returns all events which have been sent since the cursor last read from this queue
+    /// and which are still readable, and advances the cursor past them.
+    pub fn read<'a>(&'a self, cursor: &mut EventCursor) -> impl Iterator<Item = &'a T> + 'a {
+        let (previous, current) = self.unread(cursor);
+        cursor.next = self.event_count;
+        previous.iter().chain(current.iter())
+    }
+
+    fn unread(&self, cursor: &EventCursor) -> (&[T], &[T]) {
+        let current_start = self.event_count - self.current.len();
+        let previous_start = current_start - self.previous.len();
+        let skip = cursor.next.saturating_sub(previous_start);
+        if skip < self.previous.len() {
+            (&self.previous[skip..], &self.current)
+        } else {
+            let skip = (skip - self.previous.len()).min(self.current.len());
+            (&[], &self.current[skip..])
+        }
+    }
+}
+
+impl<T: Resource> Events<T> {
+    /// Constructs a system which calls [update](#method.update) on the `Events<T>` resource each
+    /// time it runs.
+    pub fn update_system() -> impl super::schedule::Runnable + Send + Sync
+    where
+        T: Send + Sync,
+    {
+        SystemBuilder::new(format!("update_events<{}>", std::any::type_name::<T>()))
+            .write_resource::<Events<T>>()
+            .build(|_, _, events, _| events.update())
+    }
+}
+
+/// The position of a reader within an [Events](struct.Events.html) queue.
+///
+/// A default cursor observes all events which are still readable.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct EventCursor {
+    next: usize,
+}
+
+/// Reads events of type `T` from the [Events](struct.Events.html) resource, advancing a
+/// cursor owned by the system.
+///
+/// Declare an event reader with [SystemBuilder::read_events](struct.SystemBuilder.html#method.read_events),
+/// or with an `&mut legion::systems::EventReader<T>` (or `#[event]`) parameter in a `#[system]`
+/// function. The schedule treats event readers as reads of the `Events<T>` resource.
+pub struct EventReader<'a, T: Resource> {
+    events: Fetch<'a, Events<T>>,
+    cursor: CursorRef<'a>,
+}
+
+enum CursorRef<'a> {
+    Borrowed(&'a mut EventCursor),
+    Owned(EventCursor),
+}
+
+impl<'a, T: Resource> EventReader<'a, T> {
+    fn cursor(&self) -> &EventCursor {
+        match &self.cursor {
+            CursorRef::Borrowed(cursor) => cursor,
+            CursorRef::Owned(cursor) => cursor,
+        }
+    }
+
+    fn cursor_mut(&mut self) -> &mut EventCursor {
+        match &mut self.cursor {
+            CursorRef::Borrowed(cursor) => cursor,
+            CursorRef::Owned(cursor) => cursor,
+        }
+    }
+
+    /// Returns all events which have not yet been read by this reader, and marks them as read.
+    pub fn read(&mut self) -> impl Iterator<Item = &T> {
+        let cursor = match &mut self.cursor {
+            CursorRef::Borrowed(cursor) => &mut **cursor,
+            CursorRef::Owned(cursor) => cursor,
+        };
+        self.events.read(cursor)
+    }
+
+    /// Returns the number of events which have not yet been read by this reader.
+    pub fn len(&self) -> usize {
+        let (previous, current) = self.events.unread(self.cursor());
+        previous.len() + current.len()
+    }
+
+    /// Returns `true` if all events have been read by this reader.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Marks all events as read, without reading them.
+    pub fn clear(&mut self) {
+        let end = self.events.cursor();
+        *self.cursor_mut() = end;
+    }
+}
+
+/// Sends events of type `T` into the [Events](struct.Events.html) resource.
+///
+/// Declare an event writer with [SystemBuilder::write_events](struct.SystemBuilder.html#method.write_events),
+/// or with an `&mut legion::systems::EventWriter<T>` (or `#[event]`) parameter in a `#[system]`
+/// function. The schedule treats event writers as writes of the `Events<T>` resource.
+pub struct EventWriter<'a, T: Resource> {
+    events: FetchMut<'a, Events<T>>,
+}
+
+impl<'a, T: Resource> EventWriter<'a, T> {
+    /// Sends an event.
+    pub fn send(&mut self, event: T) {
+        self.events.send(event);
+    }
+
+    /// Sends all events in the given iterator.
+    pub fn send_batch<I: IntoIterator<Item = T>>(&mut self, events: I) {
+        self.events.send_batch(events);
+    }
+}
+
+/// Declares that a system reads events of type `T`. Fetches an
+/// [EventReader](struct.EventReader.html).
+#[derive(Debug)]
+pub struct ReadEvents<T> {
+    cursor: EventCursor,
+    _phantom: PhantomData<*const T>,
+}
+
+unsafe impl<T> Send for ReadEvents<T> {}
+unsafe impl<T: Sync> Sync for ReadEvents<T> {}
+unsafe impl<T> ReadOnly for ReadEvents<T> {}
+
+impl<T> Default for ReadEvents<T> {
+    fn default() -> Self {
+        Self {
+            cursor: EventCursor::default(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<'a, T: Resource> ResourceSet<'a> for ReadEvents<T> {
+    type Result = EventReader<'a, T>;
+
+    unsafe fn fetch_unchecked(resources: &'a UnsafeResources) -> Self::Result {
+        EventReader {
+            events: Read::<Events<T>>::fetch_unchecked(resources),
+            cursor: CursorRef::Owned(EventCursor::default()),
+        }
+    }
+
+    unsafe fn fetch_unchecked_stateful(
+        &'a mut self,
+        resources: &'a UnsafeResources,
+    ) -> Self::Result {
+        EventReader {
+            events: Read::<Events<T>>::fetch_unchecked(resources),
+            cursor: CursorRef::Borrowed(&mut self.cursor),
+        }
+    }
+}
+
+/// Declares that a system writes events of type `T`. Fetches an
+/// [EventWriter](struct.EventWriter.html).
+#[derive(Debug)]
+pub struct WriteEvents<T>(PhantomData<*const T>);
+
+unsafe impl<T: Send> Send for WriteEvents<T> {}
+unsafe impl<T> Sync for WriteEvents<T> {}
+
+impl<T> Default for WriteEvents<T> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<'a, T: Resource> ResourceSet<'a> for WriteEvents<T> {
+    type Result = EventWriter<'a, T>;
+
+    unsafe fn fetch_unchecked(resources: &'a UnsafeResources) -> Self::Result {
+        EventWriter {
+            events: Write::<Events<T>>::fetch_unchecked(resources),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::internals::systems::{resources::ResourceTypeId, schedule::Runnable};
+
+    #[test]
+    fn system_access() {
+        let reader = SystemBuilder::new("reader")
+            .read_events::<usize>()
+            .build(|_, _, _, _| {});
+        let writer = SystemBuilder::new("writer")
+            .write_events::<usize>()
+            .build(|_, _, _, _| {});
+
+        let events = ResourceTypeId::of::<Events<usize>>();
+        assert_eq!(reader.reads().0, &[events]);
+        assert!(reader.writes().0.is_empty());
+        assert_eq!(writer.writes().0, &[events]);
+    }
+
+    #[test]
+    fn double_buffered() {
+        let mut events = Events::default();
+        let mut early = EventCursor::default();
+        let mut other = EventCursor::default();
+
+        events.send(1);
+        events.send(2);
+        assert_eq!(events.read(&mut early).collect::<Vec<_>>(), vec![&1, &2]);
+        assert_eq!(events.read(&mut early).count(), 0);
+
+        events.update();
+        events.send(3);
+        assert_eq!(events.read(&mut early).collect::<Vec<_>>(), vec![&3]);
+        assert_eq!(
+            events.read(&mut other).collect::<Vec<_>>(),
+            vec![&1, &2, &3]
+        );
+
+        events.update();
+        events.update();
+        events.send(4);
+        let mut new = EventCursor::default();
+        assert_eq!(events.read(&mut new).collect::<Vec<_>>(), vec![&4]);
+        assert_eq!(events.read(&mut early).collect::<Vec<_>>(), vec![&4]);
+    }
+}
@@ -0,0 +1,482 @@
+//! Contains types related to inspecting the dependencies between the systems of a schedule.
+
+use super::schedule::{Executor, Runnable, Step};
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter, Write},
+};
+
+/// The dependency graph of the systems in a [Schedule](struct.Schedule.html) or
+/// [Executor](struct.Executor.html).
+///
+/// Each step of a schedule is placed into its own stage. Stages run one after another, while
+/// the systems within a stage may run in parallel, except where an edge between them requires
+/// one to wait for another.
+///
+/// The graph can be rendered with Graphviz via [to_dot](#method.to_dot), and implements
+/// `Serialize` and `Deserialize` when the `serialize` feature is enabled.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScheduleGraph {
+    /// The systems and steps of the schedule, in execution order.
+    pub nodes: Vec<GraphNode>,
+    /// The dependencies between nodes, sorted by their source and then their target.
+    pub edges: Vec<GraphEdge>,
+}
+
+/// A system or step within a [ScheduleGraph](struct.ScheduleGraph.html).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct GraphNode {
+    /// The name of the system, or a description of the step.
+    pub name: String,
+    /// The kind of step.
+    pub kind: NodeKind,
+    /// The index of the stage which contains the node.
+    pub stage: usize,
+    /// `true` if the node only runs when its run criteria are met.
+    pub conditional: bool,
+    /// `true` if the node belongs to a fixed timestep schedule.
+    pub fixed_timestep: bool,
+}
+
+/// Describes the kind of a [GraphNode](struct.GraphNode.html).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum NodeKind {
+    /// A system which may run in parallel with other systems in its stage.
+    System,
+    /// A system which runs on the main thread.
+    ThreadLocalSystem,
+    /// A function which runs on the main thread.
+    ThreadLocalFn,
+    /// A flush of all outstanding command buffers.
+    Flush,
+}
+
+/// A dependency between two nodes within a [ScheduleGraph](struct.ScheduleGraph.html).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct GraphEdge {
+    /// The index of the node which runs first.
+    pub from: usize,
+    /// The index of the node which waits for `from` to complete.
+    pub to: usize,
+    /// The reasons for the dependency.
+    pub reasons: Vec<EdgeReason>,
+}
+
+/// The reason for a [GraphEdge](struct.GraphEdge.html).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum EdgeReason {
+    /// Both systems access the resource with the given type name, and at least one writes to it.
+    Resource(String),
+    /// Both systems access the component with the given type name, and at least one writes
+    /// to it.
+    ///
+    /// Component dependencies are only enforced when the queries of both systems match a
+    /// common archetype, as determined each time the schedule is executed.
+    Component(String),
+    /// The systems were explicitly ordered with `before` or `after`.
+    Ordering,
+}
+
+impl Display for EdgeReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EdgeReason::Resource(name) => write!(f, "resource {}", name),
+            EdgeReason::Component(name) => write!(f, "component {}", name),
+            EdgeReason::Ordering => write!(f, "ordering"),
+        }
+    }
+}
+
+impl ScheduleGraph {
+    /// Renders the graph in the Graphviz DOT language. Each stage is drawn as a cluster.
+    /// Conditional nodes, and edges which are only enforced when component accesses overlap,
+    /// are dashed.
+    pub fn to_dot(&self) -> String {
+        fn escape(s: &str) -> String {
+            s.replace('\\', "\\\\").replace('"', "\\\"")
+        }
+
+        let mut dot = String::new();
+        writeln!(dot, "digraph schedule {{").unwrap();
+        writeln!(dot, "    node [shape=box];").unwrap();
+
+        let mut stages: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for (i, node) in self.nodes.iter().enumerate() {
+            stages.entry(node.stage).or_default().push(i);
+        }
+        for (stage, nodes) in stages {
+            writeln!(dot, "    subgraph cluster_{} {{", stage).unwrap();
+            writeln!(dot, "        label=\"stage {}\";", stage).unwrap();
+            for i in nodes {
+                let node = &self.nodes[i];
+                let style = if node.conditional {
+                    ", style=dashed"
+                } else {
+                    ""
+                };
+                let shape = match node.kind {
+                    NodeKind::System => "",
+                    NodeKind::ThreadLocalSystem | NodeKind::ThreadLocalFn => ", shape=ellipse",
+                    NodeKind::Flush => ", shape=diamond",
+                };
+                writeln!(
+                    dot,
+                    "        n{} [label=\"{}\"{}{}];",
+                    i,
+                    escape(&node.name),
+                    shape,
+                    style
+                )
+                .unwrap();
+            }
+            writeln!(dot, "    }}").unwrap();
+        }
+
+        for edge in &self.edges {
+            let label = edge
+                .reasons
+                .iter()
+                .map(|reason| escape(&reason.to_string()))
+                .collect::<Vec<_>>()
+                .join("\\n");
+            let dynamic = edge
+                .reasons
+                .iter()
+                .all(|reason| matches!(reason, EdgeReason::Component(_)));
+            let style = if dynamic { ", style=dashed" } else { "" };
+            writeln!(
+                dot,
+                "    n{} -> n{} [label=\"{}\"{}];",
+                edge.from, edge.to, label, style
+            )
+            .unwrap();
+        }
+
+        writeln!(dot, "}}").unwrap();
+        dot
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct GraphBuilder {
+    graph: ScheduleGraph,
+    stage: usize,
+}
+
+impl GraphBuilder {
+    pub fn build(self) -> ScheduleGraph {
+        self.graph
+    }
+
+    fn add_node(
+        &mut self,
+        name: String,
+        kind: NodeKind,
+        conditional: bool,
+        fixed_timestep: bool,
+    ) -> usize {
+        self.graph.nodes.push(GraphNode {
+            name,
+            kind,
+            stage: self.stage,
+            conditional,
+            fixed_timestep,
+        });
+        self.graph.nodes.len() - 1
+    }
+
+    fn runnable_name<S: Runnable + ?Sized>(system: &S) -> String {
+        system
+            .name()
+            .map(|name| name.to_string())
+            .unwrap_or_else(|| "<unnamed system>".to_string())
+    }
+
+    /// Adds the steps of a schedule, each within its own stage.
+    pub fn add_steps(&mut self, steps: &[Step], conditional: bool, fixed_timestep: bool) {
+        for step in steps {
+            self.add_step(step, conditional, fixed_timestep);
+        }
+    }
+
+    fn add_step(&mut self, step: &Step, conditional: bool, fixed_timestep: bool) {
+        match step {
+            Step::Systems(executor) => {
+                self.add_executor(executor, conditional, fixed_timestep);
+                self.stage += 1;
+            }
+            Step::FlushCmdBuffers => {
+                let name = "flush".to_string();
+                self.add_node(name, NodeKind::Flush, conditional, fixed_timestep);
+                self.stage += 1;
+            }
+            Step::ThreadLocalFn(_) => {
+                let name = "<thread local fn>".to_string();
+                self.add_node(name, NodeKind::ThreadLocalFn, conditional, fixed_timestep);
+                self.stage += 1;
+            }
+            Step::ThreadLocalSystem(system) => {
+                let name = Self::runnable_name(system.as_ref());
+                let kind = NodeKind::ThreadLocalSystem;
+                self.add_node(name, kind, conditional, fixed_timestep);
+                self.stage += 1;
+            }
+            Step::Conditional(_, step) => self.add_step(step, true, fixed_timestep),
+            Step::FixedTimestep(schedule) => {
+                self.add_steps(schedule.steps(), conditional, true);
+            }
+        }
+    }
+
+    /// Adds the systems of an executor within the current stage, along with the dependencies
+    /// which the executor enforces between them.
+    pub fn add_executor(&mut self, executor: &Executor, conditional: bool, fixed_timestep: bool) {
+        let base = self.graph.nodes.len();
+        let systems = executor.systems().collect::<Vec<_>>();
+        for (i, system) in systems.iter().enumerate() {
+            let name = Self::runnable_name(*system);
+            let conditional = conditional || executor.has_run_criteria(i);
+            self.add_node(name, NodeKind::System, conditional, fixed_timestep);
+        }
+
+        let (static_dependants, dynamic_dependants) = executor.dependants();
+        let mut edges = BTreeMap::new();
+        for dependants in &[static_dependants, dynamic_dependants] {
+            for (from, dependants) in dependants.iter().enumerate() {
+                for to in dependants {
+                    edges.entry((from, *to)).or_insert_with(|| {
+                        let explicit = executor.explicit_dependencies()[*to].contains(&from);
+                        Self::reasons(systems[from], systems[*to], explicit)
+                    });
+                }
+            }
+        }
+
+        for ((from, to), reasons) in edges {
+            self.graph.edges.push(GraphEdge {
+                from: base + from,
+                to: base + to,
+                reasons,
+            });
+        }
+    }
+
+    /// Describes why system `to` waits for system `from`.
+    fn reasons<S: Runnable + ?Sized>(from: &S, to: &S, explicit: bool) -> Vec<EdgeReason> {
+        let mut reasons = Vec::new();
+        if explicit {
+            reasons.push(EdgeReason::Ordering);
+        }
+
+        let ((from_read_res, from_read_comp), (from_write_res, from_write_comp)) =
+            (from.reads(), from.writes());
+        let ((to_read_res, to_read_comp), (to_write_res, to_write_comp)) =
+            (to.reads(), to.writes());
+        for res in Self::conflicts(from_read_res, from_write_res, to_read_res, to_write_res) {
+            reasons.push(EdgeReason::Resource(res.name().to_string()));
+        }
+        for comp in Self::conflicts(from_read_comp, from_write_comp, to_read_comp, to_write_comp) {
+            reasons.push(EdgeReason::Component(comp.name().to_string()));
+        }
+
+        reasons
+    }
+
+    /// Returns the types which are accessed by both systems and written by at least one.
+    fn conflicts<'a, T: PartialEq>(
+        a_reads: &'a [T],
+        a_writes: &'a [T],
+        b_reads: &'a [T],
+        b_writes: &'a [T],
+    ) -> Vec<&'a T> {
+        let mut conflicts = Vec::new();
+        for ty in a_writes.iter().chain(a_reads).chain(b_writes) {
+            let conflict = (a_writes.contains(ty)
+                && (b_reads.contains(ty) || b_writes.contains(ty)))
+                || (b_writes.contains(ty) && a_reads.contains(ty));
+            if conflict && !conflicts.contains(&ty) {
+                conflicts.push(ty);
+            }
+        }
+        conflicts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::internals::{
+        query::{view::read::Read, view::write::Write, IntoQuery},
+        systems::{schedule::Schedule, system::SystemBuilder},
+    };
+
+    #[test]
+    fn schedule_graph() {
+        let schedule = Schedule::builder()
+            .add_system(
+                SystemBuilder::new("write_res")
+                    .write_resource::<usize>()
+                    .build(|_, _, _, _| {}),
+            )
+            .add_system(
+                SystemBuilder::new("read_res")
+                    .read_resource::<usize>()
+                    .build(|_, _, _, _| {}),
+            )
+            .add_system(
+                SystemBuilder::new("write_comp")
+                    .with_query(Write::<f32>::query())
+                    .build(|_, _, _, _| {}),
+            )
+            .add_system(
+                SystemBuilder::new("read_comp")
+                    .with_query(Read::<f32>::query())
+                    .build(|_, _, _, _| {}),
+            )
+            .run_if(|_: &_, _: &_| true)
+            .flush()
+            .add_thread_local_fn(|_, _| {})
+            .build();
+
+        let graph = schedule.graph();
+        let names: Vec<_> = graph.nodes.iter().map(|node| node.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "write_res",
+                "read_res",
+                "write_comp",
+                "read_comp",
+                "flush",
+                "<thread local fn>",
+                "flush"
+            ]
+        );
+        let stages: Vec<_> = graph.nodes.iter().map(|node| node.stage).collect();
+        assert_eq!(stages, vec![0, 0, 0, 0, 1, 2, 3]);
+        assert!(graph.nodes[3].conditional);
+        assert!(!graph.nodes[2].conditional);
+        assert_eq!(graph.nodes[4].kind, NodeKind::Flush);
+
+        assert_eq!(
+            graph.edges,
+            vec![
+                GraphEdge {
+                    from: 0,
+                    to: 1,
+                    reasons: vec![EdgeReason::Resource("usize".to_string())],
+                },
+                GraphEdge {
+                    from: 2,
+                    to: 3,
+                    reasons: vec![EdgeReason::Component("f32".to_string())],
+                },
+            ]
+        );
+
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph schedule {"));
+        assert!(dot.contains("n0 -> n1"));
+        assert!(dot.contains("n2 -> n3"));
+        assert!(dot.contains("subgraph cluster_2"));
+    }
+
+    #[test]
+    fn explicit_ordering_edges() {
+        let schedule = Schedule::builder()
+            .add_system(SystemBuilder::new("a").build(|_, _, _, _| {}))
+            .add_system(SystemBuilder::new("b").build(|_, _, _, _| {}))
+            .before("a")
+            .build();
+
+        let graph = schedule.graph();
+        assert_eq!(graph.nodes[0].name, "b");
+        assert_eq!(
+            graph.edges,
+            vec![GraphEdge {
+                from: 0,
+                to: 1,
+                reasons: vec![EdgeReason::Ordering],
+            }]
+        );
+    }
+
+    #[test]
+    fn edges_match_executor() {
+        let executor = Executor::new(vec![
+            Box::new(
+                SystemBuilder::new("a")
+                    .write_resource::<usize>()
+                    .with_query(Write::<f32>::query())
+                    .build(|_, _, _, _| {}),
+            ),
+            Box::new(
+                SystemBuilder::new("b")
+                    .read_resource::<usize>()
+                    .build(|_, _, _, _| {}),
+            ),
+            Box::new(
+                SystemBuilder::new("c")
+                    .read_resource::<usize>()
+                    .with_query(Read::<f32>::query())
+                    .build(|_, _, _, _| {}),
+            ),
+            Box::new(
+                SystemBuilder::new("d")
+                    .write_resource::<usize>()
+                    .build(|_, _, _, _| {}),
+            ),
+        ]);
+
+        let (static_dependants, dynamic_dependants) = executor.dependants();
+        let mut expected = Vec::new();
+        for dependants in &[static_dependants, dynamic_dependants] {
+            for (from, dependants) in dependants.iter().enumerate() {
+                expected.extend(dependants.iter().map(|to| (from, *to)));
+            }
+        }
+        expected.sort_unstable();
+
+        let graph = executor.graph();
+        let edges = graph
+            .edges
+            .iter()
+            .map(|edge| (edge.from, edge.to))
+            .collect::<Vec<_>>();
+        assert_eq!(edges, expected);
+        assert_eq!(
+            graph.edges[1].reasons,
+            vec![
+                EdgeReason::Resource("usize".to_string()),
+                EdgeReason::Component("f32".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "serialize")]
+    fn serialize_graph() {
+        let schedule = Schedule::builder()
+            .add_system(
+                SystemBuilder::new("a")
+                    .write_resource::<usize>()
+                    .build(|_, _, _, _| {}),
+            )
+            .add_system(
+                SystemBuilder::new("b")
+                    .write_resource::<usize>()
+                    .build(|_, _, _, _| {}),
+            )
+            .build();
+
+        let graph = schedule.graph();
+        let json = serde_json::to_string(&graph).unwrap();
+        let deserialized: ScheduleGraph = serde_json::from_str(&json).unwrap();
+        assert_eq!(graph, deserialized);
+    }
+}
@@ -1,4 +1,10 @@
 pub mod command;
+pub mod criteria;
+pub mod events;
+pub mod graph;
+pub mod observer;
+pub mod ordering;
 pub mod resources;
 pub mod schedule;
 pub mod system;
+pub mod timestep;
@@ -0,0 +1,283 @@
+//! Contains types related to instrumenting the execution of schedules.
+
+use super::{resources::Resources, system::SystemId};
+use crate::internals::world::World;
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    thread::{self, ThreadId},
+    time::{Duration, Instant},
+};
+
+/// Receives notifications as an [Executor](struct.Executor.html) or
+/// [Schedule](struct.Schedule.html) runs its systems and steps.
+///
+/// Observers are attached with [Schedule::set_observer](struct.Schedule.html#method.set_observer)
+/// or [Executor::set_observer](struct.Executor.html#method.set_observer). System callbacks may
+/// be invoked concurrently from the executor's worker threads; the `begin` and `end` callbacks
+/// for a single system run are always invoked on the same thread.
+///
+/// Thread local functions and unnamed systems are identified by placeholder IDs, such as
+/// `<thread local fn>`.
+///
+/// All methods have empty default implementations.
+pub trait ExecutorObserver: Send + Sync {
+    /// Called before a system runs.
+    fn begin_system(&self, _system: &SystemId) {}
+
+    /// Called after a system has run.
+    fn end_system(&self, _system: &SystemId) {}
+
+    /// Called before the command buffer of a system is flushed into the world.
+    fn begin_flush(&self, _system: &SystemId) {}
+
+    /// Called after the command buffer of a system has been flushed into the world.
+    fn end_flush(&self, _system: &SystemId) {}
+
+    /// Called before a thread local system or function runs.
+    fn begin_thread_local(&self, _system: &SystemId) {}
+
+    /// Called after a thread local system or function has run.
+    fn end_thread_local(&self, _system: &SystemId) {}
+
+    /// Called once each execution of the schedule or executor has completed.
+    fn end_execution(&self, _world: &mut World, _resources: &mut Resources) {}
+}
+
+/// The placeholder ID given to unnamed systems.
+pub(crate) fn unnamed_system() -> SystemId {
+    SystemId::from("<unnamed system>")
+}
+
+/// The placeholder ID given to thread local functions.
+pub(crate) fn thread_local_fn() -> SystemId {
+    SystemId::from("<thread local fn>")
+}
+
+/// The timings recorded for a single system.
+///
+/// See [SystemTimings](struct.SystemTimings.html).
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SystemTiming {
+    min: Duration,
+    max: Duration,
+    total: Duration,
+    samples: u32,
+}
+
+impl SystemTiming {
+    fn new(duration: Duration) -> Self {
+        Self {
+            min: duration,
+            max: duration,
+            total: duration,
+            samples: 1,
+        }
+    }
+
+    fn record(&mut self, duration: Duration) {
+        self.min = self.min.min(duration);
+        self.max = self.max.max(duration);
+        self.total += duration;
+        self.samples += 1;
+    }
+
+    /// Returns the shortest recorded run of the system.
+    pub fn min(&self) -> Duration {
+        self.min
+    }
+
+    /// Returns the longest recorded run of the system.
+    pub fn max(&self) -> Duration {
+        self.max
+    }
+
+    /// Returns the mean duration of the recorded runs of the system.
+    pub fn mean(&self) -> Duration {
+        self.total / self.samples
+    }
+
+    /// Returns the total duration of all recorded runs of the system.
+    pub fn total(&self) -> Duration {
+        self.total
+    }
+
+    /// Returns the number of recorded runs of the system.
+    pub fn samples(&self) -> u32 {
+        self.samples
+    }
+}
+
+/// A resource containing the timings of each system, as gathered by a
+/// [TimingCollector](struct.TimingCollector.html) across executions of a schedule.
+#[derive(Debug, Clone, Default)]
+pub struct SystemTimings {
+    timings: HashMap<SystemId, SystemTiming>,
+}
+
+impl SystemTimings {
+    /// Returns the timings recorded for the given system.
+    pub fn get<S: Into<SystemId>>(&self, system: S) -> Option<&SystemTiming> {
+        self.timings.get(&system.into())
+    }
+
+    /// Returns an iterator over the timings recorded for all systems.
+    pub fn iter(&self) -> impl Iterator<Item = (&SystemId, &SystemTiming)> {
+        self.timings.iter()
+    }
+
+    /// Discards all recorded timings.
+    pub fn clear(&mut self) {
+        self.timings.clear();
+    }
+
+    /// Records a single run of a system.
+    pub fn record(&mut self, system: SystemId, duration: Duration) {
+        match self.timings.get_mut(&system) {
+            Some(timing) => timing.record(duration),
+            None => {
+                self.timings.insert(system, SystemTiming::new(duration));
+            }
+        }
+    }
+}
+
+/// An [observer](trait.ExecutorObserver.html) which measures how long each system and thread
+/// local step takes to run.
+///
+/// At the end of each execution, the measurements are added to the
+/// [SystemTimings](struct.SystemTimings.html) resource, which is inserted if it does not
+/// already exist.
+///
+/// # Examples
+///
+/// ```
+/// # use legion::*;
+/// # use legion::systems::{SystemTimings, TimingCollector};
+/// # use std::sync::Arc;
+/// let mut world = World::default();
+/// let mut resources = Resources::default();
+///
+/// let mut schedule = Schedule::builder()
+///     .add_system(SystemBuilder::new("physics").build(|_, _, _, _| {}))
+///     .build();
+/// schedule.set_observer(Arc::new(TimingCollector::default()));
+///
+/// schedule.execute(&mut world, &mut resources);
+///
+/// let timings = resources.get::<SystemTimings>().unwrap();
+/// let physics = timings.get("physics").unwrap();
+/// println!("physics took {:?} on average", physics.mean());
+/// ```
+#[derive(Debug, Default)]
+pub struct TimingCollector {
+    // Placeholder IDs are shared by many systems, and a worker thread may begin another system
+    // while waiting on work inside a running one, so runs in flight are tracked as a stack per
+    // system and thread. Such runs always end in the reverse order that they began.
+    running: Mutex<HashMap<(SystemId, ThreadId), Vec<Instant>>>,
+    completed: Mutex<Vec<(SystemId, Duration)>>,
+}
+
+impl TimingCollector {
+    fn begin(&self, system: &SystemId) {
+        self.running
+            .lock()
+            .entry((system.clone(), thread::current().id()))
+            .or_default()
+            .push(Instant::now());
+    }
+
+    fn end(&self, system: &SystemId) {
+        let start = {
+            let mut running = self.running.lock();
+            let key = (system.clone(), thread::current().id());
+            match running.get_mut(&key) {
+                Some(stack) => {
+                    let start = stack.pop();
+                    if stack.is_empty() {
+                        running.remove(&key);
+                    }
+                    start
+                }
+                None => None,
+            }
+        };
+        if let Some(start) = start {
+            self.completed
+                .lock()
+                .push((system.clone(), start.elapsed()));
+        }
+    }
+}
+
+impl ExecutorObserver for TimingCollector {
+    fn begin_system(&self, system: &SystemId) {
+        self.begin(system);
+    }
+
+    fn end_system(&self, system: &SystemId) {
+        self.end(system);
+    }
+
+    fn begin_thread_local(&self, system: &SystemId) {
+        self.begin(system);
+    }
+
+    fn end_thread_local(&self, system: &SystemId) {
+        self.end(system);
+    }
+
+    fn end_execution(&self, _: &mut World, resources: &mut Resources) {
+        let mut timings = resources.get_mut_or_default::<SystemTimings>();
+        for (system, duration) in self.completed.lock().drain(..) {
+            timings.record(system, duration);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_timings() {
+        let mut timings = SystemTimings::default();
+        timings.record("a".into(), Duration::from_millis(2));
+        timings.record("a".into(), Duration::from_millis(6));
+        timings.record("a".into(), Duration::from_millis(4));
+
+        let timing = timings.get("a").unwrap();
+        assert_eq!(timing.min(), Duration::from_millis(2));
+        assert_eq!(timing.max(), Duration::from_millis(6));
+        assert_eq!(timing.mean(), Duration::from_millis(4));
+        assert_eq!(timing.samples(), 3);
+        assert!(timings.get("b").is_none());
+    }
+
+    #[test]
+    fn shared_placeholder_ids() {
+        let collector = TimingCollector::default();
+        let id = thread_local_fn();
+
+        collector.begin_thread_local(&id);
+        collector.begin_thread_local(&id);
+        collector.end_thread_local(&id);
+        collector.end_thread_local(&id);
+
+        collector.begin_system(&id);
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                collector.begin_system(&id);
+                collector.end_system(&id);
+            });
+        });
+        collector.end_system(&id);
+
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        collector.end_execution(&mut world, &mut resources);
+        let timings = resources.get::<SystemTimings>().unwrap();
+        assert_eq!(timings.get(id).unwrap().samples(), 4);
+        assert!(collector.running.lock().is_empty());
+    }
+}
@@ -0,0 +1,299 @@
+//! Contains types related to explicit ordering constraints between systems.
+
+use super::{
+    schedule::{ParallelRunnable, Runnable},
+    system::SystemId,
+};
+use thiserror::Error;
+
+/// An error describing why a [schedule](struct.Schedule.html) could not be built.
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ScheduleError {
+    /// The ordering constraints between systems form a cycle.
+    #[error("the ordering constraints between systems form a cycle: {}", format_cycle(.0))]
+    Cycle(Vec<SystemId>),
+
+    /// An ordering constraint cannot be met because the two systems are separated by a flush
+    /// or a thread local step, which fix the order in which they run.
+    #[error("{before} must run before {after}, but {after} is added to an earlier stage of the schedule")]
+    Unsatisfiable {
+        /// The system which is required to run first.
+        before: SystemId,
+        /// The system which is required to run second.
+        after: SystemId,
+    },
+
+    /// An ordering constraint refers to a label which no system in the schedule is named or
+    /// labelled with.
+    #[error("{system} is ordered relative to {label}, but no system is named or labelled {label}")]
+    UnknownLabel {
+        /// The system which declared the ordering constraint.
+        system: SystemId,
+        /// The label which does not match any system.
+        label: SystemId,
+    },
+}
+
+fn format_cycle(systems: &[SystemId]) -> String {
+    systems
+        .iter()
+        .chain(systems.first())
+        .map(|system| system.to_string())
+        .collect::<Vec<_>>()
+        .join(" -> ")
+}
+
+/// The labels and ordering constraints declared for a system added to a schedule.
+#[derive(Debug, Clone)]
+pub(crate) struct SystemOrdering {
+    pub name: SystemId,
+    pub labels: Vec<SystemId>,
+    pub before: Vec<SystemId>,
+    pub after: Vec<SystemId>,
+}
+
+impl SystemOrdering {
+    pub fn new<S: Runnable + ?Sized>(system: &S, index: usize) -> Self {
+        Self {
+            name: system
+                .name()
+                .cloned()
+                .unwrap_or_else(|| SystemId::from(format!("<unnamed system {}>", index))),
+            labels: Vec::new(),
+            before: Vec::new(),
+            after: Vec::new(),
+        }
+    }
+
+    /// Returns `true` if the system is named or labelled with `label`.
+    pub fn matches(&self, label: &SystemId) -> bool {
+        self.name == *label || self.labels.contains(label)
+    }
+}
+
+/// The result of sorting a batch of systems.
+pub(crate) struct SortedSystems {
+    /// The new order of the systems, as indices into the original order.
+    pub order: Vec<usize>,
+    /// For each system in the new order, the systems (in the new order) which it has been
+    /// explicitly ordered after.
+    pub dependencies: Vec<Vec<usize>>,
+}
+
+/// Sorts a batch of systems such that all ordering constraints between them are met.
+///
+/// Systems which access the same resources or components retain their insertion order unless
+/// an ordering constraint requires otherwise. All other systems retain their insertion order
+/// where possible.
+pub(crate) fn sort(
+    systems: &[Box<dyn ParallelRunnable>],
+    ordering: &[SystemOrdering],
+) -> Result<SortedSystems, ScheduleError> {
+    let count = systems.len();
+
+    // explicit edges from each system to those which must run after it
+    let mut explicit: Vec<Vec<usize>> = vec![Vec::new(); count];
+    for (i, system) in ordering.iter().enumerate() {
+        for (j, other) in ordering.iter().enumerate() {
+            if i == j {
+                continue;
+            }
+            let before = system.before.iter().any(|label| other.matches(label));
+            let after = other.after.iter().any(|label| system.matches(label));
+            if (before || after) && !explicit[i].contains(&j) {
+                explicit[i].push(j);
+            }
+        }
+    }
+
+    if let Some(cycle) = find_cycle(&explicit) {
+        return Err(ScheduleError::Cycle(
+            cycle
+                .into_iter()
+                .map(|i| ordering[i].name.clone())
+                .collect(),
+        ));
+    }
+
+    // order conflicting systems by insertion, unless this would contradict the explicit order
+    let mut edges = explicit.clone();
+    for j in 0..count {
+        for i in 0..j {
+            if conflicts(systems[i].as_ref(), systems[j].as_ref())
+                && !reaches(&edges, j, i)
+                && !edges[i].contains(&j)
+            {
+                edges[i].push(j);
+            }
+        }
+    }
+
+    // stable topological sort, preferring the earliest inserted system
+    let mut incoming = vec![0; count];
+    for targets in &edges {
+        for target in targets {
+            incoming[*target] += 1;
+        }
+    }
+    let mut order = Vec::with_capacity(count);
+    let mut done = vec![false; count];
+    while order.len() < count {
+        let next = (0..count)
+            .find(|i| !done[*i] && incoming[*i] == 0)
+            .expect("systems ordering contains a cycle");
+        done[next] = true;
+        order.push(next);
+        for target in &edges[next] {
+            incoming[*target] -= 1;
+        }
+    }
+
+    let mut position = vec![0; count];
+    for (new, old) in order.iter().enumerate() {
+        position[*old] = new;
+    }
+    let mut dependencies = vec![Vec::new(); count];
+    for (i, targets) in explicit.iter().enumerate() {
+        for target in targets {
+            dependencies[position[*target]].push(position[i]);
+        }
+    }
+
+    Ok(SortedSystems {
+        order,
+        dependencies,
+    })
+}
+
+/// Checks that every label referred to by an ordering constraint matches a system in one of the
+/// stages of a schedule.
+pub(crate) fn validate_labels(stages: &[Vec<SystemOrdering>]) -> Result<(), ScheduleError> {
+    let systems = || stages.iter().flatten();
+    for system in systems() {
+        for label in system.before.iter().chain(&system.after) {
+            if !systems().any(|other| other.matches(label)) {
+                return Err(ScheduleError::UnknownLabel {
+                    system: system.name.clone(),
+                    label: label.clone(),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks that no system in an earlier stage of a schedule is required to run after a system
+/// in a later stage.
+pub(crate) fn validate_stages(
+    earlier: &[SystemOrdering],
+    later: &[SystemOrdering],
+) -> Result<(), ScheduleError> {
+    for first in earlier {
+        for second in later {
+            let violated = first.after.iter().any(|label| second.matches(label))
+                || second.before.iter().any(|label| first.matches(label));
+            if violated {
+                return Err(ScheduleError::Unsatisfiable {
+                    before: second.name.clone(),
+                    after: first.name.clone(),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+fn conflicts<S: Runnable + ?Sized>(a: &S, b: &S) -> bool {
+    fn overlaps<T: PartialEq>(a: &[T], b: &[T]) -> bool {
+        a.iter().any(|x| b.contains(x))
+    }
+
+    let (a_read_res, a_read_comp) = a.reads();
+    let (a_write_res, a_write_comp) = a.writes();
+    let (b_read_res, b_read_comp) = b.reads();
+    let (b_write_res, b_write_comp) = b.writes();
+
+    overlaps(a_write_res, b_write_res)
+        || overlaps(a_write_res, b_read_res)
+        || overlaps(a_read_res, b_write_res)
+        || overlaps(a_write_comp, b_write_comp)
+        || overlaps(a_write_comp, b_read_comp)
+        || overlaps(a_read_comp, b_write_comp)
+}
+
+fn reaches(edges: &[Vec<usize>], from: usize, to: usize) -> bool {
+    let mut visited = vec![false; edges.len()];
+    let mut pending = vec![from];
+    while let Some(node) = pending.pop() {
+        if node == to {
+            return true;
+        }
+        if !std::mem::replace(&mut visited[node], true) {
+            pending.extend(edges[node].iter().copied());
+        }
+    }
+    false
+}
+
+/// Finds a cycle in the graph, returning the nodes which form it in order.
+fn find_cycle(edges: &[Vec<usize>]) -> Option<Vec<usize>> {
+    let count = edges.len();
+    let mut incoming: Vec<Vec<usize>> = vec![Vec::new(); count];
+    for (source, targets) in edges.iter().enumerate() {
+        for target in targets {
+            incoming[*target].push(source);
+        }
+    }
+
+    // remove all nodes which are not part of, or downstream of, a cycle
+    let mut remaining = vec![true; count];
+    let mut counts: Vec<usize> = incoming.iter().map(|sources| sources.len()).collect();
+    let mut pending: Vec<usize> = (0..count).filter(|i| counts[*i] == 0).collect();
+    while let Some(node) = pending.pop() {
+        remaining[node] = false;
+        for target in &edges[node] {
+            counts[*target] -= 1;
+            if counts[*target] == 0 {
+                pending.push(*target);
+            }
+        }
+    }
+
+    // every remaining node has a remaining predecessor, so walking backwards must revisit a node
+    let start = (0..count).find(|i| remaining[*i])?;
+    let mut path = vec![start];
+    let mut node = start;
+    loop {
+        node = *incoming[node]
+            .iter()
+            .find(|source| remaining[**source])
+            .unwrap();
+        if let Some(index) = path.iter().position(|visited| *visited == node) {
+            let mut cycle = path.split_off(index);
+            cycle.reverse();
+            // start the cycle from the earliest inserted system
+            let first = (0..cycle.len()).min_by_key(|i| cycle[*i]).unwrap();
+            cycle.rotate_left(first);
+            return Some(cycle);
+        }
+        path.push(node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_cycles() {
+        assert_eq!(find_cycle(&[vec![1], vec![2], vec![]]), None);
+        assert_eq!(
+            find_cycle(&[vec![1], vec![2], vec![0, 3], vec![]]),
+            Some(vec![0, 1, 2])
+        );
+        assert_eq!(
+            find_cycle(&[vec![1], vec![2], vec![3], vec![2]]),
+            Some(vec![2, 3])
+        );
+    }
+}
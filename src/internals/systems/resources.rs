@@ -23,7 +23,6 @@ use std::{
 #[derive(Copy, Clone, Debug, Eq, PartialOrd, Ord)]
 pub struct ResourceTypeId {
     type_id: TypeId,
-    #[cfg(debug_assertions)]
     name: &'static str,
 }
 
@@ -32,10 +31,14 @@ impl ResourceTypeId {
     pub fn of<T: Resource>() -> Self {
         Self {
             type_id: TypeId::of::<T>(),
-            #[cfg(debug_assertions)]
             name: std::any::type_name::<T>(),
         }
     }
+
+    /// Returns the name of the resource type.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
 }
 
 impl std::hash::Hash for ResourceTypeId {
@@ -51,15 +54,9 @@ impl PartialEq for ResourceTypeId {
 }
 
 impl Display for ResourceTypeId {
-    #[cfg(debug_assertions)]
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.name)
     }
-
-    #[cfg(not(debug_assertions))]
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?}", self.type_id)
-    }
 }
 
 /// Blanket trait for resource types.
@@ -104,6 +101,19 @@ pub trait ResourceSet<'a> {
     /// It is up to the end user to validate proper mutability rules across the resources being accessed.
     unsafe fn fetch_unchecked(resources: &'a UnsafeResources) -> Self::Result;
 
+    /// Fetches all defined resources, without checking mutability, using any state which
+    /// persists between fetches within this resource set, such as the cursors of
+    /// [event readers](struct.EventReader.html). Systems fetch their resources with this method.
+    ///
+    /// # Safety
+    /// It is up to the end user to validate proper mutability rules across the resources being accessed.
+    unsafe fn fetch_unchecked_stateful(
+        &'a mut self,
+        resources: &'a UnsafeResources,
+    ) -> Self::Result {
+        Self::fetch_unchecked(resources)
+    }
+
     /// Fetches all defined resources.
     fn fetch_mut(resources: &'a mut Resources) -> Self::Result {
         // safe because mutable borrow ensures exclusivity
@@ -163,6 +173,14 @@ macro_rules! impl_resource_tuple {
             unsafe fn fetch_unchecked(resources: &'a UnsafeResources) -> Self::Result {
                 ($( $ty::fetch_unchecked(resources), )*)
             }
+
+            unsafe fn fetch_unchecked_stateful(
+                &'a mut self,
+                resources: &'a UnsafeResources,
+            ) -> Self::Result {
+                let ($( $ty, )*) = self;
+                ($( $ty.fetch_unchecked_stateful(resources), )*)
+            }
         }
     };
 }
@@ -362,6 +380,10 @@ impl UnsafeResources {
         self.map.get(type_id)
     }
 
+    fn type_ids(&self) -> impl Iterator<Item = ResourceTypeId> + '_ {
+        self.map.keys().copied()
+    }
+
     /// # Safety
     /// Resources which are `!Sync` must be retrieved or inserted only on the main thread.
     unsafe fn merge(&mut self, mut other: Self) {
@@ -385,6 +407,11 @@ impl Resources {
         &self.internal
     }
 
+    /// Returns the type IDs of all resources in the store.
+    pub(crate) fn type_ids(&self) -> impl Iterator<Item = ResourceTypeId> + '_ {
+        self.internal.type_ids()
+    }
+
     /// Creates an accessor to resources which are Send and Sync, which itself can be sent
     /// between threads.
     pub fn sync(&mut self) -> SyncResources {
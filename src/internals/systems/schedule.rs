@@ -1,13 +1,14 @@
 //! Contains types related to defining system schedules.
 
-use std::cell::UnsafeCell;
-
-#[cfg(feature = "parallel")]
 use std::{
+    cell::UnsafeCell,
     collections::{HashMap, HashSet},
-    sync::atomic::{AtomicUsize, Ordering},
+    sync::Arc,
 };
 
+#[cfg(feature = "parallel")]
+use std::sync::atomic::{AtomicUsize, Ordering};
+
 #[cfg(feature = "parallel")]
 use rayon::prelude::*;
 
@@ -16,16 +17,19 @@ use itertools::izip;
 
 use super::{
     command::CommandBuffer,
+    criteria::{Both, RunCriteria},
+    graph::{GraphBuilder, ScheduleGraph},
+    observer::{thread_local_fn, unnamed_system, ExecutorObserver},
+    ordering::{self, ScheduleError, SystemOrdering},
     resources::{ResourceTypeId, Resources, UnsafeResources},
     system::SystemId,
+    timestep::FixedTime,
 };
 use crate::internals::{
     storage::component::ComponentTypeId,
     subworld::ArchetypeAccess,
     world::{World, WorldId},
 };
-#[cfg(feature = "parallel")]
-use std::iter::repeat;
 
 /// A `Runnable` which is also `Send` and `Sync`.
 pub trait ParallelRunnable: Runnable + Send + Sync {}
@@ -77,9 +81,11 @@ pub trait Runnable {
 /// or entities) are observed is maintained.
 pub struct Executor {
     systems: Vec<SystemBox>,
-    #[cfg(feature = "parallel")]
+    criteria: Vec<Option<Box<dyn RunCriteria>>>,
+    skipped: Vec<bool>,
+    explicit_dependencies: Vec<Vec<usize>>,
+    observer: Option<Arc<dyn ExecutorObserver>>,
     static_dependants: Vec<Vec<usize>>,
-    #[cfg(feature = "parallel")]
     dynamic_dependants: Vec<Vec<usize>>,
     #[cfg(feature = "parallel")]
     static_dependency_counts: Vec<AtomicUsize>,
@@ -96,7 +102,6 @@ unsafe impl Send for SystemBox {}
 unsafe impl Sync for SystemBox {}
 
 impl SystemBox {
-    #[cfg(feature = "parallel")]
     unsafe fn get(&self) -> &dyn ParallelRunnable {
         std::ops::Deref::deref(&*self.0.get())
     }
@@ -112,9 +117,27 @@ impl Executor {
     ///
     /// Systems are provided in the order in which side-effects (e.g. writes to resources or entities)
     /// are to be observed.
-    #[cfg(not(feature = "parallel"))]
     pub fn new(systems: Vec<Box<dyn ParallelRunnable>>) -> Self {
+        let dependencies = vec![Vec::new(); systems.len()];
+        Self::with_dependencies(systems, dependencies)
+    }
+
+    /// Constructs a new executor, where each system additionally waits for the systems
+    /// listed for it in `dependencies`, which must precede it in `systems`.
+    #[cfg(not(feature = "parallel"))]
+    pub(crate) fn with_dependencies(
+        systems: Vec<Box<dyn ParallelRunnable>>,
+        explicit_dependencies: Vec<Vec<usize>>,
+    ) -> Self {
+        let (static_dependants, dynamic_dependants) =
+            find_dependants(&systems, &explicit_dependencies);
         Self {
+            static_dependants,
+            dynamic_dependants,
+            explicit_dependencies,
+            observer: None,
+            criteria: systems.iter().map(|_| None).collect(),
+            skipped: vec![false; systems.len()],
             systems: systems
                 .into_iter()
                 .map(|s| SystemBox(UnsafeCell::new(s)))
@@ -122,104 +145,21 @@ impl Executor {
         }
     }
 
-    /// Constructs a new executor for all systems to be run in a single stage.
-    ///
-    /// Systems are provided in the order in which side-effects (e.g. writes to resources or entities)
-    /// are to be observed.
+    /// Constructs a new executor, where each system additionally waits for the systems
+    /// listed for it in `dependencies`, which must precede it in `systems`.
     #[cfg(feature = "parallel")]
-    #[allow(clippy::cognitive_complexity)]
-    // TODO: we should break this up
-    pub fn new(systems: Vec<Box<dyn ParallelRunnable>>) -> Self {
+    pub(crate) fn with_dependencies(
+        systems: Vec<Box<dyn ParallelRunnable>>,
+        explicit_dependencies: Vec<Vec<usize>>,
+    ) -> Self {
         if systems.len() > 1 {
-            let mut static_dependency_counts = Vec::with_capacity(systems.len());
-
-            let mut static_dependants: Vec<Vec<_>> =
-                repeat(Vec::with_capacity(64)).take(systems.len()).collect();
-            let mut dynamic_dependants: Vec<Vec<_>> =
-                repeat(Vec::with_capacity(64)).take(systems.len()).collect();
-
-            #[derive(Default)]
-            struct PreviousAccess {
-                readers: Vec<usize>,
-                last_writer: Option<usize>,
-            }
-
-            impl PreviousAccess {
-                fn add_read(&mut self, idx: usize) -> Option<usize> {
-                    self.readers.push(idx);
-                    self.last_writer
-                }
-
-                fn add_write(&mut self, idx: usize) -> Vec<usize> {
-                    let mut dependencies = Vec::new();
-                    std::mem::swap(&mut self.readers, &mut dependencies);
-                    if let Some(writer) = self.last_writer.replace(idx) {
-                        dependencies.push(writer)
-                    }
-                    dependencies
-                }
-            }
-
-            let mut resource_accesses =
-                HashMap::<ResourceTypeId, PreviousAccess>::with_capacity_and_hasher(
-                    64,
-                    Default::default(),
-                );
-            let mut component_accesses =
-                HashMap::<ComponentTypeId, PreviousAccess>::with_capacity_and_hasher(
-                    64,
-                    Default::default(),
-                );
-
-            for (i, system) in systems.iter().enumerate() {
-                let (read_res, read_comp) = system.reads();
-                let (write_res, write_comp) = system.writes();
-
-                // find resource access dependencies
-                let mut dependencies = HashSet::with_capacity(64);
-                for res in read_res {
-                    let access = resource_accesses.entry(*res).or_default();
-                    if let Some(dep) = access.add_read(i) {
-                        dependencies.insert(dep);
-                    }
-                }
-                for res in write_res {
-                    let access = resource_accesses.entry(*res).or_default();
-                    for dep in access.add_write(i) {
-                        dependencies.insert(dep);
-                    }
-                }
-
-                static_dependency_counts.push(AtomicUsize::from(dependencies.len()));
-                for dep in &dependencies {
-                    static_dependants[*dep].push(i);
-                }
-
-                // find component access dependencies
-                let mut comp_dependencies = HashSet::<usize>::default();
-                for comp in read_comp {
-                    let access = component_accesses.entry(*comp).or_default();
-                    if let Some(dep) = access.add_read(i) {
-                        comp_dependencies.insert(dep);
-                    }
-                }
-                for comp in write_comp {
-                    let access = component_accesses.entry(*comp).or_default();
-                    for dep in access.add_write(i) {
-                        comp_dependencies.insert(dep);
-                    }
-                }
-
-                // remove dependencies which are already static from dynamic dependencies
-                for static_dep in &dependencies {
-                    comp_dependencies.remove(static_dep);
-                }
-
-                for dep in comp_dependencies {
-                    if dep != i {
-                        // dont be dependent on ourselves
-                        dynamic_dependants[dep].push(i);
-                    }
+            let (static_dependants, dynamic_dependants) =
+                find_dependants(&systems, &explicit_dependencies);
+            let mut static_dependency_counts: Vec<_> =
+                systems.iter().map(|_| AtomicUsize::new(0)).collect();
+            for dependants in &static_dependants {
+                for dep in dependants {
+                    *static_dependency_counts[*dep].get_mut() += 1;
                 }
             }
 
@@ -233,6 +173,10 @@ impl Executor {
                 static_dependants,
                 dynamic_dependants,
                 static_dependency_counts,
+                criteria: systems.iter().map(|_| None).collect(),
+                skipped: vec![false; systems.len()],
+                explicit_dependencies,
+                observer: None,
                 systems: systems
                     .into_iter()
                     .map(|s| SystemBox(UnsafeCell::new(s)))
@@ -244,6 +188,10 @@ impl Executor {
                 static_dependants: Vec::with_capacity(0),
                 dynamic_dependants: Vec::with_capacity(0),
                 static_dependency_counts: Vec::with_capacity(0),
+                criteria: systems.iter().map(|_| None).collect(),
+                skipped: vec![false; systems.len()],
+                explicit_dependencies,
+                observer: None,
                 systems: systems
                     .into_iter()
                     .map(|s| SystemBox(UnsafeCell::new(s)))
@@ -252,37 +200,113 @@ impl Executor {
         }
     }
 
+    /// Returns the dependency graph of the executor's systems, including the reason for each
+    /// dependency. All systems are placed into stage `0`.
+    pub fn graph(&self) -> ScheduleGraph {
+        let mut builder = GraphBuilder::default();
+        builder.add_executor(self, false, false);
+        builder.build()
+    }
+
+    pub(crate) fn systems(&self) -> impl Iterator<Item = &dyn ParallelRunnable> {
+        // safety: systems are only mutably accessed through `&mut self`
+        self.systems.iter().map(|system| unsafe { system.get() })
+    }
+
+    /// Returns, for each system, the systems which always wait for it to complete, and those
+    /// which wait for it only when both systems access a common archetype. Dynamic dependants
+    /// are made static once they have been found to access a common archetype.
+    pub(crate) fn dependants(&self) -> (&[Vec<usize>], &[Vec<usize>]) {
+        (&self.static_dependants, &self.dynamic_dependants)
+    }
+
+    pub(crate) fn explicit_dependencies(&self) -> &[Vec<usize>] {
+        &self.explicit_dependencies
+    }
+
+    pub(crate) fn has_run_criteria(&self, system: usize) -> bool {
+        self.criteria[system].is_some()
+    }
+
+    /// Attaches an [observer](trait.ExecutorObserver.html) which is notified as each system runs
+    /// and as each system's command buffer is flushed, replacing any previous observer.
+    pub fn set_observer(&mut self, observer: Arc<dyn ExecutorObserver>) {
+        self.observer = Some(observer);
+    }
+
     /// Converts this executor into a vector of its component systems.
     pub fn into_vec(self) -> Vec<Box<dyn ParallelRunnable>> {
         self.systems.into_iter().map(|s| s.0.into_inner()).collect()
     }
 
+    /// Attaches [run criteria](trait.RunCriteria.html) to the system at the given index.
+    /// If the system already has criteria, both must be met for the system to run.
+    pub fn add_run_criteria<C: RunCriteria + 'static>(&mut self, system: usize, criteria: C) {
+        self.add_run_criteria_boxed(system, Box::new(criteria));
+    }
+
+    fn add_run_criteria_boxed(&mut self, system: usize, criteria: Box<dyn RunCriteria>) {
+        let slot = &mut self.criteria[system];
+        *slot = Some(match slot.take() {
+            Some(existing) => Box::new(Both(existing, criteria)),
+            None => criteria,
+        });
+    }
+
+    /// Evaluates the [run criteria](trait.RunCriteria.html) of each system, determining which
+    /// systems will be skipped by the following calls to `run_systems`.
+    ///
+    /// This is called by `execute` and by [Schedule](struct.Schedule.html) before the executor
+    /// runs its systems.
+    pub fn evaluate_run_criteria(&mut self, world: &World, resources: &Resources) {
+        for (criteria, skipped) in self.criteria.iter_mut().zip(self.skipped.iter_mut()) {
+            *skipped = match criteria {
+                Some(criteria) => !criteria.should_run(world, resources),
+                None => false,
+            };
+        }
+    }
+
     /// Executes all systems and then flushes their command buffers.
     #[cfg(not(feature = "parallel"))]
     pub fn execute(&mut self, world: &mut World, resources: &mut Resources) {
-        let resources = resources.internal();
-        self.run_systems(world, resources);
+        self.evaluate_run_criteria(world, resources);
+        self.run_systems(world, resources.internal());
         self.flush_command_buffers(world);
+        if let Some(observer) = &self.observer {
+            observer.end_execution(world, resources);
+        }
     }
 
     /// Executes all systems and then flushes their command buffers.
     #[cfg(feature = "parallel")]
     pub fn execute(&mut self, world: &mut World, resources: &mut Resources) {
-        let resources = resources.internal();
-        rayon::join(|| self.run_systems(world, resources), || {});
+        self.evaluate_run_criteria(world, resources);
+        let internal = resources.internal();
+        rayon::join(|| self.run_systems(world, internal), || {});
         self.flush_command_buffers(world);
+        if let Some(observer) = &self.observer {
+            observer.end_execution(world, resources);
+        }
     }
 
     /// Executes all systems sequentially.
     ///
+    /// Systems whose run criteria were not met when they were last evaluated are skipped.
+    ///
     /// Only enabled with parallel is disabled
     #[cfg(not(feature = "parallel"))]
     pub fn run_systems(&mut self, world: &mut World, resources: &UnsafeResources) {
-        self.systems.iter_mut().for_each(|system| {
-            let system = unsafe { system.get_mut() };
-            system.prepare(world);
-            unsafe { system.run_unsafe(world, resources) };
-        });
+        for i in 0..self.systems.len() {
+            if self.skipped[i] {
+                continue;
+            }
+            // safety: we have exlusive access to all systems, world and resources here
+            unsafe {
+                self.systems[i].get_mut().prepare(world);
+                self.run_system(i, world, resources);
+            }
+        }
     }
 
     /// Executes all systems, potentially in parallel.
@@ -290,16 +314,19 @@ impl Executor {
     /// Ordering is retained in so far as the order of observed resource and component
     /// accesses is maintained.
     ///
+    /// Systems whose run criteria were not met when they were last evaluated are skipped
+    /// without being scheduled onto the thread pool.
+    ///
     /// Call from within `rayon::ThreadPool::install()` to execute within a specific thread pool.
     #[cfg(feature = "parallel")]
     pub fn run_systems(&mut self, world: &mut World, resources: &UnsafeResources) {
         match self.systems.len() {
+            1 if self.skipped[0] => {}
             1 => {
                 // safety: we have exlusive access to all systems, world and resources here
                 unsafe {
-                    let system = self.systems[0].get_mut();
-                    system.prepare(world);
-                    system.run_unsafe(world, resources);
+                    self.systems[0].get_mut().prepare(world);
+                    self.run_system(0, world, resources);
                 };
             }
             _ => {
@@ -308,6 +335,8 @@ impl Executor {
                 let awaiting = &mut self.awaiting;
 
                 // prepare all systems - archetype filters are pre-executed here
+                // skipped systems are prepared too, as dependencies between the systems
+                // on either side of a skipped system are only found through its archetypes
                 systems
                     .par_iter_mut()
                     .for_each(|sys| unsafe { sys.get_mut() }.prepare(world));
@@ -350,7 +379,13 @@ impl Executor {
                     .for_each(|i| {
                         // safety: we are at the root of the execution tree, so we know each
                         // index is exclusive here
-                        unsafe { self.run_recursive(i, world, resources) };
+                        unsafe {
+                            if self.skipped[i] {
+                                self.release_dependants(i, world, resources);
+                            } else {
+                                self.run_recursive(i, world, resources);
+                            }
+                        };
                     });
 
                 debug_assert!(
@@ -364,15 +399,49 @@ impl Executor {
 
     /// Flushes the recorded command buffers for all systems.
     pub fn flush_command_buffers(&mut self, world: &mut World) {
+        let observer = &self.observer;
         self.systems.iter().for_each(|system| {
             // safety: systems are exlcusive due to &mut self
             let system = unsafe { system.get_mut() };
+            let id = system_id(system);
             if let Some(cmd) = system.command_buffer_mut(world.id()) {
-                cmd.flush(world);
+                trace_span!("flush", system = %id);
+                match observer {
+                    Some(observer) => {
+                        observer.begin_flush(&id);
+                        cmd.flush(world);
+                        observer.end_flush(&id);
+                    }
+                    None => cmd.flush(world),
+                }
             }
         });
     }
 
+    /// Runs the system indexed by `i`, notifying the observer.
+    ///
+    /// # Safety
+    ///
+    /// Ensure the system indexed by `i` is only accessed once.
+    unsafe fn run_system(&self, i: usize, world: &World, resources: &UnsafeResources) {
+        // safety: the caller ensures nothing else is accessing systems[i]
+        let system = self.systems[i].get_mut();
+        trace_span!(
+            "system",
+            name = %system_id(system),
+            entities = matched_entities(system, world)
+        );
+        match &self.observer {
+            Some(observer) => {
+                let id = system_id(system);
+                observer.begin_system(&id);
+                system.run_unsafe(world, resources);
+                observer.end_system(&id);
+            }
+            None => system.run_unsafe(world, resources),
+        }
+    }
+
     /// Recursively execute through the generated depedency cascade and exhaust it.
     ///
     /// # Safety
@@ -381,35 +450,245 @@ impl Executor {
     #[cfg(feature = "parallel")]
     unsafe fn run_recursive(&self, i: usize, world: &World, resources: &UnsafeResources) {
         // safety: the caller ensures nothing else is accessing systems[i]
-        self.systems[i].get_mut().run_unsafe(world, resources);
+        self.run_system(i, world, resources);
+        self.release_dependants(i, world, resources);
+    }
 
+    /// Notifies the dependants of the system indexed by `i` that it has completed, running
+    /// those which have no outstanding dependencies. Skipped dependants are completed
+    /// immediately on the current thread.
+    ///
+    /// # Safety
+    ///
+    /// Ensure the system indexed by `i` has completed, and that this is only called once for it.
+    #[cfg(feature = "parallel")]
+    unsafe fn release_dependants(&self, i: usize, world: &World, resources: &UnsafeResources) {
         self.static_dependants[i].par_iter().for_each(|dep| {
             if self.awaiting[*dep].fetch_sub(1, Ordering::Relaxed) == 1 {
                 // safety: each dependency is unique, so run_recursive is safe to call
-                self.run_recursive(*dep, world, resources);
+                if self.skipped[*dep] {
+                    self.release_dependants(*dep, world, resources);
+                } else {
+                    self.run_recursive(*dep, world, resources);
+                }
             }
         });
     }
 }
 
+/// Finds, for each system, the systems which must wait for it to complete. Static dependants
+/// are due to explicit ordering constraints or resource accesses, while dynamic dependants are
+/// due to component accesses, and are only enforced when both systems access a common archetype.
+fn find_dependants(
+    systems: &[Box<dyn ParallelRunnable>],
+    explicit_dependencies: &[Vec<usize>],
+) -> (Vec<Vec<usize>>, Vec<Vec<usize>>) {
+    #[derive(Default)]
+    struct PreviousAccess {
+        readers: Vec<usize>,
+        last_writer: Option<usize>,
+    }
+
+    impl PreviousAccess {
+        fn add_read(&mut self, idx: usize) -> Option<usize> {
+            self.readers.push(idx);
+            self.last_writer
+        }
+
+        fn add_write(&mut self, idx: usize) -> Vec<usize> {
+            let mut dependencies = Vec::new();
+            std::mem::swap(&mut self.readers, &mut dependencies);
+            if let Some(writer) = self.last_writer.replace(idx) {
+                dependencies.push(writer)
+            }
+            dependencies
+        }
+    }
+
+    let mut static_dependants = vec![Vec::new(); systems.len()];
+    let mut dynamic_dependants = vec![Vec::new(); systems.len()];
+    let mut resource_accesses = HashMap::<ResourceTypeId, PreviousAccess>::default();
+    let mut component_accesses = HashMap::<ComponentTypeId, PreviousAccess>::default();
+
+    for (i, system) in systems.iter().enumerate() {
+        let (read_res, read_comp) = system.reads();
+        let (write_res, write_comp) = system.writes();
+
+        // find resource access dependencies
+        let mut dependencies = HashSet::<usize>::default();
+        dependencies.extend(explicit_dependencies[i].iter().copied());
+        for res in read_res {
+            let access = resource_accesses.entry(*res).or_default();
+            if let Some(dep) = access.add_read(i) {
+                dependencies.insert(dep);
+            }
+        }
+        for res in write_res {
+            let access = resource_accesses.entry(*res).or_default();
+            for dep in access.add_write(i) {
+                dependencies.insert(dep);
+            }
+        }
+
+        // find component access dependencies
+        let mut comp_dependencies = HashSet::<usize>::default();
+        for comp in read_comp {
+            let access = component_accesses.entry(*comp).or_default();
+            if let Some(dep) = access.add_read(i) {
+                comp_dependencies.insert(dep);
+            }
+        }
+        for comp in write_comp {
+            let access = component_accesses.entry(*comp).or_default();
+            for dep in access.add_write(i) {
+                comp_dependencies.insert(dep);
+            }
+        }
+
+        // dont be dependent on ourselves
+        dependencies.remove(&i);
+        comp_dependencies.remove(&i);
+
+        // remove dependencies which are already static from dynamic dependencies
+        for dep in dependencies {
+            comp_dependencies.remove(&dep);
+            static_dependants[dep].push(i);
+        }
+        for dep in comp_dependencies {
+            dynamic_dependants[dep].push(i);
+        }
+    }
+
+    (static_dependants, dynamic_dependants)
+}
+
+fn system_id<S: Runnable + ?Sized>(system: &S) -> SystemId {
+    system.name().cloned().unwrap_or_else(unnamed_system)
+}
+
+/// Counts the entities in the archetypes which a prepared system's queries match.
+#[cfg(feature = "tracing")]
+fn matched_entities<S: Runnable + ?Sized>(system: &S, world: &World) -> usize {
+    match system.accesses_archetypes() {
+        ArchetypeAccess::All => world.len(),
+        ArchetypeAccess::Some(bitset) => bitset
+            .iter()
+            .filter_map(|i| world.archetypes().get(i))
+            .map(|archetype| archetype.entities().len())
+            .sum(),
+    }
+}
+
 /// A factory for `Schedule`.
 pub struct Builder {
     steps: Vec<Step>,
     accumulator: Vec<Box<dyn ParallelRunnable>>,
+    accumulator_criteria: Vec<(usize, Box<dyn RunCriteria>)>,
+    accumulator_ordering: Vec<SystemOrdering>,
+    stages: Vec<Vec<SystemOrdering>>,
+    error: Option<ScheduleError>,
+    last_added: Option<LastAdded>,
+}
+
+#[derive(Copy, Clone)]
+enum LastAdded {
+    System,
+    Step,
 }
 
 impl Builder {
     /// Adds a system to the schedule.
     pub fn add_system<T: ParallelRunnable + 'static>(&mut self, system: T) -> &mut Self {
+        self.accumulator_ordering
+            .push(SystemOrdering::new(&system, self.accumulator.len()));
         self.accumulator.push(Box::new(system));
+        self.last_added = Some(LastAdded::System);
         self
     }
 
+    /// Attaches [run criteria](trait.RunCriteria.html) to the most recently added system,
+    /// thread local step or fixed timestep schedule, such that it only runs when the criteria are met. Calling this more
+    /// than once requires all of the criteria to be met.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the previous call to the builder did not add a system, thread local step or
+    /// fixed timestep schedule.
+    pub fn run_if<C: RunCriteria + 'static>(&mut self, criteria: C) -> &mut Self {
+        let criteria = Box::new(criteria) as Box<dyn RunCriteria>;
+        match self.last_added {
+            Some(LastAdded::System) => {
+                let index = self.accumulator.len() - 1;
+                self.accumulator_criteria.push((index, criteria));
+            }
+            Some(LastAdded::Step) => {
+                let step = match self.steps.pop().unwrap() {
+                    Step::Conditional(existing, step) => {
+                        Step::Conditional(Box::new(Both(existing, criteria)), step)
+                    }
+                    step => Step::Conditional(criteria, Box::new(step)),
+                };
+                self.steps.push(step);
+            }
+            None => {
+                panic!("run criteria must follow the system or step they apply to")
+            }
+        }
+        self
+    }
+
+    /// Adds a label to the most recently added system, such that other systems can be
+    /// ordered relative to it with [before](#method.before) and [after](#method.after).
+    /// Systems are also implicitly labelled with their name.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the previous call to the builder did not add a system.
+    pub fn label<L: Into<SystemId>>(&mut self, label: L) -> &mut Self {
+        self.last_system_ordering().labels.push(label.into());
+        self
+    }
+
+    /// Requires the most recently added system to run before all systems with the given label.
+    ///
+    /// Systems between two flushes or thread local steps are sorted to meet their ordering
+    /// constraints, regardless of the order in which they were added. Systems which access the
+    /// same data otherwise run in the order in which they were added. Building the schedule fails
+    /// if no system in the schedule is named or labelled with `label`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the previous call to the builder did not add a system.
+    pub fn before<L: Into<SystemId>>(&mut self, label: L) -> &mut Self {
+        self.last_system_ordering().before.push(label.into());
+        self
+    }
+
+    /// Requires the most recently added system to run after all systems with the given label.
+    ///
+    /// See [before](#method.before).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the previous call to the builder did not add a system.
+    pub fn after<L: Into<SystemId>>(&mut self, label: L) -> &mut Self {
+        self.last_system_ordering().after.push(label.into());
+        self
+    }
+
+    fn last_system_ordering(&mut self) -> &mut SystemOrdering {
+        match self.last_added {
+            Some(LastAdded::System) => self.accumulator_ordering.last_mut().unwrap(),
+            _ => panic!("ordering constraints must follow the system they apply to"),
+        }
+    }
+
     /// Waits for executing systems to complete, and the flushes all outstanding system
     /// command buffers.
     pub fn flush(&mut self) -> &mut Self {
         self.finalize_executor();
         self.steps.push(Step::FlushCmdBuffers);
+        self.last_added = None;
         self
     }
 
@@ -417,8 +696,36 @@ impl Builder {
         if !self.accumulator.is_empty() {
             let mut systems = Vec::new();
             std::mem::swap(&mut self.accumulator, &mut systems);
-            let executor = Executor::new(systems);
+            let mut orderings = Vec::new();
+            std::mem::swap(&mut self.accumulator_ordering, &mut orderings);
+
+            let (order, dependencies) = match ordering::sort(&systems, &orderings) {
+                Ok(sorted) => (sorted.order, sorted.dependencies),
+                Err(err) => {
+                    self.error.get_or_insert(err);
+                    let dependencies = vec![Vec::new(); systems.len()];
+                    ((0..systems.len()).collect(), dependencies)
+                }
+            };
+
+            let mut position = vec![0; order.len()];
+            for (new, old) in order.iter().enumerate() {
+                position[*old] = new;
+            }
+            let mut systems: Vec<_> = systems.into_iter().map(Some).collect();
+            let systems = order.iter().map(|i| systems[*i].take().unwrap()).collect();
+            let mut orderings: Vec<_> = orderings.into_iter().map(Some).collect();
+            let orderings = order
+                .iter()
+                .map(|i| orderings[*i].take().unwrap())
+                .collect();
+
+            let mut executor = Executor::with_dependencies(systems, dependencies);
+            for (index, criteria) in self.accumulator_criteria.drain(..) {
+                executor.add_run_criteria_boxed(position[index], criteria);
+            }
             self.steps.push(Step::Systems(executor));
+            self.stages.push(orderings);
         }
     }
 
@@ -431,6 +738,7 @@ impl Builder {
         self.steps.push(Step::ThreadLocalFn(
             Box::new(f) as Box<dyn FnMut(&mut World, &mut Resources)>
         ));
+        self.last_added = Some(LastAdded::Step);
         self
     }
 
@@ -439,15 +747,57 @@ impl Builder {
         self.finalize_executor();
         let system = Box::new(system) as Box<dyn Runnable>;
         self.steps.push(Step::ThreadLocalSystem(system));
+        self.last_added = Some(LastAdded::Step);
+        self
+    }
+
+    /// Adds a schedule which runs zero or more times each time this schedule is executed,
+    /// once for each whole timestep which has accumulated in the [FixedTime](struct.FixedTime.html)
+    /// resource. The child schedule does not run if the resource does not exist.
+    ///
+    /// Command buffers recorded by the child schedule are flushed at the end of each of its
+    /// iterations, such that each iteration observes the changes made by the previous one.
+    pub fn add_fixed_timestep<S: Into<Schedule>>(&mut self, schedule: S) -> &mut Self {
+        self.finalize_executor();
+        self.steps.push(Step::FixedTimestep(schedule.into()));
+        self.last_added = Some(LastAdded::Step);
         self
     }
 
     /// Finalizes the builder into a `Schedule`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the ordering constraints between systems cannot be met, or refer to a label
+    /// which no system has. Use [try_build](#method.try_build) to handle these cases.
     pub fn build(&mut self) -> Schedule {
+        match self.try_build() {
+            Ok(schedule) => schedule,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Finalizes the builder into a `Schedule`, or returns an error describing why the
+    /// ordering constraints between systems cannot be met, or which label used in an ordering
+    /// constraint does not match any system.
+    pub fn try_build(&mut self) -> Result<Schedule, ScheduleError> {
         self.flush();
         let mut steps = Vec::new();
         std::mem::swap(&mut self.steps, &mut steps);
-        Schedule { steps }
+        let mut stages = Vec::new();
+        std::mem::swap(&mut self.stages, &mut stages);
+
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+        ordering::validate_labels(&stages)?;
+        for (i, earlier) in stages.iter().enumerate() {
+            for later in &stages[i + 1..] {
+                ordering::validate_stages(earlier, later)?;
+            }
+        }
+
+        Ok(Schedule::from(steps))
     }
 }
 
@@ -456,6 +806,11 @@ impl Default for Builder {
         Self {
             steps: Vec::new(),
             accumulator: Vec::new(),
+            accumulator_criteria: Vec::new(),
+            accumulator_ordering: Vec::new(),
+            stages: Vec::new(),
+            error: None,
+            last_added: None,
         }
     }
 }
@@ -470,6 +825,17 @@ pub enum Step {
     ThreadLocalFn(Box<dyn FnMut(&mut World, &mut Resources)>),
     /// A thread local system
     ThreadLocalSystem(Box<dyn Runnable>),
+    /// A step which only runs when its [run criteria](trait.RunCriteria.html) are met.
+    Conditional(Box<dyn RunCriteria>, Box<Step>),
+    /// A schedule which runs once for each timestep accumulated in the
+    /// [FixedTime](struct.FixedTime.html) resource, flushing its command buffers after
+    /// each iteration.
+    FixedTimestep(Schedule),
+}
+
+enum ToFlush<'a> {
+    Executor(&'a mut Executor),
+    System(SystemId, &'a mut CommandBuffer),
 }
 
 /// A schedule of systems for execution.
@@ -494,6 +860,7 @@ pub enum Step {
 /// ```
 pub struct Schedule {
     steps: Vec<Step>,
+    observer: Option<Arc<dyn ExecutorObserver>>,
 }
 
 impl Schedule {
@@ -540,34 +907,185 @@ impl Schedule {
         resources: &mut Resources,
         mut run_executor: F,
     ) {
-        enum ToFlush<'a> {
-            Executor(&'a mut Executor),
-            System(&'a mut CommandBuffer),
-        }
-
+        trace_span!("schedule");
+        let observer = self.observer.as_deref();
         let mut waiting_flush: Vec<ToFlush> = Vec::new();
         for step in &mut self.steps {
-            match step {
-                Step::Systems(executor) => {
-                    run_executor(world, resources, executor);
-                    waiting_flush.push(ToFlush::Executor(executor));
+            Self::execute_step(
+                step,
+                world,
+                resources,
+                &mut run_executor,
+                &mut waiting_flush,
+                observer,
+            );
+        }
+
+        world.update_removals();
+
+        if let Some(observer) = observer {
+            observer.end_execution(world, resources);
+        }
+    }
+
+    fn execute_step<'a, F: FnMut(&mut World, &mut Resources, &mut Executor)>(
+        step: &'a mut Step,
+        world: &mut World,
+        resources: &mut Resources,
+        run_executor: &mut F,
+        waiting_flush: &mut Vec<ToFlush<'a>>,
+        observer: Option<&dyn ExecutorObserver>,
+    ) {
+        match step {
+            Step::Systems(executor) => {
+                executor.evaluate_run_criteria(world, resources);
+                run_executor(world, resources, executor);
+                waiting_flush.push(ToFlush::Executor(executor));
+            }
+            Step::FlushCmdBuffers => Self::flush(waiting_flush, world, observer),
+            Step::ThreadLocalFn(function) => match observer {
+                Some(observer) => {
+                    let id = thread_local_fn();
+                    trace_span!("system", name = %id);
+                    observer.begin_thread_local(&id);
+                    function(world, resources);
+                    observer.end_thread_local(&id);
                 }
-                Step::FlushCmdBuffers => {
-                    waiting_flush.drain(..).for_each(|e| match e {
-                        ToFlush::Executor(exec) => exec.flush_command_buffers(world),
-                        ToFlush::System(cmd) => cmd.flush(world),
-                    });
+                None => {
+                    trace_span!("system", name = %thread_local_fn());
+                    function(world, resources);
+                }
+            },
+            Step::ThreadLocalSystem(system) => {
+                let id = system_id(system.as_ref());
+                system.prepare(world);
+                trace_span!(
+                    "system",
+                    name = %id,
+                    entities = matched_entities(system.as_ref(), world)
+                );
+                match observer {
+                    Some(observer) => {
+                        observer.begin_thread_local(&id);
+                        system.run(world, resources);
+                        observer.end_thread_local(&id);
+                    }
+                    None => system.run(world, resources),
+                }
+                if let Some(cmd) = system.command_buffer_mut(world.id()) {
+                    waiting_flush.push(ToFlush::System(id, cmd));
+                }
+            }
+            Step::Conditional(criteria, step) => {
+                if criteria.should_run(world, resources) {
+                    Self::execute_step(
+                        step,
+                        world,
+                        resources,
+                        run_executor,
+                        waiting_flush,
+                        observer,
+                    );
+                }
+            }
+            Step::FixedTimestep(schedule) => {
+                let mut iteration = 0;
+                while Self::consume_timestep(resources, iteration) {
+                    let mut child_flush = Vec::new();
+                    for step in &mut schedule.steps {
+                        Self::execute_step(
+                            step,
+                            world,
+                            resources,
+                            run_executor,
+                            &mut child_flush,
+                            observer,
+                        );
+                    }
+                    Self::flush(&mut child_flush, world, observer);
+                    iteration += 1;
                 }
-                Step::ThreadLocalFn(function) => function(world, resources),
-                Step::ThreadLocalSystem(system) => {
-                    system.prepare(world);
-                    system.run(world, resources);
-                    if let Some(cmd) = system.command_buffer_mut(world.id()) {
-                        waiting_flush.push(ToFlush::System(cmd));
+            }
+        }
+    }
+
+    fn consume_timestep(resources: &Resources, iteration: u32) -> bool {
+        match resources.get_mut::<FixedTime>() {
+            Some(mut time) => time.consume(iteration),
+            None => false,
+        }
+    }
+
+    fn flush(
+        waiting_flush: &mut Vec<ToFlush>,
+        world: &mut World,
+        observer: Option<&dyn ExecutorObserver>,
+    ) {
+        waiting_flush.drain(..).for_each(|e| match e {
+            ToFlush::Executor(exec) => exec.flush_command_buffers(world),
+            ToFlush::System(id, cmd) => {
+                trace_span!("flush", system = %id);
+                match observer {
+                    Some(observer) => {
+                        observer.begin_flush(&id);
+                        cmd.flush(world);
+                        observer.end_flush(&id);
                     }
+                    None => cmd.flush(world),
                 }
             }
+        });
+    }
+
+    /// Attaches an [observer](trait.ExecutorObserver.html) to the schedule and all of its
+    /// executors, replacing any previous observer. The observer is notified as each system and
+    /// thread local step runs, as each command buffer is flushed, and at the end of each
+    /// execution of the schedule.
+    pub fn set_observer(&mut self, observer: Arc<dyn ExecutorObserver>) {
+        for step in &mut self.steps {
+            Self::set_step_observer(step, &observer);
         }
+        self.observer = Some(observer);
+    }
+
+    fn set_step_observer(step: &mut Step, observer: &Arc<dyn ExecutorObserver>) {
+        match step {
+            Step::Systems(executor) => executor.set_observer(observer.clone()),
+            Step::Conditional(_, step) => Self::set_step_observer(step, observer),
+            Step::FixedTimestep(schedule) => schedule.set_observer(observer.clone()),
+            Step::FlushCmdBuffers | Step::ThreadLocalFn(_) | Step::ThreadLocalSystem(_) => {}
+        }
+    }
+
+    /// Returns the dependency graph of the schedule's systems, including the reason for each
+    /// dependency.
+    ///
+    /// The graph can be rendered with Graphviz or serialized, allowing changes to a schedule
+    /// to be reviewed and unnecessary dependencies between systems to be found.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use legion::*;
+    /// # let system_a = SystemBuilder::new("a").write_resource::<usize>().build(|_, _, _, _| {});
+    /// # let system_b = SystemBuilder::new("b").read_resource::<usize>().build(|_, _, _, _| {});
+    /// let schedule = Schedule::builder()
+    ///     .add_system(system_a)
+    ///     .add_system(system_b)
+    ///     .build();
+    ///
+    /// let graph = schedule.graph();
+    /// assert_eq!(graph.edges.len(), 1);
+    /// println!("{}", graph.to_dot());
+    /// ```
+    pub fn graph(&self) -> ScheduleGraph {
+        let mut builder = GraphBuilder::default();
+        builder.add_steps(&self.steps, false, false);
+        builder.build()
+    }
+
+    pub(crate) fn steps(&self) -> &[Step] {
+        &self.steps
     }
 
     /// Converts the schedule into a vector of steps.
@@ -584,7 +1102,10 @@ impl From<Builder> for Schedule {
 
 impl From<Vec<Step>> for Schedule {
     fn from(steps: Vec<Step>) -> Self {
-        Self { steps }
+        Self {
+            steps,
+            observer: None,
+        }
     }
 }
 
@@ -636,6 +1157,376 @@ mod tests {
         assert_eq!(*order, sorted);
     }
 
+    #[test]
+    fn run_criteria() {
+        use crate::internals::systems::criteria::every_n_ticks;
+
+        let mut world = World::default();
+
+        #[derive(Default)]
+        struct Resource;
+
+        #[derive(PartialEq)]
+        struct Enabled(bool);
+
+        let mut resources = Resources::default();
+        resources.insert(Resource);
+        resources.insert(Enabled(false));
+
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        let order_clone = order.clone();
+        let system_one = SystemBuilder::new("one")
+            .write_resource::<Resource>()
+            .build(move |_, _, _, _| order_clone.lock().unwrap().push(1usize));
+        let order_clone = order.clone();
+        let system_two = SystemBuilder::new("two")
+            .write_resource::<Resource>()
+            .build(move |_, _, _, _| order_clone.lock().unwrap().push(2usize));
+        let order_clone = order.clone();
+        let system_three = SystemBuilder::new("three")
+            .write_resource::<Resource>()
+            .build(move |_, _, _, _| order_clone.lock().unwrap().push(3usize));
+        let order_clone = order.clone();
+
+        let mut schedule = Schedule::builder()
+            .add_system(system_one)
+            .add_system(system_two)
+            .run_if(|_: &World, resources: &Resources| resources.get::<Enabled>().unwrap().0)
+            .add_system(system_three)
+            .run_if(every_n_ticks(2))
+            .add_thread_local_fn(move |_, _| order_clone.lock().unwrap().push(4usize))
+            .run_if(every_n_ticks(3))
+            .build();
+
+        schedule.execute(&mut world, &mut resources);
+        assert_eq!(*order.lock().unwrap(), vec![1, 3, 4]);
+
+        order.lock().unwrap().clear();
+        resources.insert(Enabled(true));
+        schedule.execute(&mut world, &mut resources);
+        assert_eq!(*order.lock().unwrap(), vec![1, 2]);
+
+        order.lock().unwrap().clear();
+        schedule.execute(&mut world, &mut resources);
+        assert_eq!(*order.lock().unwrap(), vec![1, 2, 3]);
+
+        order.lock().unwrap().clear();
+        schedule.execute(&mut world, &mut resources);
+        assert_eq!(*order.lock().unwrap(), vec![1, 2, 4]);
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn run_criteria_skipped_writer() {
+        use crate::internals::query::view::read::Read;
+        use std::time::Duration;
+
+        let mut world = World::default();
+        world.push((1usize,));
+        let mut resources = Resources::default();
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(4)
+            .build()
+            .unwrap();
+
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        let order_clone = order.clone();
+        let system_one = SystemBuilder::new("one")
+            .with_query(Write::<usize>::query())
+            .build(move |_, _, _, _| {
+                order_clone.lock().unwrap().push("begin one");
+                std::thread::sleep(Duration::from_millis(20));
+                order_clone.lock().unwrap().push("end one");
+            });
+        let system_two = SystemBuilder::new("two")
+            .with_query(Write::<usize>::query())
+            .build(move |_, _, _, _| {});
+        let order_clone = order.clone();
+        let system_three = SystemBuilder::new("three")
+            .with_query(Read::<usize>::query())
+            .build(move |_, _, _, _| order_clone.lock().unwrap().push("three"));
+
+        let mut schedule = Schedule::builder()
+            .add_system(system_one)
+            .add_system(system_two)
+            .run_if(|_: &World, _: &Resources| false)
+            .add_system(system_three)
+            .build();
+
+        // three must still wait for one, although the system between them is skipped
+        for _ in 0..5 {
+            order.lock().unwrap().clear();
+            schedule.execute_in_thread_pool(&mut world, &mut resources, &pool);
+            assert_eq!(
+                *order.lock().unwrap(),
+                vec!["begin one", "end one", "three"]
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "run criteria must follow")]
+    fn run_criteria_after_flush() {
+        Schedule::builder()
+            .flush()
+            .run_if(|_: &World, _: &Resources| true);
+    }
+
+    #[test]
+    fn fixed_timestep() {
+        use crate::internals::{query::view::read::Read, systems::timestep::FixedTime};
+        use std::time::Duration;
+
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        resources.insert(FixedTime::new(Duration::from_millis(10)));
+
+        let counts = Arc::new(Mutex::new(Vec::new()));
+        let counts_clone = counts.clone();
+        let spawn = SystemBuilder::new("spawn")
+            .with_query(Read::<usize>::query())
+            .build(move |cmd, world, _, query| {
+                counts_clone.lock().unwrap().push(query.iter(world).count());
+                cmd.push((1usize,));
+            });
+
+        let mut schedule = Schedule::builder()
+            .add_fixed_timestep(Schedule::builder().add_system(spawn).build())
+            .build();
+
+        schedule.execute(&mut world, &mut resources);
+        assert!(counts.lock().unwrap().is_empty());
+
+        resources
+            .get_mut::<FixedTime>()
+            .unwrap()
+            .accumulate(Duration::from_millis(35));
+        schedule.execute(&mut world, &mut resources);
+        assert_eq!(*counts.lock().unwrap(), vec![0, 1, 2]);
+        assert_eq!(
+            resources.get::<FixedTime>().unwrap().accumulator(),
+            Duration::from_millis(5)
+        );
+
+        resources
+            .get_mut::<FixedTime>()
+            .unwrap()
+            .accumulate(Duration::from_millis(5));
+        schedule.execute(&mut world, &mut resources);
+        assert_eq!(*counts.lock().unwrap(), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn observer() {
+        use crate::internals::systems::observer::{SystemTimings, TimingCollector};
+
+        #[derive(Default)]
+        struct Recorder {
+            events: Mutex<Vec<String>>,
+            timings: TimingCollector,
+        }
+
+        impl ExecutorObserver for Recorder {
+            fn begin_system(&self, system: &SystemId) {
+                self.events
+                    .lock()
+                    .unwrap()
+                    .push(format!("begin {}", system));
+                self.timings.begin_system(system);
+            }
+
+            fn end_system(&self, system: &SystemId) {
+                self.events.lock().unwrap().push(format!("end {}", system));
+                self.timings.end_system(system);
+            }
+
+            fn begin_flush(&self, system: &SystemId) {
+                self.events
+                    .lock()
+                    .unwrap()
+                    .push(format!("flush {}", system));
+            }
+
+            fn begin_thread_local(&self, system: &SystemId) {
+                self.events
+                    .lock()
+                    .unwrap()
+                    .push(format!("begin {}", system));
+                self.timings.begin_thread_local(system);
+            }
+
+            fn end_thread_local(&self, system: &SystemId) {
+                self.events.lock().unwrap().push(format!("end {}", system));
+                self.timings.end_thread_local(system);
+            }
+
+            fn end_execution(&self, world: &mut World, resources: &mut Resources) {
+                self.events
+                    .lock()
+                    .unwrap()
+                    .push("end execution".to_string());
+                self.timings.end_execution(world, resources);
+            }
+        }
+
+        let mut world = World::default();
+        let mut resources = Resources::default();
+
+        let mut schedule = Schedule::builder()
+            .add_system(SystemBuilder::new("a").build(|_, _, _, _| {}))
+            .add_thread_local_fn(|_, _| {})
+            .build();
+        let recorder = Arc::new(Recorder::default());
+        schedule.set_observer(recorder.clone());
+
+        schedule.execute(&mut world, &mut resources);
+        assert_eq!(
+            *recorder.events.lock().unwrap(),
+            vec![
+                "begin a",
+                "end a",
+                "begin <thread local fn>",
+                "end <thread local fn>",
+                "flush a",
+                "end execution"
+            ]
+        );
+
+        schedule.execute(&mut world, &mut resources);
+        let timings = resources.get::<SystemTimings>().unwrap();
+        let timing = timings.get("a").unwrap();
+        assert_eq!(timing.samples(), 2);
+        assert!(timing.min() <= timing.mean() && timing.mean() <= timing.max());
+        assert_eq!(timings.get("<thread local fn>").unwrap().samples(), 2);
+    }
+
+    #[test]
+    fn ordering_constraints() {
+        let mut world = World::default();
+
+        #[derive(Default)]
+        struct Resource;
+
+        let mut resources = Resources::default();
+        resources.insert(Resource);
+
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let writer = |name: &'static str| {
+            let order = order.clone();
+            SystemBuilder::new(name)
+                .write_resource::<Resource>()
+                .build(move |_, _, _, _| order.lock().unwrap().push(name))
+        };
+        let reader = |name: &'static str| {
+            let order = order.clone();
+            SystemBuilder::new(name)
+                .read_resource::<Resource>()
+                .build(move |_, _, _, _| order.lock().unwrap().push(name))
+        };
+
+        let mut schedule = Schedule::builder()
+            .add_system(writer("render"))
+            .after("physics")
+            .add_system(writer("input"))
+            .before("physics")
+            .add_system(writer("collisions"))
+            .label("physics")
+            .add_system(writer("integrate"))
+            .label("physics")
+            .after("collisions")
+            .build();
+
+        for _ in 0..4 {
+            schedule.execute(&mut world, &mut resources);
+            assert_eq!(
+                *order.lock().unwrap(),
+                vec!["input", "collisions", "integrate", "render"]
+            );
+            order.lock().unwrap().clear();
+        }
+
+        // systems with no conflicting data access still respect explicit ordering
+        let mut schedule = Schedule::builder()
+            .add_system(reader("b"))
+            .after("a")
+            .add_system(reader("a"))
+            .build();
+
+        for _ in 0..4 {
+            schedule.execute(&mut world, &mut resources);
+            assert_eq!(*order.lock().unwrap(), vec!["a", "b"]);
+            order.lock().unwrap().clear();
+        }
+    }
+
+    #[test]
+    fn ordering_cycle() {
+        let system = |name: &'static str| SystemBuilder::new(name).build(|_, _, _, _| {});
+
+        let result = Schedule::builder()
+            .add_system(system("a"))
+            .before("b")
+            .add_system(system("b"))
+            .before("c")
+            .add_system(system("c"))
+            .before("a")
+            .add_system(system("d"))
+            .after("c")
+            .try_build();
+
+        let err = result.err().unwrap();
+        assert_eq!(
+            err,
+            ScheduleError::Cycle(vec!["a".into(), "b".into(), "c".into()])
+        );
+        assert!(err.to_string().ends_with("a -> b -> c -> a"));
+    }
+
+    #[test]
+    fn ordering_across_flush() {
+        let system = |name: &'static str| SystemBuilder::new(name).build(|_, _, _, _| {});
+
+        let result = Schedule::builder()
+            .add_system(system("a"))
+            .flush()
+            .add_system(system("b"))
+            .before("a")
+            .try_build();
+
+        assert_eq!(
+            result.err(),
+            Some(ScheduleError::Unsatisfiable {
+                before: "b".into(),
+                after: "a".into(),
+            })
+        );
+    }
+
+    #[test]
+    fn ordering_unknown_label() {
+        let system = |name: &'static str| SystemBuilder::new(name).build(|_, _, _, _| {});
+
+        let result = Schedule::builder()
+            .add_system(system("a"))
+            .label("physics")
+            .flush()
+            .add_system(system("b"))
+            .after("physics")
+            .add_system(system("c"))
+            .before("rendering")
+            .try_build();
+
+        assert_eq!(
+            result.err(),
+            Some(ScheduleError::UnknownLabel {
+                system: "c".into(),
+                label: "rendering".into(),
+            })
+        );
+    }
+
     #[test]
     fn flush() {
         let mut world = World::default();
@@ -668,6 +1559,45 @@ mod tests {
         schedule.execute(&mut world, &mut resources);
     }
 
+    #[test]
+    fn read_removals() {
+        use crate::internals::storage::removed::RemovedReader;
+
+        let mut world = World::default();
+        let mut resources = Resources::default();
+
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        struct TestComp(f32, f32, f32);
+
+        world.track_removals::<TestComp>();
+        let entity = world.push((TestComp(0., 0., 0.),));
+        let removed = Arc::new(Mutex::new(Vec::new()));
+
+        let system_one = SystemBuilder::new("one").build(move |cmd, _, _, _| {
+            cmd.remove(entity);
+        });
+        let removed_clone = removed.clone();
+        let mut reader = RemovedReader::<TestComp>::default();
+        let system_two = SystemBuilder::new("two").build(move |_, world, _, _| {
+            removed_clone
+                .lock()
+                .unwrap()
+                .push(reader.read(world).collect::<Vec<_>>());
+        });
+
+        let mut schedule = Schedule::builder()
+            .add_system(system_one)
+            .add_system(system_two)
+            .build();
+
+        for _ in 0..3 {
+            schedule.execute(&mut world, &mut resources);
+        }
+
+        assert_eq!(*removed.lock().unwrap(), vec![vec![], vec![entity], vec![]]);
+        assert_eq!(world.removals().iter::<TestComp>().count(), 0);
+    }
+
     #[test]
     fn flush_thread_local() {
         let mut world = World::default();
@@ -2,6 +2,7 @@
 
 use super::{
     command::CommandBuffer,
+    events::{Events, ReadEvents, WriteEvents},
     resources::{Resource, ResourceSet, ResourceTypeId, UnsafeResources},
     schedule::Runnable,
 };
@@ -21,7 +22,7 @@ use crate::internals::{
     world::{World, WorldId},
 };
 use bit_set::BitSet;
-use std::{any::TypeId, borrow::Cow, collections::HashMap, marker::PhantomData};
+use std::{any::TypeId, borrow::Cow, collections::HashMap};
 
 /// Provides an abstraction across tuples of queries for system closures.
 pub trait QuerySet: Send + Sync {
@@ -108,10 +109,6 @@ impl<T: Into<Cow<'static, str>>> From<T> for SystemId {
     }
 }
 
-struct ResourceMarker<T>(PhantomData<*const T>);
-unsafe impl<T: Send> Send for ResourceMarker<T> {}
-unsafe impl<T: Sync> Sync for ResourceMarker<T> {}
-
 /// The concrete type which contains the system closure provided by the user.  This struct should
 /// not be constructed directly, and instead should be created using `SystemBuilder`.
 ///
@@ -121,7 +118,7 @@ unsafe impl<T: Sync> Sync for ResourceMarker<T> {}
 /// information about what queries this system will run and, as a result, its data access.
 pub struct System<R, Q, F> {
     name: Option<SystemId>,
-    _resources: ResourceMarker<R>,
+    resources: R,
     queries: Q,
     run_fn: F,
     archetypes: ArchetypeAccess,
@@ -131,7 +128,7 @@ pub struct System<R, Q, F> {
 
 impl<R, Q, F> Runnable for System<R, Q, F>
 where
-    R: for<'a> ResourceSet<'a>,
+    R: for<'a> ResourceSet<'a> + 'static,
     Q: QuerySet,
     F: SystemFn<R, Q>,
 {
@@ -175,8 +172,11 @@ where
         // As the fetch struct is created on the stack here, and the resources it is holding onto is a parameter to this function,
         // we know for certain that the lifetime of the fetch struct (which constrains the lifetime of the resource the system sees)
         // must be shorter than the lifetime of the resource.
+        // The same applies to any state held by the resource set, such as event reader cursors,
+        // which is exclusively borrowed through &mut self.
         let resources_static = &*(resources as *const UnsafeResources);
-        let mut resources = R::fetch_unchecked(resources_static);
+        let resource_set = &mut *(&mut self.resources as *mut R);
+        let mut resources = resource_set.fetch_unchecked_stateful(resources_static);
 
         let queries = &mut self.queries;
         let component_access = ComponentAccess::Allow(Cow::Borrowed(&self.access.components));
@@ -368,6 +368,52 @@ where
         }
     }
 
+    /// Declares that this system reads events of type `T`, providing an
+    /// [EventReader](struct.EventReader.html) to the system. The reader remembers which events
+    /// the system has already read.
+    ///
+    /// The `Events<T>` resource is marked as read by this system.
+    pub fn read_events<T>(mut self) -> SystemBuilder<Q, <R as ConsAppend<ReadEvents<T>>>::Output>
+    where
+        T: 'static + Resource,
+        R: ConsAppend<ReadEvents<T>>,
+        <R as ConsAppend<ReadEvents<T>>>::Output: ConsFlatten,
+    {
+        self.resource_access
+            .push_read(ResourceTypeId::of::<Events<T>>());
+
+        SystemBuilder {
+            name: self.name,
+            queries: self.queries,
+            resources: ConsAppend::append(self.resources, ReadEvents::<T>::default()),
+            resource_access: self.resource_access,
+            component_access: self.component_access,
+            access_all_archetypes: self.access_all_archetypes,
+        }
+    }
+
+    /// Declares that this system sends events of type `T`, providing an
+    /// [EventWriter](struct.EventWriter.html) to the system.
+    ///
+    /// The `Events<T>` resource is marked as written by this system.
+    pub fn write_events<T>(mut self) -> SystemBuilder<Q, <R as ConsAppend<WriteEvents<T>>>::Output>
+    where
+        T: 'static + Resource,
+        R: ConsAppend<WriteEvents<T>>,
+        <R as ConsAppend<WriteEvents<T>>>::Output: ConsFlatten,
+    {
+        self.resource_access.push(ResourceTypeId::of::<Events<T>>());
+
+        SystemBuilder {
+            name: self.name,
+            queries: self.queries,
+            resources: ConsAppend::append(self.resources, WriteEvents::<T>::default()),
+            resource_access: self.resource_access,
+            component_access: self.component_access,
+            access_all_archetypes: self.access_all_archetypes,
+        }
+    }
+
     /// This performs a soft resource block on the component for writing. The dispatcher will
     /// generally handle dispatching read and writes on components based on archetype, allowing
     /// for more granular access and more parallelization of systems.
@@ -429,7 +475,7 @@ where
         System {
             name: self.name,
             run_fn,
-            _resources: ResourceMarker(PhantomData),
+            resources: self.resources.flatten(),
             queries: self.queries.flatten(),
             archetypes: if self.access_all_archetypes {
                 ArchetypeAccess::All
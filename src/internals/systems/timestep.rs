@@ -0,0 +1,151 @@
+//! Contains types related to running schedules at a fixed rate.
+
+use std::time::Duration;
+
+/// A time accumulator resource which drives [fixed timestep](enum.Step.html#variant.FixedTimestep)
+/// schedule steps.
+///
+/// Add the elapsed frame time with [accumulate](#method.accumulate) before executing the
+/// outer schedule. Each fixed timestep step then runs its child schedule once for every whole
+/// timestep which has accumulated, consuming that time from the accumulator.
+///
+/// # Examples
+///
+/// ```
+/// # use legion::*;
+/// # use legion::systems::FixedTime;
+/// # use std::time::Duration;
+/// # let physics = SystemBuilder::new("physics").build(|_, _, _, _| {});
+/// let mut world = World::default();
+/// let mut resources = Resources::default();
+/// resources.insert(FixedTime::new(Duration::from_secs(1) / 60));
+///
+/// let mut schedule = Schedule::builder()
+///     .add_fixed_timestep(Schedule::builder().add_system(physics).build())
+///     .build();
+///
+/// // once per frame
+/// let frame_time = Duration::from_millis(20);
+/// resources.get_mut::<FixedTime>().unwrap().accumulate(frame_time);
+/// schedule.execute(&mut world, &mut resources);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct FixedTime {
+    timestep: Duration,
+    accumulator: Duration,
+    max_steps: Option<u32>,
+}
+
+impl FixedTime {
+    /// Constructs a new accumulator which runs fixed timestep schedules once per `timestep`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `timestep` is zero.
+    pub fn new(timestep: Duration) -> Self {
+        assert!(
+            timestep > Duration::from_secs(0),
+            "fixed timestep must be greater than zero"
+        );
+        Self {
+            timestep,
+            accumulator: Duration::from_secs(0),
+            max_steps: None,
+        }
+    }
+
+    /// Limits the number of times a fixed timestep schedule may run within a single execution
+    /// of its parent schedule. When the limit is reached, any further whole timesteps are
+    /// discarded from the accumulator so that a slow frame cannot cause ever longer frames.
+    pub fn with_max_steps(mut self, max_steps: u32) -> Self {
+        self.max_steps = Some(max_steps);
+        self
+    }
+
+    /// Returns the duration simulated by each run of a fixed timestep schedule.
+    pub fn timestep(&self) -> Duration {
+        self.timestep
+    }
+
+    /// Returns the time which has been accumulated but not yet consumed.
+    pub fn accumulator(&self) -> Duration {
+        self.accumulator
+    }
+
+    /// Returns the fraction of a timestep which remains in the accumulator, for use in
+    /// interpolating between the two most recent fixed updates.
+    pub fn alpha(&self) -> f64 {
+        self.accumulator.as_secs_f64() / self.timestep.as_secs_f64()
+    }
+
+    /// Adds elapsed time to the accumulator.
+    pub fn accumulate(&mut self, elapsed: Duration) {
+        self.accumulator += elapsed;
+    }
+
+    /// Consumes a timestep from the accumulator, returning `true` if a whole timestep
+    /// had accumulated. `iteration` is the number of timesteps already consumed during
+    /// this execution.
+    pub(crate) fn consume(&mut self, iteration: u32) -> bool {
+        if self.accumulator < self.timestep {
+            return false;
+        }
+
+        if let Some(max_steps) = self.max_steps {
+            if iteration >= max_steps {
+                // discard whole timesteps, in nanoseconds to avoid overflowing the step count
+                let remainder = self.accumulator.as_nanos() % self.timestep.as_nanos();
+                self.accumulator = Duration::from_nanos(remainder as u64);
+                return false;
+            }
+        }
+
+        self.accumulator -= self.timestep;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn consume() {
+        let mut time = FixedTime::new(Duration::from_millis(10));
+        time.accumulate(Duration::from_millis(25));
+
+        assert!(time.consume(0));
+        assert!(time.consume(1));
+        assert!(!time.consume(2));
+        assert_eq!(time.accumulator(), Duration::from_millis(5));
+        assert!((time.alpha() - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn max_steps() {
+        let mut time = FixedTime::new(Duration::from_millis(10)).with_max_steps(2);
+        time.accumulate(Duration::from_millis(55));
+
+        assert!(time.consume(0));
+        assert!(time.consume(1));
+        assert!(!time.consume(2));
+        assert_eq!(time.accumulator(), Duration::from_millis(5));
+    }
+
+    #[test]
+    fn max_steps_long_stall() {
+        let mut time = FixedTime::new(Duration::from_nanos(1)).with_max_steps(1);
+        time.accumulate(Duration::from_secs(10) + Duration::from_nanos(u32::MAX as u64));
+
+        assert!(time.consume(0));
+        assert!(!time.consume(1));
+        assert_eq!(time.accumulator(), Duration::from_nanos(0));
+
+        let mut time = FixedTime::new(Duration::from_millis(3)).with_max_steps(1);
+        time.accumulate(Duration::from_secs(60 * 60 * 24 * 365 * 100) + Duration::from_millis(1));
+
+        assert!(time.consume(0));
+        assert!(!time.consume(1));
+        assert_eq!(time.accumulator(), Duration::from_millis(1));
+    }
+}
@@ -0,0 +1,394 @@
+//! Contains types related to instrumenting legion with the `tracing` crate.
+
+/// Enters a `tracing` span for the remainder of the enclosing block when the `tracing`
+/// feature is enabled. The span's fields are not evaluated otherwise.
+macro_rules! trace_span {
+    ($($args:tt)*) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!($($args)*).entered();
+    };
+}
+
+#[cfg(feature = "tracing")]
+pub use chrome::ChromeTraceSubscriber;
+
+#[cfg(feature = "tracing")]
+mod chrome {
+    use parking_lot::Mutex;
+    use std::{
+        collections::HashMap,
+        fmt::{Debug, Write as _},
+        fs::File,
+        io::{BufWriter, Write},
+        path::Path,
+        sync::Arc,
+        thread::ThreadId,
+        time::{Duration, Instant},
+    };
+    use tracing::{
+        field::{Field, Visit},
+        span::{Attributes, Id, Record},
+        Event, Metadata, Subscriber,
+    };
+
+    /// A `tracing` subscriber which records spans as Chrome trace events, for viewing in a
+    /// trace viewer such as `chrome://tracing` or [Perfetto](https://ui.perfetto.dev).
+    ///
+    /// Each span is recorded as a complete event on the thread which entered it, with the span's
+    /// fields as the event's arguments. Events are recorded as instant events.
+    ///
+    /// The subscriber can be cloned, with all clones sharing the same recording, so that a clone
+    /// can be installed as the default subscriber while another is kept to write out the trace.
+    /// As systems may run on the worker threads of a thread pool, the subscriber should usually
+    /// be installed as the global default.
+    ///
+    /// System spans record the system's `name` and the number of `entities` in the archetypes its
+    /// queries match, and flush spans record the `system` whose commands are being flushed.
+    ///
+    /// This is a small alternative to the `tracing-chrome` crate, which would pull
+    /// `tracing-subscriber` and `serde_json` into every build with the `tracing` feature enabled,
+    /// and which streams every event to an output chosen up front. This subscriber instead keeps
+    /// its recording in memory, to be written or cleared at any point, such as around a chosen
+    /// frame.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use legion::*;
+    /// # use legion::trace::ChromeTraceSubscriber;
+    /// # let mut world = World::default();
+    /// # let mut resources = Resources::default();
+    /// # let mut schedule = Schedule::builder().build();
+    /// let trace = ChromeTraceSubscriber::new();
+    /// tracing::subscriber::set_global_default(trace.clone()).unwrap();
+    ///
+    /// schedule.execute(&mut world, &mut resources);
+    ///
+    /// let mut json = Vec::new();
+    /// trace.write(&mut json).unwrap();
+    /// // or trace.write_to_file("trace.json")
+    /// ```
+    #[derive(Clone)]
+    pub struct ChromeTraceSubscriber {
+        state: Arc<Mutex<TraceState>>,
+        start: Instant,
+    }
+
+    #[derive(Default)]
+    struct TraceState {
+        next_id: u64,
+        spans: HashMap<u64, SpanData>,
+        threads: HashMap<ThreadId, (u64, Option<String>)>,
+        events: Vec<TraceEvent>,
+    }
+
+    struct SpanData {
+        name: &'static str,
+        args: Vec<(&'static str, String)>,
+        references: usize,
+        entered: Vec<(u64, Duration)>,
+    }
+
+    struct TraceEvent {
+        name: &'static str,
+        complete: bool,
+        thread: u64,
+        start: Duration,
+        duration: Duration,
+        args: Vec<(&'static str, String)>,
+    }
+
+    impl TraceState {
+        fn thread(&mut self) -> u64 {
+            let thread = std::thread::current();
+            let count = self.threads.len() as u64;
+            self.threads
+                .entry(thread.id())
+                .or_insert_with(|| (count + 1, thread.name().map(|name| name.to_string())))
+                .0
+        }
+    }
+
+    /// Records field values as JSON values.
+    struct ArgVisitor<'a>(&'a mut Vec<(&'static str, String)>);
+
+    impl<'a> Visit for ArgVisitor<'a> {
+        fn record_i64(&mut self, field: &Field, value: i64) {
+            self.0.push((field.name(), value.to_string()));
+        }
+
+        fn record_u64(&mut self, field: &Field, value: u64) {
+            self.0.push((field.name(), value.to_string()));
+        }
+
+        fn record_bool(&mut self, field: &Field, value: bool) {
+            self.0.push((field.name(), value.to_string()));
+        }
+
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.0.push((field.name(), json_string(value)));
+        }
+
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            self.0
+                .push((field.name(), json_string(&format!("{:?}", value))));
+        }
+    }
+
+    fn json_string(value: &str) -> String {
+        let mut json = String::with_capacity(value.len() + 2);
+        json.push('"');
+        for c in value.chars() {
+            match c {
+                '"' => json.push_str("\\\""),
+                '\\' => json.push_str("\\\\"),
+                '\n' => json.push_str("\\n"),
+                '\r' => json.push_str("\\r"),
+                '\t' => json.push_str("\\t"),
+                c if (c as u32) < 0x20 => write!(json, "\\u{:04x}", c as u32).unwrap(),
+                c => json.push(c),
+            }
+        }
+        json.push('"');
+        json
+    }
+
+    fn micros(duration: Duration) -> f64 {
+        duration.as_secs_f64() * 1_000_000.0
+    }
+
+    impl ChromeTraceSubscriber {
+        /// Constructs a new subscriber. Timestamps are recorded relative to this call.
+        pub fn new() -> Self {
+            Self {
+                state: Arc::new(Mutex::new(TraceState::default())),
+                start: Instant::now(),
+            }
+        }
+
+        /// Writes all events recorded so far in the Chrome trace event JSON format.
+        pub fn write<W: Write>(&self, mut writer: W) -> std::io::Result<()> {
+            let state = self.state.lock();
+            write!(writer, "{{\"traceEvents\":[")?;
+            let mut first = true;
+            for (thread, name) in state.threads.values() {
+                if let Some(name) = name {
+                    if !std::mem::replace(&mut first, false) {
+                        write!(writer, ",")?;
+                    }
+                    write!(
+                        writer,
+                        "{{\"name\":\"thread_name\",\"ph\":\"M\",\"pid\":1,\"tid\":{},\"args\":{{\"name\":{}}}}}",
+                        thread,
+                        json_string(name)
+                    )?;
+                }
+            }
+            for event in &state.events {
+                if !std::mem::replace(&mut first, false) {
+                    write!(writer, ",")?;
+                }
+                write!(
+                    writer,
+                    "{{\"name\":{},\"cat\":\"legion\",\"pid\":1,\"tid\":{},\"ts\":{:.3},",
+                    json_string(event.name),
+                    event.thread,
+                    micros(event.start)
+                )?;
+                if event.complete {
+                    write!(
+                        writer,
+                        "\"ph\":\"X\",\"dur\":{:.3},",
+                        micros(event.duration)
+                    )?;
+                } else {
+                    write!(writer, "\"ph\":\"i\",\"s\":\"t\",")?;
+                }
+                write!(writer, "\"args\":{{")?;
+                for (i, (name, value)) in event.args.iter().enumerate() {
+                    if i > 0 {
+                        write!(writer, ",")?;
+                    }
+                    write!(writer, "{}:{}", json_string(name), value)?;
+                }
+                write!(writer, "}}}}")?;
+            }
+            write!(writer, "],\"displayTimeUnit\":\"ms\"}}")?;
+            writer.flush()
+        }
+
+        /// Writes all events recorded so far to a file, in the Chrome trace event JSON format.
+        pub fn write_to_file<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+            self.write(BufWriter::new(File::create(path)?))
+        }
+
+        /// Discards all events recorded so far.
+        pub fn clear(&self) {
+            self.state.lock().events.clear();
+        }
+    }
+
+    impl Default for ChromeTraceSubscriber {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl Subscriber for ChromeTraceSubscriber {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            let mut args = Vec::new();
+            span.record(&mut ArgVisitor(&mut args));
+
+            let mut state = self.state.lock();
+            state.next_id += 1;
+            let id = state.next_id;
+            state.spans.insert(
+                id,
+                SpanData {
+                    name: span.metadata().name(),
+                    args,
+                    references: 1,
+                    entered: Vec::new(),
+                },
+            );
+            Id::from_u64(id)
+        }
+
+        fn record(&self, span: &Id, values: &Record<'_>) {
+            if let Some(data) = self.state.lock().spans.get_mut(&span.into_u64()) {
+                values.record(&mut ArgVisitor(&mut data.args));
+            }
+        }
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let mut args = Vec::new();
+            event.record(&mut ArgVisitor(&mut args));
+            let start = self.start.elapsed();
+
+            let mut state = self.state.lock();
+            let thread = state.thread();
+            state.events.push(TraceEvent {
+                name: event.metadata().name(),
+                complete: false,
+                thread,
+                start,
+                duration: Duration::default(),
+                args,
+            });
+        }
+
+        fn enter(&self, span: &Id) {
+            let start = self.start.elapsed();
+            let mut state = self.state.lock();
+            let thread = state.thread();
+            if let Some(data) = state.spans.get_mut(&span.into_u64()) {
+                data.entered.push((thread, start));
+            }
+        }
+
+        fn exit(&self, span: &Id) {
+            let end = self.start.elapsed();
+            let mut state = self.state.lock();
+            let thread = state.thread();
+            let event = state.spans.get_mut(&span.into_u64()).and_then(|data| {
+                let index = data.entered.iter().rposition(|(t, _)| *t == thread)?;
+                let (_, start) = data.entered.remove(index);
+                Some(TraceEvent {
+                    name: data.name,
+                    complete: true,
+                    thread,
+                    start,
+                    duration: end - start,
+                    args: data.args.clone(),
+                })
+            });
+            if let Some(event) = event {
+                state.events.push(event);
+            }
+        }
+
+        fn clone_span(&self, span: &Id) -> Id {
+            if let Some(data) = self.state.lock().spans.get_mut(&span.into_u64()) {
+                data.references += 1;
+            }
+            span.clone()
+        }
+
+        fn try_close(&self, span: Id) -> bool {
+            let mut state = self.state.lock();
+            let id = span.into_u64();
+            let closed = match state.spans.get_mut(&id) {
+                Some(data) => {
+                    data.references -= 1;
+                    data.references == 0
+                }
+                None => false,
+            };
+            if closed {
+                state.spans.remove(&id);
+            }
+            closed
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::internals::{
+            query::{view::read::Read, IntoQuery},
+            systems::{resources::Resources, schedule::Schedule, system::SystemBuilder},
+            world::World,
+        };
+
+        #[test]
+        fn chrome_trace() {
+            let mut world = World::default();
+            let mut resources = Resources::default();
+            world.extend(vec![(1u8,), (2u8,)]);
+            let mut schedule = Schedule::builder()
+                .add_system(
+                    SystemBuilder::new("spawner")
+                        .with_query(Read::<u8>::query())
+                        .build(|cmd, _, _, _| {
+                            cmd.push((1usize,));
+                        }),
+                )
+                .build();
+
+            // systems may run on worker threads, so the subscriber must be the global default
+            let trace = ChromeTraceSubscriber::new();
+            tracing::subscriber::set_global_default(trace.clone()).unwrap();
+            schedule.execute(&mut world, &mut resources);
+
+            let mut json = Vec::new();
+            trace.write(&mut json).unwrap();
+            let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+            let events = json["traceEvents"].as_array().unwrap();
+            // other tests may run concurrently and also be recorded
+            let find = |name: &str, arg: &str, value: serde_json::Value| {
+                events
+                    .iter()
+                    .find(|event| event["name"] == name && event["args"][arg] == value)
+                    .unwrap_or_else(|| panic!("no {} event", name))
+            };
+
+            let system = find("system", "name", "spawner".into());
+            assert_eq!(system["ph"], "X");
+            assert_eq!(system["args"]["entities"], 2);
+            assert_eq!(find("flush", "system", "spawner".into())["ph"], "X");
+            assert_eq!(find("command_buffer", "commands", 1.into())["ph"], "X");
+            assert_eq!(find("create_archetype", "components", 1.into())["ph"], "X");
+        }
+
+        #[test]
+        fn escape_json() {
+            assert_eq!(json_string("a\"b\\c\n\u{1}"), "\"a\\\"b\\\\c\\n\\u0001\"");
+        }
+    }
+}
@@ -1,32 +1,43 @@
 //! Contains types related to the [World](struct.World.html) entity collection.
 
 use super::entity::{
-    Allocate, Entity, EntityHasher, EntityLocation, LocationMap, ID_CLONE_MAPPINGS,
+    Allocate, Entity, EntityAllocator, EntityHasher, EntityLocation, LocationMap, SharedAllocator,
+    ID_CLONE_MAPPINGS,
 };
 use super::insert::{ArchetypeSource, ArchetypeWriter, ComponentSource, IntoComponentSource};
 use super::{
     entry::{Entry, EntryMut, EntryRef},
-    event::{EventSender, Subscriber, Subscribers},
+    event::{Event, EventSender, RemovedCapture, RemovedCaptures, Subscriber, Subscribers},
+    hooks::{ComponentHooks, HookKind},
     query::{
         filter::{EntityFilter, LayoutFilter},
         view::{IntoView, View},
         Query,
     },
+    snapshot::HeldIds,
     storage::{
         archetype::{Archetype, ArchetypeIndex, EntityLayout},
         component::{Component, ComponentTypeId},
         group::{Group, GroupDef},
         index::SearchIndex,
-        ComponentIndex, Components, PackOptions, UnknownComponentStorage,
+        next_component_version,
+        removed::RemovalLog,
+        sparse::{SparseComponents, SparseStorage, UnknownSparseStorage},
+        ComponentIndex, Components, PackOptions, UnknownComponentStorage, Version,
     },
     subworld::{ComponentAccess, SubWorld},
 };
 use bit_set::BitSet;
 use itertools::Itertools;
 use std::{
+    cell::RefCell,
     collections::HashMap,
     ops::Range,
-    sync::atomic::{AtomicU64, Ordering},
+    rc::Rc,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, OnceLock,
+    },
 };
 
 type MapEntry<'a, K, V> = std::collections::hash_map::Entry<'a, K, V>;
@@ -58,6 +69,14 @@ pub trait EntityStore {
     fn get_component_storage<V: for<'b> View<'b>>(
         &self,
     ) -> Result<StorageAccessor, EntityAccessError>;
+
+    /// Returns the log of entities which have recently lost components.
+    ///
+    /// Defaults to an empty log, for stores which do not record removals.
+    fn removals(&self) -> &RemovalLog {
+        static EMPTY: OnceLock<RemovalLog> = OnceLock::new();
+        EMPTY.get_or_init(RemovalLog::default)
+    }
 }
 
 /// Unique identifier for a [world](struct.World.html).
@@ -78,11 +97,27 @@ impl Default for WorldId {
 }
 
 /// Describes configuration options for the creation of a new [world](struct.World.html).
-#[derive(Default)]
 pub struct WorldOptions {
     /// A vector of component [groups](../storage/struct.Group.html) to provide
     /// layout hints for query optimization.
     pub groups: Vec<GroupDef>,
+    /// The [allocator](trait.EntityAllocator.html) from which the world draws new entity IDs.
+    /// Defaults to the process-wide [SharedAllocator](struct.SharedAllocator.html).
+    ///
+    /// Giving a world its own allocator, such as a new
+    /// [GenerationalAllocator](struct.GenerationalAllocator.html), makes the IDs it assigns
+    /// deterministic. Entity IDs from worlds with different allocators may collide, so entities
+    /// should not be moved or cloned between such worlds while preserving their IDs.
+    pub allocator: Arc<dyn EntityAllocator>,
+}
+
+impl Default for WorldOptions {
+    fn default() -> Self {
+        Self {
+            groups: Vec::default(),
+            allocator: Arc::new(SharedAllocator),
+        }
+    }
 }
 
 /// A container of entities.
@@ -100,8 +135,13 @@ pub struct World {
     group_members: HashMap<ComponentTypeId, usize>,
     archetypes: Vec<Archetype>,
     entities: LocationMap,
+    allocator: Allocate,
     allocation_buffer: Vec<Entity>,
     subscribers: Subscribers,
+    captures: RemovedCaptures,
+    hooks: ComponentHooks,
+    removals: RemovalLog,
+    held_ids: HeldIds,
 }
 
 impl Default for World {
@@ -136,8 +176,13 @@ impl World {
             group_members,
             archetypes: Vec::default(),
             entities: LocationMap::default(),
+            allocator: Allocate::with_allocator(options.allocator),
             allocation_buffer: Vec::default(),
             subscribers: Subscribers::default(),
+            captures: RemovedCaptures::default(),
+            hooks: ComponentHooks::default(),
+            removals: RemovalLog::default(),
+            held_ids: HeldIds::default(),
         }
     }
 
@@ -146,6 +191,19 @@ impl World {
         self.id
     }
 
+    /// Returns the [allocator](trait.EntityAllocator.html) from which the world draws new entity IDs.
+    pub fn entity_allocator(&self) -> &Arc<dyn EntityAllocator> {
+        self.allocator.allocator()
+    }
+
+    pub(crate) fn allocate(&self) -> &Allocate {
+        &self.allocator
+    }
+
+    pub(crate) fn allocate_mut(&mut self) -> &mut Allocate {
+        &mut self.allocator
+    }
+
     /// Returns the number of entities in the world.
     pub fn len(&self) -> usize {
         self.entities.len()
@@ -166,18 +224,23 @@ impl World {
     where
         Option<T>: IntoComponentSource,
     {
-        self.remove(entity_id);
+        self.remove_retain_id(entity_id);
 
         let mut components = <Option<T> as IntoComponentSource>::into(Some(components));
 
         let arch_index = self.get_archetype_for_components(&mut components);
-        let archetype = &mut self.archetypes[arch_index.0 as usize];
-        let mut writer =
-            ArchetypeWriter::new(arch_index, archetype, self.components.get_multi_mut());
-        components.push_components(&mut writer, std::iter::once(entity_id));
+        let base = {
+            let archetype = &mut self.archetypes[arch_index.0 as usize];
+            let mut writer =
+                ArchetypeWriter::new(arch_index, archetype, self.components.get_multi_mut());
+            components.push_components(&mut writer, std::iter::once(entity_id));
 
-        let (base, entities) = writer.inserted();
-        self.entities.insert(entities, arch_index, base);
+            let (base, entities) = writer.inserted();
+            self.entities.insert(entities, arch_index, base);
+            base
+        };
+        self.run_on_add(arch_index, base);
+        self.flush_sparse(arch_index);
     }
 
     /// Appends a new entity to the world. Returns the ID of the new entity.
@@ -234,47 +297,185 @@ impl World {
     /// ```
     /// SoA inserts require all vectors to have the same length. These inserts are faster than inserting via an iterator of tuples.
     pub fn extend(&mut self, components: impl IntoComponentSource) -> &[Entity] {
-        let replaced = {
+        let (arch_index, base, replaced) = {
             let mut components = components.into();
 
             let arch_index = self.get_archetype_for_components(&mut components);
             let archetype = &mut self.archetypes[arch_index.0 as usize];
             let mut writer =
                 ArchetypeWriter::new(arch_index, archetype, self.components.get_multi_mut());
-            components.push_components(&mut writer, Allocate::new());
+            components.push_components(&mut writer, &mut self.allocator);
 
             let (base, entities) = writer.inserted();
             self.allocation_buffer.clear();
             self.allocation_buffer.extend_from_slice(entities);
-            self.entities.insert(entities, arch_index, base)
+            let replaced = self.entities.insert(entities, arch_index, base);
+            (arch_index, base, replaced)
         };
 
-        for location in replaced {
-            self.remove_at_location(location);
-        }
+        self.run_on_add(arch_index, base);
+        self.remove_replaced(replaced);
+        self.flush_sparse(arch_index);
 
         &self.allocation_buffer
     }
 
     /// Removes the specified entity from the world. Returns `true` if an entity was removed.
+    ///
+    /// The entity's ID is released, and its index may be reused by a new entity with a
+    /// later generation. The removal of each of the entity's components is recorded in the
+    /// world's [removal log](../storage/struct.RemovalLog.html) if the component type is
+    /// [tracked](#method.track_removals).
+    ///
+    /// If the entity has [children](../hierarchy/struct.Children.html), they are removed too. If it
+    /// is the child of another entity, it is detached from its parent.
     pub fn remove(&mut self, entity: Entity) -> bool {
-        let location = self.entities.remove(entity);
-        if let Some(location) = location {
-            self.remove_at_location(location);
-            true
-        } else {
-            false
+        self.remove_with_children(entity, true)
+    }
+
+    /// Removes an entity along with its children, detaching it from its parent and recording
+    /// the removal of each component. The IDs of the removed entities are released if `release`
+    /// is `true`. Returns `true` if an entity was removed.
+    pub(crate) fn remove_with_children(&mut self, entity: Entity, release: bool) -> bool {
+        if !self.contains(entity) {
+            return false;
+        }
+
+        let mut pending = vec![entity];
+        while let Some(entity) = pending.pop() {
+            pending.extend(self.take_relations(entity));
+
+            if self.remove_entity(entity, true) && release {
+                self.release_id(entity);
+            }
+        }
+
+        true
+    }
+
+    /// Releases the ID of an entity which has been removed from the world, unless the world
+    /// holds the ID for one of its snapshots.
+    pub(crate) fn release_id(&mut self, entity: Entity) {
+        self.held_ids
+            .release(self.allocator.allocator().as_ref(), entity);
+    }
+
+    /// Takes back the ID of an entity which was removed while a snapshot containing it was
+    /// alive. Returns `true` if the world held the ID.
+    pub(crate) fn take_held_id(&mut self, entity: Entity) -> bool {
+        self.held_ids.take(entity)
+    }
+
+    pub(crate) fn held_ids(&self) -> &HeldIds {
+        &self.held_ids
+    }
+
+    /// Removes the specified entity from the world without releasing its ID, as the ID is
+    /// about to be reinserted.
+    ///
+    /// Any other living entity which occupies the same index with a different generation is
+    /// removed too, without releasing its ID, as its index is about to be taken.
+    pub(crate) fn remove_retain_id(&mut self, entity: Entity) -> bool {
+        if let Some(occupant) = self.entities.occupant(entity.index()) {
+            if occupant != entity {
+                self.remove_entity(occupant, true);
+            }
+        }
+        self.remove_entity(entity, false)
+    }
+
+    /// Removes the entities at the locations returned by `LocationMap::insert`. Entities which
+    /// were replaced by one with the same ID are removed silently, while those which were
+    /// displaced from their index by an entity with a different generation are removed along
+    /// with their sparse components, and their removal is recorded.
+    fn remove_replaced(&mut self, mut replaced: Vec<EntityLocation>) {
+        // remove from the back of each archetype first, so that swap removals do not move the
+        // entities which are still to be removed
+        replaced.sort_by_key(|location| std::cmp::Reverse(location.component()));
+        for location in replaced {
+            let occupant = self.archetypes[location.archetype()].entities()[location.component().0];
+            if self.entities.contains(occupant) {
+                self.remove_at_location(location);
+            } else {
+                self.remove_entity_at(occupant, location, true);
+            }
+        }
+    }
+
+    /// Removes the specified entity from the world without releasing its ID, optionally
+    /// recording the removal of each of its components in the removal log.
+    pub(crate) fn remove_entity(&mut self, entity: Entity, record: bool) -> bool {
+        match self.entities.remove(entity) {
+            Some(location) => {
+                self.remove_entity_at(entity, location, record);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Removes an entity, which has already been removed from the location map, from its
+    /// archetype and from sparse storage.
+    fn remove_entity_at(&mut self, entity: Entity, location: EntityLocation, record: bool) {
+        let archetype = &mut self.archetypes[location.archetype()];
+        let version = next_component_version();
+        if record {
+            for type_id in archetype.layout().component_types() {
+                self.removals.record_at(*type_id, entity, version);
+            }
         }
+
+        if self.components.sparse().has_components(entity) {
+            let capture = archetype.has_subscribers();
+            for (type_id, storage) in self.components.sparse_mut().iter_mut() {
+                self.hooks
+                    .run_sparse(HookKind::Remove, type_id, storage, entity);
+                if let Some(removed) = self
+                    .captures
+                    .remove_sparse(capture, type_id, storage, entity)
+                {
+                    if record {
+                        self.removals.record_at(type_id, entity, version);
+                    }
+                    if capture {
+                        archetype.notify(Event::ComponentRemoved(entity, type_id, removed));
+                    }
+                }
+            }
+            self.components.sparse_mut().removed_all(entity);
+        }
+
+        self.remove_at_location(location);
     }
 
     fn remove_at_location(&mut self, location: EntityLocation) {
         let EntityLocation(arch_index, component_index) = location;
         let archetype = &mut self.archetypes[arch_index];
-        archetype.swap_remove(component_index.0);
-        for type_id in archetype.layout().component_types() {
+        let entity = archetype.entities()[component_index.0];
+        let capture = archetype.has_subscribers();
+        let layout = archetype.layout().clone();
+        for type_id in layout.component_types() {
             let storage = self.components.get_mut(*type_id).unwrap();
-            storage.swap_remove(arch_index, component_index);
+            self.hooks.run_packed(
+                HookKind::Remove,
+                *type_id,
+                storage,
+                arch_index,
+                component_index,
+                &[entity],
+            );
+            let removed = self.captures.remove_packed(
+                capture,
+                *type_id,
+                storage,
+                arch_index,
+                component_index,
+            );
+            if capture {
+                archetype.notify(Event::ComponentRemoved(entity, *type_id, removed));
+            }
         }
+        archetype.swap_remove(component_index.0);
         if component_index.0 < archetype.entities().len() {
             let swapped = archetype.entities()[component_index.0];
             self.entities.set(swapped, location);
@@ -335,18 +536,61 @@ impl World {
         S: EventSender + 'static,
     {
         let subscriber = Subscriber::new(filter, sender);
+        let sparse = self.components.sparse();
         for arch in &mut self.archetypes {
-            if subscriber.is_interested(arch) {
+            let staging = arch
+                .layout()
+                .component_types()
+                .iter()
+                .any(|type_id| sparse.contains_type(*type_id));
+            if !staging && subscriber.is_interested(arch) {
                 arch.subscribe(subscriber.clone());
             }
         }
         self.subscribers.push(subscriber);
     }
 
+    /// Returns the log of entities which have recently lost components.
+    pub fn removals(&self) -> &RemovalLog {
+        &self.removals
+    }
+
+    /// Starts recording the removals of components of type `T` in the world's
+    /// [removal log](../storage/struct.RemovalLog.html), so that they can be read with a
+    /// [RemovedReader](../storage/struct.RemovedReader.html). Removals of untracked component
+    /// types are not recorded.
+    pub fn track_removals<T: Component>(&mut self) {
+        self.removals.track(ComponentTypeId::of::<T>());
+    }
+
+    /// Discards removals which have been retained in the [removal log](../storage/struct.RemovalLog.html)
+    /// for two updates.
+    ///
+    /// This is called by [Schedule::execute](../systems/struct.Schedule.html#method.execute). If you
+    /// track removals but do not use a schedule, call this once per frame to prevent the log from
+    /// growing indefinitely.
+    pub fn update_removals(&mut self) {
+        self.removals.update();
+    }
+
+    pub(crate) fn record_removal(&mut self, type_id: ComponentTypeId, entity: Entity) {
+        self.removals.record(type_id, entity);
+    }
+
+    pub(crate) fn record_removal_at(
+        &mut self,
+        type_id: ComponentTypeId,
+        entity: Entity,
+        version: Version,
+    ) {
+        self.removals.record_at(type_id, entity, version);
+    }
+
     /// Packs the world's internal component storage to optimise iteration performance for
     /// [queries](../query/index.html) which match a [group](../storage/struct.Group.html)
     /// defined when this world was created.
     pub fn pack(&mut self, options: PackOptions) {
+        trace_span!("pack", entities = self.len());
         self.components.pack(&options);
     }
 
@@ -359,6 +603,264 @@ impl World {
         &mut self.components
     }
 
+    /// Opts component type `T` into [sparse storage](../storage/struct.SparseStorage.html) for
+    /// this world.
+    ///
+    /// Sparse components are held outside of their entity's archetype, so adding or removing one
+    /// with [Entry::add_component](struct.Entry.html#method.add_component) or
+    /// [Entry::remove_component](struct.Entry.html#method.remove_component) is O(1) and does not
+    /// move the entity into another archetype. Entities may also be pushed into the world with `T`
+    /// as one of their initial components, in which case it is moved into sparse storage.
+    ///
+    /// Entries retrieved from the world or from a [SubWorld](struct.SubWorld.html) can access
+    /// sparse components as usual. As sparse components are not part of the entity's archetype,
+    /// queries can only request them as optional components, with `Option<&T>` or
+    /// `Option<&mut T>`; use [sparse_components](#method.sparse_components) to iterate just
+    /// the entities which have one.
+    ///
+    /// Sparse components are carried along by [move_from](#method.move_from), and are cloned by
+    /// [clone_from](#method.clone_from) when the [merger](trait.Merger.html) supports it, as
+    /// [Duplicate](struct.Duplicate.html) does.
+    ///
+    /// Sparse components are serialized, captured by [snapshots](#method.snapshot) and compared
+    /// by [diffs](#method.diff) along with each entity's other components. When a world is
+    /// deserialized into a world which has registered the same types as sparse, such as with
+    /// [Registry::as_deserialize_into_world](../serialize/struct.Registry.html#method.as_deserialize_into_world),
+    /// their components are moved back into sparse storage.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the world already stores `T` in an archetype.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use legion::*;
+    /// struct Stunned;
+    ///
+    /// let mut world = World::default();
+    /// world.register_sparse::<Stunned>();
+    ///
+    /// let entity = world.push((1usize,));
+    /// let archetype = world.entry(entity).unwrap().location().archetype();
+    ///
+    /// let mut entry = world.entry(entity).unwrap();
+    /// entry.add_component(Stunned);
+    /// assert!(entry.get_component::<Stunned>().is_ok());
+    /// assert_eq!(entry.location().archetype(), archetype);
+    ///
+    /// let mut query = <(Entity, Option<&Stunned>)>::query();
+    /// for (entity, stunned) in query.iter(&world) {
+    ///     println!("{:?} stunned: {}", entity, stunned.is_some());
+    /// }
+    /// ```
+    pub fn register_sparse<T: Component>(&mut self) {
+        let type_id = ComponentTypeId::of::<T>();
+        assert!(
+            self.components.sparse().contains_type(type_id)
+                || self.components.get(type_id).is_none(),
+            "component {} is already stored in archetypes",
+            type_id
+        );
+        self.components.sparse_mut().register::<T>();
+    }
+
+    /// Attaches a clone of each component of type `T` which is removed from an entity to the
+    /// [ComponentRemoved](enum.Event.html#variant.ComponentRemoved) events sent to subscribers.
+    ///
+    /// The component itself is dropped as soon as it is removed. Components are only cloned when
+    /// the entity's archetype has subscribers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use legion::*;
+    /// # use legion::world::Event;
+    /// #[derive(Clone)]
+    /// struct Handle(u32);
+    ///
+    /// let mut world = World::default();
+    /// world.clone_removed::<Handle>();
+    ///
+    /// let (tx, rx) = crossbeam_channel::unbounded::<Event>();
+    /// world.subscribe(tx, component::<Handle>());
+    ///
+    /// let entity = world.push((Handle(7),));
+    /// world.entry(entity).unwrap().remove_component::<Handle>();
+    ///
+    /// for event in rx.try_iter() {
+    ///     if let Event::ComponentRemoved(_, _, Some(removed)) = event {
+    ///         let handle = removed.downcast_ref::<Handle>().unwrap();
+    ///         println!("release handle {}", handle.0);
+    ///     }
+    /// }
+    /// ```
+    pub fn clone_removed<T: Component + Clone>(&mut self) {
+        self.captures
+            .insert(ComponentTypeId::of::<T>(), RemovedCapture::cloned::<T>());
+    }
+
+    /// Moves each component of type `T` which is removed from an entity into the
+    /// [ComponentRemoved](enum.Event.html#variant.ComponentRemoved) events sent to subscribers,
+    /// rather than dropping it.
+    ///
+    /// The component is dropped once all subscribers have dropped the event. Components are
+    /// dropped immediately when the entity's archetype has no subscribers.
+    pub fn move_removed<T: Component>(&mut self) {
+        self.captures
+            .insert(ComponentTypeId::of::<T>(), RemovedCapture::moved::<T>());
+    }
+
+    /// Returns `true` if the entity has a sparse component of the given type.
+    pub(crate) fn has_sparse_component(&self, entity: Entity, type_id: ComponentTypeId) -> bool {
+        match self.components.sparse().get(type_id) {
+            Some(storage) => storage.contains(entity),
+            None => false,
+        }
+    }
+
+    /// Removes an entity's sparse component, recording the removal and notifying subscribers.
+    /// Returns `true` if the entity had the component.
+    pub(crate) fn remove_sparse_component(
+        &mut self,
+        location: EntityLocation,
+        type_id: ComponentTypeId,
+    ) -> bool {
+        let archetype = &mut self.archetypes[location.archetype()];
+        let entity = archetype.entities()[location.component().0];
+        let capture = archetype.has_subscribers();
+        let storage = match self.components.sparse_mut().get_mut(type_id) {
+            Some(storage) => storage,
+            None => return false,
+        };
+        self.hooks
+            .run_sparse(HookKind::Remove, type_id, storage, entity);
+        match self
+            .captures
+            .remove_sparse(capture, type_id, storage, entity)
+        {
+            Some(removed) => {
+                self.components.sparse_mut().removed(entity);
+                self.removals.record(type_id, entity);
+                if capture {
+                    archetype.notify(Event::ComponentRemoved(entity, type_id, removed));
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Registers a hook which is called with each component of type `T` as it is added to an
+    /// entity, whether the entity is pushed or cloned into the world with the component or the
+    /// component is added with [Entry::add_component](struct.Entry.html#method.add_component).
+    /// Hooks do not run when entities are moved in from another world with
+    /// [move_from](#method.move_from), but they do run for entities merged in by a
+    /// [WorldLoader](struct.WorldLoader.html), as they would if the entities had been
+    /// deserialized directly into this world.
+    ///
+    /// Hooks run synchronously, including when a [command buffer](../systems/struct.CommandBuffer.html)
+    /// is flushed. Replacing an entity's component runs the `on_remove` hooks for the old value
+    /// followed by the `on_add` hooks for the new value.
+    pub fn on_add<T, F>(&mut self, hook: F)
+    where
+        T: Component,
+        F: FnMut(Entity, &mut T) + Send + Sync + 'static,
+    {
+        self.hooks.push::<T>(HookKind::Add, Box::new(hook));
+    }
+
+    /// Registers a hook which is called with each component of type `T` just before it is removed
+    /// from an entity and dropped.
+    ///
+    /// Hooks run synchronously inside [remove](#method.remove), [clear](#method.clear),
+    /// [Entry::remove_component](struct.Entry.html#method.remove_component), when a component is
+    /// replaced, and when a [command buffer](../systems/struct.CommandBuffer.html) is flushed.
+    /// This allows external resources, such as GPU buffers or physics bodies, to be released as
+    /// soon as the component goes away. Hooks do not run when the world itself is dropped, or
+    /// when entities are moved into another world.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use legion::*;
+    /// # use std::sync::{Arc, Mutex};
+    /// struct RigidBody(u32);
+    ///
+    /// let released = Arc::new(Mutex::new(Vec::new()));
+    ///
+    /// let mut world = World::default();
+    /// let log = released.clone();
+    /// world.on_remove::<RigidBody, _>(move |_, body| log.lock().unwrap().push(body.0));
+    ///
+    /// let entity = world.push((RigidBody(7),));
+    /// world.remove(entity);
+    /// assert_eq!(*released.lock().unwrap(), vec![7]);
+    /// ```
+    pub fn on_remove<T, F>(&mut self, hook: F)
+    where
+        T: Component,
+        F: FnMut(Entity, &mut T) + Send + Sync + 'static,
+    {
+        self.hooks.push::<T>(HookKind::Remove, Box::new(hook));
+    }
+
+    /// Runs the `on_add` hooks for all components of the entities inserted into an archetype
+    /// from `base` onwards.
+    fn run_on_add(&mut self, arch_index: ArchetypeIndex, base: ComponentIndex) {
+        if self.hooks.is_empty() {
+            return;
+        }
+
+        let archetype = &self.archetypes[arch_index];
+        let entities = &archetype.entities()[base.0..];
+        for type_id in archetype.layout().component_types() {
+            let storage = self.components.get_mut(*type_id).unwrap();
+            self.hooks
+                .run_packed(HookKind::Add, *type_id, storage, arch_index, base, entities);
+        }
+    }
+
+    /// Runs the hooks for a single component of an entity.
+    pub(crate) fn run_hooks(
+        &mut self,
+        kind: HookKind,
+        type_id: ComponentTypeId,
+        location: EntityLocation,
+    ) {
+        if self.hooks.is_empty() {
+            return;
+        }
+
+        let entity = self.archetypes[location.archetype()].entities()[location.component().0];
+        if let Some(storage) = self.components.sparse_mut().get_mut(type_id) {
+            self.hooks.run_sparse(kind, type_id, storage, entity);
+        } else if let Some(storage) = self.components.get_mut(type_id) {
+            self.hooks.run_packed(
+                kind,
+                type_id,
+                storage,
+                location.archetype(),
+                location.component(),
+                &[entity],
+            );
+        }
+    }
+
+    pub(crate) fn notify(&mut self, archetype: ArchetypeIndex, event: Event) {
+        self.archetypes[archetype].notify(event);
+    }
+
+    /// Returns the storages of all component types which use sparse storage.
+    pub fn sparse_components(&self) -> &SparseComponents {
+        self.components.sparse()
+    }
+
+    /// Returns mutable access to the storages of all component types which use sparse storage.
+    pub fn sparse_components_mut(&mut self) -> &mut SparseComponents {
+        self.components.sparse_mut()
+    }
+
     pub(crate) fn archetypes(&self) -> &[Archetype] {
         &self.archetypes
     }
@@ -401,8 +903,9 @@ impl World {
         }
 
         // move components
-        let from_layout = from_arch.layout();
-        let to_layout = to_arch.layout();
+        let from_layout = from_arch.layout().clone();
+        let to_layout = to_arch.layout().clone();
+        let capture = from_arch.has_subscribers();
         for type_id in from_layout.component_types() {
             let storage = self.components.get_mut(*type_id).unwrap();
             if to_layout.component_types().contains(type_id) {
@@ -412,7 +915,29 @@ impl World {
                     ArchetypeIndex(to),
                 );
             } else {
-                storage.swap_remove(ArchetypeIndex(from), ComponentIndex(idx));
+                self.hooks.run_packed(
+                    HookKind::Remove,
+                    *type_id,
+                    storage,
+                    ArchetypeIndex(from),
+                    ComponentIndex(idx),
+                    &[entity],
+                );
+                let removed = self.captures.remove_packed(
+                    capture,
+                    *type_id,
+                    storage,
+                    ArchetypeIndex(from),
+                    ComponentIndex(idx),
+                );
+                if capture {
+                    from_arch.notify(Event::ComponentRemoved(entity, *type_id, removed));
+                }
+            }
+        }
+        for type_id in to_layout.component_types() {
+            if !from_layout.component_types().contains(type_id) {
+                to_arch.notify(Event::ComponentAdded(entity, *type_id));
             }
         }
 
@@ -431,11 +956,121 @@ impl World {
         }
     }
 
+    /// Returns `true` if any of the given component types use sparse storage.
+    fn has_sparse_types(&self, type_ids: &[ComponentTypeId]) -> bool {
+        let sparse = self.components.sparse();
+        type_ids
+            .iter()
+            .any(|type_id| sparse.contains_type(*type_id))
+    }
+
+    /// Moves all entities out of an archetype whose layout contains [sparse](#method.register_sparse)
+    /// component types and into the archetype for the rest of their components, moving their
+    /// sparse components into sparse storage.
+    ///
+    /// Entities which are pushed, moved or cloned into the world with sparse components are
+    /// first written into such an archetype, and flushed out of it before the operation
+    /// completes. Such archetypes are therefore always empty, and have no subscribers.
+    pub(crate) fn flush_sparse(&mut self, arch_index: ArchetypeIndex) {
+        let layout = self.archetypes[arch_index].layout().clone();
+        if self.archetypes[arch_index].entities().is_empty()
+            || !self.has_sparse_types(layout.component_types())
+        {
+            return;
+        }
+
+        // find or construct the archetype for the entities' packed components
+        let mut dst_layout = EntityLayout::new();
+        for (type_id, constructor) in layout
+            .component_types()
+            .iter()
+            .zip(layout.component_constructors())
+        {
+            if !self.components.sparse().contains_type(*type_id) {
+                unsafe { dst_layout.register_component_raw(*type_id, *constructor) };
+            }
+        }
+        let dst_arch_index = self.index.search(&dst_layout).next();
+        let dst_arch_index = dst_arch_index.unwrap_or_else(|| self.insert_archetype(dst_layout));
+
+        // entities are taken from the end of the archetype, so that each removal is a pop
+        while let Some(&entity) = self.archetypes[arch_index].entities().last() {
+            let index = ComponentIndex(self.archetypes[arch_index].entities().len() - 1);
+            for type_id in layout.component_types() {
+                if self.components.sparse().contains_type(*type_id) {
+                    self.components
+                        .move_to_sparse(*type_id, arch_index, index, entity);
+                } else {
+                    let storage = self.components.get_mut(*type_id).unwrap();
+                    storage.move_component(arch_index, index, dst_arch_index);
+                }
+            }
+
+            self.archetypes[arch_index].swap_remove(index.0);
+            let dst_arch = &mut self.archetypes[dst_arch_index];
+            dst_arch.push(entity);
+            let location = EntityLocation::new(
+                dst_arch_index,
+                ComponentIndex(dst_arch.entities().len() - 1),
+            );
+            self.entities.set(entity, location);
+        }
+    }
+
+    /// Clones an entity's sparse components from the source world with the merger, running
+    /// `on_add` hooks for them.
+    fn clone_sparse<M: Merger>(
+        &mut self,
+        source: &World,
+        src_entity: Entity,
+        dst_entity: Entity,
+        merger: &mut M,
+    ) {
+        let existing = if self.hooks.is_empty() {
+            Vec::new()
+        } else {
+            self.components
+                .sparse()
+                .iter()
+                .filter(|(_, storage)| storage.contains(dst_entity))
+                .map(|(type_id, _)| type_id)
+                .collect::<Vec<_>>()
+        };
+
+        merger.merge_sparse(
+            src_entity,
+            source.components.sparse(),
+            dst_entity,
+            self.components.sparse_mut(),
+        );
+
+        if self.hooks.is_empty() {
+            return;
+        }
+        for (type_id, storage) in self.components.sparse_mut().iter_mut() {
+            if storage.contains(dst_entity) && !existing.contains(&type_id) {
+                self.hooks
+                    .run_sparse(HookKind::Add, type_id, storage, dst_entity);
+            }
+        }
+    }
+
     fn insert_archetype(&mut self, layout: EntityLayout) -> ArchetypeIndex {
+        trace_span!(
+            "create_archetype",
+            components = layout.component_types().len(),
+        );
+
         // create and insert new archetype
         self.index.push(&layout);
         let arch_index = ArchetypeIndex(self.archetypes.len() as u32);
-        let subscribers = self.subscribers.matches_layout(layout.component_types());
+        let subscribers = if self.has_sparse_types(layout.component_types()) {
+            // entities only pass through archetypes containing sparse types, so subscribers
+            // are not told about them
+            Subscribers::default()
+        } else {
+            self.subscribers.matches_layout(layout.component_types())
+        };
         self.archetypes
             .push(Archetype::new(arch_index, layout, subscribers));
         let archetype = &self.archetypes[self.archetypes.len() - 1];
@@ -541,7 +1176,7 @@ impl World {
         }) {
             // find conflicts, and remove the existing entity, to be replaced with that defined in the source
             for src_entity in src_arch.entities() {
-                self.remove(*src_entity);
+                self.remove_retain_id(*src_entity);
             }
 
             // find or construct the destination archetype
@@ -571,16 +1206,125 @@ impl World {
                 dst_storage.move_archetype_from(src_arch.index(), src_storage);
             }
 
-            for entity in src_arch.drain() {
-                source.entities.remove(entity);
+            let version = next_component_version();
+            let moved = src_arch.drain();
+            for entity in &moved {
+                for component in src_arch.layout().component_types() {
+                    source.removals.record_at(*component, *entity, version);
+                }
+                source.entities.remove(*entity);
             }
 
             // record entity locations
             let (base, entities) = writer.inserted();
             self.entities.insert(entities, dst_arch_index, base);
+            drop(writer);
+
+            self.flush_sparse(dst_arch_index);
+
+            // carry sparse components along with their entities
+            for entity in moved {
+                let removals = &mut source.removals;
+                source.components.sparse_mut().move_entity(
+                    entity,
+                    self.components.sparse_mut(),
+                    |type_id| removals.record_at(type_id, entity, version),
+                );
+            }
         }
     }
 
+    /// Moves up to `max` entities from the end of one of the source world's archetypes into this
+    /// world. Returns the number of entities moved.
+    ///
+    /// Unlike [move_from](#method.move_from), the moved components are considered to have been
+    /// added to this world, and so [on_add](#method.on_add) hooks are run for them.
+    pub(crate) fn move_batch_from(
+        &mut self,
+        source: &mut World,
+        src_arch_index: ArchetypeIndex,
+        max: usize,
+    ) -> usize {
+        let src_arch = &mut source.archetypes[src_arch_index];
+        let len = src_arch.entities().len();
+        let count = max.min(len);
+        if count == 0 {
+            return 0;
+        }
+
+        // entities are taken from the end of the archetype, so that each removal is a pop
+        let entities = src_arch.entities()[(len - count)..]
+            .iter()
+            .rev()
+            .copied()
+            .collect::<Vec<_>>();
+
+        // find conflicts, and remove the existing entity, to be replaced with that defined in the source
+        for entity in &entities {
+            self.remove_retain_id(*entity);
+        }
+
+        // find or construct the destination archetype
+        let layout = &**src_arch.layout();
+        let dst_arch_index = self.index.search(layout).next();
+        let dst_arch_index =
+            dst_arch_index.unwrap_or_else(|| self.insert_archetype(layout.clone()));
+        let dst_arch = &mut self.archetypes[dst_arch_index.0 as usize];
+
+        // build a writer for the destination archetype
+        let mut writer =
+            ArchetypeWriter::new(dst_arch_index, dst_arch, self.components.get_multi_mut());
+
+        // push entity IDs into the archetype
+        for entity in &entities {
+            writer.push(*entity);
+        }
+
+        // move components into the archetype
+        for component in src_arch.layout().component_types() {
+            let src_storage = source.components.get_mut(*component).unwrap();
+            let mut dst_storage = writer.claim_components_unknown(*component);
+            dst_storage.ensure_capacity(count);
+            for i in 0..count {
+                dst_storage.move_component_from(
+                    src_arch_index,
+                    ComponentIndex(len - 1 - i),
+                    src_storage,
+                );
+            }
+        }
+
+        for entity in &entities {
+            src_arch.swap_remove(src_arch.entities().len() - 1);
+            source.entities.remove(*entity);
+        }
+
+        // record entity locations
+        let (base, inserted) = writer.inserted();
+        self.entities.insert(inserted, dst_arch_index, base);
+        drop(writer);
+
+        let mut sparse = Vec::new();
+        for entity in &entities {
+            source.components.sparse_mut().move_entity(
+                *entity,
+                self.components.sparse_mut(),
+                |type_id| sparse.push((*entity, type_id)),
+            );
+        }
+        self.run_on_add(dst_arch_index, base);
+        if !self.hooks.is_empty() {
+            for (entity, type_id) in sparse {
+                let storage = self.components.sparse_mut().get_mut(type_id).unwrap();
+                self.hooks
+                    .run_sparse(HookKind::Add, type_id, storage, entity);
+            }
+        }
+        self.flush_sparse(dst_arch_index);
+
+        count
+    }
+
     /// Clones the entities from a world into this world.
     ///
     /// A [filter](../query/trait.LayoutFilter.html) selects which entities to merge.  
@@ -613,7 +1357,6 @@ impl World {
         filter: &F,
         merger: &mut M,
     ) -> HashMap<Entity, Entity, EntityHasher> {
-        let mut allocator = Allocate::new();
         let mut reallocated = HashMap::default();
 
         // assign destination IDs
@@ -624,8 +1367,8 @@ impl World {
         }) {
             // find conflicts, and remove the existing entity, to be replaced with that defined in the source
             for src_entity in src_arch.entities() {
-                let dst_entity = merger.assign_id(*src_entity, &mut allocator);
-                self.remove(dst_entity);
+                let dst_entity = merger.assign_id(*src_entity, &mut self.allocator);
+                self.remove_retain_id(dst_entity);
                 reallocated.insert(*src_entity, dst_entity);
             }
         }
@@ -689,15 +1432,32 @@ impl World {
             // record entity locations
             let (base, entities) = writer.inserted();
             self.entities.insert(entities, dst_arch_index, base);
-        }
+            drop(writer);
+            self.run_on_add(dst_arch_index, base);
+            self.flush_sparse(dst_arch_index);
 
-        reallocated.unwrap_or_else(|| {
-            // switch the map context back to recover our hashmap
-            ID_CLONE_MAPPINGS.with(|cell| {
-                std::mem::swap(&mut *cell.borrow_mut(), &mut mappings);
+            // clone sparse components
+            if source.components.sparse().is_empty() {
+                continue;
+            }
+            let cloned = ID_CLONE_MAPPINGS.with(|cell| {
+                let map = cell.borrow();
+                src_arch
+                    .entities()
+                    .iter()
+                    .map(|entity| (*entity, *map.get(entity).unwrap_or(entity)))
+                    .collect::<Vec<_>>()
             });
-            mappings
-        })
+            for (src_entity, dst_entity) in cloned {
+                self.clone_sparse(source, src_entity, dst_entity, merger);
+            }
+        }
+
+        // switch the map context back to recover our hashmap
+        ID_CLONE_MAPPINGS.with(|cell| {
+            std::mem::swap(&mut *cell.borrow_mut(), &mut mappings);
+        });
+        reallocated.unwrap_or(mappings)
     }
 
     /// Clones a single entity from the source world into the destination world.
@@ -708,11 +1468,10 @@ impl World {
         merger: &mut M,
     ) -> Entity {
         // determine the destination ID
-        let mut allocator = Allocate::new();
-        let dst_entity = merger.assign_id(entity, &mut allocator);
+        let dst_entity = merger.assign_id(entity, &mut self.allocator);
 
         // find conflicts, and remove the existing entity, to be replaced with that defined in the source
-        self.remove(dst_entity);
+        self.remove_retain_id(dst_entity);
 
         // find the source
         let src_location = source
@@ -765,6 +1524,10 @@ impl World {
         // record entity location
         let (base, entities) = writer.inserted();
         self.entities.insert(entities, dst_arch_index, base);
+        drop(writer);
+        self.run_on_add(dst_arch_index, base);
+        self.flush_sparse(dst_arch_index);
+        self.clone_sparse(source, entity, dst_entity, merger);
 
         ID_CLONE_MAPPINGS.with(|cell| {
             cell.borrow_mut().clear();
@@ -863,6 +1626,10 @@ impl EntityStore for World {
     fn id(&self) -> WorldId {
         self.id
     }
+
+    fn removals(&self) -> &RemovalLog {
+        &self.removals
+    }
 }
 
 /// Provides access to the archetypes and entity components contained within a world.
@@ -982,6 +1749,19 @@ pub trait Merger {
         src_components: &Components,
         dst: &mut ArchetypeWriter,
     );
+
+    /// Merges an entity's [sparse](struct.World.html#method.register_sparse) components from the
+    /// source world into the destination world. By default, sparse components are not merged.
+    #[inline]
+    #[allow(unused_variables)]
+    fn merge_sparse(
+        &mut self,
+        src_entity: Entity,
+        src_components: &SparseComponents,
+        dst_entity: Entity,
+        dst_components: &mut SparseComponents,
+    ) {
+    }
 }
 
 /// Describes how a merger wishes `Entity` references inside cloned components to be
@@ -1020,6 +1800,10 @@ pub struct Duplicate {
             >,
         ),
     >,
+    sparse_fns: HashMap<
+        ComponentTypeId,
+        Box<dyn FnMut(Entity, &dyn UnknownSparseStorage, Entity, &mut SparseComponents)>,
+    >,
 }
 
 impl Duplicate {
@@ -1049,6 +1833,8 @@ impl Duplicate {
 
         self.duplicate_fns
             .insert(type_id, (type_id, constructor, convert));
+        self.sparse_fns
+            .insert(type_id, Self::sparse_convert(|component: &T| *component));
     }
 
     /// Allows the merger to clone the given component into the destination world.
@@ -1057,13 +1843,16 @@ impl Duplicate {
     }
 
     /// Allows the merger to clone the given component into the destination world with a custom clone function.
+    ///
+    /// If the source world stores `Source` in [sparse storage](struct.World.html#method.register_sparse),
+    /// the converted components are inserted into the destination world's sparse storage for `Target`.
     pub fn register_convert<
         Source: Component,
         Target: Component,
         F: FnMut(&Source) -> Target + 'static,
     >(
         &mut self,
-        mut convert: F,
+        convert: F,
     ) {
         use crate::internals::storage::ComponentStorage;
 
@@ -1071,6 +1860,11 @@ impl Duplicate {
         let dest_type = ComponentTypeId::of::<Target>();
         let constructor =
             || Box::new(Target::Storage::default()) as Box<dyn UnknownComponentStorage>;
+        let convert = Rc::new(RefCell::new(convert));
+        let sparse_convert = {
+            let convert = convert.clone();
+            move |component: &Source| (convert.borrow_mut())(component)
+        };
         let convert = Box::new(
             move |src_entities: Range<usize>,
                   src_arch: &Archetype,
@@ -1082,7 +1876,7 @@ impl Duplicate {
                 let src_slice = &src.get(src_arch.index()).unwrap().into_slice()[src_entities];
                 dst.ensure_capacity(src_slice.len());
                 for component in src_slice {
-                    let component = convert(component);
+                    let component = (convert.borrow_mut())(component);
 
                     unsafe {
                         dst.extend_memcopy(&component as *const Target, 1);
@@ -1094,9 +1888,13 @@ impl Duplicate {
 
         self.duplicate_fns
             .insert(source_type, (dest_type, constructor, convert));
+        self.sparse_fns
+            .insert(source_type, Self::sparse_convert(sparse_convert));
     }
 
     /// Allows the merger to clone the given component into the destination world with a custom clone function.
+    ///
+    /// Components registered this way are not cloned from [sparse storage](struct.World.html#method.register_sparse).
     pub fn register_convert_raw(
         &mut self,
         src_type: ComponentTypeId,
@@ -1109,6 +1907,20 @@ impl Duplicate {
         self.duplicate_fns
             .insert(src_type, (dst_type, constructor, duplicate_fn));
     }
+
+    #[allow(clippy::type_complexity)]
+    fn sparse_convert<Source: Component, Target: Component>(
+        mut convert: impl FnMut(&Source) -> Target + 'static,
+    ) -> Box<dyn FnMut(Entity, &dyn UnknownSparseStorage, Entity, &mut SparseComponents)> {
+        Box::new(move |src_entity, src, dst_entity, dst| {
+            let src = src.downcast_ref::<SparseStorage<Source>>().unwrap();
+            if let Some(component) = src.get(src_entity) {
+                let component = convert(component);
+                dst.register::<Target>();
+                dst.insert(dst_entity, component);
+            }
+        })
+    }
 }
 
 impl Merger for Duplicate {
@@ -1137,6 +1949,20 @@ impl Merger for Duplicate {
             }
         }
     }
+
+    fn merge_sparse(
+        &mut self,
+        src_entity: Entity,
+        src_components: &SparseComponents,
+        dst_entity: Entity,
+        dst_components: &mut SparseComponents,
+    ) {
+        for (src_type, src_storage) in src_components.iter() {
+            if let Some(convert) = self.sparse_fns.get_mut(&src_type) {
+                convert(src_entity, src_storage, dst_entity, dst_components);
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -1239,6 +2065,447 @@ mod test {
         assert_eq!(world.len(), 2);
     }
 
+    #[test]
+    fn allocator_deterministic() {
+        use crate::internals::{
+            entity::GenerationalAllocator, query::IntoQuery, systems::command::CommandBuffer,
+        };
+
+        fn run() -> Vec<Entity> {
+            let mut world = World::new(WorldOptions {
+                allocator: Arc::new(GenerationalAllocator::new()),
+                ..Default::default()
+            });
+
+            let first = world.push((1usize, true));
+            world.extend(vec![(2usize, false), (3usize, false)]);
+            world.remove(first);
+
+            let mut cmd = CommandBuffer::new(&world);
+            cmd.push((4usize, true));
+            cmd.flush(&mut world);
+
+            let mut entities = Entity::query().iter(&world).copied().collect::<Vec<_>>();
+            entities.push(world.push((5usize,)));
+            entities
+        }
+
+        assert_eq!(run(), run());
+    }
+
+    #[test]
+    fn record_removals() {
+        use crate::internals::storage::removed::RemovedReader;
+
+        let mut world = World::default();
+        world.track_removals::<usize>();
+        world.track_removals::<bool>();
+        let a = world.push((1usize, true));
+        let b = world.push((2usize, false));
+
+        let mut usize_reader = RemovedReader::<usize>::default();
+        let mut bool_reader = RemovedReader::<bool>::default();
+        assert_eq!(usize_reader.read(&world).count(), 0);
+
+        world.remove(a);
+        world.entry(b).unwrap().remove_component::<bool>();
+        world.entry(b).unwrap().remove_component::<bool>();
+
+        assert_eq!(usize_reader.read(&world).collect::<Vec<_>>(), vec![a]);
+        assert_eq!(bool_reader.read(&world).collect::<Vec<_>>(), vec![a, b]);
+
+        // all of a removed entity's components share one version
+        let version = |type_id| {
+            world
+                .removals()
+                .iter_unknown(type_id)
+                .find(|(entity, _)| *entity == a)
+                .unwrap()
+                .1
+        };
+        assert_eq!(
+            version(ComponentTypeId::of::<usize>()),
+            version(ComponentTypeId::of::<bool>())
+        );
+        assert_eq!(usize_reader.read(&world).count(), 0);
+
+        world.update_removals();
+        assert_eq!(world.removals().iter::<bool>().count(), 2);
+        world.clear();
+        world.update_removals();
+        assert_eq!(world.removals().iter::<bool>().count(), 0);
+        assert_eq!(usize_reader.read(&world).collect::<Vec<_>>(), vec![b]);
+    }
+
+    #[test]
+    fn untracked_removals() {
+        let mut world = World::default();
+        world.track_removals::<bool>();
+
+        for _ in 0..1000 {
+            let entity = world.push((1usize, true));
+            world.remove(entity);
+        }
+        let entity = world.push((1usize, true));
+        world.entry(entity).unwrap().remove_component::<usize>();
+
+        // removals are never discarded without a schedule, so only tracked types are recorded
+        assert!(!world.removals().is_tracked(ComponentTypeId::of::<usize>()));
+        assert_eq!(world.removals().iter::<usize>().count(), 0);
+        assert_eq!(world.removals().iter::<bool>().count(), 1000);
+
+        world.update_removals();
+        world.update_removals();
+        assert_eq!(world.removals().iter::<bool>().count(), 0);
+    }
+
+    #[test]
+    fn component_hooks() {
+        use parking_lot::Mutex;
+
+        #[derive(Debug, PartialEq)]
+        struct Body(usize);
+        #[derive(Debug, PartialEq)]
+        struct Marker(usize);
+
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut world = World::default();
+        world.register_sparse::<Marker>();
+        let added = log.clone();
+        world.on_add::<Body, _>(move |_, body| added.lock().push(("add", body.0)));
+        let removed = log.clone();
+        world.on_remove::<Body, _>(move |_, body| removed.lock().push(("remove", body.0)));
+        let removed = log.clone();
+        world.on_remove::<Marker, _>(move |_, marker| removed.lock().push(("unmark", marker.0)));
+        let mut take = || std::mem::take(&mut *log.lock());
+
+        let a = world.push((Body(1), 0usize));
+        world.extend(vec![(Body(2), 0usize), (Body(3), 0usize)]);
+        assert_eq!(take(), vec![("add", 1), ("add", 2), ("add", 3)]);
+
+        let b = world.push((0usize,));
+        world.entry(b).unwrap().add_component(Body(4));
+        world.entry(b).unwrap().add_component(Body(5));
+        world.entry(b).unwrap().remove_component::<Body>();
+        assert_eq!(
+            take(),
+            vec![("add", 4), ("remove", 4), ("add", 5), ("remove", 5)]
+        );
+
+        world.entry(a).unwrap().add_component(Marker(6));
+        world.entry(a).unwrap().remove_component::<Marker>();
+        world.entry(a).unwrap().add_component(Marker(7));
+        world.remove(a);
+        assert_eq!(take(), vec![("unmark", 6), ("unmark", 7), ("remove", 1)]);
+
+        world.clear();
+        let mut cleared = take();
+        cleared.sort_unstable();
+        assert_eq!(cleared, vec![("remove", 2), ("remove", 3)]);
+    }
+
+    #[test]
+    fn sparse_components() {
+        use crate::internals::storage::removed::RemovedReader;
+
+        struct Stunned(u32);
+
+        let mut world = World::default();
+        world.register_sparse::<Stunned>();
+        world.track_removals::<Stunned>();
+        let a = world.push((1usize, true));
+        let b = world.push((2usize, true));
+        let archetypes = world.archetypes().len();
+        let location = world.entry(a).unwrap().location().archetype();
+
+        world.entry(a).unwrap().add_component(Stunned(1));
+        world.entry(b).unwrap().add_component(Stunned(2));
+        world.entry(b).unwrap().add_component(Stunned(3));
+        assert_eq!(world.archetypes().len(), archetypes);
+        assert_eq!(world.entry(a).unwrap().location().archetype(), location);
+
+        world
+            .entry_mut(a)
+            .unwrap()
+            .get_component_mut::<Stunned>()
+            .unwrap()
+            .0 += 10;
+        assert_eq!(
+            world
+                .entry_ref(a)
+                .unwrap()
+                .get_component::<Stunned>()
+                .unwrap()
+                .0,
+            11
+        );
+        assert_eq!(
+            world
+                .entry(b)
+                .unwrap()
+                .into_component::<Stunned>()
+                .unwrap()
+                .0,
+            3
+        );
+
+        let mut reader = RemovedReader::<Stunned>::default();
+        world.entry(a).unwrap().remove_component::<Stunned>();
+        assert!(world
+            .entry_ref(a)
+            .unwrap()
+            .get_component::<Stunned>()
+            .is_err());
+        world.remove(b);
+        assert_eq!(reader.read(&world).collect::<Vec<_>>(), vec![a, b]);
+        assert!(world
+            .sparse_components()
+            .get_downcast::<Stunned>()
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn sparse_components_membership() {
+        use crate::internals::query::filter::any;
+
+        struct Stunned(u32);
+        struct Frozen;
+
+        let mut world = World::default();
+        world.register_sparse::<Stunned>();
+        world.register_sparse::<Frozen>();
+        let a = world.push((1usize,));
+        let b = world.push((2usize, Stunned(2)));
+        assert!(!world.sparse_components().has_components(a));
+        assert!(world.sparse_components().has_components(b));
+
+        world.entry(a).unwrap().add_component(Stunned(1));
+        world.entry(a).unwrap().add_component(Stunned(3));
+        world.entry(a).unwrap().add_component(Frozen);
+        world.entry(a).unwrap().remove_component::<Stunned>();
+        assert!(world.sparse_components().has_components(a));
+        world.entry(a).unwrap().remove_component::<Frozen>();
+        assert!(!world.sparse_components().has_components(a));
+
+        let mut other = World::default();
+        other.register_sparse::<Stunned>();
+        other.move_from(&mut world, &any());
+        assert!(!world.sparse_components().has_components(b));
+        let moved = other
+            .sparse_components()
+            .get_downcast::<Stunned>()
+            .unwrap()
+            .entities()[0];
+        assert!(other.sparse_components().has_components(moved));
+
+        other.remove(moved);
+        assert!(!other.sparse_components().has_components(moved));
+        assert!(other
+            .sparse_components()
+            .get_downcast::<Stunned>()
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn sparse_components_push() {
+        use parking_lot::Mutex;
+
+        #[derive(Debug, PartialEq)]
+        struct Stunned(u32);
+
+        let added = Arc::new(Mutex::new(Vec::new()));
+        let mut world = World::default();
+        world.register_sparse::<Stunned>();
+        let log = added.clone();
+        world.on_add::<Stunned, _>(move |_, stunned| log.lock().push(stunned.0));
+
+        let plain = world.push((0usize,));
+        let entities = world
+            .extend(vec![(1usize, Stunned(1)), (2usize, Stunned(2))])
+            .to_vec();
+        // the entities share an archetype with those pushed without the sparse component
+        let archetype = world.entry(plain).unwrap().location().archetype();
+        for (i, entity) in entities.iter().enumerate() {
+            let entry = world.entry(*entity).unwrap();
+            assert_eq!(entry.location().archetype(), archetype);
+            assert_eq!(entry.get_component::<usize>(), Ok(&(i + 1)));
+            assert_eq!(entry.get_component::<Stunned>(), Ok(&Stunned(i as u32 + 1)));
+        }
+        assert!(world
+            .archetypes()
+            .iter()
+            .filter(|arch| arch.layout().has_component::<Stunned>())
+            .all(|arch| arch.entities().is_empty()));
+
+        let mut sorted = added.lock().clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, vec![1, 2]);
+
+        // replacing an entity's ID also replaces its sparse component
+        world.push_with_id(plain, (3usize, Stunned(3)));
+        assert_eq!(
+            world.entry(plain).unwrap().get_component::<Stunned>(),
+            Ok(&Stunned(3))
+        );
+    }
+
+    #[test]
+    fn sparse_components_query() {
+        use crate::internals::query::IntoQuery;
+
+        #[derive(Debug, PartialEq)]
+        struct Stunned(u32);
+
+        let mut world = World::default();
+        world.register_sparse::<Stunned>();
+        let a = world.push((1usize,));
+        let b = world.push((2usize, Stunned(2)));
+        let c = world.push((3usize, true));
+        world.entry(c).unwrap().add_component(Stunned(3));
+
+        let mut query = <(&usize, Option<&mut Stunned>)>::query();
+        for (value, stunned) in query.iter_mut(&mut world) {
+            if let Some(stunned) = stunned {
+                stunned.0 += *value as u32;
+            }
+        }
+
+        let mut query = <(Entity, Option<&Stunned>)>::query();
+        let mut found = query
+            .iter(&world)
+            .map(|(entity, stunned)| (*entity, stunned.map(|s| s.0)))
+            .collect::<Vec<_>>();
+        found.sort_by_key(|(_, stunned)| *stunned);
+        assert_eq!(found, vec![(a, None), (b, Some(4)), (c, Some(6))]);
+    }
+
+    #[test]
+    fn sparse_components_subworld() {
+        use crate::internals::{entry::ComponentError, query::IntoQuery};
+
+        #[derive(Debug, PartialEq)]
+        struct Stunned(u32);
+
+        let mut world = World::default();
+        world.register_sparse::<Stunned>();
+        let a = world.push((1usize, Stunned(1)));
+
+        let (mut left, mut right) = world.split::<Option<&mut Stunned>>();
+        left.entry_mut(a)
+            .unwrap()
+            .get_component_mut::<Stunned>()
+            .unwrap()
+            .0 = 5;
+        assert_eq!(
+            left.entry_ref(a).unwrap().get_component::<Stunned>(),
+            Ok(&Stunned(5))
+        );
+        assert!(matches!(
+            right.entry_mut(a).unwrap().get_component_mut::<Stunned>(),
+            Err(ComponentError::Denied { .. })
+        ));
+
+        let mut query = <Option<&Stunned>>::query();
+        let found = query.iter(&left).flatten().collect::<Vec<_>>();
+        assert_eq!(found, vec![&Stunned(5)]);
+    }
+
+    #[test]
+    fn sparse_components_clone() {
+        #[derive(Clone, Debug, PartialEq)]
+        struct Target(Entity);
+
+        let mut source = World::default();
+        source.register_sparse::<Target>();
+        let a = source.push((1usize,));
+        let b = source.push((2usize, Target(a)));
+
+        let mut merger = Duplicate::default();
+        merger.register_copy::<usize>();
+        merger.register_clone::<Target>();
+
+        let mut world = World::default();
+        let mapped = world.clone_from(&source, &any(), &mut merger);
+        assert_eq!(
+            world.entry(mapped[&b]).unwrap().get_component::<Target>(),
+            Ok(&Target(mapped[&a]))
+        );
+        assert!(world
+            .entry(mapped[&a])
+            .unwrap()
+            .get_component::<Target>()
+            .is_err());
+
+        let single = world.clone_from_single(&source, b, &mut merger);
+        assert_eq!(
+            world.entry(single).unwrap().get_component::<Target>(),
+            Ok(&Target(a))
+        );
+    }
+
+    #[test]
+    fn sparse_components_move() {
+        struct Stunned;
+
+        let mut a = World::default();
+        a.register_sparse::<Stunned>();
+        let entity = a.push((1usize,));
+        a.entry(entity).unwrap().add_component(Stunned);
+
+        let mut b = World::default();
+        b.move_from(&mut a, &any());
+        assert!(a
+            .sparse_components()
+            .get_downcast::<Stunned>()
+            .unwrap()
+            .is_empty());
+        assert!(b.entry(entity).unwrap().get_component::<Stunned>().is_ok());
+    }
+
+    #[test]
+    fn push_with_stale_id() {
+        use crate::internals::{entity::GenerationalAllocator, query::IntoQuery};
+
+        let mut world = World::new(WorldOptions {
+            allocator: Arc::new(GenerationalAllocator::new()),
+            ..Default::default()
+        });
+        let stale = world.push((1usize,));
+        world.remove(stale);
+        let recycled = (2..64usize)
+            .map(|i| world.push((i,)))
+            .find(|pushed| pushed.index() == stale.index())
+            .unwrap();
+
+        // the entity which occupies the index is replaced
+        world.push_with_id(stale, (0usize, true));
+        assert!(!world.contains(recycled));
+        let entry = world.entry(stale).unwrap();
+        assert_eq!(entry.get_component::<bool>(), Ok(&true));
+        assert_eq!(<&usize>::query().iter(&world).count(), world.len());
+    }
+
+    #[test]
+    fn remove_stale_id() {
+        let mut world = World::default();
+        let removed = world.push((1usize, true));
+        assert!(world.remove(removed));
+
+        let entities: Vec<_> = world
+            .extend((0..64).map(|i| (i as usize, false)))
+            .iter()
+            .copied()
+            .collect();
+
+        assert!(!world.contains(removed));
+        assert!(world.entry(removed).is_none());
+        assert!(!entities.contains(&removed));
+        assert_eq!(world.remove(removed), false);
+        assert_eq!(world.len(), 64);
+    }
+
     #[test]
     fn pack() {
         use crate::internals::{
@@ -1263,6 +2530,7 @@ mod test {
 
         let mut world = crate::internals::world::World::new(WorldOptions {
             groups: vec![<(A, B, C, D)>::to_group()],
+            ..Default::default()
         });
 
         world.extend(std::iter::repeat((A(0f32),)).take(10000));
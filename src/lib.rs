@@ -123,6 +123,21 @@
 //! }
 //! ```
 //!
+//! `maybe_changed` rejects whole chunks in which the component has not been accessed mutably.
+//! To select only the individual entities whose component has been written, use `changed`.
+//! `added` similarly selects the entities which have gained a component:
+//!
+//! ```
+//! # use legion::*;
+//! # let mut world = World::default();
+//! # struct Position { x: f32, y: f32 }
+//! let mut query = <&Position>::query().filter(changed::<Position>());
+//!
+//! for position in query.iter(&world) {
+//!     println!("moved to {}, {}", position.x, position.y);
+//! }
+//! ```
+//!
 //! There is much more than can be done with queries. See [query](query/struct.Query.html) for
 //! more information.
 //!
@@ -181,11 +196,13 @@
 //! * `serialize` - Enables the serde serialization module and associated functionality. Enabled by default.
 //! * `crossbeam-events` - Implements the `EventSender` trait for crossbeam `Sender` channels, allowing them to be used for event subscriptions. Enabled by default.
 //! * `codegen` - Enables the `#[system]` procedural macro. Enabled by default.
+//! * `tracing` - Emits `tracing` spans for system execution (with the number of entities each system matches), command buffer flushes, world packing and archetype creation, and enables the [trace module](trace/index.html)'s Chrome trace exporter. Off by default.
 
 // implementation modules
 mod internals;
 
 // public API organized into logical modules
+pub mod hierarchy;
 pub mod query;
 pub mod storage;
 pub mod systems;
@@ -194,11 +211,14 @@ pub mod world;
 #[cfg(feature = "serialize")]
 pub mod serialize;
 
+#[cfg(feature = "tracing")]
+pub mod trace;
+
 // re-export most common types into the root
 pub use crate::{
     query::{
-        any, component, maybe_changed, passthrough, Fetch, IntoQuery, Read, TryRead, TryWrite,
-        Write,
+        added, any, changed, component, maybe_changed, passthrough, Fetch, IntoQuery, Read,
+        TryRead, TryWrite, Write,
     },
     storage::{GroupSource, IntoSoa},
     systems::{Resources, Schedule, SystemBuilder},
@@ -82,10 +82,12 @@
 
 pub use crate::internals::query::{
     filter::{
+        added::EntityAddedFilter,
         and::And,
         any::Any,
+        changed::EntityChangedFilter,
         component::ComponentFilter,
-        filter_fns::{any, component, maybe_changed, passthrough},
+        filter_fns::{added, any, changed, component, maybe_changed, passthrough},
         maybe_changed::ComponentChangedFilter,
         not::Not,
         or::Or,
@@ -97,7 +99,7 @@ pub use crate::internals::query::{
         read::Read, try_read::TryRead, try_write::TryWrite, write::Write, DefaultFilter, Fetch,
         IntoIndexableIter, ReadOnly, View,
     },
-    ChunkIter, ChunkView, IntoQuery, Query,
+    ChunkIter, ChunkView, ChunkViewIter, IntoQuery, Query,
 };
 
 #[cfg(feature = "parallel")]
@@ -33,10 +33,21 @@
 //! ```
 
 pub use crate::internals::serialize::{
+    binary::{DeserializeBinaryIntoWorld, DeserializeBinaryWorld, SerializableBinaryWorld},
     de::WorldDeserializer,
-    id::{Canon, EntityName, EntitySerializer},
+    id::{
+        Canon, CanonizeError, EntityName, EntityNames, EntitySerializer, PathNames,
+        SequentialNames, UuidNames,
+    },
+    patch::{DeserializePatch, SerializableWorldDiff, WorldPatch},
+    reflect::{DynamicComponentError, SerializableComponent},
+    resources::{
+        DeserializeIntoResources, DeserializeWorldAndResources, SerializableResources,
+        SerializableWorldAndResources,
+    },
     ser::{SerializableWorld, WorldSerializer},
-    AutoTypeKey, DeserializeIntoWorld, DeserializeNewWorld, Registry, TypeKey, UnknownType,
+    AutoTypeKey, DeserializeBatch, DeserializeIntoWorld, DeserializeLoader, DeserializeNewWorld,
+    DeserializePrefab, Registry, TypeKey, UnknownType,
 };
 
 #[cfg(feature = "type-uuid")]
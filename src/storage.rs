@@ -70,9 +70,30 @@
 //! // create a world optimized for cases where (A, B) and/or
 //! // (A, B, C) are significant queries.
 //! let group = <(A, B, C)>::to_group();
-//! let options = WorldOptions { groups: vec![group] };
+//! let options = WorldOptions {
+//!     groups: vec![group],
+//!     ..Default::default()
+//! };
 //! let world = World::new(options);
 //! ```
+//!
+//! Adding or removing a component moves the entity into a different archetype.
+//! For marker components which are toggled frequently, this cost can be avoided by
+//! opting the component type into [sparse storage](struct.SparseStorage.html) with
+//! [World::register_sparse](../world/struct.World.html#method.register_sparse). Sparse
+//! components are stored outside of the archetype layout, and can only be requested by queries
+//! as optional components.
+//!
+//! ```
+//! # use legion::*;
+//! struct Stunned;
+//!
+//! let mut world = World::default();
+//! world.register_sparse::<Stunned>();
+//!
+//! let entity = world.push((1usize,));
+//! world.entry(entity).unwrap().add_component(Stunned);
+//! ```
 
 pub use crate::internals::{
     cons::{ConsAppend, ConsFlatten},
@@ -87,7 +108,9 @@ pub use crate::internals::{
         group::{Group, GroupDef, GroupSource},
         index::SearchIndex,
         packed::PackedStorage,
+        removed::{RemovalLog, RemovedReader},
+        sparse::{SparseComponents, SparseStorage, UnknownSparseStorage},
         ComponentIndex, ComponentMeta, ComponentSlice, ComponentSliceMut, ComponentStorage,
-        Components, Epoch, MultiMut, PackOptions, UnknownComponentStorage, Version,
+        ComponentTicks, Components, Epoch, MultiMut, PackOptions, UnknownComponentStorage, Version,
     },
 };
@@ -2,9 +2,17 @@
 
 pub use crate::internals::systems::{
     command::{CommandBuffer, WorldWritable},
+    criteria::{
+        any_match, every_n_ticks, resource_eq, AnyMatch, EveryNTicks, ResourceEq, RunCriteria,
+    },
+    events::{EventCursor, EventReader, EventWriter, Events, ReadEvents, WriteEvents},
+    graph::{EdgeReason, GraphEdge, GraphNode, NodeKind, ScheduleGraph},
+    observer::{ExecutorObserver, SystemTiming, SystemTimings, TimingCollector},
+    ordering::ScheduleError,
     resources::{
         Fetch, Resource, ResourceSet, ResourceTypeId, Resources, SyncResources, UnsafeResources,
     },
     schedule::{Builder, Executor, ParallelRunnable, Runnable, Schedule, Step},
     system::{QuerySet, System, SystemAccess, SystemBuilder, SystemFn, SystemId},
+    timestep::FixedTime,
 };
@@ -0,0 +1,9 @@
+//! Integration with the `tracing` crate.
+//!
+//! With the `tracing` feature enabled, legion emits spans for each system run, command buffer
+//! flush, [World::pack](../world/struct.World.html#method.pack) and archetype creation. These
+//! can be consumed by any `tracing` subscriber, or recorded with
+//! [ChromeTraceSubscriber](struct.ChromeTraceSubscriber.html) for offline inspection in a
+//! browser trace viewer.
+
+pub use crate::internals::trace::ChromeTraceSubscriber;
@@ -105,9 +105,9 @@
 //!
 //! # Events
 //!
-//! Notifications about archetype creation and entity insertion/removal from an archetype can be sent
-//! to an [EventSender](trait.EventSender.html) by subscribing to the world. A layout filter specifies
-//! which archetypes the subscriber is interested in.
+//! Notifications about archetype creation, entity insertion/removal from an archetype, and components
+//! being added to or removed from entities can be sent to an [EventSender](trait.EventSender.html) by
+//! subscribing to the world. A layout filter specifies which archetypes the subscriber is interested in.
 //!
 //! ```ignore
 //! # use legion::*;
@@ -142,10 +142,17 @@
 //! ```
 
 pub use crate::internals::{
-    entity::{Allocate, Entity, EntityHasher, EntityLocation, LocationMap},
+    diff::WorldDiff,
+    entity::{
+        Allocate, Entity, EntityAllocator, EntityHasher, EntityLocation, GenerationalAllocator,
+        LocationMap, SharedAllocator,
+    },
     entry::{ComponentError, Entry, EntryMut, EntryRef},
-    event::{Event, EventSender},
+    event::{Event, EventSender, RemovedComponent},
+    loader::{LoadBudget, WorldLoader},
     permissions::Permissions,
+    prefab::{NestedPrefab, Prefab, PrefabOverrides},
+    snapshot::WorldSnapshot,
     subworld::{ArchetypeAccess, ComponentAccess, SubWorld},
     world::{
         Duplicate, EntityAccessError, EntityRewrite, EntityStore, Merger, StorageAccessor, World,
@@ -57,3 +57,42 @@ fn query_get() {
 
     schedule.execute(&mut world, &mut resources);
 }
+
+#[test]
+#[cfg(feature = "codegen")]
+fn event_systems() {
+    use legion::systems::{EventReader, EventWriter, Events};
+
+    struct Spawned(usize);
+
+    #[system(for_each)]
+    fn spawn(component: &usize, #[event] spawned: &mut EventWriter<Spawned>) {
+        spawned.send(Spawned(*component));
+    }
+
+    #[system]
+    fn count(#[event] spawned: &mut EventReader<Spawned>, #[resource] total: &mut usize) {
+        *total += spawned.read().map(|Spawned(x)| x).sum::<usize>();
+    }
+
+    let mut world = World::default();
+    world.extend(vec![(1usize,), (2usize,), (3usize,)]);
+
+    let mut resources = Resources::default();
+    resources.insert(0usize);
+    resources.insert(Events::<Spawned>::default());
+
+    let mut schedule = Schedule::builder()
+        .add_system(count_system())
+        .add_system(spawn_system())
+        .add_system(Events::<Spawned>::update_system())
+        .build();
+
+    // the reader runs before the writer, so observes the previous frame's events
+    schedule.execute(&mut world, &mut resources);
+    assert_eq!(*resources.get::<usize>().unwrap(), 0);
+    schedule.execute(&mut world, &mut resources);
+    assert_eq!(*resources.get::<usize>().unwrap(), 6);
+    schedule.execute(&mut world, &mut resources);
+    assert_eq!(*resources.get::<usize>().unwrap(), 12);
+}
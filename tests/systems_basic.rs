@@ -137,4 +137,41 @@ mod tests {
 
         Schedule::builder().add_system(basic_system(false)).build();
     }
+
+    #[test]
+    fn with_events() {
+        use legion::systems::{EventReader, EventWriter};
+
+        #[system]
+        fn basic(#[event] _: &mut EventReader<usize>, _: &mut legion::systems::EventWriter<bool>) {}
+
+        #[system]
+        fn single(#[event] _: &mut EventWriter<usize>) {}
+
+        Schedule::builder()
+            .add_system(basic_system())
+            .add_system(single_system())
+            .build();
+    }
+
+    #[test]
+    fn with_component_named_event_reader() {
+        use legion::{Resources, World};
+
+        struct EventReader<T>(T);
+
+        #[system(for_each)]
+        fn basic(reader: &mut EventReader<usize>) {
+            reader.0 += 1;
+        }
+
+        let mut world = World::default();
+        let entity = world.push((EventReader(1usize),));
+        let mut resources = Resources::default();
+        let mut schedule = Schedule::builder().add_system(basic_system()).build();
+        schedule.execute(&mut world, &mut resources);
+
+        let entry = world.entry(entity).unwrap();
+        assert_eq!(entry.get_component::<EventReader<usize>>().unwrap().0, 2);
+    }
 }
@@ -306,6 +306,118 @@ fn delete_entities_on_drop() {
     assert!(entities.is_empty());
 }
 
+#[test]
+#[cfg(feature = "crossbeam-events")]
+fn component_events() {
+    use legion::world::Event;
+    use storage::ComponentTypeId;
+
+    let mut world = World::default();
+    let (tx, rx) = crossbeam_channel::unbounded::<Event>();
+    world.subscribe(tx, component::<Pos>());
+
+    let watched = world.push((Pos(1., 2., 3.),));
+    let ignored = world.push((Scale(1., 1., 1.),));
+    rx.try_iter().for_each(drop);
+
+    world
+        .entry(ignored)
+        .unwrap()
+        .add_component(Rot(0.1, 0.2, 0.3));
+    world.entry(ignored).unwrap().remove_component::<Rot>();
+    assert_eq!(rx.try_iter().count(), 0);
+
+    world
+        .entry(watched)
+        .unwrap()
+        .add_component(Rot(0.1, 0.2, 0.3));
+    let added = rx
+        .try_iter()
+        .filter_map(|event| match event {
+            Event::ComponentAdded(entity, type_id) => Some((entity, type_id)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(added, vec![(watched, ComponentTypeId::of::<Rot>())]);
+
+    world.entry(watched).unwrap().remove_component::<Rot>();
+    let removed = rx
+        .try_iter()
+        .filter_map(|event| match event {
+            Event::ComponentRemoved(entity, type_id, payload) => {
+                assert!(payload.is_none());
+                Some((entity, type_id))
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(removed, vec![(watched, ComponentTypeId::of::<Rot>())]);
+}
+
+#[test]
+#[cfg(feature = "crossbeam-events")]
+fn removed_component_payloads() {
+    use legion::world::{Event, RemovedComponent};
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    struct Handle(Arc<AtomicUsize>);
+
+    impl Drop for Handle {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Marker(usize);
+
+    let mut world = World::default();
+    world.clone_removed::<Rot>();
+    world.move_removed::<Handle>();
+    world.register_sparse::<Marker>();
+    world.clone_removed::<Marker>();
+
+    let (tx, rx) = crossbeam_channel::unbounded::<Event>();
+    world.subscribe(tx, component::<Pos>());
+
+    let drops = Arc::new(AtomicUsize::new(0));
+    let entity = world.push((Pos(1., 2., 3.), Rot(0.1, 0.2, 0.3), Handle(drops.clone())));
+    world.entry(entity).unwrap().add_component(Marker(5));
+    world.remove(entity);
+
+    let payloads = rx
+        .try_iter()
+        .filter_map(|event| match event {
+            Event::ComponentRemoved(_, _, payload) => Some(payload),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(payloads.len(), 4);
+
+    let find = |f: &dyn Fn(&RemovedComponent) -> bool| {
+        payloads
+            .iter()
+            .flatten()
+            .filter(|payload| f(payload))
+            .count()
+    };
+    assert_eq!(
+        find(&|p| p.downcast_ref::<Rot>() == Some(&Rot(0.1, 0.2, 0.3))),
+        1
+    );
+    assert_eq!(find(&|p| p.downcast_ref::<Marker>() == Some(&Marker(5))), 1);
+    assert_eq!(find(&|p| p.downcast_ref::<Handle>().is_some()), 1);
+    assert_eq!(find(&|p| p.downcast_ref::<Pos>().is_some()), 0);
+
+    // the moved handle lives until the last event holding it is dropped
+    assert_eq!(drops.load(Ordering::SeqCst), 0);
+    drop(payloads);
+    assert_eq!(drops.load(Ordering::SeqCst), 1);
+}
+
 // This test repeatedly creates a world with new entities and drops it, reproducing
 // https://github.com/TomGillen/legion/issues/92
 #[test]